@@ -1,13 +1,20 @@
-use std::{collections::HashMap, str::FromStr};
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    str::FromStr,
+};
 use std::fs::{self, OpenOptions};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+
+mod model;
+use model::{slots_to_secs, BoardView, FeeWindow, MinerView, RoundView};
 
 use meteora_pools_sdk::accounts::Pool;
 use meteora_vault_sdk::accounts::Vault;
 use ore_api::prelude::*;
-use solana_account_decoder::UiAccountEncoding;
+use solana_account_decoder::{UiAccountEncoding, UiDataSliceConfig};
 use solana_client::{
     client_error::{reqwest::StatusCode, ClientErrorKind},
+    rpc_request::{RpcError, RpcResponseErrorData},
     nonblocking::rpc_client::RpcClient,
     rpc_config::{RpcAccountInfoConfig, RpcProgramAccountsConfig},
     rpc_filter::{Memcmp, RpcFilterType},
@@ -15,7 +22,7 @@ use solana_client::{
 use solana_sdk::commitment_config::CommitmentConfig;
 use solana_sdk::{
     compute_budget::ComputeBudgetInstruction,
-    native_token::lamports_to_sol,
+    native_token::{lamports_to_sol, sol_to_lamports},
     pubkey,
     pubkey::Pubkey,
     signature::{read_keypair_file, Signer},
@@ -27,11 +34,57 @@ use spl_token::{amount_to_ui_amount, ui_amount_to_amount};
 use steel::{AccountDeserialize, Clock, Discriminator, Instruction};
 use tokio::time::{sleep, Duration};
 use std::io::{self, Write};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+// 过程输出：OUTPUT=json 时改写到 stderr（QUIET 时丢弃），标准输出只留给最终的结果对象（见 finish_command）；
+// 输出目标在首次输出时确定（见 output_target），之后不再读取环境变量
+macro_rules! out {
+    ($($arg:tt)*) => {
+        match output_target() {
+            OutputTarget::Stdout => std::println!($($arg)*),
+            OutputTarget::Stderr => std::eprintln!($($arg)*),
+            OutputTarget::Discard => {}
+        }
+    };
+}
+
+// 不换行的过程输出（进度提示等），规则同 out!
+macro_rules! out_inline {
+    ($($arg:tt)*) => {
+        match output_target() {
+            OutputTarget::Stdout => std::print!($($arg)*),
+            OutputTarget::Stderr => std::eprint!($($arg)*),
+            OutputTarget::Discard => {}
+        }
+    };
+}
 
 #[derive(Debug, Deserialize)]
 struct CliConfig {
     #[serde(rename = "KEYPAIR")] keypair: Option<String>,
+    // 矿工 authority 密钥文件（与手续费支付账户 KEYPAIR 分离时设置）
+    #[serde(rename = "AUTHORITY_KEYPAIR")] authority_keypair: Option<String>,
+    // 代为部署：目标 authority 公钥 / 被管理 authority 的密钥文件列表
+    #[serde(rename = "DEPLOY_AUTHORITY")] deploy_authority: Option<String>,
+    #[serde(rename = "DELEGATE_KEYPAIRS")] delegate_keypairs: Option<String>,
+    // 代管账户：管理费比例（按正收益收取，单位 %）
+    #[serde(rename = "MANAGEMENT_FEE_PCT")] management_fee_pct: Option<f64>,
+    // compare：用于对比的资金规模（SOL）
+    #[serde(rename = "COMPARE_CAPITAL_SOL")] compare_capital_sol: Option<f64>,
+    // 垃圾代币防护：不视为垃圾代币的 mint（逗号分隔）/ cleanup 逐个确认后销毁（默认只列出）
+    #[serde(rename = "TOKEN_ALLOWLIST")] token_allowlist: Option<String>,
+    #[serde(rename = "CLEANUP_BURN")] cleanup_burn: Option<bool>,
+    // exposure：额外列出的钱包公钥（逗号分隔）
+    #[serde(rename = "EXPOSURE_WALLETS")] exposure_wallets: Option<String>,
+    // 签名方式：local | remote；远程签名服务地址 / 公钥 / 请求头
+    #[serde(rename = "SIGNER")] signer: Option<String>,
+    #[serde(rename = "REMOTE_SIGNER_URL")] remote_signer_url: Option<String>,
+    #[serde(rename = "REMOTE_SIGNER_PUBKEY")] remote_signer_pubkey: Option<String>,
+    #[serde(rename = "REMOTE_SIGNER_HEADERS")] remote_signer_headers: Option<String>,
+    // 多签管理操作：输出格式 base58 | base64 / Squads 金库地址 / set_admin 的新 admin
+    #[serde(rename = "ADMIN_PAYLOAD")] admin_payload: Option<String>,
+    #[serde(rename = "MULTISIG_VAULT")] multisig_vault: Option<String>,
+    #[serde(rename = "NEW_ADMIN")] new_admin: Option<String>,
     #[serde(rename = "RPC")] rpc: Option<String>,
     #[serde(rename = "COMMAND")] command: Option<String>,
     #[serde(rename = "AMOUNT")] amount: Option<String>,
@@ -44,13 +97,304 @@ struct CliConfig {
     #[serde(rename = "THRESHOLD_SOL")] threshold_sol: Option<f64>,
     #[serde(rename = "MIN_SQUARES_REQUIRED")] min_squares_required: Option<usize>,
     #[serde(rename = "START_BEFORE_SECONDS")] start_before_seconds: Option<f64>,
+    // 触发时间随机偏移（±秒）/ 下注金额随机浮动（±%）
+    #[serde(rename = "TRIGGER_JITTER_SECS")] trigger_jitter_secs: Option<f64>,
+    #[serde(rename = "AMOUNT_JITTER_PCT")] amount_jitter_pct: Option<f64>,
     #[serde(rename = "PICK_SQUARES")] pick_squares: Option<usize>,
+    // 动态选格数量：开关 / 最少 / 最多格子数 / 每轮预算（SOL）
+    #[serde(rename = "PICK_DYNAMIC")] pick_dynamic: Option<bool>,
+    #[serde(rename = "PICK_MIN")] pick_min: Option<usize>,
+    #[serde(rename = "PICK_MAX")] pick_max: Option<usize>,
+    #[serde(rename = "ROUND_BUDGET_SOL")] round_budget_sol: Option<f64>,
     #[serde(rename = "MAX_LOOPS")] max_loops: Option<usize>,
+    // 运行边界：实际部署回合数 / 运行时长（如 6h）/ 截止时间（unix 时间戳或 UTC 时间）
+    #[serde(rename = "MAX_ROUNDS")] max_rounds: Option<usize>,
+    #[serde(rename = "MAX_DURATION")] max_duration: Option<String>,
+    #[serde(rename = "RUN_UNTIL")] run_until: Option<String>,
     // 可选：直接使用 SOL 金额（优先级低于 AMOUNT（lamports））
     #[serde(rename = "AMOUNT_SOL")] amount_sol: Option<f64>,
     // 交易费用相关配置
     #[serde(rename = "COMPUTE_UNIT_PRICE")] compute_unit_price: Option<u64>, // microlamports per compute unit
     #[serde(rename = "COMPUTE_UNIT_LIMIT")] compute_unit_limit: Option<u32>, // compute units
+    #[serde(rename = "PACK_CU_PER_IX")] pack_cu_per_ix: Option<u64>, // 打包时每条指令的 CU 估算
+    #[serde(rename = "CRANK_CONFIRM_SECS")] crank_confirm_secs: Option<u64>, // crank 交易等待确认秒数
+    // 过期保护：开关 / 检查间隔（秒）/ 提前多少 slot 强制 checkpoint / 强制提交时的优先费
+    #[serde(rename = "EXPIRY_GUARD")] expiry_guard: Option<bool>,
+    #[serde(rename = "EXPIRY_GUARD_INTERVAL_SECS")] expiry_guard_interval_secs: Option<u64>,
+    #[serde(rename = "EXPIRY_GUARD_SLOTS")] expiry_guard_slots: Option<u64>,
+    #[serde(rename = "EXPIRY_GUARD_CU_PRICE")] expiry_guard_cu_price: Option<u64>,
+    // slot hash 归档：auto_mine 后台开关 / 检查间隔（秒）
+    #[serde(rename = "SLOT_HASH_ARCHIVE")] slot_hash_archive: Option<bool>,
+    #[serde(rename = "SLOT_HASH_ARCHIVE_SECS")] slot_hash_archive_secs: Option<u64>,
+    // board 快照对比 / board 命令记录快照 / 快照文件大小上限（字节）
+    #[serde(rename = "DIFF")] diff: Option<bool>,
+    #[serde(rename = "SNAPSHOT")] snapshot: Option<usize>,
+    #[serde(rename = "SNAPSHOT_RECORD")] snapshot_record: Option<bool>,
+    #[serde(rename = "BOARD_SNAPSHOT_MAX_BYTES")] board_snapshot_max_bytes: Option<u64>,
+    // 格子资金流入速度（策略过滤用）
+    #[serde(rename = "VELOCITY_SAMPLES")] velocity_samples: Option<usize>,
+    #[serde(rename = "MAX_INFLOW_SOL_PER_SEC")] max_inflow_sol_per_sec: Option<f64>,
+    #[serde(rename = "LATE_MONEY_RATIO")] late_money_ratio: Option<f64>,
+    // checkpoint 与 deploy 合并为同一笔交易（默认关闭）
+    #[serde(rename = "ATOMIC_CHECKPOINT")] atomic_checkpoint: Option<bool>,
+    // cashout：claim -> swap -> transfer 流水线
+    #[serde(rename = "CASHOUT_DESTINATION")] cashout_destination: Option<String>,
+    #[serde(rename = "CASHOUT_MIN_CLAIM_SOL")] cashout_min_claim_sol: Option<f64>,
+    #[serde(rename = "CASHOUT_MIN_CLAIM_ORE")] cashout_min_claim_ore: Option<f64>,
+    #[serde(rename = "CASHOUT_MIN_SWAP_ORE")] cashout_min_swap_ore: Option<f64>,
+    #[serde(rename = "CASHOUT_SWAP_ALL")] cashout_swap_all: Option<bool>,
+    #[serde(rename = "CASHOUT_MIN_TRANSFER_SOL")] cashout_min_transfer_sol: Option<f64>,
+    #[serde(rename = "CASHOUT_SLIPPAGE_BPS")] cashout_slippage_bps: Option<u64>,
+    // claim 自动模式：可领取价值需达到预期手续费的 CLAIM_FEE_MULTIPLE 倍
+    #[serde(rename = "CLAIM_AUTO")] claim_auto: Option<bool>,
+    #[serde(rename = "CLAIM_FEE_MULTIPLE")] claim_fee_multiple: Option<f64>,
+    // Meteora 流动性：lp_add 存入的 ORE / lp_remove 赎回的 LP（或 all）/ 滑点
+    #[serde(rename = "LP_ORE")] lp_ore: Option<f64>,
+    #[serde(rename = "LP_AMOUNT")] lp_amount: Option<String>,
+    #[serde(rename = "LP_SLIPPAGE_BPS")] lp_slippage_bps: Option<u64>,
+    // 显示单位：SOL / lamports、小数位、美元估值
+    #[serde(rename = "DISPLAY_SOL_UNIT")] display_sol_unit: Option<String>,
+    #[serde(rename = "DISPLAY_SOL_DECIMALS")] display_sol_decimals: Option<usize>,
+    #[serde(rename = "DISPLAY_ORE_DECIMALS")] display_ore_decimals: Option<usize>,
+    #[serde(rename = "DISPLAY_USD")] display_usd: Option<bool>,
+    #[serde(rename = "SOL_USD_PRICE")] sol_usd_price: Option<f64>,
+    #[serde(rename = "DISPLAY_USD_PRICE_URL")] display_usd_price_url: Option<String>,
+    // 启动自检：网络（mainnet / devnet / testnet 或 genesis hash）与开关（默认关闭）
+    #[serde(rename = "CLUSTER")] cluster: Option<String>,
+    #[serde(rename = "NETWORK_CHECK")] network_check: Option<bool>,
+    // 本机时钟与链上时间偏差超过该秒数时告警
+    #[serde(rename = "CLOCK_DRIFT_WARN_SECS")] clock_drift_warn_secs: Option<i64>,
+    // 部署 / 领取交易附加 memo；backfill 扫描的交易数
+    #[serde(rename = "MEMO_TAG")] memo_tag: Option<bool>,
+    #[serde(rename = "BACKFILL_LIMIT")] backfill_limit: Option<usize>,
+    // 批量命令总时长上限（秒，超时后保存进度并停止）/ 单次 RPC 请求超时（秒）
+    #[serde(rename = "COMMAND_TIMEOUT_SECS")] command_timeout_secs: Option<u64>,
+    #[serde(rename = "RPC_TIMEOUT_SECS")] rpc_timeout_secs: Option<u64>,
+    // 批量操作是否显示进度条（默认终端下显示）
+    #[serde(rename = "PROGRESS")] progress: Option<bool>,
+    // 下注总额不超过奖池的比例；clamp 缩小 / warn 只告警
+    #[serde(rename = "MAX_POT_FRACTION")] max_pot_fraction: Option<f64>,
+    #[serde(rename = "POT_GUARD")] pot_guard: Option<String>,
+    // Automation 余额：告警阈值（可支撑回合数）/ 检查间隔 / 默认补足回合数
+    #[serde(rename = "AUTOMATION_MIN_RUNWAY")] automation_min_runway: Option<u64>,
+    #[serde(rename = "AUTOMATION_CHECK_SECS")] automation_check_secs: Option<u64>,
+    #[serde(rename = "AUTOMATION_TOPUP_ROUNDS")] automation_topup_rounds: Option<u64>,
+    // 远程停止开关：URL / 链上账户 / 允许切换开关的公钥 / 检查间隔
+    #[serde(rename = "KILL_SWITCH_URL")] kill_switch_url: Option<String>,
+    #[serde(rename = "KILL_SWITCH_ACCOUNT")] kill_switch_account: Option<String>,
+    #[serde(rename = "KILL_SWITCH_SIGNER")] kill_switch_signer: Option<String>,
+    #[serde(rename = "KILL_SWITCH_SECS")] kill_switch_secs: Option<u64>,
+    // 竞争降额：流入 / 人数突增倍数、处理方式、降额系数、参考回合数
+    #[serde(rename = "COMPETITION_INFLOW_SPIKE")] competition_inflow_spike: Option<f64>,
+    #[serde(rename = "COMPETITION_MINERS_SPIKE")] competition_miners_spike: Option<f64>,
+    #[serde(rename = "COMPETITION_ACTION")] competition_action: Option<String>,
+    #[serde(rename = "COMPETITION_REDUCE_FACTOR")] competition_reduce_factor: Option<f64>,
+    #[serde(rename = "COMPETITION_HISTORY")] competition_history: Option<usize>,
+    // 结算规则："条件 => 动作; ..."
+    #[serde(rename = "RULES")] rules: Option<String>,
+    // 自更新：发布清单地址 / 发布者签名公钥 / 允许无签名更新 / 请求头
+    #[serde(rename = "SELF_UPDATE_URL")] self_update_url: Option<String>,
+    #[serde(rename = "SELF_UPDATE_PUBKEY")] self_update_pubkey: Option<String>,
+    #[serde(rename = "SELF_UPDATE_ALLOW_UNSIGNED")] self_update_allow_unsigned: Option<bool>,
+    #[serde(rename = "SELF_UPDATE_HEADERS")] self_update_headers: Option<String>,
+    // 尾盘流入预估：开关 / 统计最后多少秒 / 最少样本数
+    #[serde(rename = "LATE_INFLOW")] late_inflow: Option<bool>,
+    #[serde(rename = "LATE_INFLOW_WINDOW_SECS")] late_inflow_window_secs: Option<f64>,
+    #[serde(rename = "LATE_INFLOW_MIN_SAMPLES")] late_inflow_min_samples: Option<usize>,
+    // 优先费预测：开关 / 采样间隔（秒）/ 分位 / 上限 / 手续费超过期望收益时跳过
+    #[serde(rename = "FEE_FORECAST")] fee_forecast: Option<bool>,
+    #[serde(rename = "FEE_FORECAST_SECS")] fee_forecast_secs: Option<u64>,
+    #[serde(rename = "FEE_FORECAST_PERCENTILE")] fee_forecast_percentile: Option<f64>,
+    #[serde(rename = "FEE_FORECAST_MAX_CU_PRICE")] fee_forecast_max_cu_price: Option<u64>,
+    #[serde(rename = "FEE_FORECAST_SKIP")] fee_forecast_skip: Option<bool>,
+    // 策略预热：正式部署前只观察的回合数
+    #[serde(rename = "WARMUP_ROUNDS")] warmup_rounds: Option<usize>,
+    // 只预览不发送交易
+    #[serde(rename = "DRY_RUN")] dry_run: Option<bool>,
+    // claim 成功后在终端渲染交易二维码
+    #[serde(rename = "CLAIM_RECEIPT_QR")] claim_receipt_qr: Option<bool>,
+    // 输出模式：OUTPUT=json 时标准输出只有一个 JSON 结果对象（过程输出写到 stderr）；QUIET 隐藏过程输出
+    #[serde(rename = "OUTPUT")] output: Option<String>,
+    #[serde(rename = "QUIET")] quiet: Option<bool>,
+    // stake_record 采样间隔（秒）
+    #[serde(rename = "STAKE_RECORD_INTERVAL_SECS")] stake_record_interval_secs: Option<u64>,
+    // treasury_record 采样间隔（秒）
+    #[serde(rename = "TREASURY_RECORD_INTERVAL_SECS")] treasury_record_interval_secs: Option<u64>,
+    // arb_monitor：统计回合数 / 检查间隔（秒）/ 通知阈值（%）
+    #[serde(rename = "ARB_WINDOW")] arb_window: Option<usize>,
+    #[serde(rename = "ARB_INTERVAL_SECS")] arb_interval_secs: Option<u64>,
+    #[serde(rename = "ARB_EDGE_PCT")] arb_edge_pct: Option<f64>,
+    // 手机状态页：监听地址 / 访问 token
+    #[serde(rename = "STATUS_BIND")] status_bind: Option<String>,
+    #[serde(rename = "STATUS_TOKEN")] status_token: Option<String>,
+    // 只读公共 API：监听地址 / 响应缓存毫秒数 / 每个 IP 每分钟请求数上限
+    #[serde(rename = "API_BIND")] api_bind: Option<String>,
+    #[serde(rename = "API_CACHE_MS")] api_cache_ms: Option<u64>,
+    #[serde(rename = "API_RATE_LIMIT")] api_rate_limit: Option<u32>,
+    // 回测 / 参数优化：算法与参数网格（逗号分隔）
+    #[serde(rename = "ALGORITHM")] algorithm: Option<String>,
+    // 随机 / 加权随机选格的种子（不设置时每次运行不同）
+    #[serde(rename = "RNG_SEED")] rng_seed: Option<u64>,
+    // 最优化算法阈值系数：slope * 总部署 - offset
+    #[serde(rename = "OPTIMIZED_SLOPE")] optimized_slope: Option<f64>,
+    #[serde(rename = "OPTIMIZED_OFFSET_SOL")] optimized_offset_sol: Option<f64>,
+    #[serde(rename = "OPT_THRESHOLDS")] opt_thresholds: Option<String>,
+    #[serde(rename = "OPT_MIN_SQUARES")] opt_min_squares: Option<String>,
+    #[serde(rename = "OPT_PICKS")] opt_picks: Option<String>,
+    #[serde(rename = "OPT_AMOUNTS_SOL")] opt_amounts_sol: Option<String>,
+    #[serde(rename = "OPT_TRAIN_FRACTION")] opt_train_fraction: Option<f64>,
+    #[serde(rename = "OPT_TOP")] opt_top: Option<usize>,
+    #[serde(rename = "WF_TRAIN_ROUNDS")] wf_train_rounds: Option<usize>,
+    #[serde(rename = "WF_TEST_ROUNDS")] wf_test_rounds: Option<usize>,
+    // 多策略组合：name[=阈值]:百分比，逗号分隔
+    #[serde(rename = "STRATEGIES")] strategies: Option<String>,
+    // 格子白名单 / 黑名单（逗号分隔的格子编号 0-24）
+    #[serde(rename = "SQUARE_PIN")] square_pin: Option<String>,
+    #[serde(rename = "SQUARE_EXCLUDE")] square_exclude: Option<String>,
+    // 格子模板：{"名称": [格子...]}；按格子 / 模板调整金额
+    #[serde(rename = "SQUARE_TEMPLATES")] square_templates: Option<serde_json::Value>,
+    #[serde(rename = "SQUARE_BOOST")] square_boost: Option<String>,
+    // 亏损冷却：连续未中轮数 / 每小时亏损上限 / 冷却秒数 / 冷却期间下注缩减系数（不设置则暂停）
+    #[serde(rename = "TILT_MAX_LOSSES")] tilt_max_losses: Option<usize>,
+    #[serde(rename = "TILT_MAX_LOSS_SOL_PER_HOUR")] tilt_max_loss_sol_per_hour: Option<f64>,
+    #[serde(rename = "TILT_COOLDOWN_SECS")] tilt_cooldown_secs: Option<u64>,
+    #[serde(rename = "TILT_REDUCE_FACTOR")] tilt_reduce_factor: Option<f64>,
+    // 部署前资金对账：允许偏差（SOL）/ 超出时是否停止
+    #[serde(rename = "RECONCILE_MAX_DRIFT_SOL")] reconcile_max_drift_sol: Option<f64>,
+    #[serde(rename = "RECONCILE_HALT")] reconcile_halt: Option<bool>,
+    // 实例锁：允许多实例 / 启动时链上活动检查窗口（秒，0 关闭）
+    #[serde(rename = "ALLOW_MULTI_INSTANCE")] allow_multi_instance: Option<bool>,
+    #[serde(rename = "INSTANCE_CHECK_SECS")] instance_check_secs: Option<u64>,
+    // 分钱包部署：密钥文件（逗号分隔）/ 金额浮动百分比 / 提交前最大随机等待 / split_fund 目标余额
+    #[serde(rename = "SPLIT_WALLETS")] split_wallets: Option<String>,
+    #[serde(rename = "SPLIT_AMOUNT_JITTER_PCT")] split_amount_jitter_pct: Option<f64>,
+    #[serde(rename = "SPLIT_DELAY_MAX_MS")] split_delay_max_ms: Option<u64>,
+    #[serde(rename = "SPLIT_FUND_SOL")] split_fund_sol: Option<f64>,
+    // 多钱包并行（fleet）：密钥文件（逗号分隔）/ 数据源轮询间隔 / 健康状态输出间隔
+    #[serde(rename = "FLEET_WALLETS")] fleet_wallets: Option<String>,
+    #[serde(rename = "FLEET_POLL_MS")] fleet_poll_ms: Option<u64>,
+    #[serde(rename = "FLEET_HEALTH_SECS")] fleet_health_secs: Option<u64>,
+    // 矿工群体采样：每轮结束前多少秒采样
+    #[serde(rename = "COHORT_SAMPLE_BEFORE_SECONDS")] cohort_sample_before_seconds: Option<f64>,
+    // 回合数据归档：文件路径 / auto_mine 中记录快照
+    #[serde(rename = "ARCHIVE_FILE")] archive_file: Option<String>,
+    #[serde(rename = "RECORD_SNAPSHOTS")] record_snapshots: Option<bool>,
+    // auto_mine 决策录制文件（供 REPLAY 重放）
+    #[serde(rename = "REPLAY_RECORD")] replay_record: Option<String>,
+    // 决策解释日志（默认开启）
+    #[serde(rename = "DECISION_LOG")] decision_log: Option<bool>,
+    // board 持续监听
+    #[serde(rename = "FOLLOW")] follow: Option<bool>,
+    #[serde(rename = "FOLLOW_INTERVAL_MS")] follow_interval_ms: Option<u64>,
+    // 对外事件流（webhook）
+    #[serde(rename = "EVENT_STREAM_URL")] event_stream_url: Option<String>,
+    #[serde(rename = "EVENT_STREAM_HEADERS")] event_stream_headers: Option<String>,
+    // 指标：抓取端点 / Pushgateway / OTLP
+    #[serde(rename = "METRICS_BIND")] metrics_bind: Option<String>,
+    #[serde(rename = "METRICS_PUSHGATEWAY_URL")] metrics_pushgateway_url: Option<String>,
+    #[serde(rename = "METRICS_OTLP_ENDPOINT")] metrics_otlp_endpoint: Option<String>,
+    #[serde(rename = "METRICS_PUSH_INTERVAL_SECS")] metrics_push_interval_secs: Option<u64>,
+    // 挖矿循环分阶段耗时输出
+    #[serde(rename = "PROFILE_TIMING")] profile_timing: Option<bool>,
+    // 通知：事件过滤 / SMTP 邮件
+    #[serde(rename = "NOTIFY_EVENTS")] notify_events: Option<String>,
+    #[serde(rename = "SMTP_URL")] smtp_url: Option<String>,
+    #[serde(rename = "SMTP_USER")] smtp_user: Option<String>,
+    #[serde(rename = "SMTP_PASSWORD")] smtp_password: Option<String>,
+    #[serde(rename = "SMTP_FROM")] smtp_from: Option<String>,
+    #[serde(rename = "SMTP_TO")] smtp_to: Option<String>,
+    #[serde(rename = "SMTP_INSECURE")] smtp_insecure: Option<bool>,
+    #[serde(rename = "SMTP_SUBJECT_TEMPLATE")] smtp_subject_template: Option<String>,
+    #[serde(rename = "SMTP_BODY_TEMPLATE")] smtp_body_template: Option<String>,
+    #[serde(rename = "SMTP_DIGEST_SUBJECT")] smtp_digest_subject: Option<String>,
+    // 通知：Webhook（POST JSON）
+    #[serde(rename = "WEBHOOK_URL")] webhook_url: Option<String>,
+    #[serde(rename = "WEBHOOK_TEMPLATE")] webhook_template: Option<String>,
+    #[serde(rename = "WEBHOOK_HEADERS")] webhook_headers: Option<String>,
+    // 通知：开奖结果附带棋盘图片（Telegram / Discord）
+    #[serde(rename = "TELEGRAM_BOT_TOKEN")] telegram_bot_token: Option<String>,
+    #[serde(rename = "TELEGRAM_CHAT_ID")] telegram_chat_id: Option<String>,
+    #[serde(rename = "DISCORD_WEBHOOK_URL")] discord_webhook_url: Option<String>,
+    // 奖励日志轮转：按大小 / 按天
+    #[serde(rename = "REWARD_LOG_MAX_BYTES")] reward_log_max_bytes: Option<u64>,
+    #[serde(rename = "REWARD_LOG_ROTATE_DAILY")] reward_log_rotate_daily: Option<bool>,
+    // slot 延迟监控：参考节点 / 最大落后 slot 数 / 无参考节点时使用 getHealth
+    #[serde(rename = "RPC_REFERENCE")] rpc_reference: Option<String>,
+    #[serde(rename = "MAX_SLOT_LAG")] max_slot_lag: Option<u64>,
+    #[serde(rename = "SLOT_LAG_CHECK")] slot_lag_check: Option<bool>,
+    // 交易发送节点（与读取节点 RPC 分离）：地址 / 连续失败多少次暂停 / 暂停秒数
+    #[serde(rename = "RPC_SUBMIT")] rpc_submit: Option<String>,
+    #[serde(rename = "RPC_SUBMIT_MAX_FAILURES")] rpc_submit_max_failures: Option<u32>,
+    #[serde(rename = "RPC_SUBMIT_COOLDOWN_SECS")] rpc_submit_cooldown_secs: Option<u64>,
+    // sysvar（Clock / SlotHashes）备用读取节点（逗号分隔）/ 缓存毫秒数
+    #[serde(rename = "RPC_SYSVAR_FALLBACKS")] rpc_sysvar_fallbacks: Option<String>,
+    #[serde(rename = "SYSVAR_CACHE_MS")] sysvar_cache_ms: Option<u64>,
+    // RPC 断连模式：连续失败多少次进入 / 探测退避上限（秒）
+    #[serde(rename = "BLACKOUT_AFTER")] blackout_after: Option<u32>,
+    #[serde(rename = "BLACKOUT_MAX_BACKOFF_SECS")] blackout_max_backoff_secs: Option<u64>,
+    // 各类操作的确认级别：processed / confirmed / finalized
+    #[serde(rename = "COMMITMENT_STRATEGY")] commitment_strategy: Option<String>,
+    #[serde(rename = "COMMITMENT_CONFIRMATION")] commitment_confirmation: Option<String>,
+    #[serde(rename = "COMMITMENT_ANALYTICS")] commitment_analytics: Option<String>,
+    // getProgramAccounts：按首字节分 256 段拉取 / 关闭 dataSize 过滤
+    #[serde(rename = "GPA_CHUNKED")] gpa_chunked: Option<bool>,
+    #[serde(rename = "GPA_DATA_SIZE")] gpa_data_size: Option<bool>,
+    // 账户发现后端：gpa | helius | index，及分页大小 / 自建索引服务地址与请求头
+    #[serde(rename = "ACCOUNT_INDEX")] account_index: Option<String>,
+    #[serde(rename = "ACCOUNT_INDEX_PAGE_SIZE")] account_index_page_size: Option<u64>,
+    #[serde(rename = "ACCOUNT_INDEX_URL")] account_index_url: Option<String>,
+    #[serde(rename = "ACCOUNT_INDEX_HEADERS")] account_index_headers: Option<String>,
+    // 本地 Miner 索引：开关 / 最大增量回合数
+    #[serde(rename = "MINER_INDEX")] miner_index: Option<bool>,
+    #[serde(rename = "MINER_INDEX_MAX_GAP")] miner_index_max_gap: Option<u64>,
+    // 内置地址覆盖：{"KNOWN_ADDRESSES": {"SEEKER_MINT": "...", ...}}
+    #[serde(rename = "KNOWN_ADDRESSES")] known_addresses: Option<KnownAddressesConfig>,
+}
+
+// 已知地址：配置文件 KNOWN_ADDRESSES 段或同名环境变量覆盖，未设置时使用内置默认值
+#[derive(Debug, Deserialize)]
+struct KnownAddressesConfig {
+    #[serde(rename = "SEEKER_MINT")] seeker_mint: Option<String>,
+    #[serde(rename = "REGOLITH_EXECUTOR")] regolith_executor: Option<String>,
+    #[serde(rename = "METEORA_POOL")] meteora_pool: Option<String>,
+    #[serde(rename = "ATA_USER")] ata_user: Option<String>,
+    #[serde(rename = "ATA_TOKEN")] ata_token: Option<String>,
+}
+
+const DEFAULT_SEEKER_MINT: Pubkey = pubkey!("5mXbkqKz883aufhAsx3p5Z1NcvD2ppZbdTTznM6oUKLj");
+const DEFAULT_REGOLITH_EXECUTOR: Pubkey = pubkey!("HNWhK5f8RMWBqcA7mXJPaxdTPGrha3rrqUrri7HSKb3T");
+const DEFAULT_METEORA_POOL: Pubkey = pubkey!("GgaDTFbqdgjoZz3FP7zrtofGwnRS4E6MCzmmD5Ni1Mxj");
+const DEFAULT_ATA_USER: Pubkey = pubkey!("FgZFnb3bi7QexKCdXWPwWy91eocUD7JCFySHb83vLoPD");
+const DEFAULT_ATA_TOKEN: Pubkey = pubkey!("8H8rPiWW4iTFCfEkSnf7jpqeNpFfvdH9gLouAL3Fe2Zx");
+
+const KNOWN_ADDRESS_KEYS: [&str; 5] = ["SEEKER_MINT", "REGOLITH_EXECUTOR", "METEORA_POOL", "ATA_USER", "ATA_TOKEN"];
+
+// 覆盖的地址在启动时由 check_known_addresses 校验，这里无法解析时只会是未校验的键，回退到默认值
+fn known_address(key: &str, default: Pubkey) -> Pubkey {
+    parse_env_opt::<Pubkey>(key).ok().flatten().unwrap_or(default)
+}
+
+fn check_known_addresses() -> Result<(), CommandError> {
+    for key in KNOWN_ADDRESS_KEYS {
+        parse_env_opt::<Pubkey>(key)?;
+    }
+    Ok(())
+}
+
+// 布尔开关：1/true/yes/on 视为开启
+fn env_flag(key: &str) -> bool {
+    std::env::var(key)
+        .map(|v| matches!(v.trim().to_lowercase().as_str(), "1" | "true" | "yes" | "on"))
+        .unwrap_or(false)
+}
+
+// 配置文件中的值仅在对应环境变量缺失时写入（环境变量优先）
+fn set_env_if_missing<T: ToString>(key: &str, value: &Option<T>) {
+    if let Some(v) = value {
+        if std::env::var(key).is_err() {
+            std::env::set_var(key, v.to_string());
+        }
+    }
 }
 
 fn load_and_apply_config_from_file() {
@@ -66,6 +410,21 @@ fn load_and_apply_config_from_file() {
                 }
             };
             set_if_missing("KEYPAIR", &cfg.keypair);
+            set_if_missing("AUTHORITY_KEYPAIR", &cfg.authority_keypair);
+            set_if_missing("DEPLOY_AUTHORITY", &cfg.deploy_authority);
+            set_if_missing("DELEGATE_KEYPAIRS", &cfg.delegate_keypairs);
+            set_env_if_missing("MANAGEMENT_FEE_PCT", &cfg.management_fee_pct);
+            set_env_if_missing("COMPARE_CAPITAL_SOL", &cfg.compare_capital_sol);
+            set_if_missing("TOKEN_ALLOWLIST", &cfg.token_allowlist);
+            set_env_if_missing("CLEANUP_BURN", &cfg.cleanup_burn);
+            set_if_missing("EXPOSURE_WALLETS", &cfg.exposure_wallets);
+            set_if_missing("SIGNER", &cfg.signer);
+            set_if_missing("REMOTE_SIGNER_URL", &cfg.remote_signer_url);
+            set_if_missing("REMOTE_SIGNER_PUBKEY", &cfg.remote_signer_pubkey);
+            set_if_missing("REMOTE_SIGNER_HEADERS", &cfg.remote_signer_headers);
+            set_if_missing("ADMIN_PAYLOAD", &cfg.admin_payload);
+            set_if_missing("MULTISIG_VAULT", &cfg.multisig_vault);
+            set_if_missing("NEW_ADMIN", &cfg.new_admin);
             set_if_missing("RPC", &cfg.rpc);
             set_if_missing("COMMAND", &cfg.command);
             set_if_missing("AMOUNT", &cfg.amount);
@@ -74,6 +433,13 @@ fn load_and_apply_config_from_file() {
             set_if_missing("ID", &cfg.id);
             set_if_missing("FEE_COLLECTOR", &cfg.fee_collector);
             set_if_missing("MINT", &cfg.mint);
+            if let Some(known) = &cfg.known_addresses {
+                set_if_missing("SEEKER_MINT", &known.seeker_mint);
+                set_if_missing("REGOLITH_EXECUTOR", &known.regolith_executor);
+                set_if_missing("METEORA_POOL", &known.meteora_pool);
+                set_if_missing("ATA_USER", &known.ata_user);
+                set_if_missing("ATA_TOKEN", &known.ata_token);
+            }
             // 将 AMOUNT_SOL 转为 lamports 写入 AMOUNT（若 AMOUNT 未设置）
             if std::env::var("AMOUNT").is_err() {
                 if let Some(a) = cfg.amount_sol {
@@ -97,16 +463,25 @@ fn load_and_apply_config_from_file() {
                     std::env::set_var("START_BEFORE_SECONDS", sbs.to_string());
                 }
             }
+            set_env_if_missing("TRIGGER_JITTER_SECS", &cfg.trigger_jitter_secs);
+            set_env_if_missing("AMOUNT_JITTER_PCT", &cfg.amount_jitter_pct);
             if std::env::var("PICK_SQUARES").is_err() {
                 if let Some(ps) = cfg.pick_squares {
                     std::env::set_var("PICK_SQUARES", ps.to_string());
                 }
             }
+            set_env_if_missing("PICK_DYNAMIC", &cfg.pick_dynamic);
+            set_env_if_missing("PICK_MIN", &cfg.pick_min);
+            set_env_if_missing("PICK_MAX", &cfg.pick_max);
+            set_env_if_missing("ROUND_BUDGET_SOL", &cfg.round_budget_sol);
             if std::env::var("MAX_LOOPS").is_err() {
                 if let Some(ml) = cfg.max_loops {
                     std::env::set_var("MAX_LOOPS", ml.to_string());
                 }
             }
+            set_env_if_missing("MAX_ROUNDS", &cfg.max_rounds);
+            set_env_if_missing("MAX_DURATION", &cfg.max_duration);
+            set_env_if_missing("RUN_UNTIL", &cfg.run_until);
             if std::env::var("COMPUTE_UNIT_PRICE").is_err() {
                 if let Some(cup) = cfg.compute_unit_price {
                     std::env::set_var("COMPUTE_UNIT_PRICE", cup.to_string());
@@ -117,672 +492,7568 @@ fn load_and_apply_config_from_file() {
                     std::env::set_var("COMPUTE_UNIT_LIMIT", cul.to_string());
                 }
             }
-            println!("[info] 已加载当前目录的 ore.config.json");
+            set_env_if_missing("PACK_CU_PER_IX", &cfg.pack_cu_per_ix);
+            set_env_if_missing("CRANK_CONFIRM_SECS", &cfg.crank_confirm_secs);
+            set_env_if_missing("EXPIRY_GUARD", &cfg.expiry_guard);
+            set_env_if_missing("EXPIRY_GUARD_INTERVAL_SECS", &cfg.expiry_guard_interval_secs);
+            set_env_if_missing("EXPIRY_GUARD_SLOTS", &cfg.expiry_guard_slots);
+            set_env_if_missing("EXPIRY_GUARD_CU_PRICE", &cfg.expiry_guard_cu_price);
+            set_env_if_missing("SLOT_HASH_ARCHIVE", &cfg.slot_hash_archive);
+            set_env_if_missing("SLOT_HASH_ARCHIVE_SECS", &cfg.slot_hash_archive_secs);
+            set_env_if_missing("DIFF", &cfg.diff);
+            set_env_if_missing("SNAPSHOT", &cfg.snapshot);
+            set_env_if_missing("SNAPSHOT_RECORD", &cfg.snapshot_record);
+            set_env_if_missing("BOARD_SNAPSHOT_MAX_BYTES", &cfg.board_snapshot_max_bytes);
+            set_env_if_missing("VELOCITY_SAMPLES", &cfg.velocity_samples);
+            set_env_if_missing("MAX_INFLOW_SOL_PER_SEC", &cfg.max_inflow_sol_per_sec);
+            set_env_if_missing("LATE_MONEY_RATIO", &cfg.late_money_ratio);
+            set_env_if_missing("ATOMIC_CHECKPOINT", &cfg.atomic_checkpoint);
+            set_env_if_missing("CASHOUT_DESTINATION", &cfg.cashout_destination);
+            set_env_if_missing("CASHOUT_MIN_CLAIM_SOL", &cfg.cashout_min_claim_sol);
+            set_env_if_missing("CASHOUT_MIN_CLAIM_ORE", &cfg.cashout_min_claim_ore);
+            set_env_if_missing("CASHOUT_MIN_SWAP_ORE", &cfg.cashout_min_swap_ore);
+            set_env_if_missing("CASHOUT_SWAP_ALL", &cfg.cashout_swap_all);
+            set_env_if_missing("CASHOUT_MIN_TRANSFER_SOL", &cfg.cashout_min_transfer_sol);
+            set_env_if_missing("CASHOUT_SLIPPAGE_BPS", &cfg.cashout_slippage_bps);
+            set_env_if_missing("CLAIM_AUTO", &cfg.claim_auto);
+            set_env_if_missing("CLAIM_FEE_MULTIPLE", &cfg.claim_fee_multiple);
+            set_env_if_missing("LP_ORE", &cfg.lp_ore);
+            set_env_if_missing("LP_AMOUNT", &cfg.lp_amount);
+            set_env_if_missing("LP_SLIPPAGE_BPS", &cfg.lp_slippage_bps);
+            set_env_if_missing("DISPLAY_SOL_UNIT", &cfg.display_sol_unit);
+            set_env_if_missing("DISPLAY_SOL_DECIMALS", &cfg.display_sol_decimals);
+            set_env_if_missing("DISPLAY_ORE_DECIMALS", &cfg.display_ore_decimals);
+            set_env_if_missing("DISPLAY_USD", &cfg.display_usd);
+            set_env_if_missing("SOL_USD_PRICE", &cfg.sol_usd_price);
+            set_env_if_missing("DISPLAY_USD_PRICE_URL", &cfg.display_usd_price_url);
+            set_env_if_missing("CLUSTER", &cfg.cluster);
+            set_env_if_missing("NETWORK_CHECK", &cfg.network_check);
+            set_env_if_missing("CLOCK_DRIFT_WARN_SECS", &cfg.clock_drift_warn_secs);
+            set_env_if_missing("MEMO_TAG", &cfg.memo_tag);
+            set_env_if_missing("BACKFILL_LIMIT", &cfg.backfill_limit);
+            set_env_if_missing("COMMAND_TIMEOUT_SECS", &cfg.command_timeout_secs);
+            set_env_if_missing("RPC_TIMEOUT_SECS", &cfg.rpc_timeout_secs);
+            set_env_if_missing("PROGRESS", &cfg.progress);
+            set_env_if_missing("MAX_POT_FRACTION", &cfg.max_pot_fraction);
+            set_env_if_missing("POT_GUARD", &cfg.pot_guard);
+            set_env_if_missing("AUTOMATION_MIN_RUNWAY", &cfg.automation_min_runway);
+            set_env_if_missing("AUTOMATION_CHECK_SECS", &cfg.automation_check_secs);
+            set_env_if_missing("AUTOMATION_TOPUP_ROUNDS", &cfg.automation_topup_rounds);
+            set_env_if_missing("KILL_SWITCH_URL", &cfg.kill_switch_url);
+            set_env_if_missing("KILL_SWITCH_ACCOUNT", &cfg.kill_switch_account);
+            set_env_if_missing("KILL_SWITCH_SIGNER", &cfg.kill_switch_signer);
+            set_env_if_missing("KILL_SWITCH_SECS", &cfg.kill_switch_secs);
+            set_env_if_missing("COMPETITION_INFLOW_SPIKE", &cfg.competition_inflow_spike);
+            set_env_if_missing("COMPETITION_MINERS_SPIKE", &cfg.competition_miners_spike);
+            set_env_if_missing("COMPETITION_ACTION", &cfg.competition_action);
+            set_env_if_missing("COMPETITION_REDUCE_FACTOR", &cfg.competition_reduce_factor);
+            set_env_if_missing("COMPETITION_HISTORY", &cfg.competition_history);
+            set_env_if_missing("RULES", &cfg.rules);
+            set_env_if_missing("SELF_UPDATE_URL", &cfg.self_update_url);
+            set_env_if_missing("SELF_UPDATE_PUBKEY", &cfg.self_update_pubkey);
+            set_env_if_missing("SELF_UPDATE_ALLOW_UNSIGNED", &cfg.self_update_allow_unsigned);
+            set_env_if_missing("SELF_UPDATE_HEADERS", &cfg.self_update_headers);
+            set_env_if_missing("LATE_INFLOW", &cfg.late_inflow);
+            set_env_if_missing("LATE_INFLOW_WINDOW_SECS", &cfg.late_inflow_window_secs);
+            set_env_if_missing("LATE_INFLOW_MIN_SAMPLES", &cfg.late_inflow_min_samples);
+            set_env_if_missing("FEE_FORECAST", &cfg.fee_forecast);
+            set_env_if_missing("FEE_FORECAST_SECS", &cfg.fee_forecast_secs);
+            set_env_if_missing("FEE_FORECAST_PERCENTILE", &cfg.fee_forecast_percentile);
+            set_env_if_missing("FEE_FORECAST_MAX_CU_PRICE", &cfg.fee_forecast_max_cu_price);
+            set_env_if_missing("FEE_FORECAST_SKIP", &cfg.fee_forecast_skip);
+            set_env_if_missing("WARMUP_ROUNDS", &cfg.warmup_rounds);
+            set_env_if_missing("DRY_RUN", &cfg.dry_run);
+            set_env_if_missing("CLAIM_RECEIPT_QR", &cfg.claim_receipt_qr);
+            set_env_if_missing("OUTPUT", &cfg.output);
+            set_env_if_missing("QUIET", &cfg.quiet);
+            set_env_if_missing("STAKE_RECORD_INTERVAL_SECS", &cfg.stake_record_interval_secs);
+            set_env_if_missing("TREASURY_RECORD_INTERVAL_SECS", &cfg.treasury_record_interval_secs);
+            set_env_if_missing("ARB_WINDOW", &cfg.arb_window);
+            set_env_if_missing("ARB_INTERVAL_SECS", &cfg.arb_interval_secs);
+            set_env_if_missing("ARB_EDGE_PCT", &cfg.arb_edge_pct);
+            set_env_if_missing("STATUS_BIND", &cfg.status_bind);
+            set_env_if_missing("STATUS_TOKEN", &cfg.status_token);
+            set_env_if_missing("API_BIND", &cfg.api_bind);
+            set_env_if_missing("API_CACHE_MS", &cfg.api_cache_ms);
+            set_env_if_missing("API_RATE_LIMIT", &cfg.api_rate_limit);
+            set_env_if_missing("ALGORITHM", &cfg.algorithm);
+            set_env_if_missing("RNG_SEED", &cfg.rng_seed);
+            set_env_if_missing("OPTIMIZED_SLOPE", &cfg.optimized_slope);
+            set_env_if_missing("OPTIMIZED_OFFSET_SOL", &cfg.optimized_offset_sol);
+            set_env_if_missing("OPT_THRESHOLDS", &cfg.opt_thresholds);
+            set_env_if_missing("OPT_MIN_SQUARES", &cfg.opt_min_squares);
+            set_env_if_missing("OPT_PICKS", &cfg.opt_picks);
+            set_env_if_missing("OPT_AMOUNTS_SOL", &cfg.opt_amounts_sol);
+            set_env_if_missing("OPT_TRAIN_FRACTION", &cfg.opt_train_fraction);
+            set_env_if_missing("OPT_TOP", &cfg.opt_top);
+            set_env_if_missing("WF_TRAIN_ROUNDS", &cfg.wf_train_rounds);
+            set_env_if_missing("WF_TEST_ROUNDS", &cfg.wf_test_rounds);
+            set_env_if_missing("STRATEGIES", &cfg.strategies);
+            set_env_if_missing("SQUARE_PIN", &cfg.square_pin);
+            set_env_if_missing("SQUARE_EXCLUDE", &cfg.square_exclude);
+            set_env_if_missing("SQUARE_TEMPLATES", &cfg.square_templates);
+            set_env_if_missing("SQUARE_BOOST", &cfg.square_boost);
+            set_env_if_missing("TILT_MAX_LOSSES", &cfg.tilt_max_losses);
+            set_env_if_missing("TILT_MAX_LOSS_SOL_PER_HOUR", &cfg.tilt_max_loss_sol_per_hour);
+            set_env_if_missing("TILT_COOLDOWN_SECS", &cfg.tilt_cooldown_secs);
+            set_env_if_missing("TILT_REDUCE_FACTOR", &cfg.tilt_reduce_factor);
+            set_env_if_missing("RECONCILE_MAX_DRIFT_SOL", &cfg.reconcile_max_drift_sol);
+            set_env_if_missing("RECONCILE_HALT", &cfg.reconcile_halt);
+            set_env_if_missing("ALLOW_MULTI_INSTANCE", &cfg.allow_multi_instance);
+            set_env_if_missing("INSTANCE_CHECK_SECS", &cfg.instance_check_secs);
+            set_env_if_missing("SPLIT_WALLETS", &cfg.split_wallets);
+            set_env_if_missing("SPLIT_AMOUNT_JITTER_PCT", &cfg.split_amount_jitter_pct);
+            set_env_if_missing("SPLIT_DELAY_MAX_MS", &cfg.split_delay_max_ms);
+            set_env_if_missing("SPLIT_FUND_SOL", &cfg.split_fund_sol);
+            set_env_if_missing("FLEET_WALLETS", &cfg.fleet_wallets);
+            set_env_if_missing("FLEET_POLL_MS", &cfg.fleet_poll_ms);
+            set_env_if_missing("FLEET_HEALTH_SECS", &cfg.fleet_health_secs);
+            set_env_if_missing("COHORT_SAMPLE_BEFORE_SECONDS", &cfg.cohort_sample_before_seconds);
+            set_env_if_missing("ARCHIVE_FILE", &cfg.archive_file);
+            set_env_if_missing("RECORD_SNAPSHOTS", &cfg.record_snapshots);
+            set_env_if_missing("REPLAY_RECORD", &cfg.replay_record);
+            set_env_if_missing("DECISION_LOG", &cfg.decision_log);
+            set_env_if_missing("FOLLOW", &cfg.follow);
+            set_env_if_missing("FOLLOW_INTERVAL_MS", &cfg.follow_interval_ms);
+            set_env_if_missing("EVENT_STREAM_URL", &cfg.event_stream_url);
+            set_env_if_missing("EVENT_STREAM_HEADERS", &cfg.event_stream_headers);
+            set_env_if_missing("METRICS_BIND", &cfg.metrics_bind);
+            set_env_if_missing("METRICS_PUSHGATEWAY_URL", &cfg.metrics_pushgateway_url);
+            set_env_if_missing("METRICS_OTLP_ENDPOINT", &cfg.metrics_otlp_endpoint);
+            set_env_if_missing("METRICS_PUSH_INTERVAL_SECS", &cfg.metrics_push_interval_secs);
+            set_env_if_missing("PROFILE_TIMING", &cfg.profile_timing);
+            set_env_if_missing("NOTIFY_EVENTS", &cfg.notify_events);
+            set_env_if_missing("SMTP_URL", &cfg.smtp_url);
+            set_env_if_missing("SMTP_USER", &cfg.smtp_user);
+            set_env_if_missing("SMTP_PASSWORD", &cfg.smtp_password);
+            set_env_if_missing("SMTP_FROM", &cfg.smtp_from);
+            set_env_if_missing("SMTP_TO", &cfg.smtp_to);
+            set_env_if_missing("SMTP_INSECURE", &cfg.smtp_insecure);
+            set_env_if_missing("SMTP_SUBJECT_TEMPLATE", &cfg.smtp_subject_template);
+            set_env_if_missing("SMTP_BODY_TEMPLATE", &cfg.smtp_body_template);
+            set_env_if_missing("SMTP_DIGEST_SUBJECT", &cfg.smtp_digest_subject);
+            set_env_if_missing("WEBHOOK_URL", &cfg.webhook_url);
+            set_env_if_missing("WEBHOOK_TEMPLATE", &cfg.webhook_template);
+            set_env_if_missing("WEBHOOK_HEADERS", &cfg.webhook_headers);
+            set_env_if_missing("TELEGRAM_BOT_TOKEN", &cfg.telegram_bot_token);
+            set_env_if_missing("TELEGRAM_CHAT_ID", &cfg.telegram_chat_id);
+            set_env_if_missing("DISCORD_WEBHOOK_URL", &cfg.discord_webhook_url);
+            set_env_if_missing("REWARD_LOG_MAX_BYTES", &cfg.reward_log_max_bytes);
+            set_env_if_missing("REWARD_LOG_ROTATE_DAILY", &cfg.reward_log_rotate_daily);
+            set_env_if_missing("RPC_REFERENCE", &cfg.rpc_reference);
+            set_env_if_missing("MAX_SLOT_LAG", &cfg.max_slot_lag);
+            set_env_if_missing("SLOT_LAG_CHECK", &cfg.slot_lag_check);
+            set_env_if_missing("RPC_SUBMIT", &cfg.rpc_submit);
+            set_env_if_missing("RPC_SUBMIT_MAX_FAILURES", &cfg.rpc_submit_max_failures);
+            set_env_if_missing("RPC_SUBMIT_COOLDOWN_SECS", &cfg.rpc_submit_cooldown_secs);
+            set_env_if_missing("RPC_SYSVAR_FALLBACKS", &cfg.rpc_sysvar_fallbacks);
+            set_env_if_missing("SYSVAR_CACHE_MS", &cfg.sysvar_cache_ms);
+            set_env_if_missing("BLACKOUT_AFTER", &cfg.blackout_after);
+            set_env_if_missing("BLACKOUT_MAX_BACKOFF_SECS", &cfg.blackout_max_backoff_secs);
+            set_env_if_missing("COMMITMENT_STRATEGY", &cfg.commitment_strategy);
+            set_env_if_missing("COMMITMENT_CONFIRMATION", &cfg.commitment_confirmation);
+            set_env_if_missing("COMMITMENT_ANALYTICS", &cfg.commitment_analytics);
+            set_env_if_missing("GPA_CHUNKED", &cfg.gpa_chunked);
+            set_env_if_missing("GPA_DATA_SIZE", &cfg.gpa_data_size);
+            set_env_if_missing("ACCOUNT_INDEX", &cfg.account_index);
+            set_env_if_missing("ACCOUNT_INDEX_PAGE_SIZE", &cfg.account_index_page_size);
+            set_env_if_missing("ACCOUNT_INDEX_URL", &cfg.account_index_url);
+            set_env_if_missing("ACCOUNT_INDEX_HEADERS", &cfg.account_index_headers);
+            set_env_if_missing("MINER_INDEX", &cfg.miner_index);
+            set_env_if_missing("MINER_INDEX_MAX_GAP", &cfg.miner_index_max_gap);
+            if !env_flag("QUIET") {
+                out!("[info] 已加载当前目录的 ore.config.json");
+            }
         } else {
-            println!("[warn] ore.config.json 解析失败，请检查 JSON 格式是否正确。");
+            out!("[warn] ore.config.json 解析失败，请检查 JSON 格式是否正确。");
         }
-    } else {
-        println!(
+    } else if !env_flag("QUIET") {
+        out!(
             "[warn] 未在当前目录检测到 ore.config.json，将仅使用环境变量。如果是首次运行，请在当前目录创建 ore.config.json 后重试。"
         );
     }
 }
 
+// ============ 新增：显示单位与精度 ============
+// 所有命令与报告中的 SOL / ORE 金额统一经 fmt_sol / fmt_ore 输出：
+// DISPLAY_SOL_UNIT=sol|lamports、DISPLAY_SOL_DECIMALS（默认 6）、DISPLAY_ORE_DECIMALS（默认完整精度），
+// DISPLAY_USD=true 时在金额后附美元估值：SOL 价格取 SOL_USD_PRICE，未设置时启动时从 DISPLAY_USD_PRICE_URL 获取一次，
+// ORE 价格 = Meteora 池子中间价 × SOL 价格
+const DEFAULT_USD_PRICE_URL: &str = "https://lite-api.jup.ag/price/v3?ids=So11111111111111111111111111111111111111112";
+
+struct DisplayUnits {
+    lamports: bool,
+    sol_decimals: usize,
+    ore_decimals: Option<usize>,
+    sol_usd: Option<f64>,
+    ore_usd: Option<f64>,
+}
+
+static DISPLAY_UNITS: std::sync::OnceLock<DisplayUnits> = std::sync::OnceLock::new();
+
+impl DisplayUnits {
+    fn from_env() -> Self {
+        Self {
+            lamports: std::env::var("DISPLAY_SOL_UNIT").map(|u| u.trim() == "lamports").unwrap_or(false),
+            sol_decimals: std::env::var("DISPLAY_SOL_DECIMALS")
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(6),
+            ore_decimals: std::env::var("DISPLAY_ORE_DECIMALS")
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok()),
+            sol_usd: None,
+            ore_usd: None,
+        }
+    }
+}
+
+fn display_units() -> &'static DisplayUnits {
+    DISPLAY_UNITS.get_or_init(DisplayUnits::from_env)
+}
+
+// 启动时调用一次；价格获取失败时只提示，不显示美元列
+async fn init_display_units(rpc: &RpcClient) {
+    let mut units = DisplayUnits::from_env();
+    if env_flag("DISPLAY_USD") {
+        units.sol_usd = match std::env::var("SOL_USD_PRICE").ok().and_then(|s| s.parse::<f64>().ok()) {
+            Some(price) => Some(price),
+            None => fetch_sol_usd_price().await.map_err(|e| out!("[display] ⚠️  获取 SOL 美元价格失败: {}", e)).ok(),
+        };
+        if let Some(sol_usd) = units.sol_usd {
+            if let Ok(reserves) = get_meteora_reserves(rpc, known_address("METEORA_POOL", DEFAULT_METEORA_POOL)).await {
+                units.ore_usd = Some(reserves.mid_price() * sol_usd);
+            }
+        }
+    }
+    let _ = DISPLAY_UNITS.set(units);
+}
+
+// 价格接口返回 JSON，取第一个 usdPrice / price 数值字段
+async fn fetch_sol_usd_price() -> Result<f64, anyhow::Error> {
+    let url = std::env::var("DISPLAY_USD_PRICE_URL").unwrap_or(DEFAULT_USD_PRICE_URL.to_string());
+    let client = solana_client::client_error::reqwest::Client::new();
+    let body: serde_json::Value = client
+        .get(&url)
+        .timeout(Duration::from_secs(5))
+        .send()
+        .await?
+        .json()
+        .await?;
+    fn find_price(value: &serde_json::Value) -> Option<f64> {
+        match value {
+            serde_json::Value::Object(fields) => ["usdPrice", "price"]
+                .iter()
+                .find_map(|k| fields.get(*k).and_then(|v| v.as_f64().or_else(|| v.as_str()?.parse().ok())))
+                .or_else(|| fields.values().find_map(find_price)),
+            serde_json::Value::Array(items) => items.iter().find_map(find_price),
+            _ => None,
+        }
+    }
+    find_price(&body).ok_or_else(|| anyhow::anyhow!("{} 的响应中没有价格字段", url))
+}
+
+fn fmt_sol(lamports: u64) -> String {
+    let units = display_units();
+    let mut out = if units.lamports {
+        format!("{} lamports", lamports)
+    } else {
+        format!("{:.*} SOL", units.sol_decimals, lamports_to_sol(lamports))
+    };
+    if let Some(price) = units.sol_usd {
+        out.push_str(&format!(" (${:.2})", lamports_to_sol(lamports) * price));
+    }
+    out
+}
+
+// 盈亏等带符号金额
+fn fmt_sol_signed(lamports: i64) -> String {
+    let sign = if lamports < 0 { "-" } else { "+" };
+    format!("{}{}", sign, fmt_sol(lamports.unsigned_abs()))
+}
+
+fn fmt_ore(amount: u64) -> String {
+    let units = display_units();
+    let ui = amount_to_ui_amount(amount, TOKEN_DECIMALS);
+    let mut out = match units.ore_decimals {
+        Some(decimals) => format!("{:.*} ORE", decimals, ui),
+        None => format!("{} ORE", ui),
+    };
+    if let Some(price) = units.ore_usd {
+        out.push_str(&format!(" (${:.2})", ui * price));
+    }
+    out
+}
+
 #[tokio::main]
 async fn main() {
     // 优先从 ore.config.json 注入缺失的环境变量
     load_and_apply_config_from_file();
     // 若仍缺少 COMMAND，默认降级为 interactive
     if std::env::var("COMMAND").is_err() {
-        println!("[warn] 未设置 COMMAND，默认使用 interactive 模式。");
+        if !env_flag("QUIET") {
+            out!("[warn] 未设置 COMMAND，默认使用 interactive 模式。");
+        }
         std::env::set_var("COMMAND", "interactive");
     }
-    // Read keypair from file
-    let payer =
-        read_keypair_file(&std::env::var("KEYPAIR").expect("Missing KEYPAIR env var")).unwrap();
+    let command = std::env::var("COMMAND").unwrap_or_default();
+    // 自更新不需要密钥与 RPC，在加载签名者之前处理
+    if command == "self_update" {
+        finish_command(&command, self_update().await.and_then(command_output));
+        return;
+    }
+    // 升级本地状态格式；状态版本比本程序新时拒绝运行
+    if let Err(e) = migrate_state() {
+        finish_command(&command, Err(e));
+        return;
+    }
+    if let Err(e) = check_known_addresses() {
+        finish_command(&command, Err(e.into()));
+        return;
+    }
+    // 纯本地命令不加载签名者、不连接 RPC
+    if OFFLINE_COMMANDS.contains(&command.as_str()) {
+        let result = run_offline_command(&command).await;
+        flush_notifications().await;
+        finish_command(&command, result);
+        return;
+    }
+    // 交易签名者：本地密钥文件或远程签名服务（SIGNER）
+    let payer_signer = match payer_signer_from_env() {
+        Ok(signer) => signer,
+        Err(e) => {
+            finish_command(&command, Err(e.into()));
+            return;
+        }
+    };
+    let payer: &dyn TxSigner = payer_signer.as_ref();
+    if let Err(e) = load_authority_signer() {
+        finish_command(&command, Err(e));
+        return;
+    }
+    let _ = EVENT_STREAM_AUTHORITY.set(mining_authority(payer).pubkey().to_string());
 
     // Build transaction
-    let rpc_url = std::env::var("RPC").expect("Missing RPC env var");
-    // 使用 processed 确认级别以获得最快的数据读取（几乎实时）
+    let rpc_url = match required_env("RPC") {
+        Ok(url) => url,
+        Err(e) => {
+            finish_command(&command, Err(e.into()));
+            return;
+        }
+    };
+    // 确认级别按操作类别区分（见 commitment_for）：
     // processed < confirmed < finalized
-    // - processed: 最快（~400ms），数据可能被回滚，适合实时监控
-    // - confirmed: 中等（~1-2秒），需要 1 个区块确认，适合大多数场景
+    // - processed: 最快（~400ms），数据可能被回滚，适合实时监控（策略读取默认）
+    // - confirmed: 中等（~1-2秒），需要 1 个区块确认，适合大多数场景（交易确认/统计默认）
     // - finalized: 最慢（~30秒），需要 32 个区块确认，数据不可回滚
-    // 对于自动挖矿，使用 processed 可以获得最快的响应，减少延迟导致的数据不一致
-    let commitment = CommitmentConfig::processed();
-    let rpc = RpcClient::new_with_commitment(rpc_url, commitment);
-    match std::env::var("COMMAND")
-        .expect("Missing COMMAND env var")
-        .as_str()
-    {
+    // 客户端默认级别用于交易确认（send_and_confirm）与未显式指定级别的读取
+    let commitment = commitment_for(OpClass::Confirmation);
+    let rpc = RpcClient::new_with_timeout_and_commitment(rpc_url, rpc_request_timeout(), commitment);
+    if let Err(e) = network_preflight(&rpc).await {
+        finish_command(&command, Err(e));
+        return;
+    }
+    // 读取一次 Clock，记录本机时钟与链上时间的偏差（见 observe_chain_clock）
+    let _ = get_clock(&rpc).await;
+    init_display_units(&rpc).await;
+    let result = match command.as_str() {
         "automations" => {
-            log_automations(&rpc).await.unwrap();
+            log_automations(&rpc).await.and_then(command_output)
+        }
+        "automation_monitor" => {
+            automation_monitor(&rpc, payer).await.and_then(command_output)
+        }
+        "automation_topup" => {
+            automation_topup(&rpc, payer).await.and_then(command_output)
+        }
+        "kill_switch" => {
+            kill_switch(&rpc, payer).await.and_then(command_output)
         }
         "clock" => {
-            log_clock(&rpc).await.unwrap();
+            log_clock(&rpc).await.and_then(command_output)
         }
         "claim" => {
-            claim(&rpc, &payer).await.unwrap();
+            claim(&rpc, payer).await.and_then(command_output)
+        }
+        "lp_add" => {
+            lp_add(&rpc, payer).await.and_then(command_output)
+        }
+        "lp_remove" => {
+            lp_remove(&rpc, payer).await.and_then(command_output)
+        }
+        "cashout" => {
+            cashout(&rpc, payer).await.and_then(command_output)
+        }
+        "split_fund" => {
+            split_fund(&rpc, payer).await.and_then(command_output)
+        }
+        "split_report" => {
+            split_report(&rpc).await.and_then(command_output)
+        }
+        "exposure" => {
+            exposure(&rpc, payer).await.and_then(command_output)
+        }
+        "client_deposit" => {
+            client_deposit(&rpc).await.and_then(command_output)
+        }
+        "client_statement" => {
+            client_statement(&rpc).await.and_then(command_output)
+        }
+        "fleet" => {
+            fleet(&rpc).await.and_then(command_output)
         }
         "board" => {
-            log_board(&rpc).await.unwrap();
+            log_board(&rpc).await.and_then(command_output)
         }
         "config" => {
-            log_config(&rpc).await.unwrap();
+            log_config(&rpc).await.and_then(command_output)
         }
         "initialize" => {
-            initialize(&rpc, &payer).await.unwrap();
+            initialize(&rpc, payer).await.and_then(command_output)
         }
         "bury" => {
-            bury(&rpc, &payer).await.unwrap();
+            bury(&rpc, payer).await.and_then(command_output)
         }
         "reset" => {
-            reset(&rpc, &payer).await.unwrap();
+            reset(&rpc, payer).await.and_then(command_output)
         }
         "treasury" => {
-            log_treasury(&rpc).await.unwrap();
+            log_treasury(&rpc).await.and_then(command_output)
         }
         "miner" => {
-            log_miner(&rpc, &payer).await.unwrap();
+            log_miner(&rpc, payer).await.and_then(command_output)
         }
         "pool" => {
-            log_meteora_pool(&rpc).await.unwrap();
+            log_meteora_pool(&rpc).await.and_then(command_output)
         }
         "deploy" => {
-            deploy(&rpc, &payer).await.unwrap();
+            deploy(&rpc, payer).await.and_then(command_output)
         }
         "stake" => {
-            log_stake(&rpc, &payer).await.unwrap();
+            log_stake(&rpc, payer).await.and_then(command_output)
+        }
+        "stake_record" => {
+            stake_record(&rpc, payer).await.and_then(command_output)
+        }
+        "compare" => {
+            compare(&rpc).await.and_then(command_output)
+        }
+        "treasury_record" => {
+            treasury_record(&rpc).await.and_then(command_output)
+        }
+        "arb_monitor" => {
+            arb_monitor(&rpc).await.and_then(command_output)
+        }
+        "backfill" => {
+            backfill(&rpc, payer).await.and_then(command_output)
+        }
+        "cohort_record" => {
+            cohort_record(&rpc).await.and_then(command_output)
         }
         "deploy_all" => {
-            deploy_all(&rpc, &payer).await.unwrap();
+            deploy_all(&rpc, payer).await.and_then(command_output)
         }
         "round" => {
-            log_round(&rpc).await.unwrap();
+            log_round(&rpc).await.and_then(command_output)
+        }
+        "rounds" => {
+            log_rounds(&rpc).await.and_then(command_output)
         }
         "seeker" => {
-            log_seeker(&rpc).await.unwrap();
+            log_seeker(&rpc).await.and_then(command_output)
+        }
+        "seeker_status" => {
+            seeker_status(&rpc, payer).await.and_then(command_output)
         }
         "set_admin" => {
-            set_admin(&rpc, &payer).await.unwrap();
+            set_admin(&rpc, payer).await.and_then(command_output)
         }
         "set_fee_collector" => {
-            set_fee_collector(&rpc, &payer).await.unwrap();
+            set_fee_collector(&rpc, payer).await.and_then(command_output)
         }
         "ata" => {
-            ata(&rpc, &payer).await.unwrap();
+            ata(&rpc, payer).await.and_then(command_output)
         }
         "checkpoint" => {
-            checkpoint(&rpc, &payer).await.unwrap();
+            checkpoint(&rpc, payer).await.and_then(command_output)
         }
         "checkpoint_all" => {
-            checkpoint_all(&rpc, &payer).await.unwrap();
+            checkpoint_all(&rpc, payer).await.and_then(command_output)
+        }
+        "expiry_guard" => {
+            expiry_guard(&rpc, payer).await.and_then(command_output)
+        }
+        "slot_hash_recorder" => {
+            slot_hash_recorder(&rpc).await.and_then(command_output)
+        }
+        "audit_rng" => {
+            audit_rng(&rpc).await.and_then(command_output)
+        }
+        "verify_round" => {
+            verify_round(&rpc, payer).await.and_then(command_output)
+        }
+        "miner_index" => {
+            miner_index(&rpc).await.and_then(command_output)
         }
         "close_all" => {
-            close_all(&rpc, &payer).await.unwrap();
+            close_all(&rpc, payer).await.and_then(command_output)
         }
         "claim_seeker" => {
-            claim_seeker(&rpc, &payer).await.unwrap();
+            claim_seeker(&rpc, payer).await.and_then(command_output)
         }
         "participating_miners" => {
-            participating_miners(&rpc).await.unwrap();
+            participating_miners(&rpc).await.and_then(command_output)
         }
-        "keys" => {
-            keys().await.unwrap();
+        "status_server" => {
+            status_server(&rpc, payer).await.and_then(command_output)
+        }
+        "api_server" => {
+            api_server(&rpc).await.and_then(command_output)
+        }
+        "doctor" => {
+            doctor(&rpc, payer).await.and_then(command_output)
+        }
+        "init_miner" => {
+            init_miner(&rpc, payer).await.and_then(command_output)
+        }
+        "cleanup" => {
+            cleanup(&rpc, payer).await.and_then(command_output)
+        }
+        "rent" => {
+            rent(&rpc, payer).await.and_then(command_output)
         }
         "auto_mine" => {
-            // 命令行直接调用时，默认使用阈值算法（原算法）
-            auto_mine(&rpc, &payer, SquareSelectionAlgorithm::Threshold).await.unwrap();
+            // 命令行直接调用时按 ALGORITHM 选择，默认使用阈值算法（原算法）
+            auto_mine(&rpc, payer, SquareSelectionAlgorithm::from_env()).await.and_then(command_output)
         }
         "interactive" => {
-            interactive_menu(&rpc, &payer).await.unwrap();
+            interactive_menu(&rpc, payer).await.and_then(command_output)
         }
-        _ => panic!("Invalid command"),
+        _ => Err(CommandError::config(format!("Invalid COMMAND: {}", command)).into()),
     };
+    flush_notifications().await;
+    // 临时机器上运行时，退出前再推送一次最终指标
+    if metrics_push_configured() && METRICS_EXPORTERS_STARTED.load(std::sync::atomic::Ordering::Relaxed) {
+        push_metrics(&payer.pubkey().to_string()).await;
+    }
+    finish_command(&command, result);
 }
 
-async fn participating_miners(rpc: &RpcClient) -> Result<(), anyhow::Error> {
-    let round_id = std::env::var("ID").expect("Missing ID env var");
-    let round_id = u64::from_str(&round_id).expect("Invalid ID");
-    let miners = get_miners_participating(rpc, round_id).await?;
-    for (i, (_address, miner)) in miners.iter().enumerate() {
-        println!("{}: {}", i, miner.authority);
+// ============ 新增：启动时网络自检 ============
+// 发起任何 RPC 命令前确认：节点 getHealth 正常、genesis hash 与 CLUSTER 一致（mainnet / devnet / testnet 或直接给出 hash，
+// 默认 mainnet）、ORE 程序账户存在且可执行。任一项失败立即退出并说明原因，不再事后猜测"是否连到了 devnet"。
+// 默认关闭，NETWORK_CHECK=true 开启；genesis hash 校验始终进行（本地状态按网络隔离依赖它，见 cluster_name）。
+// 纯本地命令不连接 RPC，不做自检
+const OFFLINE_COMMANDS: [&str; 17] = [
+    "stake_history",
+    "tokenomics",
+    "log_parse",
+    "log_import",
+    "backtest",
+    "optimize",
+    "calibrate_optimized",
+    "walk_forward",
+    "cohort_report",
+    "archive_export",
+    "archive_import",
+    "crank_report",
+    "explain",
+    "digest",
+    "keys",
+    "authority_spend",
+    "twap_report",
+];
+
+// 纯本地命令：只读写本地状态文件，不需要密钥与 RPC
+async fn run_offline_command(command: &str) -> Result<serde_json::Value, anyhow::Error> {
+    match command {
+        "stake_history" => {
+            log_stake_history().and_then(command_output)
+        }
+        "tokenomics" => {
+            tokenomics().and_then(command_output)
+        }
+        "log_parse" => {
+            log_parse().and_then(command_output)
+        }
+        "log_import" => {
+            log_import().and_then(command_output)
+        }
+        "backtest" => {
+            backtest_command().and_then(command_output)
+        }
+        "optimize" => {
+            optimize().and_then(command_output)
+        }
+        "calibrate_optimized" => {
+            calibrate_optimized().and_then(command_output)
+        }
+        "walk_forward" => {
+            walk_forward().and_then(command_output)
+        }
+        "cohort_report" => {
+            cohort_report().and_then(command_output)
+        }
+        "archive_export" => {
+            archive_export().and_then(command_output)
+        }
+        "archive_import" => {
+            archive_import().and_then(command_output)
+        }
+        "crank_report" => {
+            crank_report().and_then(command_output)
+        }
+        "explain" => {
+            explain_command().and_then(command_output)
+        }
+        "digest" => {
+            digest().and_then(command_output)
+        }
+        "keys" => {
+            keys().await.and_then(command_output)
+        }
+        "authority_spend" => {
+            authority_spend().and_then(command_output)
+        }
+        "twap_report" => {
+            twap_report().and_then(command_output)
+        }
+        _ => Err(CommandError::config(format!("Invalid COMMAND: {}", command)).into()),
     }
-    Ok(())
 }
 
-async fn log_stake(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let authority = std::env::var("AUTHORITY").unwrap_or(payer.pubkey().to_string());
-    let authority = Pubkey::from_str(&authority).expect("Invalid AUTHORITY");
-    let staker_address = ore_api::state::stake_pda(authority).0;
-    let stake = get_stake(rpc, authority).await?;
-    println!("Stake");
-    println!("  address: {}", staker_address);
-    println!("  authority: {}", authority);
-    println!(
-        "  balance: {} ORE",
-        amount_to_ui_amount(stake.balance, TOKEN_DECIMALS)
-    );
-    println!("  last_claim_at: {}", stake.last_claim_at);
-    println!("  last_deposit_at: {}", stake.last_deposit_at);
-    println!("  last_withdraw_at: {}", stake.last_withdraw_at);
-    println!(
-        "  rewards_factor: {}",
-        stake.rewards_factor.to_i80f48().to_string()
-    );
-    println!(
-        "  rewards: {} ORE",
-        amount_to_ui_amount(stake.rewards, TOKEN_DECIMALS)
-    );
-    println!(
-        "  lifetime_rewards: {} ORE",
-        amount_to_ui_amount(stake.lifetime_rewards, TOKEN_DECIMALS)
-    );
-
-    Ok(())
+fn cluster_genesis_hash(cluster: &str) -> &str {
+    match cluster {
+        "mainnet" | "mainnet-beta" => "5eykt4UsFv8P8NJdTREpY1vzqKqZKvdpKuc147dw2N9d",
+        "devnet" => "EtWTRABZaYq6iMfeYKouRu166VU2xqa1wcaWoxPkrZBG",
+        "testnet" => "4uhcVJyU9pJkvQyS88uRDiswHXSCkY3zQawwpjk2NsNY",
+        other => other,
+    }
 }
 
-async fn ata(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let user = pubkey!("FgZFnb3bi7QexKCdXWPwWy91eocUD7JCFySHb83vLoPD");
-    let token = pubkey!("8H8rPiWW4iTFCfEkSnf7jpqeNpFfvdH9gLouAL3Fe2Zx");
-    let ata = get_associated_token_address(&user, &token);
-    let ix = spl_associated_token_account::instruction::create_associated_token_account(
-        &payer.pubkey(),
-        &user,
-        &token,
-        &spl_token::ID,
-    );
-    submit_transaction(rpc, payer, &[ix]).await?;
-    let account = rpc.get_account(&ata).await?;
-    println!("ATA: {}", ata);
-    println!("Account: {:?}", account);
-    Ok(())
+// 本地状态按网络隔离：mainnet 沿用当前目录下的原有文件；其他网络（devnet / testnet / 自定义 genesis hash）
+// 的奖励日志、决策记录、last-deployed、实例锁等全部写入 ore.<网络>/ 目录，切换网络不会混入 mainnet 账目。
+// 网络以 RPC 的 genesis hash 为准：启动时 network_preflight 读取 genesis hash，未设置 CLUSTER 时据此设置，
+// 与已设置的 CLUSTER 不一致时拒绝运行，因此不会把 devnet 的状态写进 mainnet 的文件。
+// 自定义网络使用完整的 genesis hash 作为目录名，只保留字母数字（不会出现 ../ 或截断后的重名）
+fn cluster_name() -> String {
+    let cluster = std::env::var("CLUSTER").unwrap_or("mainnet".to_string());
+    match cluster.trim() {
+        "mainnet" | "mainnet-beta" | "" => "mainnet".to_string(),
+        "devnet" => "devnet".to_string(),
+        "testnet" => "testnet".to_string(),
+        other => {
+            let sanitized: String = other.chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+            if sanitized.is_empty() {
+                "custom".to_string()
+            } else {
+                sanitized
+            }
+        }
+    }
 }
 
-async fn keys() -> Result<(), anyhow::Error> {
-    let treasury_address = ore_api::state::treasury_pda().0;
-    let config_address = ore_api::state::config_pda().0;
-    let board_address = ore_api::state::board_pda().0;
-    let address = pubkey!("pqspJ298ryBjazPAr95J9sULCVpZe3HbZTWkbC1zrkS");
-    let miner_address = ore_api::state::miner_pda(address).0;
-    println!("Treasury: {}", treasury_address);
-    println!("Config: {}", config_address);
-    println!("Board: {}", board_address);
-    println!("Miner: {}", miner_address);
-    Ok(())
+fn state_path(name: &str) -> String {
+    static STATE_DIR: std::sync::OnceLock<Option<String>> = std::sync::OnceLock::new();
+    let dir = STATE_DIR.get_or_init(|| {
+        let cluster = cluster_name();
+        if cluster == "mainnet" {
+            return None;
+        }
+        let dir = format!("ore.{}", cluster);
+        if let Err(e) = fs::create_dir_all(&dir) {
+            out!("[cluster] ⚠️  无法创建状态目录 {}: {}", dir, e);
+        }
+        Some(dir)
+    });
+    match dir {
+        Some(dir) => format!("{}/{}", dir, name),
+        None => name.to_string(),
+    }
 }
 
-async fn initialize(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let ix = ore_api::sdk::initialize(payer.pubkey());
-    submit_transaction(rpc, payer, &[ix]).await?;
-    Ok(())
+async fn network_preflight(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let genesis = rpc
+        .get_genesis_hash()
+        .await
+        .map_err(|e| CommandError::rpc(format!("无法读取 genesis hash: {}", e)))?
+        .to_string();
+    let actual = ["mainnet", "devnet", "testnet"]
+        .into_iter()
+        .find(|c| cluster_genesis_hash(c) == genesis);
+    let cluster = match std::env::var("CLUSTER") {
+        Ok(cluster) if !cluster.trim().is_empty() => cluster,
+        _ => {
+            // 未设置 CLUSTER：按 genesis hash 确定网络，本地状态写入对应目录
+            let derived = actual.unwrap_or(genesis.as_str()).to_string();
+            std::env::set_var("CLUSTER", &derived);
+            derived
+        }
+    };
+    if genesis != cluster_genesis_hash(cluster.trim()) {
+        return Err(CommandError::config(format!(
+            "RPC {} 属于 {}（genesis {}），与 CLUSTER={} 不一致；拒绝运行以免写错网络的本地状态",
+            rpc.url(),
+            actual.unwrap_or("未知网络"),
+            genesis,
+            cluster
+        ))
+        .into());
+    }
+
+    if !env_flag("NETWORK_CHECK") {
+        return Ok(());
+    }
+    if let Err(e) = rpc.get_health().await {
+        return Err(CommandError::rpc(format!("RPC 节点 {} 报告不健康（getHealth）: {}", rpc.url(), e)).into());
+    }
+
+    match rpc.get_account(&ore_api::ID).await {
+        Ok(account) if account.executable => Ok(()),
+        Ok(_) => Err(CommandError::config(format!("{} 在该网络上不是可执行程序", ore_api::ID)).into()),
+        Err(_) => Err(CommandError::config(format!(
+            "ORE 程序 {} 在 {}（{}）上不存在",
+            ore_api::ID,
+            cluster,
+            rpc.url()
+        ))
+        .into()),
+    }
 }
 
-async fn claim(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let ix_sol = ore_api::sdk::claim_sol(payer.pubkey());
-    let ix_ore = ore_api::sdk::claim_ore(payer.pubkey());
-    submit_transaction(rpc, payer, &[ix_sol, ix_ore]).await?;
-    Ok(())
+// ============ 新增：退出码与机器可读结果 ============
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExitStatus {
+    Success,
+    Failure,
+    ConfigError,
+    RpcError,
+    TxFailed,
+    NothingToDo,
 }
 
-async fn bury(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let amount_str = std::env::var("AMOUNT").expect("Missing AMOUNT env var");
-    let amount_f64 = f64::from_str(&amount_str).expect("Invalid AMOUNT");
-    let amount_u64 = ui_amount_to_amount(amount_f64, TOKEN_DECIMALS);
-    let wrap_ix = ore_api::sdk::wrap(payer.pubkey());
-    let bury_ix = ore_api::sdk::bury(payer.pubkey(), amount_u64);
-    simulate_transaction(rpc, payer, &[wrap_ix, bury_ix]).await;
-    Ok(())
+impl ExitStatus {
+    fn code(self) -> i32 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::Failure => 1,
+            ExitStatus::ConfigError => 2,
+            ExitStatus::RpcError => 3,
+            ExitStatus::TxFailed => 4,
+            ExitStatus::NothingToDo => 5,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ExitStatus::Success => "ok",
+            ExitStatus::Failure => "error",
+            ExitStatus::ConfigError => "config_error",
+            ExitStatus::RpcError => "rpc_error",
+            ExitStatus::TxFailed => "tx_failed",
+            ExitStatus::NothingToDo => "nothing_to_do",
+        }
+    }
 }
 
-async fn reset(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let board = get_board(rpc).await?;
-    let config = get_config(rpc).await?;
-    let slot_hashes = get_slot_hashes(rpc).await?;
-    if let Some(slot_hash) = slot_hashes.get(&board.end_slot) {
-        let id = get_winning_square(&slot_hash.to_bytes());
-        println!("Winning square: {}", id);
-    };
-    let reset_ix = ore_api::sdk::reset(
-        payer.pubkey(),
-        config.fee_collector,
-        board.round_id,
-        Pubkey::default(),
-    );
-    submit_transaction(rpc, payer, &[reset_ix]).await?;
-    Ok(())
+// 命令主动返回的带退出码的错误
+#[derive(Debug)]
+struct CommandError {
+    status: ExitStatus,
+    message: String,
 }
 
-async fn deploy(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let amount = std::env::var("AMOUNT").expect("Missing AMOUNT env var");
-    let amount = u64::from_str(&amount).expect("Invalid AMOUNT");
-    let square_id = std::env::var("SQUARE").expect("Missing SQUARE env var");
-    let square_id = u64::from_str(&square_id).expect("Invalid SQUARE");
-    let board = get_board(rpc).await?;
-    let mut squares = [false; 25];
-    squares[square_id as usize] = true;
-    let ix = ore_api::sdk::deploy(
-        payer.pubkey(),
-        payer.pubkey(),
-        amount,
-        board.round_id,
-        squares,
-    );
-    submit_transaction(rpc, payer, &[ix]).await?;
-    Ok(())
+impl CommandError {
+    fn config(message: impl Into<String>) -> Self {
+        Self {
+            status: ExitStatus::ConfigError,
+            message: message.into(),
+        }
+    }
+
+    fn rpc(message: impl Into<String>) -> Self {
+        Self {
+            status: ExitStatus::RpcError,
+            message: message.into(),
+        }
+    }
+
+    fn nothing_to_do(message: impl Into<String>) -> Self {
+        Self {
+            status: ExitStatus::NothingToDo,
+            message: message.into(),
+        }
+    }
 }
 
-async fn deploy_all(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let amount = std::env::var("AMOUNT").expect("Missing AMOUNT env var");
-    let amount = u64::from_str(&amount).expect("Invalid AMOUNT");
-    let board = get_board(rpc).await?;
-    let squares = [true; 25];
-    let ix = ore_api::sdk::deploy(
-        payer.pubkey(),
-        payer.pubkey(),
-        amount,
-        board.round_id,
-        squares,
-    );
-    submit_transaction(rpc, payer, &[ix]).await?;
-    Ok(())
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
 }
 
-// ============ 新增：自动挖矿 ============
+impl std::error::Error for CommandError {}
 
-fn read_auto_params_from_env() -> (u64, f64, usize, usize, usize) {
-    // 下注金额（lamports），优先 AMOUNT，否则 0
-    let amount_lamports: u64 = std::env::var("AMOUNT")
-        .ok()
-        .and_then(|s| s.parse::<u64>().ok())
-        .unwrap_or(0);
+fn classify_error(err: &anyhow::Error) -> ExitStatus {
+    if let Some(e) = err.downcast_ref::<CommandError>() {
+        return e.status;
+    }
+    if let Some(e) = err.downcast_ref::<solana_client::client_error::ClientError>() {
+        return if is_transaction_rejection(e) {
+            ExitStatus::TxFailed
+        } else {
+            ExitStatus::RpcError
+        };
+    }
+    ExitStatus::Failure
+}
 
-    // 阈值（SOL）
-    let threshold_sol: f64 = std::env::var("THRESHOLD_SOL")
-        .ok()
-        .and_then(|s| s.parse::<f64>().ok())
-        .or_else(|| {
-            // 从 ore.config.json 中（已在 load 中设置 env）
-            None
-        })
-        .unwrap_or(0.01);
+// 读取必填环境变量；缺失时返回配置错误（退出码 2）
+fn required_env(key: &str) -> Result<String, CommandError> {
+    std::env::var(key).map_err(|_| CommandError::config(format!("Missing {} env var", key)))
+}
 
-    // 最少满足条件的格子数量
-    let min_squares_required: usize = std::env::var("MIN_SQUARES_REQUIRED")
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(12);
+// 读取并解析必填环境变量
+fn parse_env<T: FromStr>(key: &str) -> Result<T, CommandError> {
+    let value = required_env(key)?;
+    value
+        .trim()
+        .parse::<T>()
+        .map_err(|_| CommandError::config(format!("Invalid {}: {}", key, value)))
+}
 
-    // 选择的格子数量
-    let pick_squares: usize = std::env::var("PICK_SQUARES")
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(5);
+// 读取并解析可选环境变量：未设置时为 None，设置了但无法解析时为配置错误
+fn parse_env_opt<T: FromStr>(key: &str) -> Result<Option<T>, CommandError> {
+    match std::env::var(key) {
+        Ok(_) => parse_env(key).map(Some),
+        Err(_) => Ok(None),
+    }
+}
 
-    // 最大循环次数
-    let max_loops: usize = std::env::var("MAX_LOOPS")
-        .ok()
-        .and_then(|s| s.parse::<usize>().ok())
-        .unwrap_or(100);
+// 发送交易类命令的结果：已上链的交易签名
+#[derive(Debug, Default, Serialize)]
+struct TxResult {
+    signatures: Vec<String>,
+}
 
-    (amount_lamports, threshold_sol, min_squares_required, pick_squares, max_loops)
+// 命令的返回值作为结果对象的 result 字段；没有返回值的命令为 null
+fn command_output<T: Serialize>(value: T) -> Result<serde_json::Value, anyhow::Error> {
+    Ok(serde_json::to_value(value)?)
 }
 
-// 算法类型枚举
-enum SquareSelectionAlgorithm {
-    Threshold,  // 阈值算法（原算法）
-    Optimized,  // 最优化算法（新算法）
+// OUTPUT=json（FOLLOW 的事件流除外）时标准输出只留给最终的结果对象
+fn json_result_mode() -> bool {
+    static MODE: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *MODE.get_or_init(|| std::env::var("OUTPUT").map(|o| o == "json").unwrap_or(false) && !env_flag("FOLLOW"))
 }
 
-const REWARD_LOG_FILE: &str = "reward.log";
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum OutputTarget {
+    Stdout,
+    Stderr,
+    Discard,
+}
 
-fn append_reward_log(message: &str) {
-    let timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
-    if let Ok(mut file) = OpenOptions::new()
-        .create(true)
-        .append(true)
-        .open(REWARD_LOG_FILE)
-    {
-        let _ = writeln!(file, "[{}] {}", timestamp, message);
-    }
+// out! / out_inline! 的输出目标，只计算一次
+fn output_target() -> OutputTarget {
+    static TARGET: std::sync::OnceLock<OutputTarget> = std::sync::OnceLock::new();
+    *TARGET.get_or_init(|| match (json_result_mode(), env_flag("QUIET")) {
+        (false, _) => OutputTarget::Stdout,
+        (true, false) => OutputTarget::Stderr,
+        (true, true) => OutputTarget::Discard,
+    })
 }
 
-async fn auto_mine(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-    algorithm: SquareSelectionAlgorithm,
-) -> Result<(), anyhow::Error> {
-    let (amount_lamports, threshold_sol, min_squares_required, pick_squares, max_loops) =
-        read_auto_params_from_env();
-    if amount_lamports == 0 {
-        println!("[auto] AMOUNT/AMOUNT_SOL 未设置或为 0，退出。");
-        return Ok(());
+// 输出命令结果并以对应退出码结束：OUTPUT=json 时输出一个 JSON 结果对象，否则打印错误信息
+fn finish_command(command: &str, result: Result<serde_json::Value, anyhow::Error>) {
+    let status = match &result {
+        Ok(_) => ExitStatus::Success,
+        Err(e) => classify_error(e),
+    };
+    if json_result_mode() {
+        let mut out = serde_json::json!({
+            "command": command,
+            "ok": status == ExitStatus::Success,
+            "status": status.label(),
+            "exit_code": status.code(),
+        });
+        match &result {
+            Ok(value) => out["result"] = value.clone(),
+            Err(e) => out["error"] = serde_json::json!(format!("{:#}", e)),
+        }
+        std::println!("{}", out);
+    } else if let Err(e) = &result {
+        if status == ExitStatus::NothingToDo {
+            out!("[info] {}", e);
+        } else {
+            eprintln!("[error] {}: {:?}", status.label(), e);
+        }
     }
+    if status != ExitStatus::Success {
+        std::process::exit(status.code());
+    }
+}
 
-    let mut processed_round: Option<u64> = None;
-    // 保存本轮部署信息：round_id -> (格子数量, 花费 SOL)
-    let mut round_deployment_info: Option<(u64, usize, u64)> = None;
-    let mut loops_done: usize = 0;
-    let mut total_spent: u128 = 0;
+// ============ 新增：二进制自更新 ============
+// SELF_UPDATE_URL 指向发布清单（JSON）：
+//   {"version": "1.4.0", "assets": {"x86_64-linux": {"url": "...", "sha256": "<hex>", "signature": "<base58>"}}}
+// 按本机 <arch>-<os> 选择二进制。signature 为 SELF_UPDATE_PUBKEY 对 "ore-release:v1:<version>:<target>:<sha256>"
+// 的 ed25519 签名（见 release_message），下载前先校验，版本号、目标平台与 sha256 因此都经过发布者认证：
+// 清单不能把旧版本标成新版本，也不能换成其他平台的二进制；下载后再校验二进制的 sha256。
+// 未设置公钥时拒绝更新，除非显式设置 SELF_UPDATE_ALLOW_UNSIGNED=true。校验通过后写入 <exe>.new 并原子替换，
+// 旧版本保留为 <exe>.old。版本不高于当前版本时不更新（SELF_UPDATE_FORCE=true 强制）；DRY_RUN 只下载校验不替换
+fn release_message(version: &str, target: &str, sha256: &str) -> String {
+    format!("ore-release:v1:{}:{}:{}", version.trim(), target.trim(), sha256.trim().to_lowercase())
+}
 
-    // 持久化记录已部署轮次，避免重复部署
-    const LAST_DEPLOYED_ROUND_FILE: &str = "ore.last_deployed_round";
-    let read_last_deployed_round = || -> Option<u64> {
-        fs::read_to_string(LAST_DEPLOYED_ROUND_FILE)
-            .ok()
-            .and_then(|s| s.trim().parse::<u64>().ok())
-    };
-    let write_last_deployed_round = |round_id: u64| {
-        let _ = fs::write(LAST_DEPLOYED_ROUND_FILE, round_id.to_string());
+
+fn parse_version(version: &str) -> Vec<u64> {
+    version
+        .trim()
+        .trim_start_matches('v')
+        .split(['.', '-', '+'])
+        .map_while(|part| part.parse::<u64>().ok())
+        .collect()
+}
+
+async fn self_update() -> Result<(), anyhow::Error> {
+    let url = std::env::var("SELF_UPDATE_URL")
+        .map_err(|_| CommandError::config("self_update 需要设置 SELF_UPDATE_URL（发布清单地址）"))?;
+    let current = env!("CARGO_PKG_VERSION");
+    let target = std::env::var("SELF_UPDATE_TARGET")
+        .unwrap_or(format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS));
+    let publisher = match std::env::var("SELF_UPDATE_PUBKEY") {
+        Ok(pubkey) => Some(
+            Pubkey::from_str(pubkey.trim())
+                .map_err(|_| CommandError::config(format!("SELF_UPDATE_PUBKEY 不是有效的公钥: {}", pubkey)))?,
+        ),
+        Err(_) if env_flag("SELF_UPDATE_ALLOW_UNSIGNED") => None,
+        Err(_) => {
+            return Err(CommandError::config(
+                "self_update 需要设置 SELF_UPDATE_PUBKEY（发布者公钥）以校验签名；确需跳过请设置 SELF_UPDATE_ALLOW_UNSIGNED=true",
+            )
+            .into())
+        }
     };
-    let clear_last_deployed_round = || {
-        let _ = std::fs::remove_file(LAST_DEPLOYED_ROUND_FILE);
+    let client = solana_client::client_error::reqwest::Client::new();
+
+    let request = client.get(&url).timeout(Duration::from_secs(30));
+    let response = with_env_headers(request, "SELF_UPDATE_HEADERS").send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("发布清单返回 HTTP {}", response.status()));
+    }
+    let manifest: serde_json::Value = response.json().await?;
+    let latest = manifest["version"]
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("发布清单缺少 version 字段"))?;
+    let asset = &manifest["assets"][&target];
+    let (Some(asset_url), Some(expected_sha256)) = (asset["url"].as_str(), asset["sha256"].as_str()) else {
+        return Err(CommandError::config(format!("版本 {} 没有 {} 的二进制（或缺少 url / sha256）", latest, target)).into());
     };
+    match publisher {
+        Some(pubkey) => {
+            let signature = asset["signature"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("清单中 {} 没有 signature，拒绝更新", target))?;
+            let signature = solana_sdk::signature::Signature::from_str(signature.trim())?;
+            let message = release_message(latest, &target, expected_sha256);
+            if !signature.verify(pubkey.as_ref(), message.as_bytes()) {
+                return Err(anyhow::anyhow!("签名校验失败：{} 不是由 {} 签发", message, pubkey));
+            }
+            out!("[update] ✅ 发布签名校验通过（{}）", pubkey);
+        }
+        None => out!("[update] ⚠️  SELF_UPDATE_ALLOW_UNSIGNED：未校验发布者签名，版本号与 sha256 均未经认证"),
+    }
+    out!("[update] 当前版本 {}，最新版本 {}（{}）", current, latest, target);
+    if parse_version(latest) <= parse_version(current) && !env_flag("SELF_UPDATE_FORCE") {
+        return Err(CommandError::nothing_to_do(format!("已是最新版本 {}", current)).into());
+    }
 
-    loop {
-        if loops_done >= max_loops { break; }
+    let request = client.get(asset_url).timeout(Duration::from_secs(300));
+    let response = with_env_headers(request, "SELF_UPDATE_HEADERS").send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("下载 {} 返回 HTTP {}", asset_url, response.status()));
+    }
+    let binary = response.bytes().await?.to_vec();
+    out!("[update] 已下载 {} 字节", binary.len());
+
+    let sha256: String = solana_sdk::hash::hash(&binary)
+        .to_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    if !sha256.eq_ignore_ascii_case(expected_sha256.trim()) {
+        return Err(anyhow::anyhow!("sha256 不匹配：期望 {}，实际 {}", expected_sha256, sha256));
+    }
+    out!("[update] ✅ sha256 校验通过");
 
-        // 使用重试机制处理 RPC 错误，避免因网络问题导致程序崩溃
-        let board = match get_board(rpc).await {
-            Ok(b) => b,
-            Err(e) => {
-                println!("[auto] ⚠️  读取 Board 失败: {:?}，等待 2 秒后重试...", e);
-                sleep(Duration::from_secs(2)).await;
-                continue;
-            }
-        };
+    if env_flag("DRY_RUN") {
+        out!("[update] DRY_RUN：校验通过，不替换当前二进制");
+        return Ok(());
+    }
+    let exe = std::env::current_exe()?;
+    let staged = exe.with_extension("new");
+    let backup = exe.with_extension("old");
+    fs::write(&staged, &binary)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    }
+    fs::copy(&exe, &backup)?;
+    fs::rename(&staged, &exe)?;
+    out!(
+        "[update] ✅ 已更新到 {}：{}（旧版本保留在 {}）",
+        latest,
+        exe.display(),
+        backup.display()
+    );
+    Ok(())
+}
 
-        let clock = match get_clock(rpc).await {
-            Ok(c) => c,
-            Err(e) => {
-                println!("[auto] ⚠️  读取 Clock 失败: {:?}，等待 2 秒后重试...", e);
-                sleep(Duration::from_secs(2)).await;
-                continue;
-            }
-        };
-        let current_slot = clock.slot;
 
-        // 数据一致性验证：确保 Board 和 Clock 数据是有效的
-        if board.end_slot <= board.start_slot {
-            println!("[auto] ⚠️  警告：Board 数据异常 (start_slot={} >= end_slot={})，等待 2 秒后重试...",
-                board.start_slot, board.end_slot);
-            sleep(Duration::from_secs(2)).await;
+async fn participating_miners(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let round_id: u64 = parse_env("ID")?;
+    // 只需要 authority 字段：通过 dataSlice 只取 32 字节，避免下载整个 Miner 账户
+    let filter = RpcFilterType::Memcmp(Memcmp::new_base58_encoded(512, &round_id.to_le_bytes()));
+    let miners =
+        get_program_accounts_sliced::<Miner>(rpc, ore_api::ID, vec![filter], 8, 32).await?;
+    for (i, (_address, data)) in miners.iter().enumerate() {
+        let Ok(authority) = Pubkey::try_from(data.as_slice()) else {
             continue;
-        }
-
-        // 使用项目原始代码中的简单计算方法（与 print_board 保持一致）
-        let slot_diff = if board.end_slot > current_slot {
-            board.end_slot.saturating_sub(current_slot)
-        } else {
-            0
         };
-        let secs_left = (slot_diff as f64) * 0.4;
+        out!("{}: {}", i, authority);
+    }
+    Ok(())
+}
 
-        // 输出状态
-        println!(
-            "[auto] round={} 剩余 {} slots ({:.2}s)，等待触发阈值（< START_BEFORE_SECONDS）",
-            board.round_id, slot_diff, secs_left
-        );
+// ORE 以最小单位计；奖励因子为十进制字符串
+#[derive(Debug, Serialize)]
+struct StakeResult {
+    address: String,
+    authority: String,
+    balance: u64,
+    last_claim_at: i64,
+    last_deposit_at: i64,
+    last_withdraw_at: i64,
+    rewards_factor: String,
+    rewards: u64,
+    lifetime_rewards: u64,
+}
 
-        let start_before_seconds: f64 = std::env::var("START_BEFORE_SECONDS")
-            .ok()
-            .and_then(|s| s.parse::<f64>().ok())
-            .unwrap_or(40.0);
+async fn log_stake(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<StakeResult, anyhow::Error> {
+    let authority = parse_env_opt::<Pubkey>("AUTHORITY")?.unwrap_or(mining_authority(payer).pubkey());
+    let staker_address = ore_api::state::stake_pda(authority).0;
+    let stake = get_stake(rpc, authority).await?;
+    out!("Stake");
+    out!("  address: {}", staker_address);
+    out!("  authority: {}", authority);
+    out!(
+        "  balance: {}",
+        fmt_ore(stake.balance)
+    );
+    out!("  last_claim_at: {}", stake.last_claim_at);
+    out!("  last_deposit_at: {}", stake.last_deposit_at);
+    out!("  last_withdraw_at: {}", stake.last_withdraw_at);
+    out!(
+        "  rewards_factor: {}",
+        stake.rewards_factor.to_i80f48().to_string()
+    );
+    out!(
+        "  rewards: {}",
+        fmt_ore(stake.rewards)
+    );
+    out!(
+        "  lifetime_rewards: {}",
+        fmt_ore(stake.lifetime_rewards)
+    );
 
-        if secs_left <= start_before_seconds {
-            // 读取持久化记录，避免同一轮次重复部署（即使进程重启）
-            let persisted_last = read_last_deployed_round();
-            if processed_round == Some(board.round_id) || persisted_last == Some(board.round_id) {
-                // 已成功部署过该回合，等待下一回合，跳过所有读取和判定
-                if let Some((round_id, square_count, cost_lamports)) = round_deployment_info {
-                    if round_id == board.round_id {
-                        println!("[auto] 本轮 (round={}) 已部署完成：{} 个格子，花费 {:.6} SOL，等待下一轮...", 
-                            board.round_id, square_count, lamports_to_sol(cost_lamports));
-                    } else {
-                        println!("[auto] 本轮 (round={}) 已部署完成，等待下一轮...", board.round_id);
-                    }
-                } else {
-                    println!("[auto] 本轮 (round={}) 已部署完成，等待下一轮...", board.round_id);
-                }
-            } else {
-                // 未成功部署，继续读取棋盘格并判定
-                // 获取当前回合部署分布（使用重试机制）
-                let round = match get_round(rpc, board.round_id).await {
-                    Ok(r) => {
-                        // 立即验证 round_id 一致性，避免使用过时的 Round 数据
-                        if r.id != board.round_id {
-                            println!("[auto] ⚠️  Round ID 不一致 (board.round_id={}, round.id={})，可能是新回合刚启动，等待 1 秒后重试...", board.round_id, r.id);
-                            sleep(Duration::from_secs(1)).await;
-                            continue;
-                        }
-                        r
-                    }
-                    Err(e) => {
-                        println!("[auto] ⚠️  读取 Round {} 失败: {:?}，等待 1 秒后重试...", board.round_id, e);
-                        sleep(Duration::from_secs(1)).await;
-                        continue;
-                    }
-                };
-                
-                // 输出调试信息：显示当前 slot 和数据获取时间
-                println!("[auto] 数据获取时间: slot={}, 当前回合: {}", current_slot, board.round_id);
-                
-                let all_squares: Vec<(usize, f64)> = round
-                    .deployed
-                    .iter()
-                    .enumerate()
-                    .map(|(i, &lamports)| (i, lamports_to_sol(lamports)))
-                    .collect();
-                
-                // 输出所有 25 个格子的部署情况
-                println!("[auto] 当前回合所有格子的部署情况:");
-                for (square_idx, sol_amt) in &all_squares {
-                    print!("  #{}: {:.6} SOL  ", square_idx, sol_amt);
-                    if (square_idx + 1) % 5 == 0 {
-                        println!(); // 每 5 个换行，形成 5x5 网格显示
-                    }
-                }
-                if all_squares.len() % 5 != 0 {
-                    println!(); // 如果最后一行不满 5 个，也要换行
-                }
-                
-                // 根据算法类型选择格子
-                let picked = match algorithm {
-                    SquareSelectionAlgorithm::Threshold => {
-                        // 原算法：阈值算法
-                        let mut candidates: Vec<(usize, f64)> = all_squares
-                            .iter()
-                            .cloned()
-                            .filter(|(_, v_sol)| *v_sol < threshold_sol)
-                            .collect();
-                        println!(
-                            "[auto] [阈值算法] 低于阈值({:.4} SOL)的格子数量: {}",
-                            threshold_sol,
-                            candidates.len()
-                        );
-                        if candidates.len() >= min_squares_required {
-                            // 从小到大排序
-                            candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-                            let picked = candidates
-                                .into_iter()
-                                .take(pick_squares)
-                                .map(|(idx, _)| idx)
-                                .collect::<Vec<_>>();
-                            if picked.is_empty() {
-                                println!("[auto] 未选中任何格子，跳过。");
-                                None
-                            } else {
-                                Some(picked)
-                            }
-                        } else {
-                            println!("[auto] 符合阈值的格子不足 {} 个，跳过本次。", min_squares_required);
-                            None
-                        }
-                    }
-                    SquareSelectionAlgorithm::Optimized => {
-                        // 新算法：最优化算法
-                        // 1. 统计所有25个格子的部署总和
-                        let total_deployed: u64 = round.deployed.iter().sum();
-                        let total_deployed_sol = lamports_to_sol(total_deployed);
+    // 顺便记录一次奖励因子样本
+    if let Ok(treasury) = get_treasury(rpc).await {
+        append_stake_sample(&stake_sample(authority, &treasury, &stake));
+    }
 
-                        // 2. 计算阈值：(0.036 * 部署总数) - 0.005
-                        // 修复：确保运算优先级正确
-                        let threshold = (total_deployed_sol * 0.036) - 0.005;
+    Ok(StakeResult {
+        address: staker_address.to_string(),
+        authority: authority.to_string(),
+        balance: stake.balance,
+        last_claim_at: stake.last_claim_at,
+        last_deposit_at: stake.last_deposit_at,
+        last_withdraw_at: stake.last_withdraw_at,
+        rewards_factor: stake.rewards_factor.to_i80f48().to_string(),
+        rewards: stake.rewards,
+        lifetime_rewards: stake.lifetime_rewards,
+    })
+}
 
-                        println!(
-                            "[auto] [最优化算法] 所有格子部署总和: {:.6} SOL, 阈值: {:.6} SOL (0.036 * 总和 - 0.005)",
-                            total_deployed_sol, threshold
-                        );
+// ============ 新增：质押奖励因子历史 ============
 
-                        // 3. 选择所有部署数量 < (0.036 * 总和 - 0.005) 的格子
-                        let mut candidates: Vec<(usize, f64)> = all_squares
-                            .iter()
-                            .cloned()
-                            .filter(|(_, v_sol)| *v_sol < threshold)
-                            .collect();
+const STAKE_HISTORY_FILE: &str = "ore.stake_history.jsonl";
 
-                        println!(
-                            "[auto] [最优化算法] 符合条件的格子数量: {}",
-                            candidates.len()
-                        );
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StakeSample {
+    timestamp: u64,
+    authority: String,
+    treasury_stake_rewards_factor: f64,
+    stake_rewards_factor: f64,
+    balance: u64,
+    rewards: u64,
+}
 
-                        // 检查是否符合最低下限要求
-                        if candidates.len() >= min_squares_required {
-                            // 从小到大排序
-                            candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
-                            // 受 PICK_SQUARES 限制
-                            let picked = candidates
-                                .into_iter()
-                                .take(pick_squares)
-                                .map(|(idx, _)| idx)
-                                .collect::<Vec<_>>();
-                            if picked.is_empty() {
-                                println!("[auto] 未选中任何格子，跳过。");
-                                None
-                            } else {
-                                Some(picked)
-                            }
-                        } else {
-                            println!("[auto] [最优化算法] 符合条件的格子不足 {} 个，跳过本次。", min_squares_required);
-                            None
-                        }
-                    }
-                };
+impl StakeSample {
+    // 截至采样时累计的奖励：已记账的 rewards + 尚未结算的 (全局因子 - 个人因子) * 余额
+    fn accrued(&self) -> f64 {
+        let pending = (self.treasury_stake_rewards_factor - self.stake_rewards_factor).max(0.0)
+            * self.balance as f64;
+        self.rewards as f64 + pending
+    }
+}
 
-                if let Some(picked) = picked {
-                        println!("[auto] 选中格子: {:?}", picked);
-                        
-                        // 部署前检查是否需要 checkpoint
-                        // 重要：只有在满足以下条件时才执行 checkpoint：
-                        // 1. miner 所在的 round_id < 当前 board 的 round_id
-                        // 2. miner 尚未 checkpoint 到该 round
-                        // 3. 当前轮次还有充足时间部署
-                        let mut did_checkpoint = false;
-                        match get_miner(rpc, payer.pubkey()).await {
-                            Ok(miner) => {
-                                let miner_before = miner;
-                                // 修复：更严格的 checkpoint 条件检查
-                                // 只有当 miner 完全处于旧轮次时才需要 checkpoint
-                                if miner.round_id < board.round_id && miner.checkpoint_id < miner.round_id {
-                                    println!("[auto] 检测到需要 checkpoint：miner.round_id={}, checkpoint_id={}, 当前 round_id={}",
-                                        miner.round_id, miner.checkpoint_id, board.round_id);
-                                    println!("[auto] 正在执行 checkpoint...");
-                                    let checkpoint_ix = ore_api::sdk::checkpoint(
-                                        payer.pubkey(),
-                                        payer.pubkey(),
-                                        miner.round_id,
-                                    );
-                                    match submit_transaction(rpc, payer, &[checkpoint_ix]).await {
-                                        Ok(sig) => {
-                                            println!("[auto] ✅ Checkpoint 成功！交易签名: {}", sig);
-                                            if let Ok(miner_after) = get_miner(rpc, payer.pubkey()).await {
-                                                let delta_rewards_sol = miner_after
-                                                    .rewards_sol
-                                                    .saturating_sub(miner_before.rewards_sol);
-                                                let delta_rewards_ore = miner_after
-                                                    .rewards_ore
-                                                    .saturating_sub(miner_before.rewards_ore);
-                                                let delta_refined_ore = miner_after
-                                                    .refined_ore
-                                                    .saturating_sub(miner_before.refined_ore);
-                                                append_reward_log(&format!(
-                                                    "round={} event=checkpoint delta_sol={:.6} delta_rewards_ore={} delta_refined_ore={} tx={}",
-                                                    miner_before.round_id,
-                                                    lamports_to_sol(delta_rewards_sol),
-                                                    amount_to_ui_amount(
-                                                        delta_rewards_ore,
-                                                        TOKEN_DECIMALS
-                                                    ),
-                                                    amount_to_ui_amount(
-                                                        delta_refined_ore,
-                                                        TOKEN_DECIMALS
-                                                    ),
-                                                    sig
-                                                ));
-                                            }
-                                            did_checkpoint = true;
-                                        }
-                                        Err(e) => {
-                                            // Checkpoint 可能失败（例如 round 还未结束或已过期），尝试继续部署
-                                            // 如果部署时仍然失败，会在部署阶段报错
-                                            println!("[auto] ⚠️  Checkpoint 失败（可能 round 还未结束或已过期）: {:?}", e);
-                                            println!("[auto] 尝试继续部署...");
-                                        }
-                                    }
-                                } else if miner.round_id == board.round_id && miner.checkpoint_id < miner.round_id {
-                                    // 同一轮但未 checkpoint，这种情况不需要 checkpoint，可以直接部署
-                                    println!("[auto] Miner 已在当前轮次，无需 checkpoint，直接部署");
-                                }
-                            }
-                            Err(e) => {
-                                println!("[auto] 警告：无法读取 Miner 账户: {:?}，继续尝试部署", e);
-                            }
-                        }
-                        // 如果刚刚执行了 checkpoint，则跳过本次部署，进入下一循环刷新最新的 board/round 状态
-                        if did_checkpoint {
-                            println!("[auto] 已完成 checkpoint，本次不部署，等待状态刷新...");
-                            continue;
-                        }
-                        
-                        // 部署前再次验证 Board/Round 一致性，并尽量使用最新快照，降低竞态
-                        let latest_board = match get_board(rpc).await {
-                            Ok(b) => b,
-                            Err(e) => {
-                                println!("[auto] 警告：读取 Board 失败: {:?}，跳过本次部署", e);
-                                continue;
-                            }
-                        };
+fn stake_sample(authority: Pubkey, treasury: &Treasury, stake: &Stake) -> StakeSample {
+    StakeSample {
+        timestamp: chain_now(),
+        authority: authority.to_string(),
+        treasury_stake_rewards_factor: treasury.stake_rewards_factor.to_i80f48().to_num::<f64>(),
+        stake_rewards_factor: stake.rewards_factor.to_i80f48().to_num::<f64>(),
+        balance: stake.balance,
+        rewards: stake.rewards,
+    }
+}
+
+fn append_stake_sample(sample: &StakeSample) {
+    let Ok(line) = serde_json::to_string(sample) else {
+        return;
+    };
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(STAKE_HISTORY_FILE))
+    {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+fn read_stake_samples(authority: &Pubkey) -> Vec<StakeSample> {
+    let authority = authority.to_string();
+    fs::read_to_string(state_path(STAKE_HISTORY_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<StakeSample>(line).ok())
+        .filter(|s| s.authority == authority)
+        .collect()
+}
+
+// 定期采样：每 STAKE_RECORD_INTERVAL_SECS 秒记录一次（默认 1 小时）
+async fn stake_record(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let authority = parse_env_opt::<Pubkey>("AUTHORITY")?.unwrap_or(mining_authority(payer).pubkey());
+    let interval_secs: u64 = std::env::var("STAKE_RECORD_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(3600);
+    loop {
+        match (get_treasury(rpc).await, get_stake(rpc, authority).await) {
+            (Ok(treasury), Ok(stake)) => {
+                let sample = stake_sample(authority, &treasury, &stake);
+                out!(
+                    "[stake] 已记录：余额 {}，累计奖励约 {}",
+                    fmt_ore(sample.balance),
+                    fmt_ore(sample.accrued() as u64)
+                );
+                append_stake_sample(&sample);
+            }
+            (Err(e), _) | (_, Err(e)) => {
+                out!("[stake] ⚠️  读取 Treasury/Stake 失败: {:?}，下次再试", e);
+            }
+        }
+        sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+fn log_stake_history() -> Result<(), anyhow::Error> {
+    // 纯本地命令：只有未设置 AUTHORITY 时才读取密钥文件确定 authority
+    let authority = match parse_env_opt::<Pubkey>("AUTHORITY")? {
+        Some(authority) => authority,
+        None => {
+            load_authority_signer()?;
+            let payer = payer_signer_from_env()?;
+            mining_authority(payer.as_ref()).pubkey()
+        }
+    };
+    let samples = read_stake_samples(&authority);
+    if samples.is_empty() {
+        return Err(CommandError::nothing_to_do(format!(
+            "没有 {} 的质押历史样本，请先运行 stake 或 stake_record",
+            authority
+        ))
+        .into());
+    }
+    out!("Stake history ({})", authority);
+    out!(
+        "  {:>12}  {:>16}  {:>16}  {:>16}",
+        "timestamp", "balance (ORE)", "accrued (ORE)", "delta (ORE)"
+    );
+    let mut prev: Option<&StakeSample> = None;
+    for sample in samples.iter() {
+        let delta = prev
+            .map(|p| (sample.accrued() - p.accrued()).max(0.0))
+            .unwrap_or(0.0);
+        out!(
+            "  {:>12}  {:>16}  {:>16}  {:>16}",
+            sample.timestamp,
+            amount_to_ui_amount(sample.balance, TOKEN_DECIMALS),
+            amount_to_ui_amount(sample.accrued() as u64, TOKEN_DECIMALS),
+            amount_to_ui_amount(delta as u64, TOKEN_DECIMALS)
+        );
+        prev = Some(sample);
+    }
+    if let (Some(first), Some(last)) = (samples.first(), samples.last()) {
+        let days = (last.timestamp.saturating_sub(first.timestamp)) as f64 / 86400.0;
+        let total = (last.accrued() - first.accrued()).max(0.0);
+        out!(
+            "  合计：{:.2} 天内累计 {}{}",
+            days,
+            fmt_ore(total as u64),
+            if days > 0.0 {
+                format!("（约 {} ORE/天）", amount_to_ui_amount((total / days) as u64, TOKEN_DECIMALS))
+            } else {
+                String::new()
+            }
+        );
+    }
+    Ok(())
+}
+
+async fn ata(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let user = known_address("ATA_USER", DEFAULT_ATA_USER);
+    let token = known_address("ATA_TOKEN", DEFAULT_ATA_TOKEN);
+    let ata = get_associated_token_address(&user, &token);
+    let ix = spl_associated_token_account::instruction::create_associated_token_account(
+        &payer.pubkey(),
+        &user,
+        &token,
+        &spl_token::ID,
+    );
+    submit_transaction(rpc, payer, &[ix]).await?;
+    let account = rpc.get_account(&ata).await?;
+    out!("ATA: {}", ata);
+    out!("Account: {:?}", account);
+    Ok(())
+}
+
+async fn keys() -> Result<(), anyhow::Error> {
+    let treasury_address = ore_api::state::treasury_pda().0;
+    let config_address = ore_api::state::config_pda().0;
+    let board_address = ore_api::state::board_pda().0;
+    let address = pubkey!("pqspJ298ryBjazPAr95J9sULCVpZe3HbZTWkbC1zrkS");
+    let miner_address = ore_api::state::miner_pda(address).0;
+    out!("Treasury: {}", treasury_address);
+    out!("Config: {}", config_address);
+    out!("Board: {}", board_address);
+    out!("Miner: {}", miner_address);
+    Ok(())
+}
+
+async fn initialize(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let ix = ore_api::sdk::initialize(payer.pubkey());
+    submit_transaction(rpc, payer, &[ix]).await?;
+    Ok(())
+}
+
+// 领取结果：kind 为 sol / ore / sol+ore；DRY_RUN 时 signature 为空
+#[derive(Debug, Serialize)]
+struct ClaimResult {
+    kind: String,
+    signature: Option<String>,
+}
+
+async fn claim(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<ClaimResult, anyhow::Error> {
+    if env_flag("CLAIM_AUTO") {
+        return claim_auto(rpc, payer).await;
+    }
+    let authority = mining_authority(payer).pubkey();
+    let mut ixs = vec![ore_api::sdk::claim_sol(authority), ore_api::sdk::claim_ore(authority)];
+    ixs.extend(memo_instruction(&[("claim", "sol+ore".to_string())]));
+    let sig = submit_transaction(rpc, payer, &ixs).await?;
+    print_claim_receipt(rpc, "sol+ore", &sig);
+    Ok(ClaimResult {
+        kind: "sol+ore".to_string(),
+        signature: Some(sig.to_string()),
+    })
+}
+
+// ============ 新增：claim 手续费效率（CLAIM_AUTO） ============
+// 可领取价值不足预期交易费的 CLAIM_FEE_MULTIPLE 倍时不领取，避免为零头付手续费；
+// ORE 按 Meteora 中间价折算成 SOL，首次领取 ORE 还要计入 ATA 租金。
+// SOL 与 ORE 都达标时 claim_sol + 建 ATA + claim_ore 合并为一笔交易，只付一次手续费
+
+// 预期手续费：每个签名 5000 lamports 基础费 + 按 CU 上限计收的优先费
+fn expected_tx_fee_lamports(signatures: u64) -> u64 {
+    let compute_unit_price: u64 = std::env::var("COMPUTE_UNIT_PRICE")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(1_000);
+    tx_fee_lamports(signatures, compute_unit_price)
+}
+
+fn tx_fee_lamports(signatures: u64, compute_unit_price: u64) -> u64 {
+    let compute_unit_limit: u64 = std::env::var("COMPUTE_UNIT_LIMIT")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(1_400_000);
+    signatures * 5_000 + compute_unit_price.saturating_mul(compute_unit_limit) / 1_000_000
+}
+
+async fn claim_auto(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<ClaimResult, anyhow::Error> {
+    use solana_sdk::program_pack::Pack;
+
+    let dry_run = env_flag("DRY_RUN");
+    let multiple = env_f64("CLAIM_FEE_MULTIPLE", 10.0).max(1.0);
+    let authority = mining_authority(payer).pubkey();
+    let miner = MinerView::new(get_miner(rpc, authority).await?);
+    let claimable_sol = miner.claimable_sol_lamports();
+    let claimable_ore = miner.claimable_ore_amount();
+
+    let signatures = if authority == payer.pubkey() { 1 } else { 2 };
+    let fee = expected_tx_fee_lamports(signatures);
+
+    let ore_ata = get_associated_token_address(&authority, &MINT_ADDRESS);
+    let ata_missing = rpc.get_account(&ore_ata).await.is_err();
+    let ata_rent = if ata_missing {
+        rpc.get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+            .await
+            .unwrap_or(2_039_280)
+    } else {
+        0
+    };
+
+    // ORE 折算成 SOL：取不到价格时按 0 处理（不领 ORE）
+    let ore_value = if claimable_ore > 0 {
+        match get_meteora_reserves(rpc, known_address("METEORA_POOL", DEFAULT_METEORA_POOL)).await {
+            Ok(reserves) => sol_to_lamports(reserves.mid_price() * miner.claimable_ore()),
+            Err(e) => {
+                out!("[claim] ⚠️  获取 ORE 价格失败，本次不领取 ORE: {}", e);
+                0
+            }
+        }
+    } else {
+        0
+    };
+
+    let sol_threshold = (fee as f64 * multiple) as u64;
+    let ore_threshold = ((fee + ata_rent) as f64 * multiple) as u64;
+    let claim_sol = claimable_sol > 0 && claimable_sol >= sol_threshold;
+    let claim_ore = ore_value > 0 && ore_value >= ore_threshold;
+
+    out!(
+        "[claim] 预期手续费 {}，要求可领价值 ≥ {:.1} 倍{}",
+        fmt_sol(fee),
+        multiple,
+        if ata_missing { "（ORE 另计 ATA 租金）" } else { "" }
+    );
+    out!(
+        "[claim] SOL: 可领 {}，阈值 {} -> {}",
+        fmt_sol(claimable_sol),
+        fmt_sol(sol_threshold),
+        if claim_sol { "领取" } else { "跳过" }
+    );
+    out!(
+        "[claim] ORE: 可领 {} ≈ {}，阈值 {} -> {}",
+        fmt_ore(claimable_ore),
+        fmt_sol(ore_value),
+        fmt_sol(ore_threshold),
+        if claim_ore { "领取" } else { "跳过" }
+    );
+
+    if !claim_sol && !claim_ore {
+        return Err(CommandError::nothing_to_do(format!(
+            "可领取价值不足手续费的 {} 倍，继续累积后再领取（CLAIM_FEE_MULTIPLE 可调）",
+            multiple
+        ))
+        .into());
+    }
+    let kind = match (claim_sol, claim_ore) {
+        (true, true) => "sol+ore",
+        (true, false) => "sol",
+        _ => "ore",
+    };
+    if dry_run {
+        out!("[claim] 预览模式（DRY_RUN），不会发送交易");
+        return Ok(ClaimResult {
+            kind: kind.to_string(),
+            signature: None,
+        });
+    }
+
+    let mut ixs = vec![];
+    if claim_sol {
+        ixs.push(ore_api::sdk::claim_sol(authority));
+    }
+    if claim_ore {
+        if ata_missing {
+            ixs.push(
+                spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                    &payer.pubkey(),
+                    &authority,
+                    &MINT_ADDRESS,
+                    &spl_token::ID,
+                ),
+            );
+        }
+        ixs.push(ore_api::sdk::claim_ore(authority));
+    }
+    ixs.extend(memo_instruction(&[("claim", kind.to_string())]));
+    let sig = submit_transaction(rpc, payer, &ixs).await?;
+    print_claim_receipt(rpc, kind, &sig);
+    Ok(ClaimResult {
+        kind: kind.to_string(),
+        signature: Some(sig.to_string()),
+    })
+}
+
+// ============ 新增：claim 回执（浏览器链接 / 终端二维码） ============
+
+fn explorer_tx_url(rpc: &RpcClient, sig: &solana_sdk::signature::Signature) -> String {
+    let url = rpc.url().to_lowercase();
+    let cluster = if url.contains("devnet") || cluster_name() == "devnet" {
+        "?cluster=devnet"
+    } else if url.contains("testnet") || cluster_name() == "testnet" {
+        "?cluster=testnet"
+    } else {
+        ""
+    };
+    format!("https://explorer.solana.com/tx/{}{}", sig, cluster)
+}
+
+fn print_claim_receipt(rpc: &RpcClient, kind: &str, sig: &solana_sdk::signature::Signature) {
+    let link = explorer_tx_url(rpc, sig);
+    out!("[claim] ✅ 领取 ({}) 已上链: {}", kind, link);
+    append_reward_event(RewardEvent {
+        kind: Some(kind.to_string()),
+        tx: Some(sig.to_string()),
+        explorer: Some(link.clone()),
+        ..RewardEvent::new("claim")
+    });
+    if env_flag("CLAIM_RECEIPT_QR") {
+        print_terminal_qr(&link);
+    }
+}
+
+// 终端二维码交给系统的 qrencode 命令渲染，未安装时只保留链接
+fn print_terminal_qr(text: &str) {
+    match std::process::Command::new("qrencode")
+        .args(["-t", "ANSIUTF8", text])
+        .status()
+    {
+        Ok(status) if status.success() => {}
+        _ => out!("[warn] 无法渲染二维码（需要安装 qrencode 命令），请直接使用上面的链接。"),
+    }
+}
+
+async fn bury(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let amount_f64: f64 = parse_env("AMOUNT")?;
+    let amount_u64 = ui_amount_to_amount(amount_f64, TOKEN_DECIMALS);
+    if let Some(format) = admin_payload_format()? {
+        let vault = multisig_vault()?;
+        let ixs = [ore_api::sdk::wrap(vault), ore_api::sdk::bury(vault, amount_u64)];
+        return print_admin_payload(rpc, format, vault, "bury", &ixs).await;
+    }
+    let wrap_ix = ore_api::sdk::wrap(payer.pubkey());
+    let bury_ix = ore_api::sdk::bury(payer.pubkey(), amount_u64);
+    simulate_transaction(rpc, payer, &[wrap_ix, bury_ix]).await;
+    Ok(())
+}
+
+async fn reset(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let board = get_board(rpc).await?;
+    let config = get_config(rpc).await?;
+    let slot_hashes = get_slot_hashes(rpc).await?;
+    if let Some(slot_hash) = slot_hashes.get(&board.end_slot) {
+        let id = get_winning_square(&slot_hash.to_bytes());
+        out!("Winning square: {}", id);
+    };
+    let reset_ix = ore_api::sdk::reset(
+        payer.pubkey(),
+        config.fee_collector,
+        board.round_id,
+        Pubkey::default(),
+    );
+    let target = CrankTarget::Reset { round_id: board.round_id };
+    submit_crank(rpc, payer, "reset", &[reset_ix], &[target]).await?;
+    Ok(())
+}
+
+async fn deploy(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let amount: u64 = parse_env("AMOUNT")?;
+    let mut squares = [false; 25];
+    // TEMPLATE=corners：部署模板中的所有格子（见 square_template），否则部署 SQUARE 指定的单个格子
+    if let Ok(name) = std::env::var("TEMPLATE") {
+        let template = square_template(&name)
+            .ok_or_else(|| CommandError::config(format!("格子模板 {} 不存在", name)))?;
+        for i in template.into_iter().filter(|&i| i < 25) {
+            squares[i] = true;
+        }
+        out!("[deploy] 模板 {}：格子 {:?}", name, (0..25).filter(|&i| squares[i]).collect::<Vec<_>>());
+    } else {
+        let square_id: u64 = parse_env("SQUARE")?;
+        squares[square_id as usize] = true;
+    }
+    let board = get_board(rpc).await?;
+    if let Some(authority) = delegated_deploy_authority()? {
+        return deploy_delegated(rpc, payer, authority, amount, board.round_id, squares).await;
+    }
+    let authority = mining_authority(payer).pubkey();
+    let ix = ore_api::sdk::deploy(
+        authority,
+        authority,
+        amount,
+        board.round_id,
+        squares,
+    );
+    submit_transaction(rpc, payer, &[ix]).await?;
+    Ok(())
+}
+
+async fn deploy_all(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let amount: u64 = parse_env("AMOUNT")?;
+    let board = get_board(rpc).await?;
+    let squares = [true; 25];
+    if let Some(authority) = delegated_deploy_authority()? {
+        return deploy_delegated(rpc, payer, authority, amount, board.round_id, squares).await;
+    }
+    let authority = mining_authority(payer).pubkey();
+    let ix = ore_api::sdk::deploy(
+        authority,
+        authority,
+        amount,
+        board.round_id,
+        squares,
+    );
+    submit_transaction(rpc, payer, &[ix]).await?;
+    Ok(())
+}
+
+// ============ 新增：代为部署（管理钱包出资） ============
+// DEPLOY_AUTHORITY=<PUBKEY>：deploy / deploy_all 为另一个 authority 的矿工部署。
+// 程序要求 deploy 的签名者就是 authority，因此该 authority 的密钥需在 DELEGATE_KEYPAIRS 中共同签名；
+// 同一笔交易先由 KEYPAIR 向 authority 转入本次部署金额，再以 authority 身份部署，
+// 资金与手续费都由管理钱包承担，被管理钱包余额不变。每次部署以 wallet / funder 记入 reward.jsonl，
+// authority_spend 按 authority 汇总代为部署的花费
+fn delegated_deploy_authority() -> Result<Option<Pubkey>, anyhow::Error> {
+    let Ok(value) = std::env::var("DEPLOY_AUTHORITY") else {
+        return Ok(None);
+    };
+    let authority = Pubkey::from_str(value.trim())
+        .map_err(|_| CommandError::config(format!("DEPLOY_AUTHORITY 不是有效的公钥: {}", value)))?;
+    if delegate_signer(&authority).is_none() {
+        return Err(CommandError::config(format!(
+            "DELEGATE_KEYPAIRS 中没有 {} 的密钥，无法以该 authority 签名部署",
+            authority
+        ))
+        .into());
+    }
+    Ok(Some(authority))
+}
+
+async fn deploy_delegated(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+    authority: Pubkey,
+    amount: u64,
+    round_id: u64,
+    squares: [bool; 25],
+) -> Result<(), anyhow::Error> {
+    let count = squares.iter().filter(|s| **s).count();
+    let cost = amount * count as u64;
+    out!(
+        "[delegate] 由 {} 出资为 {} 部署 {} 格 × {}（合计 {}）",
+        payer.pubkey(),
+        authority,
+        count,
+        fmt_sol(amount),
+        fmt_sol(cost)
+    );
+    if env_flag("DRY_RUN") {
+        out!("[delegate] 预览模式（DRY_RUN），不发送交易");
+        return Ok(());
+    }
+    let instructions = [
+        solana_sdk::system_instruction::transfer(&payer.pubkey(), &authority, cost),
+        ore_api::sdk::deploy(authority, authority, amount, round_id, squares),
+    ];
+    let sig = submit_transaction(rpc, payer, &instructions).await?;
+    out!("[delegate] ✅ 交易签名: {}", sig);
+    append_reward_event(RewardEvent {
+        round: Some(round_id),
+        algorithm: Some("manual".to_string()),
+        squares: Some(count),
+        cost_lamports: Some(cost),
+        tx: Some(sig.to_string()),
+        wallet: Some(authority.to_string()),
+        funder: Some(payer.pubkey().to_string()),
+        ..RewardEvent::new("deploy")
+    });
+    Ok(())
+}
+
+fn authority_spend() -> Result<(), anyhow::Error> {
+    let mut totals: std::collections::BTreeMap<String, (usize, u64, u64)> = std::collections::BTreeMap::new();
+    for event in read_reward_events()
+        .into_iter()
+        .filter(|e| e.event == "deploy" && e.funder.is_some())
+    {
+        let Some(wallet) = event.wallet else {
+            continue;
+        };
+        let entry = totals.entry(wallet).or_insert((0, 0, 0));
+        entry.0 += 1;
+        entry.1 += event.cost_lamports.unwrap_or(0);
+        entry.2 = entry.2.max(event.ts);
+    }
+    if totals.is_empty() {
+        return Err(CommandError::nothing_to_do("[delegate] reward.jsonl 中没有代为部署的记录").into());
+    }
+    out!("{:<44} {:>8} {:>14} {:>12}", "authority", "deploys", "spent", "last");
+    let (mut deploys, mut spent) = (0usize, 0u64);
+    for (wallet, (count, cost, last)) in totals.iter() {
+        out!("{:<44} {:>8} {:>14} {:>12}", wallet, count, fmt_sol(*cost), utc_date(*last));
+        deploys += count;
+        spent += cost;
+    }
+    out!("{:<44} {:>8} {:>14}", "total", deploys, fmt_sol(spent));
+    Ok(())
+}
+
+// ============ 新增：未结算资金一览 ============
+// COMMAND=exposure：列出各钱包仍锁在未结算回合中的 SOL——当前回合的部署（open），以及已结束但尚未 checkpoint
+// 的部署（unsettled，奖励未入账，过期前不 checkpoint 会被收取费用）。钱包范围：矿工 authority、SPLIT_WALLETS、
+// FLEET_WALLETS、DELEGATE_KEYPAIRS、DEPLOY_AUTHORITY，以及 EXPOSURE_WALLETS 中额外列出的公钥（只读，无需私钥）。
+// 分别按钱包和按回合汇总
+async fn exposure(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let mut wallets = vec![mining_authority(payer).pubkey()];
+    for key in ["SPLIT_WALLETS", "FLEET_WALLETS", "DELEGATE_KEYPAIRS"] {
+        wallets.extend(read_wallet_files(key)?.iter().map(Signer::pubkey));
+    }
+    wallets.extend(delegated_deploy_authority()?);
+    for value in env_list::<String>("EXPOSURE_WALLETS", &[]) {
+        wallets.push(
+            Pubkey::from_str(value.trim())
+                .map_err(|_| CommandError::config(format!("EXPOSURE_WALLETS 中的公钥无效: {}", value)))?,
+        );
+    }
+    let mut seen = std::collections::HashSet::new();
+    wallets.retain(|w| seen.insert(*w));
+
+    let board = get_board(rpc).await?;
+    let clock = get_clock(rpc).await?;
+    let addresses: Vec<Pubkey> = wallets.iter().map(|w| miner_pda(*w).0).collect();
+    let mut miners = vec![];
+    for chunk in addresses.chunks(100) {
+        miners.extend(
+            rpc.get_multiple_accounts_with_commitment(chunk, commitment_for(OpClass::Analytics))
+                .await?
+                .value
+                .into_iter()
+                .map(|account| account.and_then(|a| Miner::try_from_bytes(&a.data).ok().copied())),
+        );
+    }
+
+    // (钱包, 回合, 金额, 状态)
+    let mut positions = vec![];
+    for (wallet, miner) in wallets.iter().zip(miners) {
+        let Some(miner) = miner else {
+            continue;
+        };
+        let view = MinerView::new(miner);
+        let amount = view.deployed_in(miner.round_id);
+        if amount == 0 {
+            continue;
+        }
+        if miner.round_id >= board.round_id {
+            positions.push((*wallet, miner.round_id, amount, "open"));
+        } else if view.needs_checkpoint(board.round_id) {
+            positions.push((*wallet, miner.round_id, amount, "unsettled"));
+        }
+    }
+    out!(
+        "[exposure] {} 个钱包，当前回合 {}（剩余 {:.1}s）",
+        wallets.len(),
+        board.round_id,
+        BoardView::new(board, clock.slot).secs_remaining()
+    );
+    if positions.is_empty() {
+        return Err(CommandError::nothing_to_do("没有锁在未结算回合中的资金").into());
+    }
+
+    out!("{:<44} {:>10} {:>10} {:>16}", "wallet", "round", "status", "deployed");
+    for (wallet, round_id, amount, status) in positions.iter() {
+        out!("{:<44} {:>10} {:>10} {:>16}", wallet, round_id, status, fmt_sol(*amount));
+    }
+    let mut by_round: std::collections::BTreeMap<u64, (usize, u64)> = std::collections::BTreeMap::new();
+    for (_, round_id, amount, _) in positions.iter() {
+        let entry = by_round.entry(*round_id).or_default();
+        entry.0 += 1;
+        entry.1 += amount;
+    }
+    out!();
+    out!("{:<10} {:>8} {:>16}", "round", "wallets", "deployed");
+    for (round_id, (count, amount)) in by_round.iter() {
+        out!("{:<10} {:>8} {:>16}", round_id, count, fmt_sol(*amount));
+    }
+    let total: u64 = positions.iter().map(|p| p.2).sum();
+    let unsettled: u64 = positions.iter().filter(|p| p.3 == "unsettled").map(|p| p.2).sum();
+    out!();
+    out!(
+        "[exposure] 合计 {}（当前回合 {}，待 checkpoint {}）",
+        fmt_sol(total),
+        fmt_sol(total - unsettled),
+        fmt_sol(unsettled)
+    );
+    Ok(())
+}
+
+// ============ 新增：代管账户分润记账 ============
+// 为他人代为挖矿时的账目：client_deposit 记录客户入金（CLIENT=<authority 公钥>，DEPOSIT_SOL=金额），
+// 首次入金时同时记下该矿工的累计收益作为基线；花费取 reward.jsonl 中代为部署（funder 非空）的 deploy 记录；
+// 收益 = 链上 Miner 累计收益（lifetime_rewards_sol / lifetime_rewards_ore）减去基线，ORE 按 Meteora 中间价折算为 SOL。
+// MANAGEMENT_FEE_PCT（默认 0）按正收益（收益 - 花费）收取管理费；client_statement 为每个客户
+// （或仅 CLIENT 指定的客户）生成对账单 statement-<authority>-<日期>.txt
+#[derive(Debug, Default)]
+struct ClientLedger {
+    deposits: u64,
+    deposit_count: usize,
+    spent: u64,
+    deploys: usize,
+    baseline_sol: Option<u64>,
+    baseline_ore: Option<u64>,
+    first_ts: Option<u64>,
+}
+
+fn client_from_env() -> Result<Option<Pubkey>, anyhow::Error> {
+    match std::env::var("CLIENT") {
+        Ok(value) => Pubkey::from_str(value.trim())
+            .map(Some)
+            .map_err(|_| CommandError::config(format!("CLIENT 不是有效的公钥: {}", value)).into()),
+        Err(_) => Ok(None),
+    }
+}
+
+fn read_client_ledgers() -> std::collections::BTreeMap<String, ClientLedger> {
+    let mut ledgers: std::collections::BTreeMap<String, ClientLedger> = std::collections::BTreeMap::new();
+    for event in read_reward_events() {
+        let Some(wallet) = event.wallet.clone() else {
+            continue;
+        };
+        match event.event.as_str() {
+            "deposit" => {
+                let ledger = ledgers.entry(wallet).or_default();
+                // 旧版本把入金写在 delta_sol_lamports 中
+                ledger.deposits += event.deposit_lamports.or(event.delta_sol_lamports).unwrap_or(0);
+                ledger.deposit_count += 1;
+                ledger.baseline_sol = ledger.baseline_sol.or(event.lifetime_sol_lamports);
+                ledger.baseline_ore = ledger.baseline_ore.or(event.lifetime_ore);
+                ledger.first_ts = ledger.first_ts.or(Some(event.ts));
+            }
+            "deploy" if event.funder.is_some() => {
+                let ledger = ledgers.entry(wallet).or_default();
+                ledger.spent += event.cost_lamports.unwrap_or(0);
+                ledger.deploys += 1;
+                ledger.first_ts = ledger.first_ts.or(Some(event.ts));
+            }
+            _ => {}
+        }
+    }
+    ledgers
+}
+
+async fn client_deposit(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let client = client_from_env()?.ok_or_else(|| CommandError::config("CLIENT 未设置"))?;
+    let amount = sol_to_lamports(env_f64("DEPOSIT_SOL", 0.0));
+    if amount == 0 {
+        return Err(CommandError::config("DEPOSIT_SOL 未设置或为 0").into());
+    }
+    let first = read_client_ledgers()
+        .get(&client.to_string())
+        .is_none_or(|l| l.deposit_count == 0);
+    // 首次入金记录基线，之后的收益才计入该客户
+    let (lifetime_sol, lifetime_ore) = if first {
+        match get_miner(rpc, client).await {
+            Ok(miner) => (Some(miner.lifetime_rewards_sol), Some(miner.lifetime_rewards_ore)),
+            Err(_) => (Some(0), Some(0)),
+        }
+    } else {
+        (None, None)
+    };
+    append_reward_event(RewardEvent {
+        deposit_lamports: Some(amount),
+        wallet: Some(client.to_string()),
+        lifetime_sol_lamports: lifetime_sol,
+        lifetime_ore,
+        ..RewardEvent::new("deposit")
+    });
+    out!("[client] 已记录 {} 入金 {}", client, fmt_sol(amount));
+    if let (Some(sol), Some(ore)) = (lifetime_sol, lifetime_ore) {
+        out!("[client] 收益基线：累计 SOL {}，累计 ORE {}", fmt_sol(sol), fmt_ore(ore));
+    }
+    Ok(())
+}
+
+async fn client_statement(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let fee_pct = env_f64("MANAGEMENT_FEE_PCT", 0.0).clamp(0.0, 100.0);
+    let only = client_from_env()?;
+    let ledgers: Vec<(String, ClientLedger)> = read_client_ledgers()
+        .into_iter()
+        .filter(|(wallet, _)| only.is_none_or(|c| c.to_string() == *wallet))
+        .collect();
+    if ledgers.is_empty() {
+        return Err(CommandError::nothing_to_do("[client] reward.jsonl 中没有客户入金或代为部署记录").into());
+    }
+    let ore_price = match get_meteora_reserves(rpc, known_address("METEORA_POOL", DEFAULT_METEORA_POOL)).await {
+        Ok(reserves) => reserves.mid_price(),
+        Err(e) => {
+            out!("[client] ⚠️  获取 ORE 价格失败，ORE 收益按 0 折算: {}", e);
+            0.0
+        }
+    };
+    let today = utc_date(chain_now());
+    for (wallet, ledger) in ledgers.iter() {
+        let authority = Pubkey::from_str(wallet)?;
+        let (winnings_sol, winnings_ore) = match get_miner(rpc, authority).await {
+            Ok(miner) => (
+                miner.lifetime_rewards_sol.saturating_sub(ledger.baseline_sol.unwrap_or(0)),
+                miner.lifetime_rewards_ore.saturating_sub(ledger.baseline_ore.unwrap_or(0)),
+            ),
+            Err(_) => (0, 0),
+        };
+        let ore_value = sol_to_lamports(ore_price * amount_to_ui_amount(winnings_ore, TOKEN_DECIMALS));
+        let profit = (winnings_sol + ore_value) as i64 - ledger.spent as i64;
+        let fee = (profit.max(0) as f64 * fee_pct / 100.0) as u64;
+        let equity = ledger.deposits as i64 - ledger.spent as i64 + (winnings_sol + ore_value) as i64 - fee as i64;
+
+        let mut statement = String::new();
+        statement.push_str(&format!("客户对账单  {}\n", wallet));
+        statement.push_str(&format!(
+            "期间          {} ~ {}\n",
+            ledger.first_ts.map(utc_date).unwrap_or_else(|| "-".to_string()),
+            today
+        ));
+        statement.push_str(&format!("入金          {}（{} 笔）\n", fmt_sol(ledger.deposits), ledger.deposit_count));
+        statement.push_str(&format!("部署花费      {}（{} 次）\n", fmt_sol(ledger.spent), ledger.deploys));
+        statement.push_str(&format!("SOL 收益      {}\n", fmt_sol(winnings_sol)));
+        statement.push_str(&format!("ORE 收益      {}（≈ {}，{:.6} SOL/ORE）\n", fmt_ore(winnings_ore), fmt_sol(ore_value), ore_price));
+        statement.push_str(&format!("净收益        {}\n", fmt_sol_signed(profit)));
+        statement.push_str(&format!("管理费 {:.2}%   {}\n", fee_pct, fmt_sol(fee)));
+        statement.push_str(&format!("客户权益      {}\n", fmt_sol_signed(equity)));
+        out_inline!("{}", statement);
+        out!();
+        let path = state_path(&format!("statement-{}-{}.txt", wallet, today));
+        fs::write(&path, statement)?;
+        out!("[client] 对账单已写入 {}", path);
+    }
+    Ok(())
+}
+
+// ============ 新增：自动挖矿 ============
+
+fn read_auto_params_from_env() -> (u64, f64, usize, usize, usize) {
+    // 下注金额（lamports），优先 AMOUNT，否则 0
+    let amount_lamports: u64 = std::env::var("AMOUNT")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0);
+
+    // 阈值（SOL）
+    let threshold_sol: f64 = std::env::var("THRESHOLD_SOL")
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .or_else(|| {
+            // 从 ore.config.json 中（已在 load 中设置 env）
+            None
+        })
+        .unwrap_or(0.01);
+
+    // 最少满足条件的格子数量
+    let min_squares_required: usize = std::env::var("MIN_SQUARES_REQUIRED")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(12);
+
+    // 选择的格子数量
+    let pick_squares: usize = std::env::var("PICK_SQUARES")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(5);
+
+    // 最大循环次数（观察到的回合切换次数，另见 RunBounds）
+    let max_loops: usize = std::env::var("MAX_LOOPS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(100);
+
+    (amount_lamports, threshold_sol, min_squares_required, pick_squares, max_loops)
+}
+
+// 算法类型枚举
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SquareSelectionAlgorithm {
+    Threshold,  // 阈值算法（原算法）
+    Optimized,  // 最优化算法（新算法）
+    Random,  // 纯随机：在允许的格子中均匀抽取 PICK_SQUARES 个
+    WeightedRandom,  // 加权随机：部署越少的格子被抽中的概率越高
+}
+
+impl SquareSelectionAlgorithm {
+    fn parse(s: &str) -> Option<Self> {
+        match s.trim() {
+            "threshold" => Some(Self::Threshold),
+            "optimized" => Some(Self::Optimized),
+            "random" => Some(Self::Random),
+            "weighted_random" => Some(Self::WeightedRandom),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Threshold => "threshold",
+            Self::Optimized => "optimized",
+            Self::Random => "random",
+            Self::WeightedRandom => "weighted_random",
+        }
+    }
+
+    fn is_random(&self) -> bool {
+        matches!(self, Self::Random | Self::WeightedRandom)
+    }
+
+    // ALGORITHM 环境变量，未设置或无法识别时使用阈值算法
+    fn from_env() -> Self {
+        std::env::var("ALGORITHM")
+            .ok()
+            .and_then(|a| Self::parse(&a))
+            .unwrap_or(Self::Threshold)
+    }
+}
+
+// 格子资金流入速度采样：保存本回合最近 N 次 Round 快照
+// 同时累计本回合每个格子的时间加权平均部署量（TWAP）：早到的资金在均值中占比高，尾盘涌入的资金占比低。
+// 回合切换时 push 返回上一回合的 RoundTwap，auto_mine 追加到 ore.round_twap.jsonl（COMMAND=twap_report 汇总）
+struct InflowTracker {
+    round_id: Option<u64>,
+    capacity: usize,
+    samples: VecDeque<(u64, [u64; 25])>,
+    // 本回合首次观察的 slot 与 Σ 部署量 × 持续 slot 数
+    twap_start: u64,
+    twap_accum: [f64; 25],
+}
+
+impl InflowTracker {
+    fn new(capacity: usize) -> Self {
+        Self {
+            round_id: None,
+            capacity: capacity.max(2),
+            samples: VecDeque::new(),
+            twap_start: 0,
+            twap_accum: [0.0; 25],
+        }
+    }
+
+    fn push(&mut self, round_id: u64, slot: u64, deployed: [u64; 25]) -> Option<RoundTwap> {
+        // 新回合清空旧样本
+        let mut finished = None;
+        if self.round_id != Some(round_id) {
+            if let (Some(previous), Some((last_slot, last))) = (self.round_id, self.samples.back()) {
+                finished = Some(RoundTwap {
+                    round_id: previous,
+                    observed_slots: last_slot.saturating_sub(self.twap_start),
+                    twap: self.twap().map(|v| v as u64),
+                    last: *last,
+                });
+            }
+            self.round_id = Some(round_id);
+            self.samples.clear();
+            self.twap_start = slot;
+            self.twap_accum = [0.0; 25];
+        }
+        if let Some((last_slot, last)) = self.samples.back() {
+            if *last_slot >= slot {
+                return finished;
+            }
+            let held = (slot - last_slot) as f64;
+            for (accum, lamports) in self.twap_accum.iter_mut().zip(last) {
+                *accum += *lamports as f64 * held;
+            }
+        }
+        self.samples.push_back((slot, deployed));
+        while self.samples.len() > self.capacity {
+            self.samples.pop_front();
+        }
+        finished
+    }
+
+    // 本回合到目前为止每个格子的时间加权平均部署量（lamports）；只有一个样本时即当前值
+    fn twap(&self) -> [f64; 25] {
+        let Some((last_slot, last)) = self.samples.back() else {
+            return [0.0; 25];
+        };
+        let slots = last_slot.saturating_sub(self.twap_start);
+        if slots == 0 {
+            return last.map(|v| v as f64);
+        }
+        self.twap_accum.map(|v| v / slots as f64)
+    }
+
+    // 每个格子的流入速度（lamports/秒），样本不足时全部为 0
+    fn velocities(&self) -> [f64; 25] {
+        let mut out = [0.0; 25];
+        let (Some((first_slot, first)), Some((last_slot, last))) =
+            (self.samples.front(), self.samples.back())
+        else {
+            return out;
+        };
+        let secs = (last_slot.saturating_sub(*first_slot) as f64) * 0.4;
+        if secs <= 0.0 {
+            return out;
+        }
+        for i in 0..25 {
+            out[i] = last[i].saturating_sub(first[i]) as f64 / secs;
+        }
+        out
+    }
+}
+
+// 一个回合的时间加权部署量（从首次观察到最后一次观察）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RoundTwap {
+    round_id: u64,
+    observed_slots: u64,
+    twap: [u64; 25],
+    last: [u64; 25],
+}
+
+const ROUND_TWAP_FILE: &str = "ore.round_twap.jsonl";
+
+fn append_round_twap(twap: &RoundTwap) {
+    let Ok(line) = serde_json::to_string(twap) else {
+        return;
+    };
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(ROUND_TWAP_FILE))
+    {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+// 最近 TWAP_ROUNDS（默认 50）个回合中每个格子的平均 TWAP、平均最终金额，以及尾盘资金占比（1 - TWAP / 最终）
+fn twap_report() -> Result<(), anyhow::Error> {
+    let rounds: Vec<RoundTwap> = fs::read_to_string(state_path(ROUND_TWAP_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RoundTwap>(line).ok())
+        .collect();
+    let limit = std::env::var("TWAP_ROUNDS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(50);
+    let recent = &rounds[rounds.len().saturating_sub(limit)..];
+    if recent.is_empty() {
+        return Err(CommandError::nothing_to_do(format!("{} 中没有记录（由 auto_mine 写入）", state_path(ROUND_TWAP_FILE))).into());
+    }
+    out!("TWAP（最近 {} 个回合）", recent.len());
+    out!("{:<6} {:>16} {:>16} {:>10}", "square", "avg twap", "avg final", "late %");
+    for i in 0..25 {
+        let twap: u64 = recent.iter().map(|r| r.twap[i]).sum::<u64>() / recent.len() as u64;
+        let last: u64 = recent.iter().map(|r| r.last[i]).sum::<u64>() / recent.len() as u64;
+        let late = if last > 0 { (1.0 - twap as f64 / last as f64) * 100.0 } else { 0.0 };
+        out!("#{:<5} {:>16} {:>16} {:>9.1}%", i, fmt_sol(twap), fmt_sol(last), late);
+    }
+    Ok(())
+}
+
+// ============ 新增：部署决策与会话回放 ============
+// auto_mine 的选格 / 下注金额决策集中在 DeployDecider::plan，实盘与回放共用同一份逻辑。
+// REPLAY_RECORD=<文件>：auto_mine 每次决策时把读到的 Board / Clock / Round 数据和决策结果逐行写入 JSONL；
+// REPLAY=<文件>：auto_mine 不连接链上，按录制顺序把数据重新喂给流入速度采样与 DeployDecider，
+// 用当前配置重放每次决策并与录制结果对比（只读，不发送交易）。
+// 亏损冷却依赖回合结算结果，不在录制的 RPC 数据中，回放时直接使用录制的下注系数
+// 每次决策同时生成 DecisionExplanation（候选、被过滤的格子及原因、阈值、排序），见下方决策解释
+struct DeployDecider {
+    algorithm: SquareSelectionAlgorithm,
+    amount_lamports: u64,
+    threshold_sol: f64,
+    min_squares_required: usize,
+    pick_squares: usize,
+    velocity_samples: usize,
+    max_inflow_lamports_per_sec: Option<f64>,
+    late_money_ratio: Option<f64>,
+    optimized: OptimizedThreshold,
+    dynamic_pick: Option<DynamicPick>,
+    ensemble: Option<Vec<EnsembleMember>>,
+    constraints: SquareConstraints,
+    pot_guard: Option<PotGuard>,
+    late_inflow: Option<LateInflowModel>,
+}
+
+impl DeployDecider {
+    fn from_env(algorithm: SquareSelectionAlgorithm) -> Result<Self, anyhow::Error> {
+        let (amount_lamports, threshold_sol, min_squares_required, pick_squares, _) =
+            read_auto_params_from_env();
+        // 流入速度采样缓冲区
+        let velocity_samples: usize = std::env::var("VELOCITY_SAMPLES")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(10);
+        let max_inflow_lamports_per_sec: Option<f64> = std::env::var("MAX_INFLOW_SOL_PER_SEC")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .map(|sol| sol * 1_000_000_000.0);
+        Ok(Self {
+            algorithm,
+            amount_lamports,
+            threshold_sol,
+            min_squares_required,
+            pick_squares,
+            velocity_samples,
+            max_inflow_lamports_per_sec,
+            late_money_ratio: parse_env_opt("LATE_MONEY_RATIO")?,
+            optimized: OptimizedThreshold::from_env(),
+            dynamic_pick: DynamicPick::from_env(pick_squares),
+            ensemble: ensemble_from_env()?,
+            constraints: SquareConstraints::from_env(),
+            pot_guard: PotGuard::from_env()?,
+            late_inflow: LateInflowModel::from_env(),
+        })
+    }
+
+    // 奖池比例上限（MAX_POT_FRACTION）作用于最终下注计划，不在 plan 内执行：
+    // 调用方在金额随机化（AMOUNT_JITTER_PCT / 分钱包浮动）之后、组装部署指令之前调用，随机化不会把缩小后的金额放大回去
+    fn guard_pot(&self, plan: [u64; 25], deployed: &[u64; 25]) -> Option<[u64; 25]> {
+        match self.pot_guard {
+            Some(guard) => guard.apply(plan, deployed),
+            None => Some(plan),
+        }
+    }
+
+    // 策略描述（会话汇总 / 回放文件中记录）
+    fn labels(&self) -> Vec<String> {
+        match &self.ensemble {
+            Some(members) => members
+                .iter()
+                .map(|m| format!("{:.0}% {}", m.allocation_pct, m.params.label()))
+                .collect(),
+            None => vec![StrategyParams { algorithm: self.algorithm, ..strategy_params_from_env() }.label()],
+        }
+    }
+
+    // 排序后的候选中选取前几个（动态模式时打印边际 EV 决定的数量）
+    fn pick_count(&self, ranked: &[(usize, f64)], deployed: &[u64; 25]) -> usize {
+        let take = pick_count(&self.dynamic_pick, self.pick_squares, ranked, deployed, self.amount_lamports);
+        if let Some(dynamic) = &self.dynamic_pick {
+            out!(
+                "[auto] 动态选格 {}：{} 个候选，按边际 EV 选取 {} 个",
+                dynamic.describe(),
+                ranked.len(),
+                take
+            );
+        }
+        take
+    }
+
+    // 每个格子的部署金额（本轮不部署时为 None）及决策解释
+    fn plan(
+        &self,
+        round_id: u64,
+        deployed: &[u64; 25],
+        velocity: &[f64; 25],
+        twap: &[f64; 25],
+        bet_factor: f64,
+        secs_remaining: f64,
+    ) -> (Option<[u64; 25]>, DecisionExplanation) {
+        let observed = *deployed;
+        // 尾盘流入预估：之后的判断都基于预估的最终金额
+        let late = self
+            .late_inflow
+            .as_ref()
+            .map(|model| model.expected(deployed, secs_remaining));
+        let projected: [u64; 25];
+        let deployed = match &late {
+            Some(late) => {
+                out!(
+                    "[late] 剩余 {:.1}s，预计尾盘再流入 {}",
+                    secs_remaining,
+                    fmt_sol(late.iter().sum())
+                );
+                projected = std::array::from_fn(|i| deployed[i] + late[i]);
+                &projected
+            }
+            None => deployed,
+        };
+        let hot_squares: Vec<String> = velocity
+            .iter()
+            .enumerate()
+            .filter(|(_, v)| **v > 0.0)
+            .map(|(i, v)| format!("#{}={:.6}", i, lamports_to_sol(*v as u64)))
+            .collect();
+        if !hot_squares.is_empty() {
+            out!("[auto] 流入速度 (SOL/s): {}", hot_squares.join(" "));
+        }
+        // 过滤流入过快的格子（晚到的大资金）；LATE_MONEY_RATIO：当前金额超过本回合 TWAP 的 N 倍也视为尾盘涌入
+        let inflow_ok = |idx: usize| -> bool {
+            let velocity_ok = match self.max_inflow_lamports_per_sec {
+                Some(max) => velocity[idx] <= max,
+                None => true,
+            };
+            let late_money_ok = match self.late_money_ratio {
+                Some(ratio) if twap[idx] > 0.0 => observed[idx] as f64 <= twap[idx] * ratio,
+                _ => true,
+            };
+            velocity_ok && late_money_ok
+        };
+
+        let all_squares: Vec<(usize, f64)> =
+            deployed.iter().map(|&lamports| lamports_to_sol(lamports)).enumerate().collect();
+
+        // 输出所有 25 个格子的部署情况
+        out!("[auto] 当前回合所有格子的部署情况:");
+        for (square_idx, sol_amt) in &all_squares {
+            out_inline!("  #{}: {:.6} SOL  ", square_idx, sol_amt);
+            if (square_idx + 1) % 5 == 0 {
+                out!(); // 每 5 个换行，形成 5x5 网格显示
+            }
+        }
+        if !all_squares.len().is_multiple_of(5) {
+            out!(); // 如果最后一行不满 5 个，也要换行
+        }
+
+        let mut explain = DecisionExplanation {
+            timestamp: chain_now(),
+            round_id,
+            strategy: match &self.ensemble {
+                Some(_) => "ensemble".to_string(),
+                None => self.algorithm.name().to_string(),
+            },
+            squares_sol: all_squares.iter().map(|(_, v)| *v).collect(),
+            velocity_sol_per_sec: velocity.iter().map(|v| lamports_to_sol(*v as u64)).collect(),
+            threshold_sol: None,
+            min_squares: self.min_squares_required,
+            removed: vec![],
+            ranking: vec![],
+            bet_factor,
+            picked: vec![],
+            skip_reason: None,
+            late_inflow_sol: late.map(|l| l.iter().map(|v| lamports_to_sol(*v)).collect()).unwrap_or_default(),
+            twap_sol: twap.iter().map(|v| lamports_to_sol(*v as u64)).collect(),
+        };
+
+        // 每个格子的部署金额：组合模式下合并各策略，否则按算法选格、每格 AMOUNT
+        let plan = match &self.ensemble {
+            Some(members) => {
+                explain.remove_where(|i| !inflow_ok(i), "inflow");
+                plan_ensemble(members, round_id, deployed, inflow_ok)
+            }
+            None => {
+                // 根据算法类型选择格子
+                let picked = match self.algorithm {
+                    SquareSelectionAlgorithm::Threshold => {
+                        // 原算法：阈值算法
+                        let mut candidates =
+                            explain.filter_candidates(&all_squares, self.threshold_sol, inflow_ok);
+                        out!(
+                            "[auto] [阈值算法] 低于阈值({:.4} SOL)的格子数量: {}",
+                            self.threshold_sol,
+                            candidates.len()
+                        );
+                        if candidates.len() >= self.min_squares_required {
+                            // 从小到大排序
+                            candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+                            explain.ranking = candidates.iter().map(|(idx, _)| *idx).collect();
+                            let take = self.pick_count(&candidates, deployed);
+                            let picked = candidates
+                                .into_iter()
+                                .take(take)
+                                .map(|(idx, _)| idx)
+                                .collect::<Vec<_>>();
+                            if picked.is_empty() {
+                                out!("[auto] 未选中任何格子，跳过。");
+                                None
+                            } else {
+                                Some(picked)
+                            }
+                        } else {
+                            out!("[auto] 符合阈值的格子不足 {} 个，跳过本次。", self.min_squares_required);
+                            explain.skip_reason = Some(format!(
+                                "candidates {} < MIN_SQUARES_REQUIRED {}",
+                                candidates.len(),
+                                self.min_squares_required
+                            ));
+                            None
+                        }
+                    }
+                    SquareSelectionAlgorithm::Optimized => {
+                        // 新算法：最优化算法
+                        // 1. 统计所有25个格子的部署总和
+                        let total_deployed_sol = lamports_to_sol(deployed.iter().sum());
+
+                        // 2. 计算阈值：(slope * 部署总数) - offset，默认 0.036 / 0.005
+                        let threshold = self.optimized.threshold(total_deployed_sol);
+
+                        out!(
+                            "[auto] [最优化算法] 所有格子部署总和: {:.6} SOL, 阈值: {:.6} SOL ({} * 总和 - {})",
+                            total_deployed_sol, threshold, self.optimized.slope, self.optimized.offset_sol
+                        );
+
+                        // 3. 选择所有部署数量 < 阈值的格子
+                        let mut candidates = explain.filter_candidates(&all_squares, threshold, inflow_ok);
+
+                        out!(
+                            "[auto] [最优化算法] 符合条件的格子数量: {}",
+                            candidates.len()
+                        );
+
+                        // 检查是否符合最低下限要求
+                        if candidates.len() >= self.min_squares_required {
+                            // 从小到大排序
+                            candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+                            explain.ranking = candidates.iter().map(|(idx, _)| *idx).collect();
+                            // 受 PICK_SQUARES（或动态选格）限制
+                            let take = self.pick_count(&candidates, deployed);
+                            let picked = candidates
+                                .into_iter()
+                                .take(take)
+                                .map(|(idx, _)| idx)
+                                .collect::<Vec<_>>();
+                            if picked.is_empty() {
+                                out!("[auto] 未选中任何格子，跳过。");
+                                None
+                            } else {
+                                Some(picked)
+                            }
+                        } else {
+                            out!("[auto] [最优化算法] 符合条件的格子不足 {} 个，跳过本次。", self.min_squares_required);
+                            explain.skip_reason = Some(format!(
+                                "candidates {} < MIN_SQUARES_REQUIRED {}",
+                                candidates.len(),
+                                self.min_squares_required
+                            ));
+                            None
+                        }
+                    }
+                    SquareSelectionAlgorithm::Random | SquareSelectionAlgorithm::WeightedRandom => {
+                        let params = StrategyParams { algorithm: self.algorithm, ..strategy_params_from_env() };
+                        explain.remove_where(|i| !inflow_ok(i), "inflow");
+                        let picked = select_squares_where(&params, round_id, deployed, inflow_ok);
+                        out!(
+                            "[auto] [{}] seed={} round={} 抽取结果: {:?}",
+                            self.algorithm.name(),
+                            params.seed,
+                            round_id,
+                            picked
+                        );
+                        picked
+                    }
+                };
+                picked.map(|p| uniform_plan(&p, self.amount_lamports))
+            }
+        };
+        // 亏损冷却（TILT_REDUCE_FACTOR）与竞争降额（CompetitionGuard）合并后的下注系数，0 表示跳过
+        let plan = match plan {
+            Some(_) if bet_factor <= 0.0 => {
+                out!("[auto] 下注系数为 0，跳过本次。");
+                explain.skip_reason = Some("bet_factor 0".to_string());
+                None
+            }
+            Some(plan) if bet_factor < 1.0 => {
+                out!("[auto] 下注金额 ×{}（亏损冷却 / 竞争降额）", bet_factor);
+                Some(plan.map(|v| (v as f64 * bet_factor) as u64))
+            }
+            plan => plan,
+        };
+        let plan = match plan {
+            Some(plan) if !self.constraints.is_empty() => {
+                let constrained = self.constraints.apply(plan, self.amount_lamports);
+                for &i in self.constraints.excluded.iter() {
+                    if plan[i] > 0 {
+                        explain.removed.push((i, "SQUARE_EXCLUDE".to_string()));
+                    }
+                }
+                if constrained.is_none() {
+                    out!("[auto] 选中的格子均在 SQUARE_EXCLUDE 中，跳过本次。");
+                }
+                constrained
+            }
+            plan => plan,
+        };
+        match plan {
+            Some(plan) => explain.picked = (0..25).filter(|&i| plan[i] > 0).map(|i| (i, plan[i])).collect(),
+            None if explain.skip_reason.is_none() => explain.skip_reason = Some("no squares picked".to_string()),
+            None => {}
+        }
+        (plan, explain)
+    }
+}
+
+// ============ 新增：决策解释日志 ============
+// 每次决策追加一行 JSON 到 ore.decisions.jsonl（DECISION_LOG=false 关闭）：各格子金额与流入速度、
+// 阈值、被过滤的格子及原因（threshold / inflow / SQUARE_EXCLUDE）、候选排序、最终选中格子与金额、未部署原因。
+// COMMAND=explain 查看某一回合（EXPLAIN_ROUND，默认最近一次）的决策过程
+const DECISION_LOG_FILE: &str = "ore.decisions.jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DecisionExplanation {
+    timestamp: u64,
+    round_id: u64,
+    strategy: String,
+    squares_sol: Vec<f64>,
+    velocity_sol_per_sec: Vec<f64>,
+    threshold_sol: Option<f64>,
+    min_squares: usize,
+    // (格子, 原因)
+    removed: Vec<(usize, String)>,
+    // 通过过滤的候选，按部署金额从小到大
+    ranking: Vec<usize>,
+    bet_factor: f64,
+    // (格子, lamports)
+    picked: Vec<(usize, u64)>,
+    skip_reason: Option<String>,
+    // 尾盘流入预估（LATE_INFLOW），squares_sol 已包含该部分
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    late_inflow_sol: Vec<f64>,
+    // 本回合到决策时为止的时间加权平均部署量
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    twap_sol: Vec<f64>,
+}
+
+impl DecisionExplanation {
+    fn remove_where(&mut self, removed: impl Fn(usize) -> bool, reason: &str) {
+        for i in 0..self.squares_sol.len() {
+            if removed(i) {
+                self.removed.push((i, reason.to_string()));
+            }
+        }
+    }
+
+    // 低于阈值且流入速度正常的格子为候选，其余记录剔除原因
+    fn filter_candidates(
+        &mut self,
+        squares: &[(usize, f64)],
+        threshold: f64,
+        allow: impl Fn(usize) -> bool,
+    ) -> Vec<(usize, f64)> {
+        self.threshold_sol = Some(threshold);
+        let mut candidates = vec![];
+        for &(i, v) in squares {
+            if v >= threshold {
+                self.removed.push((i, "threshold".to_string()));
+            } else if !allow(i) {
+                self.removed.push((i, "inflow".to_string()));
+            } else {
+                candidates.push((i, v));
+            }
+        }
+        candidates
+    }
+
+    fn print(&self) {
+        out!("Decision (round {}, strategy {}, ts {})", self.round_id, self.strategy, self.timestamp);
+        if let Some(threshold) = self.threshold_sol {
+            out!("  threshold: {:.6} SOL (min squares {})", threshold, self.min_squares);
+        }
+        for (i, v) in self.squares_sol.iter().enumerate() {
+            let status = match self.removed.iter().find(|(square, _)| *square == i) {
+                Some((_, reason)) => format!("removed: {}", reason),
+                None => match self.ranking.iter().position(|&square| square == i) {
+                    Some(rank) => format!("rank {}", rank + 1),
+                    None => "-".to_string(),
+                },
+            };
+            let picked = self
+                .picked
+                .iter()
+                .find(|(square, _)| *square == i)
+                .map(|(_, lamports)| format!("  -> {}", fmt_sol(*lamports)))
+                .unwrap_or_default();
+            let twap = self
+                .twap_sol
+                .get(i)
+                .map(|t| format!("twap {:>12.6}  ", t))
+                .unwrap_or_default();
+            out!(
+                "  #{:<2} {:>12.6} SOL  inflow {:>10.6} SOL/s  {}{}{}",
+                i,
+                v,
+                self.velocity_sol_per_sec.get(i).copied().unwrap_or(0.0),
+                twap,
+                status,
+                picked
+            );
+        }
+        if !self.late_inflow_sol.is_empty() {
+            out!("  expected late inflow: {:.6} SOL (included above)", self.late_inflow_sol.iter().sum::<f64>());
+        }
+        if self.bet_factor < 1.0 {
+            out!("  bet factor: ×{}", self.bet_factor);
+        }
+        if let Some(reason) = &self.skip_reason {
+            out!("  skipped: {}", reason);
+        }
+    }
+}
+
+fn append_decision_explanation(explain: &DecisionExplanation) {
+    if std::env::var("DECISION_LOG").is_ok() && !env_flag("DECISION_LOG") {
+        return;
+    }
+    let Ok(line) = serde_json::to_string(explain) else {
+        return;
+    };
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(DECISION_LOG_FILE))
+    {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+fn explain_command() -> Result<(), anyhow::Error> {
+    let decisions: Vec<DecisionExplanation> = fs::read_to_string(state_path(DECISION_LOG_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<DecisionExplanation>(line).ok())
+        .collect();
+    let Some(last) = decisions.last() else {
+        return Err(CommandError::nothing_to_do(format!("{} 中没有决策记录", state_path(DECISION_LOG_FILE))).into());
+    };
+    let round_id = parse_env_opt::<u64>("EXPLAIN_ROUND")?.unwrap_or(last.round_id);
+    let matching: Vec<&DecisionExplanation> = decisions.iter().filter(|d| d.round_id == round_id).collect();
+    if matching.is_empty() {
+        return Err(CommandError::nothing_to_do(format!("没有回合 {} 的决策记录", round_id)).into());
+    }
+    for explain in matching {
+        explain.print();
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ReplayFrame {
+    timestamp: u64,
+    // Clock slot 与 Board 的回合区间
+    slot: u64,
+    board_start_slot: u64,
+    board_end_slot: u64,
+    // Round 账户的部署分布
+    round_id: u64,
+    deployed: [u64; 25],
+    bet_factor: f64,
+    strategy: Vec<String>,
+    plan: Option<[u64; 25]>,
+}
+
+fn append_replay_frame(path: &str, frame: &ReplayFrame) {
+    let Ok(line) = serde_json::to_string(frame) else {
+        return;
+    };
+    if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+fn plan_squares(plan: &Option<[u64; 25]>) -> Vec<usize> {
+    plan.map(|p| (0..25).filter(|&i| p[i] > 0).collect()).unwrap_or_default()
+}
+
+fn replay_session(path: &str, algorithm: SquareSelectionAlgorithm) -> Result<(), anyhow::Error> {
+    let raw = fs::read_to_string(path)
+        .map_err(|e| CommandError::config(format!("无法读取回放文件 {}: {}", path, e)))?;
+    let frames: Vec<ReplayFrame> = raw
+        .lines()
+        .filter_map(|line| serde_json::from_str::<ReplayFrame>(line).ok())
+        .collect();
+    let Some(first) = frames.first() else {
+        return Err(CommandError::nothing_to_do(format!("回放文件 {} 中没有决策记录", path)).into());
+    };
+    let decider = DeployDecider::from_env(algorithm)?;
+    let labels = decider.labels();
+    out!("[replay] {} 条决策记录，策略: {}", frames.len(), labels.join(" + "));
+    if first.strategy != labels {
+        out!(
+            "[replay] ⚠️  录制时策略为 {}，以下为当前配置下的假设重放",
+            first.strategy.join(" + ")
+        );
+    }
+
+    let mut inflow = InflowTracker::new(decider.velocity_samples);
+    let mut matched = 0usize;
+    let mut deployed_rounds = std::collections::HashSet::new();
+    let mut spent_lamports: u64 = 0;
+    for frame in frames.iter() {
+        out!(
+            "[replay] round={} slot={} 剩余 {:.2}s",
+            frame.round_id,
+            frame.slot,
+            slots_to_secs(frame.board_end_slot.saturating_sub(frame.slot))
+        );
+        inflow.push(frame.round_id, frame.slot, frame.deployed);
+        let secs_remaining = slots_to_secs(frame.board_end_slot.saturating_sub(frame.slot));
+        let (plan, _) = decider.plan(
+            frame.round_id,
+            &frame.deployed,
+            &inflow.velocities(),
+            &inflow.twap(),
+            frame.bet_factor,
+            secs_remaining,
+        );
+        if plan == frame.plan {
+            matched += 1;
+        } else {
+            out!(
+                "[replay] ⚠️  与录制结果不同：录制 {:?}，重放 {:?}",
+                plan_squares(&frame.plan),
+                plan_squares(&plan)
+            );
+        }
+        if let Some(plan) = plan {
+            out!("[replay] 选中格子: {:?}", plan_squares(&Some(plan)));
+            // 与 auto_mine 一致：同一回合只部署一次
+            if deployed_rounds.insert(frame.round_id) {
+                spent_lamports += plan.iter().sum::<u64>();
+            }
+        }
+    }
+    out!(
+        "[replay] 完成：{} / {} 次决策与录制一致，{} 个回合部署，共 {}",
+        matched,
+        frames.len(),
+        deployed_rounds.len(),
+        fmt_sol(spent_lamports)
+    );
+    Ok(())
+}
+
+// ============ 新增：RPC 断连（blackout）模式 ============
+// 连续 BLACKOUT_AFTER 次（默认 5）读取 Board / Clock 失败时进入 blackout：不再每 2 秒重试刷屏，
+// 改为指数退避（2 秒起翻倍，上限 BLACKOUT_MAX_BACKOFF_SECS，默认 60 秒）每次只发一个 getSlot 探测主节点与参考节点，
+// 任一节点恢复即退出 blackout，打印断连时长与期间错过的回合数，并发送 rpc_blackout / rpc_recovered 通知
+struct BlackoutMonitor {
+    threshold: u32,
+    max_backoff: Duration,
+    failures: u32,
+    since: Option<Instant>,
+    last_round_id: Option<u64>,
+}
+
+impl BlackoutMonitor {
+    fn from_env() -> Self {
+        let threshold = std::env::var("BLACKOUT_AFTER")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(5)
+            .max(1);
+        let max_backoff = std::env::var("BLACKOUT_MAX_BACKOFF_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(60)
+            .max(2);
+        Self {
+            threshold,
+            max_backoff: Duration::from_secs(max_backoff),
+            failures: 0,
+            since: None,
+            last_round_id: None,
+        }
+    }
+
+    // 读取失败计数，达到阈值时进入 blackout，返回是否处于 blackout
+    fn failure(&mut self, what: &str, error: &anyhow::Error) -> bool {
+        self.failures += 1;
+        if self.failures < self.threshold {
+            return false;
+        }
+        if self.since.is_none() {
+            self.since = Some(Instant::now());
+            let message = format!("连续 {} 次读取 {} 失败（{}），进入 RPC 断连模式", self.failures, what, error);
+            out!("[blackout] ❌ {}", message);
+            notify_reward_event(&RewardEvent {
+                round: self.last_round_id,
+                kind: Some(message),
+                ..RewardEvent::new("rpc_blackout")
+            });
+        }
+        true
+    }
+
+    // 读取成功：退出 blackout 时返回期间错过的回合数
+    fn success(&mut self, round_id: u64) -> Option<u64> {
+        self.failures = 0;
+        let last_round_id = self.last_round_id.replace(round_id);
+        let since = self.since.take()?;
+        let missed = last_round_id.map(|last| round_id.saturating_sub(last)).unwrap_or(0);
+        let message = format!(
+            "RPC 已恢复：断连 {} 秒，错过 {} 个回合（{} -> {}）",
+            since.elapsed().as_secs(),
+            missed,
+            last_round_id.map(|r| r.to_string()).unwrap_or("?".to_string()),
+            round_id
+        );
+        out!("[blackout] ✅ {}", message);
+        notify_reward_event(&RewardEvent {
+            round: Some(round_id),
+            kind: Some(message),
+            ..RewardEvent::new("rpc_recovered")
+        });
+        Some(missed)
+    }
+
+    // 指数退避探测，直到任一节点响应 getSlot（或收到停止信号）
+    async fn wait_for_recovery(&self, endpoints: &[&RpcClient]) {
+        let mut backoff = Duration::from_secs(2);
+        let mut probes = 0u32;
+        while !stop_requested() {
+            sleep(backoff).await;
+            probes += 1;
+            for rpc in endpoints {
+                if rpc.get_slot().await.is_ok() {
+                    out!("[blackout] 第 {} 次探测成功，恢复读取", probes);
+                    return;
+                }
+            }
+            if probes % 5 == 1 {
+                out!(
+                    "[blackout] 第 {} 次探测失败，已断连 {} 秒，{} 秒后再试",
+                    probes,
+                    self.since.map(|s| s.elapsed().as_secs()).unwrap_or(0),
+                    (backoff * 2).min(self.max_backoff).as_secs()
+                );
+            }
+            backoff = (backoff * 2).min(self.max_backoff);
+        }
+    }
+}
+
+// RPC slot 延迟监控：与参考节点（RPC_REFERENCE）比较 slot，
+// 主节点落后超过 MAX_SLOT_LAG 时告警并切换到参考节点；未配置参考节点时按 SLOT_LAG_CHECK 使用 getHealth
+struct SlotLagWatchdog {
+    reference: Option<RpcClient>,
+    max_lag: u64,
+    health_check: bool,
+    use_reference: bool,
+}
+
+impl SlotLagWatchdog {
+    fn from_env() -> Self {
+        let reference = std::env::var("RPC_REFERENCE")
+            .ok()
+            .filter(|url| !url.trim().is_empty())
+            .map(|url| RpcClient::new_with_commitment(url, commitment_for(OpClass::Confirmation)));
+        let max_lag = std::env::var("MAX_SLOT_LAG")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(20);
+        Self {
+            reference,
+            max_lag,
+            health_check: env_flag("SLOT_LAG_CHECK"),
+            use_reference: false,
+        }
+    }
+
+    // 全部节点（主节点在前），供断连探测
+    fn endpoints<'a>(&'a self, primary: &'a RpcClient) -> Vec<&'a RpcClient> {
+        std::iter::once(primary).chain(self.reference.as_ref()).collect()
+    }
+
+    // 当前应使用的节点
+    fn active<'a>(&'a self, primary: &'a RpcClient) -> &'a RpcClient {
+        match (&self.reference, self.use_reference) {
+            (Some(reference), true) => reference,
+            _ => primary,
+        }
+    }
+
+    // 返回 true 表示本次读取的数据可能已过期，应暂停部署
+    async fn check(&mut self, primary: &RpcClient) -> bool {
+        let Some(reference) = &self.reference else {
+            if !self.health_check {
+                return false;
+            }
+            return match primary.get_health().await {
+                Ok(()) => false,
+                Err(e) => {
+                    out!("[rpc] ⚠️  getHealth 失败（节点可能落后）: {}，暂停本次部署", e);
+                    true
+                }
+            };
+        };
+        let commitment = CommitmentConfig::processed();
+        let (primary_slot, reference_slot) = tokio::join!(
+            primary.get_slot_with_commitment(commitment),
+            reference.get_slot_with_commitment(commitment)
+        );
+        match (primary_slot, reference_slot) {
+            (Ok(primary_slot), Ok(reference_slot)) => {
+                let lag = reference_slot.saturating_sub(primary_slot);
+                if lag > self.max_lag {
+                    if !self.use_reference {
+                        out!(
+                            "[rpc] ⚠️  主 RPC 落后参考节点 {} slots (> {})，切换到参考节点，暂停本次部署",
+                            lag, self.max_lag
+                        );
+                        self.use_reference = true;
+                        return true;
+                    }
+                } else if self.use_reference {
+                    out!("[rpc] 主 RPC 已追上（落后 {} slots），切回主节点", lag);
+                    self.use_reference = false;
+                }
+                false
+            }
+            (Err(e), Ok(_)) => {
+                if !self.use_reference {
+                    out!("[rpc] ⚠️  主 RPC 读取 slot 失败: {}，切换到参考节点，暂停本次部署", e);
+                    self.use_reference = true;
+                    return true;
+                }
+                false
+            }
+            (Ok(_), Err(e)) => {
+                if self.use_reference {
+                    out!("[rpc] ⚠️  参考节点读取 slot 失败: {}，切回主节点", e);
+                    self.use_reference = false;
+                    return true;
+                }
+                false
+            }
+            (Err(e), Err(_)) => {
+                out!("[rpc] ⚠️  主 RPC 与参考节点均无法读取 slot: {}，暂停本次部署", e);
+                true
+            }
+        }
+    }
+}
+
+// 奖励日志：JSON Lines，每行一个 RewardEvent
+const REWARD_LOG_FILE: &str = "reward.jsonl";
+const REWARD_LOG_SCHEMA_VERSION: u32 = 1;
+
+// reward.jsonl schema（v1），金额一律使用最小单位（SOL 为 lamports，ORE 为 11 位小数的整数）：
+//   v                   schema 版本
+//   ts                  unix 时间戳（秒）
+//   event               deploy | checkpoint | claim | deposit
+//   round               回合 ID（deploy / checkpoint）
+//   algorithm           deploy：threshold | optimized | random | weighted_random | ensemble
+//   squares             deploy：部署格子数量
+//   cost_lamports       deploy：本次花费
+//   delta_sol_lamports  checkpoint：SOL 奖励增量
+//   delta_rewards_ore   checkpoint：ORE 奖励增量
+//   delta_refined_ore   checkpoint：refined ORE 增量
+//   kind                claim：sol | ore | sol+ore
+//   tx                  交易签名
+//   explorer            claim：浏览器链接
+//   wallet              deploy：实际部署的 authority（分钱包 / fleet / 代为部署）
+//   funder              deploy：代为部署时出资的管理钱包
+//   deposit_lamports    deposit：客户入金（wallet 为客户 authority）
+//   lifetime_sol_lamports / lifetime_ore  deposit：首次入金时矿工的累计收益基线
+// 未使用的字段不写出
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct RewardEvent {
+    v: u32,
+    ts: u64,
+    event: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    round: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    algorithm: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    squares: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cost_lamports: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    delta_sol_lamports: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    delta_rewards_ore: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    delta_refined_ore: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    kind: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tx: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    explorer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    top_miner_ore: Option<u64>,
+    // 分钱包模式下实际部署的钱包
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    wallet: Option<String>,
+    // 代为部署时出资的管理钱包
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    funder: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    deposit_lamports: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lifetime_sol_lamports: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    lifetime_ore: Option<u64>,
+}
+
+impl RewardEvent {
+    fn new(event: &str) -> Self {
+        Self {
+            v: REWARD_LOG_SCHEMA_VERSION,
+            ts: chain_now(),
+            event: event.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+// 轮转：超过 REWARD_LOG_MAX_BYTES（默认 10MB），或开启 REWARD_LOG_ROTATE_DAILY 且跨天（UTC）时，
+// 将当前文件重命名为 reward.<最后修改时间戳>.jsonl；读取方通过 read_reward_log / read_reward_events 按顺序读取全部文件
+fn rotate_reward_log_if_needed(now: u64) {
+    let Ok(metadata) = fs::metadata(state_path(REWARD_LOG_FILE)) else {
+        return;
+    };
+    let max_bytes = std::env::var("REWARD_LOG_MAX_BYTES")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(10 * 1024 * 1024);
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(now);
+    let new_day = env_flag("REWARD_LOG_ROTATE_DAILY") && modified / 86400 != now / 86400;
+    if metadata.len() >= max_bytes || new_day {
+        let rotated = state_path(&format!("reward.{}.jsonl", modified));
+        if fs::rename(state_path(REWARD_LOG_FILE), &rotated).is_ok() {
+            out!("[log] 已轮转奖励日志 -> {}", rotated);
+        }
+    }
+}
+
+fn append_reward_event(event: RewardEvent) {
+    rotate_reward_log_if_needed(event.ts);
+    let Ok(line) = serde_json::to_string(&event) else {
+        return;
+    };
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(REWARD_LOG_FILE))
+    {
+        let _ = writeln!(file, "{}", line);
+    }
+    record_reward_event_metrics(&event);
+    notify_reward_event(&event);
+    emit_reward_stream_event(&event);
+}
+
+// 奖励日志文件：已轮转的 reward.<ts>.jsonl 按时间先后排列，最后是当前的 reward.jsonl
+fn reward_log_files() -> Vec<String> {
+    let current = state_path(REWARD_LOG_FILE);
+    let dir = match std::path::Path::new(&current).parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => std::path::PathBuf::from("."),
+    };
+    let mut rotated: Vec<(u64, String)> = fs::read_dir(&dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let name = entry.file_name().to_string_lossy().to_string();
+                    let ts = name.strip_prefix("reward.")?.strip_suffix(".jsonl")?.parse::<u64>().ok()?;
+                    Some((ts, entry.path().to_string_lossy().to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    rotated.sort();
+    let mut files: Vec<String> = rotated.into_iter().map(|(_, path)| path).collect();
+    files.push(current);
+    files
+}
+
+// 完整的奖励日志内容（含已轮转的文件），所有汇总 / 报表 / 去重都应通过它读取
+fn read_reward_log() -> String {
+    let mut content = String::new();
+    for path in reward_log_files() {
+        if let Ok(part) = fs::read_to_string(&path) {
+            content.push_str(&part);
+            if !content.ends_with('\n') {
+                content.push('\n');
+            }
+        }
+    }
+    content
+}
+
+fn read_reward_events() -> Vec<RewardEvent> {
+    read_reward_log()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RewardEvent>(line).ok())
+        .collect()
+}
+
+// 只读取当前的 reward.jsonl：整体重写（rewrite_reward_log）时使用，已轮转的文件保持不变
+fn read_current_reward_events() -> Vec<RewardEvent> {
+    fs::read_to_string(state_path(REWARD_LOG_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RewardEvent>(line).ok())
+        .collect()
+}
+
+// 汇总奖励日志（LOG_FILE 指定文件，默认 reward.jsonl 及其已轮转的文件）
+fn log_parse() -> Result<(), anyhow::Error> {
+    let (path, content) = match std::env::var("LOG_FILE") {
+        Ok(path) => {
+            let content = fs::read_to_string(&path)
+                .map_err(|e| CommandError::config(format!("无法读取 {}: {}", path, e)))?;
+            (path, content)
+        }
+        Err(_) => (reward_log_files().join(" + "), read_reward_log()),
+    };
+    let mut events: Vec<RewardEvent> = vec![];
+    let mut invalid = 0usize;
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        match serde_json::from_str::<RewardEvent>(line) {
+            Ok(event) => events.push(event),
+            Err(_) => invalid += 1,
+        }
+    }
+    if events.is_empty() {
+        return Err(CommandError::nothing_to_do(format!("{} 中没有可解析的事件", path)).into());
+    }
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut rounds = std::collections::HashSet::new();
+    let (mut cost, mut squares, mut delta_sol, mut delta_ore, mut delta_refined) = (0u64, 0usize, 0u64, 0u64, 0u64);
+    let (mut top_miner_rounds, mut top_miner_ore) = (0usize, 0u64);
+    for event in events.iter() {
+        *counts.entry(event.event.clone()).or_default() += 1;
+        if event.event == "deploy" {
+            if let Some(round) = event.round {
+                rounds.insert(round);
+            }
+            cost += event.cost_lamports.unwrap_or(0);
+            squares += event.squares.unwrap_or(0);
+        }
+        if event.event == "checkpoint" {
+            delta_sol += event.delta_sol_lamports.unwrap_or(0);
+            delta_ore += event.delta_rewards_ore.unwrap_or(0);
+            delta_refined += event.delta_refined_ore.unwrap_or(0);
+        }
+        if let Some(ore) = event.top_miner_ore {
+            top_miner_rounds += 1;
+            top_miner_ore += ore;
+        }
+    }
+    let first_ts = events.iter().map(|e| e.ts).min().unwrap_or(0);
+    let last_ts = events.iter().map(|e| e.ts).max().unwrap_or(0);
+
+    out!("Reward log ({})", path);
+    out!("  events: {}（无法解析 {} 行）", events.len(), invalid);
+    let mut kinds: Vec<_> = counts.into_iter().collect();
+    kinds.sort();
+    for (kind, count) in kinds {
+        out!("    {}: {}", kind, count);
+    }
+    out!(
+        "  time range: {} - {}（{:.2} 天）",
+        first_ts,
+        last_ts,
+        last_ts.saturating_sub(first_ts) as f64 / 86400.0
+    );
+    out!("  rounds deployed: {}，squares: {}", rounds.len(), squares);
+    out!("  deploy cost: {}", fmt_sol(cost));
+    out!("  checkpoint SOL: {}", fmt_sol(delta_sol));
+    out!(
+        "  checkpoint ORE: {}（refined {}）",
+        fmt_ore(delta_ore),
+        fmt_ore(delta_refined)
+    );
+    out!(
+        "  top miner ORE: {}（{} 轮，已含在 checkpoint ORE 中）",
+        fmt_ore(top_miner_ore),
+        top_miner_rounds
+    );
+    out!(
+        "  net SOL: {:.6} SOL",
+        lamports_to_sol(delta_sol) - lamports_to_sol(cost)
+    );
+    Ok(())
+}
+
+// 解析旧版 reward.log 的一行：
+//   [ts] round=.. event=deploy algorithm=.. squares=.. cost_sol=.. cost_lamports=.. tx=..
+//   [ts] round=.. event=checkpoint delta_sol=.. delta_rewards_ore=.. delta_refined_ore=.. tx=..
+//   [ts] event=claim kind=.. tx=.. explorer=..
+// 旧格式中 SOL/ORE 增量为 UI 小数，这里换算回最小单位
+fn parse_legacy_reward_line(line: &str) -> Option<RewardEvent> {
+    let rest = line.trim().strip_prefix('[')?;
+    let (ts, rest) = rest.split_once(']')?;
+    let fields: HashMap<&str, &str> = rest
+        .split_whitespace()
+        .filter_map(|kv| kv.split_once('='))
+        .collect();
+    let mut event = RewardEvent::new(fields.get("event")?);
+    event.ts = ts.trim().parse().ok()?;
+    event.round = fields.get("round").and_then(|v| v.parse().ok());
+    event.tx = fields.get("tx").map(|v| v.to_string());
+    match event.event.as_str() {
+        "deploy" => {
+            event.algorithm = fields.get("algorithm").map(|v| v.to_string());
+            event.squares = fields.get("squares").and_then(|v| v.parse().ok());
+            event.cost_lamports = fields
+                .get("cost_lamports")
+                .and_then(|v| v.parse().ok())
+                .or_else(|| {
+                    fields
+                        .get("cost_sol")
+                        .and_then(|v| v.parse::<f64>().ok())
+                        .map(sol_to_lamports)
+                });
+        }
+        "checkpoint" => {
+            event.delta_sol_lamports = fields
+                .get("delta_sol")
+                .and_then(|v| v.parse::<f64>().ok())
+                .map(sol_to_lamports);
+            event.delta_rewards_ore = fields
+                .get("delta_rewards_ore")
+                .and_then(|v| v.parse::<f64>().ok())
+                .map(|v| ui_amount_to_amount(v, TOKEN_DECIMALS));
+            event.delta_refined_ore = fields
+                .get("delta_refined_ore")
+                .and_then(|v| v.parse::<f64>().ok())
+                .map(|v| ui_amount_to_amount(v, TOKEN_DECIMALS));
+        }
+        "claim" => {
+            event.kind = fields.get("kind").map(|v| v.to_string());
+            event.explorer = fields.get("explorer").map(|v| v.to_string());
+        }
+        _ => return None,
+    }
+    Some(event)
+}
+
+// 将旧版 reward.log（LEGACY_LOG_FILE，默认 reward.log）导入 reward.jsonl：
+// 按 tx 去重、按时间排序后整体重写，旧文件保留不动；DRY_RUN 时只统计
+fn log_import() -> Result<(), anyhow::Error> {
+    let legacy_path = std::env::var("LEGACY_LOG_FILE").unwrap_or("reward.log".to_string());
+    let content = fs::read_to_string(&legacy_path)
+        .map_err(|e| CommandError::config(format!("无法读取 {}: {}", legacy_path, e)))?;
+    let (mut events, imported, duplicate, invalid) = merge_legacy_reward_log(&content);
+    out!(
+        "[import] {}：可导入 {} 条，重复 {} 条，无法解析 {} 行",
+        legacy_path, imported, duplicate, invalid
+    );
+    if imported == 0 {
+        return Err(CommandError::nothing_to_do("没有需要导入的事件").into());
+    }
+    if env_flag("DRY_RUN") {
+        out!("[import] DRY_RUN：未写入 {}", state_path(REWARD_LOG_FILE));
+        return Ok(());
+    }
+
+    rewrite_reward_log(&mut events)?;
+    out!("[import] ✅ 已写入 {}（共 {} 条事件）", state_path(REWARD_LOG_FILE), events.len());
+    Ok(())
+}
+
+// 把旧版 reward.log 的内容合并到现有 reward.jsonl 事件中，返回（合并后的事件, 导入数, 重复数, 无法解析行数）
+fn merge_legacy_reward_log(content: &str) -> (Vec<RewardEvent>, usize, usize, usize) {
+    let mut events: Vec<RewardEvent> = read_current_reward_events();
+    // 已轮转文件中的事件同样参与去重
+    let mut known_txs: std::collections::HashSet<String> = read_reward_events()
+        .iter()
+        .filter_map(|e| e.tx.as_ref().map(|tx| format!("{}:{}", e.event, tx)))
+        .collect();
+
+    let (mut imported, mut duplicate, mut invalid) = (0usize, 0usize, 0usize);
+    for line in content.lines().filter(|l| !l.trim().is_empty()) {
+        let Some(event) = parse_legacy_reward_line(line) else {
+            invalid += 1;
+            continue;
+        };
+        // 同一笔交易的同类事件只导入一次（同一 tx 可能同时有 checkpoint 与 deploy）
+        let key = format!("{}:{}", event.event, event.tx.clone().unwrap_or_default());
+        if event.tx.is_some() && !known_txs.insert(key) {
+            duplicate += 1;
+            continue;
+        }
+        events.push(event);
+        imported += 1;
+    }
+    (events, imported, duplicate, invalid)
+}
+
+// ============ 新增：本地状态迁移 ============
+// 本地状态的格式版本记录在 STATE_VERSION_FILE。启动时按顺序执行高于当前记录的迁移，每完成一步立即写回版本号，
+// 中途失败下次从失败处继续；记录的版本高于本程序支持的版本（新版本运行过后又换回旧二进制）时拒绝运行，
+// 避免旧代码按旧格式改写新数据。新增迁移：在 STATE_MIGRATIONS 末尾追加一项，版本号依次加一。
+// DRY_RUN 时只列出待执行的迁移
+const STATE_VERSION_FILE: &str = "ore.state_version";
+
+type StateMigration = (u32, &'static str, fn() -> Result<(), anyhow::Error>);
+
+const STATE_MIGRATIONS: [StateMigration; 1] = [
+    (1, "旧版 reward.log 导入 reward.jsonl", migrate_legacy_reward_log),
+];
+
+const STATE_VERSION: u32 = STATE_MIGRATIONS.len() as u32;
+
+fn migrate_state() -> Result<(), anyhow::Error> {
+    let path = state_path(STATE_VERSION_FILE);
+    let mut version = match fs::read_to_string(&path) {
+        Ok(content) => content
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| CommandError::config(format!("{} 内容无效: {}", path, content.trim())))?,
+        Err(_) => 0,
+    };
+    if version > STATE_VERSION {
+        return Err(CommandError::config(format!(
+            "本地状态版本 v{}（{}）高于本程序支持的 v{}，请先升级程序再运行",
+            version, path, STATE_VERSION
+        ))
+        .into());
+    }
+    let recorded = version;
+    for (target, description, migrate) in STATE_MIGRATIONS.iter().filter(|(v, _, _)| *v > recorded) {
+        if env_flag("DRY_RUN") {
+            out!("[migrate] DRY_RUN：待执行 v{} → v{}：{}", version, target, description);
+            continue;
+        }
+        out!("[migrate] 本地状态 v{} → v{}：{}", version, target, description);
+        migrate().map_err(|e| anyhow::anyhow!("迁移到 v{} 失败（{}）: {}", target, description, e))?;
+        version = *target;
+        fs::write(&path, version.to_string())?;
+    }
+    Ok(())
+}
+
+// v1：存在旧版 reward.log 时合并进 reward.jsonl（与 log_import 相同的去重规则），旧文件保留不动
+fn migrate_legacy_reward_log() -> Result<(), anyhow::Error> {
+    let legacy_path = std::env::var("LEGACY_LOG_FILE").unwrap_or(state_path("reward.log"));
+    let Ok(content) = fs::read_to_string(&legacy_path) else {
+        return Ok(());
+    };
+    let (mut events, imported, duplicate, invalid) = merge_legacy_reward_log(&content);
+    out!(
+        "[migrate] {}：导入 {} 条，重复 {} 条，无法解析 {} 行",
+        legacy_path, imported, duplicate, invalid
+    );
+    if imported > 0 {
+        rewrite_reward_log(&mut events)?;
+    }
+    Ok(())
+}
+
+// 按时间排序后整体重写奖励日志（先写临时文件再替换）
+fn rewrite_reward_log(events: &mut [RewardEvent]) -> Result<(), anyhow::Error> {
+    events.sort_by_key(|e| e.ts);
+    let mut out = String::new();
+    for event in events.iter() {
+        out.push_str(&serde_json::to_string(event)?);
+        out.push('\n');
+    }
+    let tmp_path = format!("{}.tmp", state_path(REWARD_LOG_FILE));
+    fs::write(&tmp_path, out)?;
+    fs::rename(&tmp_path, state_path(REWARD_LOG_FILE))?;
+    Ok(())
+}
+
+// ============ 新增：交易 memo 标记与链上回填 ============
+// MEMO_TAG=true 时在部署与领取交易中附加 SPL Memo，例如 ore-bot:v1:round=123:strategy=ev:cost=50000:squares=5、
+// ore-bot:v1:claim=sol+ore，链上历史可以明确归属到本程序。
+// COMMAND=backfill 扫描 authority 最近 BACKFILL_LIMIT 笔交易（默认 1000）的 memo，把奖励日志中缺失的部署 / 领取补回去；
+// 中途取消（Ctrl-C / COMMAND_TIMEOUT_SECS）时写入已找到的事件，并把分页位置记到 BACKFILL_CURSOR_FILE，下次运行从该处继续
+const MEMO_PROGRAM_ID: Pubkey = pubkey!("MemoSq4gqABAXKb96qnH8TysNcWxMyWCqXgDLGmfcHr");
+const MEMO_PREFIX: &str = "ore-bot:v1:";
+const BACKFILL_CURSOR_FILE: &str = "ore.backfill.cursor.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackfillCursor {
+    authority: String,
+    before: String,
+    scanned: usize,
+}
+
+fn memo_instruction(fields: &[(&str, String)]) -> Option<solana_sdk::instruction::Instruction> {
+    if !env_flag("MEMO_TAG") {
+        return None;
+    }
+    let pairs: Vec<String> = fields.iter().map(|(k, v)| format!("{}={}", k, v)).collect();
+    Some(solana_sdk::instruction::Instruction {
+        program_id: MEMO_PROGRAM_ID,
+        accounts: vec![],
+        data: format!("{}{}", MEMO_PREFIX, pairs.join(":")).into_bytes(),
+    })
+}
+
+// getSignaturesForAddress 返回的 memo 形如 "[41] ore-bot:v1:..."，多条 memo 以 "; " 分隔
+fn parse_bot_memo(memo: &str) -> Option<HashMap<String, String>> {
+    let start = memo.find(MEMO_PREFIX)? + MEMO_PREFIX.len();
+    let body = memo[start..].split(';').next().unwrap_or_default();
+    Some(
+        body.split(':')
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+            .collect(),
+    )
+}
+
+// 读取交易的签名者（account_keys 的前 num_required_signatures 个，第 0 个为手续费支付者）
+async fn fetch_transaction_signers(rpc: &RpcClient, signature: &solana_sdk::signature::Signature) -> Result<Vec<Pubkey>, anyhow::Error> {
+    let config = solana_client::rpc_config::RpcTransactionConfig {
+        encoding: None,
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    let tx = rpc.get_transaction_with_config(signature, config).await?;
+    let value = serde_json::to_value(&tx.transaction.transaction)?;
+    let message = &value["message"];
+    let required = message["header"]["numRequiredSignatures"].as_u64().unwrap_or(0) as usize;
+    Ok(message["accountKeys"]
+        .as_array()
+        .map(|keys| {
+            keys.iter()
+                .take(required)
+                .filter_map(|k| k.as_str().and_then(|k| Pubkey::from_str(k).ok()))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+fn memo_reward_event(fields: &HashMap<String, String>, signature: &str, ts: u64) -> Option<RewardEvent> {
+    let parse = |key: &str| fields.get(key).and_then(|v| v.parse::<u64>().ok());
+    let mut event = if let Some(kind) = fields.get("claim") {
+        RewardEvent {
+            kind: Some(kind.clone()),
+            ..RewardEvent::new("claim")
+        }
+    } else {
+        RewardEvent {
+            round: Some(parse("round")?),
+            algorithm: fields.get("strategy").cloned(),
+            squares: parse("squares").map(|n| n as usize),
+            cost_lamports: parse("cost"),
+            ..RewardEvent::new("deploy")
+        }
+    };
+    event.ts = ts;
+    event.tx = Some(signature.to_string());
+    Some(event)
+}
+
+// tagged：带本程序 memo 的交易数；foreign：非本钱包签名而被忽略的 memo 交易数；written：是否已写入奖励日志
+#[derive(Debug, Serialize)]
+struct BackfillResult {
+    authority: String,
+    scanned: usize,
+    tagged: usize,
+    imported: usize,
+    foreign: usize,
+    cancelled: bool,
+    written: bool,
+}
+
+async fn backfill(rpc: &RpcClient, payer: &dyn TxSigner) -> Result<BackfillResult, anyhow::Error> {
+    let authority = mining_authority(payer).pubkey();
+    let wallets = [payer.pubkey(), authority];
+    let limit: usize = std::env::var("BACKFILL_LIMIT")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(1000);
+
+    let mut events: Vec<RewardEvent> = read_current_reward_events();
+    // 已轮转文件中的事件同样参与去重
+    let mut known_txs: std::collections::HashSet<String> = read_reward_events()
+        .iter()
+        .filter_map(|e| e.tx.as_ref().map(|tx| format!("{}:{}", e.event, tx)))
+        .collect();
+
+    install_cancellation("backfill");
+    let (mut scanned, mut tagged, mut imported, mut foreign) = (0usize, 0usize, 0usize, 0usize);
+    let mut before = None;
+    if let Some(cursor) = fs::read_to_string(state_path(BACKFILL_CURSOR_FILE))
+        .ok()
+        .and_then(|s| serde_json::from_str::<BackfillCursor>(&s).ok())
+        .filter(|c| c.authority == authority.to_string())
+    {
+        out!("[backfill] 从上次中断处继续（已扫描 {} 笔，before={}）", cursor.scanned, cursor.before);
+        before = Some(solana_sdk::signature::Signature::from_str(&cursor.before)?);
+        scanned = cursor.scanned;
+    }
+    let mut cancelled = false;
+    let mut progress = Progress::new("backfill", limit as u64);
+    progress.set(scanned as u64);
+    while scanned < limit {
+        if stop_requested() {
+            cancelled = true;
+            break;
+        }
+        let page = rpc
+            .get_signatures_for_address_with_config(
+                &authority,
+                solana_client::rpc_client::GetConfirmedSignaturesForAddress2Config {
+                    before,
+                    limit: Some((limit - scanned).min(1000)),
+                    commitment: Some(commitment_for(OpClass::Analytics)),
+                    ..Default::default()
+                },
+            )
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        before = Some(solana_sdk::signature::Signature::from_str(&last.signature)?);
+        scanned += page.len();
+        progress.set(scanned as u64);
+        for entry in page.iter().filter(|e| e.err.is_none()) {
+            let Some(fields) = entry.memo.as_deref().and_then(parse_bot_memo) else {
+                continue;
+            };
+            tagged += 1;
+            let ts = entry.block_time.unwrap_or_default().max(0) as u64;
+            let Some(event) = memo_reward_event(&fields, &entry.signature, ts) else {
+                continue;
+            };
+            let key = format!("{}:{}", event.event, entry.signature);
+            if known_txs.contains(&key) {
+                continue;
+            }
+            // 任何人都可以发送引用该地址的 memo 交易：只导入由付款账户或 authority 签名的交易
+            let signature = solana_sdk::signature::Signature::from_str(&entry.signature)?;
+            let signers = match fetch_transaction_signers(rpc, &signature).await {
+                Ok(signers) => signers,
+                Err(e) => {
+                    progress.println(&format!("[backfill] ⚠️  读取交易 {} 失败，本次跳过: {}", entry.signature, e));
+                    continue;
+                }
+            };
+            if !signers.iter().any(|s| wallets.contains(s)) {
+                foreign += 1;
+                continue;
+            }
+            known_txs.insert(key);
+            events.push(event);
+            imported += 1;
+        }
+    }
+    progress.finish();
+    out!(
+        "[backfill] {}：扫描 {} 笔交易，带本程序 memo {} 笔，奖励日志缺失 {} 条",
+        authority, scanned, tagged, imported
+    );
+    if foreign > 0 {
+        out!("[backfill] ⚠️  忽略 {} 笔非本钱包签名的 memo 交易", foreign);
+    }
+    if !env_flag("DRY_RUN") {
+        match (cancelled, before) {
+            (true, Some(before)) => {
+                let cursor = BackfillCursor {
+                    authority: authority.to_string(),
+                    before: before.to_string(),
+                    scanned,
+                };
+                fs::write(state_path(BACKFILL_CURSOR_FILE), serde_json::to_string(&cursor)?)?;
+                out!("[backfill] 已取消，进度已保存到 {}，再次运行时继续", state_path(BACKFILL_CURSOR_FILE));
+            }
+            _ => {
+                let _ = fs::remove_file(state_path(BACKFILL_CURSOR_FILE));
+            }
+        }
+    }
+    if imported == 0 {
+        return Err(CommandError::nothing_to_do("没有需要回填的事件").into());
+    }
+    let mut result = BackfillResult {
+        authority: authority.to_string(),
+        scanned,
+        tagged,
+        imported,
+        foreign,
+        cancelled,
+        written: false,
+    };
+    if env_flag("DRY_RUN") {
+        out!("[backfill] DRY_RUN：未写入 {}", state_path(REWARD_LOG_FILE));
+        return Ok(result);
+    }
+    rewrite_reward_log(&mut events)?;
+    out!("[backfill] ✅ 已写入 {}（共 {} 条事件）", state_path(REWARD_LOG_FILE), events.len());
+    result.written = true;
+    Ok(result)
+}
+
+// ============ 新增：远程停止开关（kill-switch） ============
+// 一处设置即可让所有实例（auto_mine / fleet）停止部署，checkpoint / claim 不受影响：
+//   KILL_SWITCH_URL      GET 返回 "1" / "true" / "halt"，或 JSON 中 halt / kill 为 true 时视为已停止
+//   KILL_SWITCH_ACCOUNT  链上账户：最近一条带 ore-bot:v1:kill=on|off memo、且由 KILL_SWITCH_SIGNER 签名的交易
+//                        决定开关状态，其他人发送的 memo 一律忽略；用 COMMAND=kill_switch KILL_SWITCH=on|off 发送
+//                        （建议使用专用账户，避免被其他交易挤出查询范围）
+//   KILL_SWITCH_SIGNER   设置 KILL_SWITCH_ACCOUNT 时必填：允许切换开关的公钥
+//   KILL_SWITCH_SECS     检查间隔（默认 30 秒），后台任务检查，不阻塞部署循环
+// 读取失败时保持上一次的状态
+static KILL_SWITCH_ENGAGED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+fn kill_switch_engaged() -> bool {
+    KILL_SWITCH_ENGAGED.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+fn kill_switch_account() -> Result<Option<Pubkey>, anyhow::Error> {
+    match std::env::var("KILL_SWITCH_ACCOUNT") {
+        Ok(value) => Pubkey::from_str(value.trim())
+            .map(Some)
+            .map_err(|_| CommandError::config(format!("KILL_SWITCH_ACCOUNT 不是有效的公钥: {}", value)).into()),
+        Err(_) => Ok(None),
+    }
+}
+
+async fn read_kill_switch_url(url: &str) -> Result<bool, anyhow::Error> {
+    let request = solana_client::client_error::reqwest::Client::new()
+        .get(url)
+        .timeout(Duration::from_secs(10));
+    let response = with_env_headers(request, "KILL_SWITCH_HEADERS").send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("HTTP {}", response.status()));
+    }
+    let body = response.text().await?;
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(&body) {
+        if value.is_object() {
+            return Ok(value["halt"].as_bool().or(value["kill"].as_bool()).unwrap_or(false));
+        }
+    }
+    Ok(matches!(body.trim().to_lowercase().as_str(), "1" | "true" | "halt" | "on"))
+}
+
+fn kill_switch_signer() -> Result<Pubkey, anyhow::Error> {
+    parse_env_opt::<Pubkey>("KILL_SWITCH_SIGNER")?
+        .ok_or_else(|| CommandError::config("KILL_SWITCH_ACCOUNT 需要同时设置 KILL_SWITCH_SIGNER").into())
+}
+
+// 从新到旧找第一条由 signer 签名的 kill memo；verified 缓存已查过的交易签名（签名者是否为 signer）
+async fn read_kill_switch_account(
+    rpc: &RpcClient,
+    account: &Pubkey,
+    signer: &Pubkey,
+    verified: &mut HashMap<String, bool>,
+) -> Result<bool, anyhow::Error> {
+    let recent = rpc.get_signatures_for_address(account).await?;
+    for entry in recent.iter().filter(|e| e.err.is_none()) {
+        let Some(flag) = entry.memo.as_deref().and_then(parse_bot_memo).and_then(|f| f.get("kill").cloned()) else {
+            continue;
+        };
+        let authorized = match verified.get(&entry.signature) {
+            Some(authorized) => *authorized,
+            None => {
+                let signature = solana_sdk::signature::Signature::from_str(&entry.signature)?;
+                let authorized = fetch_transaction_signers(rpc, &signature).await?.contains(signer);
+                verified.insert(entry.signature.clone(), authorized);
+                authorized
+            }
+        };
+        if authorized {
+            return Ok(flag == "on");
+        }
+    }
+    Ok(false)
+}
+
+// 启动后台检查任务；未配置任何开关时不启动
+fn start_kill_switch_watch() -> Result<(), anyhow::Error> {
+    let url = std::env::var("KILL_SWITCH_URL").ok().filter(|s| !s.trim().is_empty());
+    let account = kill_switch_account()?;
+    if url.is_none() && account.is_none() {
+        return Ok(());
+    }
+    let signer = match account {
+        Some(_) => Some(kill_switch_signer()?),
+        None => None,
+    };
+    let interval = Duration::from_secs(
+        std::env::var("KILL_SWITCH_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30)
+            .max(1),
+    );
+    let rpc = RpcClient::new_with_commitment(required_env("RPC")?, commitment_for(OpClass::Confirmation));
+    out!("[kill] 已启用远程停止开关，每 {} 秒检查一次", interval.as_secs());
+    tokio::spawn(async move {
+        let mut verified = HashMap::new();
+        loop {
+            let mut engaged = None;
+            if let Some(url) = url.as_deref() {
+                match read_kill_switch_url(url).await {
+                    Ok(flag) => engaged = Some(flag),
+                    Err(e) => out!("[kill] ⚠️  读取 KILL_SWITCH_URL 失败，保持当前状态: {}", e),
+                }
+            }
+            if let (Some(account), Some(signer)) = (account.as_ref(), signer.as_ref()) {
+                match read_kill_switch_account(&rpc, account, signer, &mut verified).await {
+                    Ok(flag) => engaged = Some(engaged.unwrap_or(false) || flag),
+                    Err(e) => out!("[kill] ⚠️  读取 KILL_SWITCH_ACCOUNT 失败，保持当前状态: {}", e),
+                }
+            }
+            if let Some(engaged) = engaged {
+                let previous = KILL_SWITCH_ENGAGED.swap(engaged, std::sync::atomic::Ordering::Relaxed);
+                if engaged && !previous {
+                    out!("[kill] 🛑 远程停止开关已开启，停止部署（checkpoint / claim 继续）");
+                    notify_alert("kill_switch", "远程停止开关已开启，所有实例停止部署");
+                } else if !engaged && previous {
+                    out!("[kill] ✅ 远程停止开关已关闭，恢复部署");
+                }
+            }
+            sleep(interval).await;
+        }
+    });
+    Ok(())
+}
+
+async fn kill_switch(rpc: &RpcClient, payer: &dyn TxSigner) -> Result<(), anyhow::Error> {
+    let account = kill_switch_account()?.ok_or_else(|| CommandError::config("KILL_SWITCH_ACCOUNT 未设置"))?;
+    let signer = kill_switch_signer()?;
+    let state = match std::env::var("KILL_SWITCH").as_deref() {
+        Ok("on") => "on",
+        Ok("off") => "off",
+        _ => {
+            let current = read_kill_switch_account(rpc, &account, &signer, &mut HashMap::new()).await?;
+            out!("[kill] {} 当前状态：{}", account, if current { "on（停止部署）" } else { "off" });
+            return Ok(());
+        }
+    };
+    if payer.pubkey() != signer {
+        return Err(CommandError::config(format!(
+            "KEYPAIR {} 不是 KILL_SWITCH_SIGNER {}，发送的开关会被忽略",
+            payer.pubkey(),
+            signer
+        ))
+        .into());
+    }
+    // 0 lamports 转账让交易出现在该账户的签名列表中，memo 记录开关状态
+    let instructions = [
+        solana_sdk::system_instruction::transfer(&payer.pubkey(), &account, 0),
+        solana_sdk::instruction::Instruction {
+            program_id: MEMO_PROGRAM_ID,
+            accounts: vec![],
+            data: format!("{}kill={}", MEMO_PREFIX, state).into_bytes(),
+        },
+    ];
+    if env_flag("DRY_RUN") {
+        out!("[kill] 预览模式（DRY_RUN），不发送交易：{} -> {}", account, state);
+        return Ok(());
+    }
+    let sig = submit_transaction(rpc, payer, &instructions).await?;
+    out!("[kill] ✅ 已设置 {} kill={}，交易签名: {}", account, state, sig);
+    Ok(())
+}
+
+// ============ 新增：通知（SMTP 邮件 / Webhook） ============
+
+// 奖励事件的可读摘要，用作通知正文
+fn describe_reward_event(event: &RewardEvent) -> String {
+    match event.event.as_str() {
+        "deploy" => format!(
+            "round {} 部署 {} 个格子，花费 {}",
+            event.round.unwrap_or_default(),
+            event.squares.unwrap_or_default(),
+            fmt_sol(event.cost_lamports.unwrap_or_default())
+        ),
+        "checkpoint" => format!(
+            "round {} 结算：+{}，+{}（refined +{}）",
+            event.round.unwrap_or_default(),
+            fmt_sol(event.delta_sol_lamports.unwrap_or_default()),
+            fmt_ore(event.delta_rewards_ore.unwrap_or_default()),
+            fmt_ore(event.delta_refined_ore.unwrap_or_default())
+        ),
+        "claim" => format!(
+            "领取 ({}) 已上链 {}",
+            event.kind.clone().unwrap_or_default(),
+            event.explorer.clone().unwrap_or_default()
+        ),
+        "cooldown" => format!("亏损冷却：{}", event.kind.clone().unwrap_or_default()),
+        "cooldown_end" => "亏损冷却结束，已恢复部署".to_string(),
+        "balance_drift" | "arb_signal" | "top_miner_status" | "expiry_guard" | "rpc_blackout" | "rpc_recovered" => event.kind.clone().unwrap_or_default(),
+        "top_miner" => format!(
+            "round {} 成为 top miner：+{}",
+            event.round.unwrap_or_default(),
+            fmt_ore(event.top_miner_ore.unwrap_or_default())
+        ),
+        other => other.to_string(),
+    }
+}
+
+// 模板渲染：{字段名} 替换为事件 JSON 中对应字段，另提供 {summary}
+fn render_template(template: &str, event: &RewardEvent) -> String {
+    render_template_with(template, event, |s| s.to_string())
+}
+
+// JSON 模板：替换值按 JSON 字符串转义，模板中写 "text": "{summary}" 即可
+fn render_json_template(template: &str, event: &RewardEvent) -> String {
+    render_template_with(template, event, |s| {
+        let quoted = serde_json::to_string(s).unwrap_or_default();
+        quoted[1..quoted.len() - 1].to_string()
+    })
+}
+
+fn render_template_with(template: &str, event: &RewardEvent, escape: fn(&str) -> String) -> String {
+    let mut out = template.replace("{summary}", &escape(&describe_reward_event(event)));
+    if let Ok(serde_json::Value::Object(fields)) = serde_json::to_value(event) {
+        for (key, value) in fields {
+            let value = match value {
+                serde_json::Value::String(s) => s,
+                other => other.to_string(),
+            };
+            out = out.replace(&format!("{{{}}}", key), &escape(&value));
+        }
+    }
+    out
+}
+
+// NOTIFY_EVENTS：逗号分隔的事件类型（deploy,checkpoint,claim），未设置时全部通知
+fn notify_enabled_for(event: &str) -> bool {
+    match std::env::var("NOTIFY_EVENTS") {
+        Ok(list) => list.split(',').any(|e| e.trim() == event),
+        Err(_) => true,
+    }
+}
+
+// 尚未完成的通知任务，命令退出前统一等待（见 flush_notifications）
+static PENDING_NOTIFICATIONS: std::sync::Mutex<Vec<tokio::task::JoinHandle<()>>> =
+    std::sync::Mutex::new(Vec::new());
+
+fn track_notification(handle: tokio::task::JoinHandle<()>) {
+    if let Ok(mut pending) = PENDING_NOTIFICATIONS.lock() {
+        pending.retain(|h| !h.is_finished());
+        pending.push(handle);
+    }
+}
+
+// 等待未完成的通知（每个最多 15 秒），避免短命令退出时丢失通知
+async fn flush_notifications() {
+    let pending: Vec<_> = match PENDING_NOTIFICATIONS.lock() {
+        Ok(mut pending) => pending.drain(..).collect(),
+        Err(_) => return,
+    };
+    for handle in pending {
+        let _ = tokio::time::timeout(Duration::from_secs(15), handle).await;
+    }
+}
+
+// 分发到已配置的通知后端；在后台任务中发送，不阻塞挖矿循环
+fn notify_reward_event(event: &RewardEvent) {
+    if !notify_enabled_for(&event.event) {
+        return;
+    }
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    if let Some(smtp) = SmtpSettings::from_env() {
+        let subject = render_template(
+            &std::env::var("SMTP_SUBJECT_TEMPLATE").unwrap_or("[ore] {event} {round}".to_string()),
+            event,
+        );
+        let body = render_template(
+            &std::env::var("SMTP_BODY_TEMPLATE").unwrap_or("{summary}\ntx: {tx}".to_string()),
+            event,
+        );
+        track_notification(runtime.spawn_blocking(move || {
+            if let Err(e) = smtp.send(&subject, &body, None) {
+                out!("[notify] ⚠️  邮件发送失败: {}", e);
+            }
+        }));
+    }
+    if let Ok(url) = std::env::var("WEBHOOK_URL") {
+        if url.trim().is_empty() {
+            return;
+        }
+        // 未设置 WEBHOOK_TEMPLATE 时直接发送事件 JSON（附带 summary 字段）
+        let payload = match std::env::var("WEBHOOK_TEMPLATE") {
+            Ok(template) => render_json_template(&template, event),
+            Err(_) => {
+                let mut value = serde_json::to_value(event).unwrap_or_default();
+                if let Some(fields) = value.as_object_mut() {
+                    fields.insert(
+                        "summary".to_string(),
+                        serde_json::Value::String(describe_reward_event(event)),
+                    );
+                }
+                value.to_string()
+            }
+        };
+        if serde_json::from_str::<serde_json::Value>(&payload).is_err() {
+            out!("[notify] ⚠️  WEBHOOK_TEMPLATE 渲染结果不是合法 JSON，仍按原样发送");
+        }
+        track_notification(runtime.spawn(async move {
+            if let Err(e) = post_webhook(&url, payload, "WEBHOOK_HEADERS").await {
+                out!("[notify] ⚠️  Webhook 发送失败: {}", e);
+            }
+        }));
+    }
+}
+
+// headers_env 指定的环境变量中为额外请求头，格式 "Name: value;Name2: value2"（例如 Authorization）
+// headers_env 格式："Name: value; Name2: value2"
+fn with_env_headers(
+    mut request: solana_client::client_error::reqwest::RequestBuilder,
+    headers_env: &str,
+) -> solana_client::client_error::reqwest::RequestBuilder {
+    if let Ok(headers) = std::env::var(headers_env) {
+        for header in headers.split(';') {
+            if let Some((name, value)) = header.split_once(':') {
+                request = request.header(name.trim(), value.trim());
+            }
+        }
+    }
+    request
+}
+
+async fn post_webhook(url: &str, payload: String, headers_env: &str) -> Result<(), anyhow::Error> {
+    let client = solana_client::client_error::reqwest::Client::new();
+    let request = client
+        .post(url)
+        .header("Content-Type", "application/json")
+        .timeout(Duration::from_secs(10))
+        .body(payload);
+    let response = with_env_headers(request, headers_env).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("HTTP {}", response.status()));
+    }
+    Ok(())
+}
+
+// SMTP 配置：SMTP_URL（smtps://host:465 为隐式 TLS；smtp://host:587 默认强制 STARTTLS，
+// SMTP_INSECURE=true 时允许明文）、SMTP_USER / SMTP_PASSWORD、SMTP_FROM、SMTP_TO（逗号分隔）
+struct SmtpSettings {
+    url: String,
+    user: Option<String>,
+    password: Option<String>,
+    from: String,
+    to: Vec<String>,
+    insecure: bool,
+}
+
+impl SmtpSettings {
+    fn from_env() -> Option<Self> {
+        let url = std::env::var("SMTP_URL").ok().filter(|s| !s.trim().is_empty())?;
+        let from = std::env::var("SMTP_FROM").ok()?;
+        let to: Vec<String> = std::env::var("SMTP_TO")
+            .ok()?
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .collect();
+        if to.is_empty() {
+            return None;
+        }
+        Some(Self {
+            url,
+            user: std::env::var("SMTP_USER").ok(),
+            password: std::env::var("SMTP_PASSWORD").ok(),
+            from,
+            to,
+            insecure: env_flag("SMTP_INSECURE"),
+        })
+    }
+
+    // 通过 curl 发送（需要 curl >= 7.56 以支持 SMTP MIME 附件）
+    fn send(&self, subject: &str, body: &str, attachment: Option<&str>) -> Result<(), anyhow::Error> {
+        let mut cmd = std::process::Command::new("curl");
+        cmd.args(["--silent", "--show-error", "--url", &self.url]);
+        if !self.url.starts_with("smtps://") && !self.insecure {
+            cmd.arg("--ssl-reqd");
+        }
+        // 账号密码经 stdin 的 curl 配置传入，不出现在命令行（ps / /proc 可见）中
+        let mut config = String::new();
+        if let (Some(user), Some(password)) = (&self.user, &self.password) {
+            config.push_str(&format!("user = {}\n", curl_config_value(&format!("{}:{}", user, password))));
+        }
+        cmd.args(["--mail-from", &self.from]);
+        for to in self.to.iter() {
+            cmd.args(["--mail-rcpt", to]);
+        }
+        cmd.args(["-H", &format!("Subject: {}", encode_mime_header(subject))]);
+        cmd.args(["-H", &format!("From: {}", self.from)]);
+        cmd.args(["-H", &format!("To: {}", self.to.join(", "))]);
+        // 正文写入临时文件再由 -F 读取，避免模板中的 ; 或引号被 curl 的 -F 语法解析（stdin 用于传配置）
+        let body_path = create_temp_file("ore.mail", "txt", body.as_bytes())?;
+        cmd.args([
+            "-F",
+            &format!("=<{};type=text/plain; charset=utf-8;encoder=base64", body_path.display()),
+        ]);
+        if let Some(path) = attachment {
+            cmd.args(["-F", &format!("attachment=@{};encoder=base64", path)]);
+        }
+        let result = run_curl_with_config(cmd, &config);
+        let _ = fs::remove_file(&body_path);
+        result
+    }
+}
+
+// curl 配置文件中的字符串值：加双引号，转义 \ 与 "
+fn curl_config_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+// 运行 curl，config（curl -K 格式）经 stdin 传入：密码、带 token 的 URL 等不出现在命令行中
+fn run_curl_with_config(mut cmd: std::process::Command, config: &str) -> Result<(), anyhow::Error> {
+    cmd.args(["--config", "-"])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped());
+    let mut child = cmd.spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(config.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "curl 退出码 {:?}: {}",
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+// 非 ASCII 的邮件头按 RFC 2047 编码为 =?UTF-8?B?...?=
+fn encode_mime_header(value: &str) -> String {
+    if value.is_ascii() {
+        return value.to_string();
+    }
+    format!("=?UTF-8?B?{}?=", base64_encode(value.as_bytes()))
+}
+
+// ============ 新增：开奖通知附带棋盘图片（Telegram / Discord / 邮件） ============
+// 文字网格在手机上很难看清，开奖时渲染一张 5x5 PNG：按部署量着色的热力图，
+// 开奖格子加金色边框，我部署的格子中间画白点。PNG 用 stored deflate 手写编码，不引入图像库；
+// 与邮件一样通过 curl 上传：TELEGRAM_BOT_TOKEN + TELEGRAM_CHAT_ID（sendPhoto）、DISCORD_WEBHOOK_URL（附件），
+// 已配置 SMTP 时作为邮件附件。NOTIFY_EVENTS 中的事件名为 round_result
+const BOARD_CELL_PX: usize = 36;
+const BOARD_GAP_PX: usize = 4;
+
+fn render_board_png(deployed: &[u64; 25], winning_square: usize, mine: &[bool; 25]) -> Vec<u8> {
+    let size = 5 * BOARD_CELL_PX + 6 * BOARD_GAP_PX;
+    let max = deployed.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let mut pixels = vec![[24u8, 24, 32]; size * size];
+    for square in 0..25 {
+        let x0 = BOARD_GAP_PX + (square % 5) * (BOARD_CELL_PX + BOARD_GAP_PX);
+        let y0 = BOARD_GAP_PX + (square / 5) * (BOARD_CELL_PX + BOARD_GAP_PX);
+        // 热力图：深蓝 -> 橙红
+        let t = deployed[square] as f64 / max;
+        let fill = [
+            (40.0 + 200.0 * t) as u8,
+            (60.0 + 60.0 * t) as u8,
+            (120.0 - 90.0 * t) as u8,
+        ];
+        for dy in 0..BOARD_CELL_PX {
+            for dx in 0..BOARD_CELL_PX {
+                let border = dx < 3 || dy < 3 || dx >= BOARD_CELL_PX - 3 || dy >= BOARD_CELL_PX - 3;
+                let center = BOARD_CELL_PX / 2;
+                let dot = dx.abs_diff(center) < 5 && dy.abs_diff(center) < 5;
+                pixels[(y0 + dy) * size + x0 + dx] = if square == winning_square && border {
+                    [255, 200, 0]
+                } else if mine[square] && dot {
+                    [255, 255, 255]
+                } else {
+                    fill
+                };
+            }
+        }
+    }
+
+    // 每行前加 filter 字节 0
+    let mut raw = Vec::with_capacity(size * (size * 3 + 1));
+    for row in pixels.chunks(size) {
+        raw.push(0);
+        for pixel in row {
+            raw.extend_from_slice(pixel);
+        }
+    }
+    let mut ihdr = vec![];
+    ihdr.extend_from_slice(&(size as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(size as u32).to_be_bytes());
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]); // 8 位 RGB
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    png_chunk(&mut png, b"IHDR", &ihdr);
+    png_chunk(&mut png, b"IDAT", &zlib_stored(&raw));
+    png_chunk(&mut png, b"IEND", &[]);
+    png
+}
+
+fn png_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+// 不压缩的 zlib 流（stored 块，每块最多 65535 字节）
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let blocks: Vec<&[u8]> = data.chunks(65_535).collect();
+    for (i, block) in blocks.iter().enumerate() {
+        out.push(if i + 1 == blocks.len() { 1 } else { 0 });
+        let len = block.len() as u16;
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(block);
+    }
+    let (mut a, mut b) = (1u32, 0u32);
+    for byte in data {
+        a = (a + *byte as u32) % 65_521;
+        b = (b + a) % 65_521;
+    }
+    out.extend_from_slice(&((b << 16) | a).to_be_bytes());
+    out
+}
+
+// curl 上传图片（multipart），fields 为普通表单字段；URL（Telegram 的 bot token、Discord webhook 密钥）经 stdin 传入
+fn curl_upload(url: &str, fields: &[(&str, String)], file_field: &str, path: &str) -> Result<(), anyhow::Error> {
+    let mut cmd = std::process::Command::new("curl");
+    cmd.args(["--silent", "--show-error", "--fail", "--max-time", "20"]);
+    for (name, value) in fields {
+        // --form-string 不解析 @ 与 <，caption 中的任意文本都按原样发送
+        cmd.args(["--form-string", &format!("{}={}", name, value)]);
+    }
+    cmd.args(["-F", &format!("{}=@{};type=image/png", file_field, path)]);
+    run_curl_with_config(cmd, &format!("url = {}\n", curl_config_value(url)))
+}
+
+fn notify_round_result(result: &RoundResult, my_squares: &[usize], won: bool) {
+    if !notify_enabled_for("round_result") {
+        return;
+    }
+    let telegram = match (std::env::var("TELEGRAM_BOT_TOKEN"), std::env::var("TELEGRAM_CHAT_ID")) {
+        (Ok(token), Ok(chat)) if !token.trim().is_empty() => Some((token, chat)),
+        _ => None,
+    };
+    let discord = std::env::var("DISCORD_WEBHOOK_URL").ok().filter(|s| !s.trim().is_empty());
+    let smtp = SmtpSettings::from_env();
+    if telegram.is_none() && discord.is_none() && smtp.is_none() {
+        return;
+    }
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+
+    let mut mine = [false; 25];
+    for square in my_squares.iter().filter(|s| **s < 25) {
+        mine[*square] = true;
+    }
+    let round_id = result.round_id;
+    let winning_square = result.winning_square as usize;
+    let caption = format!(
+        "round {} 开奖格子 #{}，{}（我部署 {} 格），总部署 {}",
+        result.round_id,
+        winning_square,
+        if won { "已命中" } else { "未命中" },
+        my_squares.len(),
+        fmt_sol(result.total_deployed)
+    );
+    let png = render_board_png(&result.deployed, winning_square, &mine);
+    let path = match create_temp_file(&format!("ore.board.{}", result.round_id), "png", &png) {
+        Ok(path) => path,
+        Err(e) => {
+            out!("[notify] ⚠️  写入棋盘图片失败: {}", e);
+            return;
+        }
+    };
+
+    track_notification(runtime.spawn_blocking(move || {
+        let file = path.to_string_lossy().to_string();
+        if let Some((token, chat)) = telegram {
+            let url = format!("https://api.telegram.org/bot{}/sendPhoto", token.trim());
+            let fields = [("chat_id", chat), ("caption", caption.clone())];
+            if let Err(e) = curl_upload(&url, &fields, "photo", &file) {
+                out!("[notify] ⚠️  Telegram 发送失败: {}", e);
+            }
+        }
+        if let Some(url) = discord {
+            let payload = serde_json::json!({ "content": &caption }).to_string();
+            if let Err(e) = curl_upload(&url, &[("payload_json", payload)], "files[0]", &file) {
+                out!("[notify] ⚠️  Discord 发送失败: {}", e);
+            }
+        }
+        if let Some(smtp) = smtp {
+            let subject = format!("[ore] round_result {}", round_id);
+            if let Err(e) = smtp.send(&subject, &caption, Some(&file)) {
+                out!("[notify] ⚠️  邮件发送失败: {}", e);
+            }
+        }
+        let _ = fs::remove_file(&file);
+    }));
+}
+
+// 在临时目录新建文件：create_new 不会打开已存在的文件或符号链接，重名时换名重试；unix 下只有本用户可读写
+fn create_temp_file(prefix: &str, extension: &str, contents: &[u8]) -> io::Result<std::path::PathBuf> {
+    for attempt in 0..16 {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "{}.{}.{}.{}.{}",
+            prefix,
+            std::process::id(),
+            nanos,
+            attempt,
+            extension
+        ));
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        match options.open(&path) {
+            Ok(mut file) => {
+                file.write_all(contents)?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Err(io::Error::new(io::ErrorKind::AlreadyExists, "无法创建临时文件"))
+}
+
+// 通用文本告警：发送到已配置的 Telegram / Discord / Webhook / 邮件，event 受 NOTIFY_EVENTS 控制
+fn notify_alert(event: &str, text: &str) {
+    if !notify_enabled_for(event) {
+        return;
+    }
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    if let (Ok(token), Ok(chat)) = (std::env::var("TELEGRAM_BOT_TOKEN"), std::env::var("TELEGRAM_CHAT_ID")) {
+        let url = format!("https://api.telegram.org/bot{}/sendMessage", token.trim());
+        let payload = serde_json::json!({ "chat_id": chat, "text": text }).to_string();
+        track_notification(runtime.spawn(async move {
+            if let Err(e) = post_webhook(&url, payload, "").await {
+                out!("[notify] ⚠️  Telegram 发送失败: {}", e);
+            }
+        }));
+    }
+    if let Ok(url) = std::env::var("DISCORD_WEBHOOK_URL") {
+        let payload = serde_json::json!({ "content": text }).to_string();
+        track_notification(runtime.spawn(async move {
+            if let Err(e) = post_webhook(&url, payload, "").await {
+                out!("[notify] ⚠️  Discord 发送失败: {}", e);
+            }
+        }));
+    }
+    if let Ok(url) = std::env::var("WEBHOOK_URL") {
+        let payload = serde_json::json!({ "event": event, "summary": text }).to_string();
+        track_notification(runtime.spawn(async move {
+            if let Err(e) = post_webhook(&url, payload, "WEBHOOK_HEADERS").await {
+                out!("[notify] ⚠️  Webhook 发送失败: {}", e);
+            }
+        }));
+    }
+    if let Some(smtp) = SmtpSettings::from_env() {
+        let subject = format!("[ore] {}", event);
+        let body = text.to_string();
+        track_notification(runtime.spawn_blocking(move || {
+            if let Err(e) = smtp.send(&subject, &body, None) {
+                out!("[notify] ⚠️  邮件发送失败: {}", e);
+            }
+        }));
+    }
+}
+
+// unix 时间戳 -> UTC 日期（YYYY-MM-DD）
+fn utc_date(ts: u64) -> String {
+    // civil_from_days（Howard Hinnant 算法）
+    let z = (ts / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+const PNL_DAILY_CSV_FILE: &str = "ore.pnl_daily.csv";
+// top_miner_ore 已包含在 reward_ore 中，单列展示
+const PNL_DAILY_CSV_HEADER: &str = "date,deploys,cost_sol,reward_sol,reward_ore,net_sol,top_miner_ore";
+
+// 按 UTC 日汇总 reward.jsonl，写出每日 PnL CSV，返回 (日期, CSV 行) 列表
+fn write_daily_pnl_csv() -> Result<Vec<(String, String)>, anyhow::Error> {
+    let mut days: std::collections::BTreeMap<String, (usize, u64, u64, u64, u64)> =
+        std::collections::BTreeMap::new();
+    for event in read_reward_events() {
+        let day = days.entry(utc_date(event.ts)).or_default();
+        if event.event == "deploy" {
+            day.0 += 1;
+            day.1 += event.cost_lamports.unwrap_or(0);
+        }
+        if event.event == "checkpoint" {
+            day.2 += event.delta_sol_lamports.unwrap_or(0);
+            day.3 += event.delta_rewards_ore.unwrap_or(0) + event.delta_refined_ore.unwrap_or(0);
+        }
+        day.4 += event.top_miner_ore.unwrap_or(0);
+    }
+    let mut rows = vec![];
+    let mut csv = format!("{}\n", PNL_DAILY_CSV_HEADER);
+    for (date, (deploys, cost, reward_sol, reward_ore, top_miner_ore)) in days {
+        let row = format!(
+            "{},{},{:.9},{:.9},{},{:.9},{}",
+            date,
+            deploys,
+            lamports_to_sol(cost),
+            lamports_to_sol(reward_sol),
+            amount_to_ui_amount(reward_ore, TOKEN_DECIMALS),
+            lamports_to_sol(reward_sol) - lamports_to_sol(cost),
+            amount_to_ui_amount(top_miner_ore, TOKEN_DECIMALS)
+        );
+        csv.push_str(&row);
+        csv.push('\n');
+        rows.push((date, row));
+    }
+    fs::write(state_path(PNL_DAILY_CSV_FILE), csv)?;
+    Ok(rows)
+}
+
+// 日终摘要：生成每日 PnL CSV，并通过 SMTP 发送当日摘要（附 CSV），适合由 cron 在每日结束时调用
+fn digest() -> Result<(), anyhow::Error> {
+    let rows = write_daily_pnl_csv()?;
+    let today = utc_date(chain_now());
+    let today_row = rows
+        .iter()
+        .find(|(date, _)| *date == today)
+        .map(|(_, row)| row.clone())
+        .unwrap_or(format!("{},0,0,0,0,0,0", today));
+    out!("[digest] 已写入 {}（{} 天）", state_path(PNL_DAILY_CSV_FILE), rows.len());
+    out!("[digest] {}", PNL_DAILY_CSV_HEADER);
+    out!("[digest] {}", today_row);
+
+    let Some(smtp) = SmtpSettings::from_env() else {
+        out!("[digest] 未配置 SMTP_URL / SMTP_FROM / SMTP_TO，跳过邮件发送");
+        return Ok(());
+    };
+    let subject = std::env::var("SMTP_DIGEST_SUBJECT")
+        .unwrap_or("[ore] 日终摘要 {date}".to_string())
+        .replace("{date}", &today);
+    let body = format!(
+        "{} 日终摘要\n\n{}\n{}\n\n完整每日 PnL 见附件。",
+        today, PNL_DAILY_CSV_HEADER, today_row
+    );
+    smtp.send(&subject, &body, Some(&state_path(PNL_DAILY_CSV_FILE)))?;
+    out!("[digest] ✅ 邮件已发送至 {}", smtp.to.join(", "));
+    Ok(())
+}
+
+// ============ 新增：挖矿与质押收益率对比 ============
+// COMMAND=compare：对比 reward.jsonl 中的已实现挖矿收益与同等资金质押 ORE 的收益。
+// 质押 APY 由 ore.stake_history.jsonl 中 Treasury stake_rewards_factor 的增长推算（每单位质押的累计奖励，与账户无关），
+// 至少需要两个相隔一段时间的样本（stake / stake_record 记录）。
+// 资金规模取 COMPARE_CAPITAL_SOL，未设置时以单日最大部署花费近似挖矿占用的资金；ORE 按 Meteora 中间价折算 SOL
+fn derived_staking_apy() -> Option<(f64, f64, usize)> {
+    let mut samples: Vec<StakeSample> = fs::read_to_string(state_path(STAKE_HISTORY_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<StakeSample>(line).ok())
+        .collect();
+    samples.sort_by_key(|s| s.timestamp);
+    let (first, last) = (samples.first()?, samples.last()?);
+    let days = last.timestamp.saturating_sub(first.timestamp) as f64 / 86400.0;
+    if days < 1.0 / 24.0 {
+        return None;
+    }
+    let growth = (last.treasury_stake_rewards_factor - first.treasury_stake_rewards_factor).max(0.0);
+    Some((growth / days * 365.0, days, samples.len()))
+}
+
+async fn compare(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let events: Vec<RewardEvent> = read_reward_events();
+    let (Some(first), Some(last)) = (events.iter().map(|e| e.ts).min(), events.iter().map(|e| e.ts).max()) else {
+        return Err(CommandError::nothing_to_do("reward.jsonl 中没有记录，无法计算挖矿收益").into());
+    };
+    let Some((apy, sample_days, sample_count)) = derived_staking_apy() else {
+        return Err(CommandError::nothing_to_do(
+            "质押历史样本不足（至少需要两个相隔 1 小时以上的样本），请先运行 stake_record",
+        )
+        .into());
+    };
+    let days = ((last - first) as f64 / 86400.0).max(1.0);
+
+    let mut cost = 0u64;
+    let mut reward_sol = 0u64;
+    let mut reward_ore = 0u64;
+    let mut daily_cost: HashMap<String, u64> = HashMap::new();
+    for event in events.iter() {
+        if event.event == "deploy" {
+            cost += event.cost_lamports.unwrap_or(0);
+            *daily_cost.entry(utc_date(event.ts)).or_default() += event.cost_lamports.unwrap_or(0);
+        }
+        if event.event == "checkpoint" {
+            reward_sol += event.delta_sol_lamports.unwrap_or(0);
+            reward_ore += event.delta_rewards_ore.unwrap_or(0) + event.delta_refined_ore.unwrap_or(0);
+        }
+    }
+    let price = get_meteora_reserves(rpc, known_address("METEORA_POOL", DEFAULT_METEORA_POOL))
+        .await?
+        .mid_price();
+    if price <= 0.0 {
+        return Err(CommandError::rpc("Meteora 池子价格为 0，无法折算").into());
+    }
+    let ore_value = sol_to_lamports(amount_to_ui_amount(reward_ore, TOKEN_DECIMALS) * price);
+    let mining_net = (reward_sol + ore_value) as i64 - cost as i64;
+    let capital = match std::env::var("COMPARE_CAPITAL_SOL") {
+        Ok(_) => sol_to_lamports(env_f64("COMPARE_CAPITAL_SOL", 0.0)),
+        Err(_) => daily_cost.values().copied().max().unwrap_or(0),
+    };
+    if capital == 0 {
+        return Err(CommandError::config("无法确定资金规模：没有部署记录且未设置 COMPARE_CAPITAL_SOL").into());
+    }
+    let mining_return = mining_net as f64 / capital as f64;
+    let mining_annualized = mining_return / days * 365.0;
+    let staking_ore = lamports_to_sol(capital) / price * apy * days / 365.0;
+    let staking_value = sol_to_lamports(staking_ore * price) as i64;
+
+    out!("收益率对比（{} ~ {}，{:.1} 天）", utc_date(first), utc_date(last), days);
+    out!("  资金规模        {}{}", fmt_sol(capital), if std::env::var("COMPARE_CAPITAL_SOL").is_ok() { "" } else { "（单日最大部署花费）" });
+    out!("  ORE 价格        {:.6} SOL", price);
+    out!("挖矿（已实现）");
+    out!("  部署花费        {}", fmt_sol(cost));
+    out!("  SOL 奖励        {}", fmt_sol(reward_sol));
+    out!("  ORE 奖励        {}（≈ {}）", fmt_ore(reward_ore), fmt_sol(ore_value));
+    out!("  净收益          {}", fmt_sol_signed(mining_net));
+    out!("  资金收益率      {:+.2}%（年化 {:+.2}%）", mining_return * 100.0, mining_annualized * 100.0);
+    out!("质押（同等资金）");
+    out!("  推算 APY        {:.2}%（{} 个样本，跨 {:.1} 天）", apy * 100.0, sample_count, sample_days);
+    out!("  预期奖励        {}（≈ {}）", fmt_ore(ui_amount_to_amount(staking_ore, TOKEN_DECIMALS)), fmt_sol(staking_value as u64));
+    let diff = mining_net - staking_value;
+    out!(
+        "结论：同期挖矿比质押{} {}",
+        if diff >= 0 { "多赚" } else { "少赚" },
+        fmt_sol(diff.unsigned_abs())
+    );
+    Ok(())
+}
+
+// ============ 新增：对外事件流（round_started / deployed / round_settled / claimed） ============
+
+// 事件中的 authority（由 main 在读取 keypair 后设置）
+static EVENT_STREAM_AUTHORITY: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+// 统一格式：{"type", "ts", "round", "authority", "data"}，POST 到 EVENT_STREAM_URL（请求头见 EVENT_STREAM_HEADERS）
+fn emit_stream_event(kind: &str, round: Option<u64>, data: serde_json::Value) {
+    let Ok(url) = std::env::var("EVENT_STREAM_URL") else {
+        return;
+    };
+    if url.trim().is_empty() {
+        return;
+    }
+    let Ok(runtime) = tokio::runtime::Handle::try_current() else {
+        return;
+    };
+    let payload = serde_json::json!({
+        "type": kind,
+        "ts": chain_now(),
+        "round": round,
+        "authority": EVENT_STREAM_AUTHORITY.get(),
+        "data": data,
+    })
+    .to_string();
+    track_notification(runtime.spawn(async move {
+        if let Err(e) = post_webhook(&url, payload, "EVENT_STREAM_HEADERS").await {
+            out!("[stream] ⚠️  事件推送失败: {}", e);
+        }
+    }));
+}
+
+// deploy / claim 奖励事件映射为 deployed / claimed
+fn emit_reward_stream_event(event: &RewardEvent) {
+    let kind = match event.event.as_str() {
+        "deploy" => "deployed",
+        "claim" => "claimed",
+        _ => return,
+    };
+    let mut data = serde_json::to_value(event).unwrap_or_default();
+    if let Some(fields) = data.as_object_mut() {
+        for key in ["v", "ts", "event", "round"] {
+            fields.remove(key);
+        }
+    }
+    emit_stream_event(kind, event.round, data);
+}
+
+fn emit_round_started(board: &Board) {
+    emit_stream_event(
+        "round_started",
+        Some(board.round_id),
+        serde_json::json!({
+            "start_slot": board.start_slot,
+            "end_slot": board.end_slot,
+        }),
+    );
+}
+
+// 上一轮结束后读取其 Round 账户：记录开奖结果（供归档）并推送 round_settled（slot_hash 尚未写入时跳过）
+async fn record_round_settled(rpc: &RpcClient, round_id: u64) -> Option<RoundResult> {
+    let round = get_round(rpc, round_id).await.ok()?;
+    let result = RoundResult::from_round(&round)?;
+    append_round_result(&result);
+    emit_stream_event(
+        "round_settled",
+        Some(round_id),
+        serde_json::json!({
+            "winning_square": result.winning_square,
+            "total_deployed": round.total_deployed,
+            "total_vaulted": round.total_vaulted,
+            "total_winnings": round.total_winnings,
+            "top_miner": result.top_miner,
+            "top_miner_reward": round.top_miner_reward,
+            "motherlode": round.motherlode,
+        }),
+    );
+    Some(result)
+}
+
+// checkpoint 后读取 Miner，记录本次结算得到的奖励增量
+async fn log_checkpoint_rewards(
+    rpc: &RpcClient,
+    authority: Pubkey,
+    miner_before: &Miner,
+    sig: &solana_sdk::signature::Signature,
+) -> Option<u64> {
+    let commitment = commitment_for(OpClass::Confirmation);
+    let miner_after = get_miner_with_commitment(rpc, authority, commitment).await.ok()?;
+    let delta_rewards_sol = miner_after
+        .rewards_sol
+        .saturating_sub(miner_before.rewards_sol);
+    let delta_rewards_ore = miner_after
+        .rewards_ore
+        .saturating_sub(miner_before.rewards_ore);
+    let delta_refined_ore = miner_after
+        .refined_ore
+        .saturating_sub(miner_before.refined_ore);
+    append_reward_event(RewardEvent {
+        round: Some(miner_before.round_id),
+        delta_sol_lamports: Some(delta_rewards_sol),
+        delta_rewards_ore: Some(delta_rewards_ore),
+        delta_refined_ore: Some(delta_refined_ore),
+        tx: Some(sig.to_string()),
+        ..RewardEvent::new("checkpoint")
+    });
+    Some(delta_rewards_sol)
+}
+
+// ============ 新增：过期保护 ============
+// 独立于主循环的后台任务：每 EXPIRY_GUARD_INTERVAL_SECS 秒（默认 300）检查我参与过但尚未 checkpoint 的回合，
+// 距 expires_at 不足 EXPIRY_GUARD_SLOTS（默认 2 小时）时以 EXPIRY_GUARD_CU_PRICE（默认 100,000 microlamports/CU）强制提交 checkpoint，
+// 主循环卡在等待、冷却或 RPC 重试时奖励也不会因过期丢失
+// auto_mine 默认启动（EXPIRY_GUARD=false 关闭），也可单独运行 COMMAND=expiry_guard
+struct ExpiryGuard {
+    interval: Duration,
+    slots_before: u64,
+    cu_price: u64,
+}
+
+impl ExpiryGuard {
+    fn from_env() -> Self {
+        let parse = |key: &str, default: u64| {
+            std::env::var(key)
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(default)
+        };
+        Self {
+            interval: Duration::from_secs(parse("EXPIRY_GUARD_INTERVAL_SECS", 300).max(1)),
+            slots_before: parse("EXPIRY_GUARD_SLOTS", 2 * ONE_HOUR_SLOTS),
+            cu_price: parse("EXPIRY_GUARD_CU_PRICE", 100_000),
+        }
+    }
+
+    async fn check(&self, rpc: &RpcClient, payer: &dyn TxSigner, authority: Pubkey) -> Result<(), anyhow::Error> {
+        let miner = get_miner(rpc, authority).await?;
+        let board = get_board(rpc).await?;
+        if !MinerView::new(miner).needs_checkpoint(board.round_id) {
+            return Ok(());
+        }
+        let clock = get_clock(rpc).await?;
+        let round = get_round(rpc, miner.round_id).await?;
+        if clock.slot >= round.expires_at {
+            out!("[expiry] ❌ round {} 已过期，无法再 checkpoint", miner.round_id);
+            return Ok(());
+        }
+        let slots_left = round.expires_at - clock.slot;
+        if slots_left > self.slots_before {
+            return Ok(());
+        }
+        out!(
+            "[expiry] ⚠️  round {} 距过期约 {:.0} 秒仍未 checkpoint，以 {} microlamports/CU 强制提交",
+            miner.round_id,
+            slots_to_secs(slots_left),
+            self.cu_price
+        );
+        let ix = ore_api::sdk::checkpoint(payer.pubkey(), authority, miner.round_id);
+        let compute_unit_limit: u32 = std::env::var("COMPUTE_UNIT_LIMIT")
+            .ok()
+            .and_then(|s| s.parse::<u32>().ok())
+            .unwrap_or(1_400_000);
+        let sig = submit_transaction_with_fee(rpc, payer, &[ix], self.cu_price, compute_unit_limit).await?;
+        log_checkpoint_rewards(rpc, authority, &miner, &sig).await;
+        notify_reward_event(&RewardEvent {
+            round: Some(miner.round_id),
+            kind: Some(format!("round {} 临近过期，已强制 checkpoint", miner.round_id)),
+            ..RewardEvent::new("expiry_guard")
+        });
+        Ok(())
+    }
+}
+
+// 后台任务使用独立的 RPC 连接与签名者，不与主循环共享状态
+fn spawn_expiry_guard(authority: Pubkey) {
+    if std::env::var("EXPIRY_GUARD").is_ok() && !env_flag("EXPIRY_GUARD") {
+        return;
+    }
+    let Ok(rpc_url) = std::env::var("RPC") else {
+        return;
+    };
+    let guard = ExpiryGuard::from_env();
+    out!(
+        "[expiry] 过期保护已启动：每 {} 秒检查，距过期 {:.0} 秒内强制 checkpoint",
+        guard.interval.as_secs(),
+        slots_to_secs(guard.slots_before)
+    );
+    tokio::spawn(async move {
+        let rpc = RpcClient::new_with_commitment(rpc_url, commitment_for(OpClass::Confirmation));
+        let payer = match payer_signer_from_env() {
+            Ok(payer) => payer,
+            Err(e) => {
+                out!("[expiry] ⚠️  无法加载签名者，过期保护未启动: {}", e);
+                return;
+            }
+        };
+        loop {
+            if let Err(e) = guard.check(&rpc, payer.as_ref(), authority).await {
+                out!("[expiry] ⚠️  检查失败: {:?}，下次再试", e);
+            }
+            sleep(guard.interval).await;
+        }
+    });
+}
+
+async fn expiry_guard(rpc: &RpcClient, payer: &dyn TxSigner) -> Result<(), anyhow::Error> {
+    let authority = mining_authority(payer).pubkey();
+    let guard = ExpiryGuard::from_env();
+    install_stop_handler();
+    while !stop_requested() {
+        if let Err(e) = guard.check(rpc, payer, authority).await {
+            out!("[expiry] ⚠️  检查失败: {:?}，下次再试", e);
+        }
+        sleep(guard.interval).await;
+    }
+    Ok(())
+}
+
+// ============ 新增：slot hash 归档 ============
+// 开奖格子由回合 end_slot 的 slot hash 决定，但 SlotHashes sysvar 只保留最近 512 个 slot（约 3 分钟），
+// Round 账户过期后也会被关闭，事后无法再核对。归档任务每 SLOT_HASH_ARCHIVE_SECS 秒（默认 20）读取一次 board，
+// 回合结束后立即从 SlotHashes 取出 end_slot 的 hash，连同算出的开奖格子追加到 ore.slot_hashes.jsonl；
+// end_slot 没有出块（SlotHashes 中缺失）时记为 skipped。
+// auto_mine 在 SLOT_HASH_ARCHIVE=true 时于后台运行，也可单独运行 COMMAND=slot_hash_recorder；
+// COMMAND=audit_rng 用归档核对开奖结果（AUDIT_ROUND 只核对指定回合）
+const SLOT_HASH_ARCHIVE_FILE: &str = "ore.slot_hashes.jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ArchivedSlotHash {
+    round_id: u64,
+    end_slot: u64,
+    // base58；end_slot 没有出块时为空
+    slot_hash: Option<String>,
+    winning_square: Option<u64>,
+    recorded_at: u64,
+}
+
+fn read_slot_hash_archive() -> BTreeMap<u64, ArchivedSlotHash> {
+    fs::read_to_string(state_path(SLOT_HASH_ARCHIVE_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<ArchivedSlotHash>(line).ok())
+        .map(|entry| (entry.round_id, entry))
+        .collect()
+}
+
+struct SlotHashRecorder {
+    interval: Duration,
+    archived: std::collections::HashSet<u64>,
+    // 已观察到、尚未归档的回合 (round_id, end_slot)
+    pending: Vec<(u64, u64)>,
+}
+
+impl SlotHashRecorder {
+    fn from_env() -> Self {
+        Self {
+            interval: Duration::from_secs(
+                std::env::var("SLOT_HASH_ARCHIVE_SECS")
+                    .ok()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(20)
+                    .max(1),
+            ),
+            archived: read_slot_hash_archive().into_keys().collect(),
+            pending: vec![],
+        }
+    }
+
+    async fn poll(&mut self, rpc: &RpcClient) -> Result<(), anyhow::Error> {
+        let board = get_board(rpc).await?;
+        let clock = get_clock(rpc).await?;
+        // 首笔部署前 end_slot 尚未确定，之后以最新读到的为准
+        if board.end_slot > board.start_slot
+            && board.end_slot != u64::MAX
+            && !self.archived.contains(&board.round_id)
+        {
+            self.pending.retain(|(id, _)| *id != board.round_id);
+            self.pending.push((board.round_id, board.end_slot));
+        }
+        if !self.pending.iter().any(|(_, end_slot)| *end_slot < clock.slot) {
+            return Ok(());
+        }
+
+        let slot_hashes = get_slot_hashes(rpc).await?;
+        let newest = slot_hashes.slot_hashes().first().map(|(slot, _)| *slot).unwrap_or(0);
+        let oldest = slot_hashes.slot_hashes().last().map(|(slot, _)| *slot).unwrap_or(0);
+        let mut still_pending = vec![];
+        for (round_id, end_slot) in std::mem::take(&mut self.pending) {
+            let hash = slot_hashes.get(&end_slot);
+            if hash.is_none() && end_slot >= newest {
+                // sysvar 还没有包含 end_slot
+                still_pending.push((round_id, end_slot));
+                continue;
+            }
+            if hash.is_none() && end_slot < oldest {
+                out!("[slothash] ⚠️  round {} 的 end_slot {} 已超出 SlotHashes 范围，无法归档", round_id, end_slot);
+                continue;
+            }
+            let entry = ArchivedSlotHash {
+                round_id,
+                end_slot,
+                slot_hash: hash.map(|h| h.to_string()),
+                winning_square: hash.map(|h| get_winning_square(&h.to_bytes())),
+                recorded_at: chain_now(),
+            };
+            let line = serde_json::to_string(&entry)?;
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(state_path(SLOT_HASH_ARCHIVE_FILE))?;
+            writeln!(file, "{}", line)?;
+            self.archived.insert(round_id);
+            match entry.winning_square {
+                Some(square) => out!("[slothash] round {} end_slot {} → #{}", round_id, end_slot, square),
+                None => out!("[slothash] round {} end_slot {} 没有出块（skipped）", round_id, end_slot),
+            }
+        }
+        self.pending = still_pending;
+        Ok(())
+    }
+}
+
+fn spawn_slot_hash_recorder() {
+    if !env_flag("SLOT_HASH_ARCHIVE") {
+        return;
+    }
+    let Ok(rpc_url) = std::env::var("RPC") else {
+        return;
+    };
+    let mut recorder = SlotHashRecorder::from_env();
+    out!("[slothash] slot hash 归档已启动：每 {} 秒检查一次", recorder.interval.as_secs());
+    tokio::spawn(async move {
+        let rpc = RpcClient::new_with_commitment(rpc_url, commitment_for(OpClass::Analytics));
+        loop {
+            if let Err(e) = recorder.poll(&rpc).await {
+                out!("[slothash] ⚠️  归档失败: {:?}，下次再试", e);
+            }
+            sleep(recorder.interval).await;
+        }
+    });
+}
+
+async fn slot_hash_recorder(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let mut recorder = SlotHashRecorder::from_env();
+    install_stop_handler();
+    while !stop_requested() {
+        if let Err(e) = recorder.poll(rpc).await {
+            out!("[slothash] ⚠️  归档失败: {:?}，下次再试", e);
+        }
+        sleep(recorder.interval).await;
+    }
+    Ok(())
+}
+
+// 逐回合核对：归档 hash 重新计算的格子、ore.round_results.jsonl 记录的开奖格子、链上 Round.slot_hash（账户仍在时）
+async fn audit_rng(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let only: Option<u64> = parse_env_opt("AUDIT_ROUND")?;
+    let archive: Vec<ArchivedSlotHash> = read_slot_hash_archive()
+        .into_values()
+        .filter(|e| only.is_none_or(|id| e.round_id == id))
+        .collect();
+    if archive.is_empty() {
+        return Err(CommandError::nothing_to_do(format!(
+            "{} 中没有可核对的回合（开启 SLOT_HASH_ARCHIVE 或运行 slot_hash_recorder）",
+            state_path(SLOT_HASH_ARCHIVE_FILE)
+        ))
+        .into());
+    }
+    let results: HashMap<u64, u64> = read_round_results()
+        .into_iter()
+        .map(|r| (r.round_id, r.winning_square))
+        .collect();
+    let addresses: Vec<Pubkey> = archive.iter().map(|e| round_pda(e.round_id).0).collect();
+    let mut onchain = vec![];
+    for chunk in addresses.chunks(100) {
+        onchain.extend(
+            rpc.get_multiple_accounts_with_commitment(chunk, commitment_for(OpClass::Analytics))
+                .await?
+                .value
+                .into_iter()
+                .map(|account| account.and_then(|a| Round::try_from_bytes(&a.data).ok().map(|r| r.slot_hash))),
+        );
+    }
+
+    let (mut verified, mut mismatched) = (0usize, 0usize);
+    out!("{:<10} {:>12} {:>8}  result", "round", "end_slot", "square");
+    for (entry, onchain_hash) in archive.iter().zip(onchain) {
+        let hash = match entry.slot_hash.as_deref().map(solana_sdk::hash::Hash::from_str) {
+            Some(Ok(hash)) => Some(hash.to_bytes()),
+            Some(Err(_)) => {
+                mismatched += 1;
+                out!("{:<10} {:>12}        -  ❌ 归档 hash 无法解析", entry.round_id, entry.end_slot);
+                continue;
+            }
+            None => None,
+        };
+        let square = hash.map(|h| get_winning_square(&h));
+        let mut problems = vec![];
+        if square != entry.winning_square {
+            problems.push("归档记录的格子与 hash 不符".to_string());
+        }
+        if let (Some(square), Some(recorded)) = (square, results.get(&entry.round_id)) {
+            if square != *recorded {
+                problems.push(format!("round_results 记录为 #{}", recorded));
+            }
+        }
+        let mut checked = results.contains_key(&entry.round_id);
+        if let Some(onchain_hash) = onchain_hash.filter(|h| *h != [0; 32]) {
+            checked = true;
+            let expected = hash.unwrap_or([u8::MAX; 32]);
+            if onchain_hash != expected {
+                problems.push("链上 Round.slot_hash 不同".to_string());
+            }
+        }
+        let result = if !problems.is_empty() {
+            mismatched += 1;
+            format!("❌ {}", problems.join("；"))
+        } else if checked {
+            verified += 1;
+            "✅".to_string()
+        } else {
+            "（没有可对照的开奖记录）".to_string()
+        };
+        let square = square.map(|s| format!("#{}", s)).unwrap_or("skipped".to_string());
+        out!("{:<10} {:>12} {:>8}  {}", entry.round_id, entry.end_slot, square, result);
+    }
+    out!("[audit] {} 个回合：一致 {}，不一致 {}", archive.len(), verified, mismatched);
+    if mismatched > 0 {
+        return Err(anyhow::anyhow!("{} 个回合的开奖结果与归档的 slot hash 不一致", mismatched));
+    }
+    Ok(())
+}
+
+// ============ 新增：单个回合开奖核对 ============
+// COMMAND=verify_round VERIFY_ROUND=<id>：取该回合的 slot hash（链上 Round 账户仍在时直接读取，否则用 slot hash 归档），
+// 按链上相同的推导（Round::rng / Round::winning_square）重新计算开奖格子，与链上记录、ore.round_results.jsonl、
+// slot hash 归档逐一比对；再用我在该回合的部署（Miner 仍停留在该回合时读链上，否则取决策日志中已上链的部署）
+// 推算应得 SOL，与 reward.jsonl 中该回合 checkpoint 的实际入账比较。任何不一致都列出并以非零退出码结束
+// SOL 以 lamports 计；没有开奖格子（end_slot 未出块）时 winning_square 为空；
+// 没有参与本回合时 my_deployed / expected_sol 为空；不一致时命令报错，不返回结果
+#[derive(Debug, Serialize)]
+struct VerifyRoundResult {
+    round_id: u64,
+    slot_hash: String,
+    slot_hash_source: &'static str,
+    winning_square: Option<usize>,
+    my_deployed: Option<u64>,
+    expected_sol: Option<u64>,
+    checkpoint_sol: Option<u64>,
+}
+
+async fn verify_round(rpc: &RpcClient, payer: &dyn TxSigner) -> Result<VerifyRoundResult, anyhow::Error> {
+    let round_id: u64 = parse_env("VERIFY_ROUND")?;
+    let authority = mining_authority(payer).pubkey();
+    let onchain = rpc
+        .get_account_with_commitment(&round_pda(round_id).0, commitment_for(OpClass::Analytics))
+        .await?
+        .value
+        .and_then(|a| Round::try_from_bytes(&a.data).ok().copied());
+    let archived = read_slot_hash_archive().remove(&round_id);
+    let recorded = read_round_results().into_iter().find(|r| r.round_id == round_id);
+    let mut problems: Vec<String> = vec![];
+
+    out!("Verify round {}", round_id);
+    // slot hash：链上优先，其次归档
+    let slot_hash = match (&onchain, &archived) {
+        (Some(round), _) if round.slot_hash != [0; 32] => Some(round.slot_hash),
+        (Some(_), _) => {
+            return Err(CommandError::nothing_to_do(format!("回合 {} 尚未结算（slot_hash 未写入）", round_id)).into());
+        }
+        (None, Some(entry)) => match entry.slot_hash.as_deref() {
+            Some(hash) => Some(solana_sdk::hash::Hash::from_str(hash)?.to_bytes()),
+            None => Some([u8::MAX; 32]),
+        },
+        (None, None) => None,
+    };
+    let Some(slot_hash) = slot_hash else {
+        return Err(CommandError::nothing_to_do(format!(
+            "回合 {} 的 Round 账户已关闭，且 {} 中没有归档（开启 SLOT_HASH_ARCHIVE）",
+            round_id,
+            state_path(SLOT_HASH_ARCHIVE_FILE)
+        ))
+        .into());
+    };
+    let mut result = VerifyRoundResult {
+        round_id,
+        slot_hash: solana_sdk::hash::Hash::new_from_array(slot_hash).to_string(),
+        slot_hash_source: if onchain.is_some() { "round" } else { "archive" },
+        winning_square: None,
+        my_deployed: None,
+        expected_sol: None,
+        checkpoint_sol: None,
+    };
+    out!(
+        "  slot hash: {}（来源：{}）",
+        result.slot_hash,
+        if onchain.is_some() { "链上 Round" } else { "slot hash 归档" }
+    );
+    // 链上推导只依赖 slot_hash，用任意 Round 承载即可
+    let mut round = onchain.unwrap_or(Round {
+        id: round_id,
+        deployed: recorded.as_ref().map(|r| r.deployed).unwrap_or([0; 25]),
+        slot_hash,
+        count: [0; 25],
+        expires_at: 0,
+        motherlode: 0,
+        rent_payer: Pubkey::default(),
+        top_miner: Pubkey::default(),
+        top_miner_reward: 0,
+        total_deployed: 0,
+        total_vaulted: 0,
+        total_winnings: recorded.as_ref().map(|r| r.total_winnings).unwrap_or(0),
+    });
+    round.slot_hash = slot_hash;
+    let Some(rng) = round.rng() else {
+        out!("  end_slot 没有出块（slot_hash 为空），本回合没有开奖格子");
+        return Ok(result);
+    };
+    let winning_square = round.winning_square(rng);
+    result.winning_square = Some(winning_square);
+    out!("  winning square: #{}", winning_square);
+
+    if let Some(entry) = &archived {
+        if onchain.is_some() && entry.slot_hash.as_deref() != Some(&solana_sdk::hash::Hash::new_from_array(slot_hash).to_string()) {
+            problems.push("链上 slot_hash 与归档不同".to_string());
+        }
+        if entry.winning_square != Some(winning_square as u64) {
+            problems.push(format!("归档记录的开奖格子为 {:?}", entry.winning_square));
+        }
+    }
+    if let Some(result) = &recorded {
+        if result.winning_square != winning_square as u64 {
+            problems.push(format!("round_results 记录的开奖格子为 #{}", result.winning_square));
+        }
+    }
+    if onchain.is_some() && archived.is_none() && recorded.is_none() {
+        out!("  （没有本地记录可对照，只核对链上数据）");
+    }
+
+    // 我的部署：Miner 仍停留在该回合时读链上，否则取决策日志中该回合最后一次选中的格子（需有对应的 deploy 记录）
+    let reward_events: Vec<RewardEvent> = read_reward_events()
+        .into_iter()
+        .filter(|e| e.round == Some(round_id) && e.wallet.as_ref().is_none_or(|w| *w == authority.to_string()))
+        .collect();
+    let my_deployed: Option<[u64; 25]> = match get_miner(rpc, authority).await {
+        Ok(miner) if miner.round_id == round_id => Some(miner.deployed),
+        _ if reward_events.iter().any(|e| e.event == "deploy") => fs::read_to_string(state_path(DECISION_LOG_FILE))
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| serde_json::from_str::<DecisionExplanation>(line).ok())
+            .rfind(|d| d.round_id == round_id && !d.picked.is_empty())
+            .map(|d| {
+                let mut deployed = [0u64; 25];
+                for (square, lamports) in d.picked {
+                    deployed[square] = lamports;
+                }
+                deployed
+            }),
+        _ => None,
+    };
+    match my_deployed {
+        None => out!("  我（{}）没有参与本回合", authority),
+        Some(mine) => {
+            let on_winner = mine[winning_square];
+            let expected = if on_winner > 0 && round.deployed[winning_square] > 0 {
+                on_winner
+                    + (round.total_winnings as u128 * on_winner as u128 / round.deployed[winning_square] as u128) as u64
+            } else {
+                0
+            };
+            out!(
+                "  我的部署: {}（开奖格子上 {}），应得 SOL: {}",
+                fmt_sol(mine.iter().sum()),
+                fmt_sol(on_winner),
+                fmt_sol(expected)
+            );
+            result.my_deployed = Some(mine.iter().sum());
+            result.expected_sol = Some(expected);
+            match reward_events.iter().find(|e| e.event == "checkpoint") {
+                Some(checkpoint) => {
+                    let actual = checkpoint.delta_sol_lamports.unwrap_or(0);
+                    result.checkpoint_sol = Some(actual);
+                    out!("  checkpoint 实际入账: {}", fmt_sol(actual));
+                    // 按比例分配的整数除法最多差 1 lamport
+                    if round.total_winnings > 0 && actual.abs_diff(expected) > 1 {
+                        problems.push(format!(
+                            "checkpoint 入账 {} 与应得 {} 相差 {}",
+                            fmt_sol(actual),
+                            fmt_sol(expected),
+                            fmt_sol(actual.abs_diff(expected))
+                        ));
+                    }
+                }
+                None => out!("  reward.jsonl 中没有本回合的 checkpoint 记录"),
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        out!("[verify] ✅ 回合 {} 核对一致", round_id);
+        return Ok(result);
+    }
+    for problem in problems.iter() {
+        out!("[verify] ❌ {}", problem);
+    }
+    Err(anyhow::anyhow!("回合 {} 有 {} 处不一致", round_id, problems.len()))
+}
+
+// ============ 新增：优先费预测 ============
+// FEE_FORECAST=true 时 auto_mine 在后台每 FEE_FORECAST_SECS 秒（默认 10）读取一次 Board 账户的 getRecentPrioritizationFees，
+// 累积最近约 3000 个 slot 的样本，并记下每个回合的 end_slot。预测值取历史回合最后 15 个 slot（危险区间）内
+// 优先费的 FEE_FORECAST_PERCENTILE 分位（默认 75）；尾盘样本不足时取全部样本的该分位，上限 FEE_FORECAST_MAX_CU_PRICE。
+// 危险区间的单次提交直接使用预测值（高于 COMPUTE_UNIT_PRICE 时）。部署前按本次提交实际使用的手续费
+// （危险区间按预测值，其余按 COMPUTE_UNIT_PRICE）与本轮期望收益（SOL 按回测费用模型，ORE 按份额与 Meteora 中间价折算）比较：
+// 只有期望收益本为非负、扣除手续费后变为负时才告警（fee_forecast），FEE_FORECAST_SKIP=true 时跳过本轮；
+// 本来就是负期望的回合由策略决定，不归咎于手续费
+#[derive(Debug, Clone, Copy)]
+struct FeeForecast {
+    cu_price: u64,
+    ore_price_sol: f64,
+    updated: Instant,
+    interval: Duration,
+}
+
+static FEE_FORECAST: std::sync::Mutex<Option<FeeForecast>> = std::sync::Mutex::new(None);
+
+// 最新的预测；后台任务停止更新（超过 3 个周期）时视为无预测
+fn fee_forecast() -> Option<FeeForecast> {
+    let forecast = (*FEE_FORECAST.lock().ok()?)?;
+    (forecast.updated.elapsed() < forecast.interval * 3).then_some(forecast)
+}
+
+fn spawn_fee_forecaster() {
+    if !env_flag("FEE_FORECAST") {
+        return;
+    }
+    let Ok(rpc_url) = std::env::var("RPC") else {
+        return;
+    };
+    let interval = Duration::from_secs(
+        std::env::var("FEE_FORECAST_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(10)
+            .max(1),
+    );
+    let percentile = env_f64("FEE_FORECAST_PERCENTILE", 75.0).clamp(0.0, 100.0);
+    let max_cu_price: Option<u64> = std::env::var("FEE_FORECAST_MAX_CU_PRICE").ok().and_then(|s| s.parse().ok());
+    out!(
+        "[fee] 优先费预测已启动：每 {} 秒采样，取尾盘 p{:.0}",
+        interval.as_secs(),
+        percentile
+    );
+    tokio::spawn(async move {
+        let rpc = RpcClient::new_with_commitment(rpc_url, commitment_for(OpClass::Analytics));
+        let board_address = board_pda().0;
+        let mut samples: BTreeMap<u64, u64> = BTreeMap::new();
+        let mut round_ends: VecDeque<u64> = VecDeque::new();
+        let mut ore_price_sol = 0.0;
+        loop {
+            match rpc.get_recent_prioritization_fees(&[board_address]).await {
+                Ok(fees) => {
+                    for fee in fees {
+                        samples.insert(fee.slot, fee.prioritization_fee);
+                    }
+                }
+                Err(e) => out!("[fee] ⚠️  读取优先费失败: {}", e),
+            }
+            if let Ok(board) = get_board(&rpc).await {
+                if board.end_slot > board.start_slot && board.end_slot != u64::MAX && round_ends.back() != Some(&board.end_slot) {
+                    round_ends.push_back(board.end_slot);
+                }
+            }
+            if let Ok(reserves) = get_meteora_reserves(&rpc, known_address("METEORA_POOL", DEFAULT_METEORA_POOL)).await {
+                ore_price_sol = reserves.mid_price();
+            }
+            if let Some(&newest) = samples.keys().next_back() {
+                samples.retain(|slot, _| *slot + 3000 >= newest);
+                round_ends.retain(|end| *end + 3000 >= newest);
+            }
+
+            let mut near_end: Vec<u64> = samples
+                .iter()
+                .filter(|(slot, _)| round_ends.iter().any(|end| *slot <= end && *slot + 15 >= *end))
+                .map(|(_, fee)| *fee)
+                .collect();
+            if near_end.len() < 5 {
+                near_end = samples.values().copied().collect();
+            }
+            if !near_end.is_empty() {
+                near_end.sort_unstable();
+                let index = ((near_end.len() - 1) as f64 * percentile / 100.0).round() as usize;
+                let mut cu_price = near_end[index];
+                if let Some(max) = max_cu_price {
+                    cu_price = cu_price.min(max);
+                }
+                let previous = fee_forecast().map(|f| f.cu_price);
+                if previous != Some(cu_price) {
+                    out!("[fee] 预测部署时优先费: {} microlamports/CU（{} 个样本）", cu_price, near_end.len());
+                }
+                if let Ok(mut forecast) = FEE_FORECAST.lock() {
+                    *forecast = Some(FeeForecast {
+                        cu_price,
+                        ore_price_sol,
+                        updated: Instant::now(),
+                        interval,
+                    });
+                }
+            }
+            sleep(interval).await;
+        }
+    });
+}
+
+// 一轮部署的期望净收益（lamports，已扣除本金）：任一格子 1/25 概率开奖，
+// 中奖时取回本金并按份额分得失败池（扣除 vault 费用），每轮 1 ORE 按份额折算为 SOL
+fn plan_expected_value(plan: &[u64; 25], deployed: &[u64; 25], ore_price_sol: f64) -> f64 {
+    let stakes = plan.map(|v| v as f64 * (1.0 - BACKTEST_ADMIN_FEE));
+    let total = deployed.iter().sum::<u64>() as f64 + stakes.iter().sum::<f64>();
+    let mut value = 0.0;
+    for (square, stake) in stakes.iter().enumerate().filter(|(_, s)| **s > 0.0) {
+        let pool = deployed[square] as f64 + stake;
+        let share = stake / pool;
+        value += stake + (total - pool) * (1.0 - BACKTEST_VAULT_FEE) * share + ore_price_sol * 1e9 * share;
+    }
+    value / 25.0 - plan.iter().sum::<u64>() as f64
+}
+
+// ============ 新增：top miner 跟踪 ============
+// auto_mine 每次读取 Round 时比对 round.top_miner：我的 authority 成为 / 失去 top miner 时通知（top_miner_status）；
+// 结算后若我是 top miner，在 reward.jsonl 记录 top_miner 事件（top_miner_ore 已包含在 checkpoint ORE 中，PnL 里单列展示）
+struct TopMinerTracker {
+    authority: Pubkey,
+    round_id: u64,
+    is_top: bool,
+}
+
+impl TopMinerTracker {
+    fn new(authority: Pubkey) -> Self {
+        Self {
+            authority,
+            round_id: 0,
+            is_top: false,
+        }
+    }
+
+    fn observe(&mut self, round: &Round) {
+        if round.id != self.round_id {
+            self.round_id = round.id;
+            self.is_top = false;
+        }
+        let is_top = round.top_miner == self.authority;
+        if is_top == self.is_top {
+            return;
+        }
+        self.is_top = is_top;
+        let message = if is_top {
+            format!("round {} 成为 top miner（奖励 {}）", round.id, fmt_ore(round.top_miner_reward))
+        } else {
+            format!("round {} 失去 top miner（当前 {}）", round.id, round.top_miner)
+        };
+        out!("[top] {}", message);
+        notify_reward_event(&RewardEvent {
+            round: Some(round.id),
+            kind: Some(message),
+            ..RewardEvent::new("top_miner_status")
+        });
+    }
+
+    fn settled(&mut self, result: &RoundResult) {
+        if result.top_miner == self.authority.to_string() {
+            out!(
+                "[top] 🏆 round {} 最终 top miner 是我，奖励 {}",
+                result.round_id,
+                fmt_ore(result.top_miner_reward)
+            );
+            append_reward_event(RewardEvent {
+                round: Some(result.round_id),
+                top_miner_ore: Some(result.top_miner_reward),
+                ..RewardEvent::new("top_miner")
+            });
+        }
+        self.is_top = false;
+    }
+}
+
+// ============ 新增：亏损冷却（tilt protection） ============
+// 连续 TILT_MAX_LOSSES 轮未中，或最近一小时亏损超过 TILT_MAX_LOSS_SOL_PER_HOUR（按未中回合的花费计）时，
+// 进入 TILT_COOLDOWN_SECS（默认 1800）秒冷却：默认暂停部署；设置 TILT_REDUCE_FACTOR 时改为按该系数缩小下注金额。
+// 进入与结束冷却都会发送通知（事件名 cooldown / cooldown_end），到期后自动恢复
+struct TiltGuard {
+    max_losing_streak: Option<usize>,
+    max_hourly_loss_lamports: Option<u64>,
+    cooldown: Duration,
+    reduce_factor: Option<f64>,
+    losing_streak: usize,
+    // 最近一小时未中回合：(时间, 花费)
+    recent_losses: VecDeque<(Instant, u64)>,
+    cooldown_until: Option<Instant>,
+}
+
+impl TiltGuard {
+    fn from_env() -> Self {
+        Self {
+            max_losing_streak: std::env::var("TILT_MAX_LOSSES")
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+                .filter(|n| *n > 0),
+            max_hourly_loss_lamports: std::env::var("TILT_MAX_LOSS_SOL_PER_HOUR")
+                .ok()
+                .and_then(|s| s.parse::<f64>().ok())
+                .filter(|sol| *sol > 0.0)
+                .map(sol_to_lamports),
+            cooldown: Duration::from_secs(
+                std::env::var("TILT_COOLDOWN_SECS")
+                    .ok()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(1800),
+            ),
+            reduce_factor: std::env::var("TILT_REDUCE_FACTOR")
+                .ok()
+                .and_then(|s| s.parse::<f64>().ok())
+                .filter(|f| *f > 0.0 && *f < 1.0),
+            losing_streak: 0,
+            recent_losses: VecDeque::new(),
+            cooldown_until: None,
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        self.max_losing_streak.is_some() || self.max_hourly_loss_lamports.is_some()
+    }
+
+    // 记录已结算回合的结果，触发条件满足时进入冷却
+    fn record(&mut self, round_id: u64, won: bool, cost_lamports: u64) {
+        if !self.enabled() {
+            return;
+        }
+        let now = Instant::now();
+        if won {
+            self.losing_streak = 0;
+        } else {
+            self.losing_streak += 1;
+            self.recent_losses.push_back((now, cost_lamports));
+        }
+        while let Some((at, _)) = self.recent_losses.front() {
+            if now.duration_since(*at) > Duration::from_secs(3600) {
+                self.recent_losses.pop_front();
+            } else {
+                break;
+            }
+        }
+        if self.cooldown_until.is_some() {
+            return;
+        }
+        let hourly_loss: u64 = self.recent_losses.iter().map(|(_, cost)| cost).sum();
+        let reason = if self.max_losing_streak.is_some_and(|max| self.losing_streak >= max) {
+            format!("连续 {} 轮未中", self.losing_streak)
+        } else if self.max_hourly_loss_lamports.is_some_and(|max| hourly_loss > max) {
+            format!("最近一小时亏损 {}", fmt_sol(hourly_loss))
+        } else {
+            return;
+        };
+        self.cooldown_until = Some(now + self.cooldown);
+        let action = match self.reduce_factor {
+            Some(factor) => format!("下注金额 ×{}", factor),
+            None => "暂停部署".to_string(),
+        };
+        out!(
+            "[tilt] ⚠️  {}，进入 {} 秒冷却：{}",
+            reason,
+            self.cooldown.as_secs(),
+            action
+        );
+        notify_reward_event(&RewardEvent {
+            round: Some(round_id),
+            kind: Some(format!("{}，冷却 {} 秒：{}", reason, self.cooldown.as_secs(), action)),
+            ..RewardEvent::new("cooldown")
+        });
+    }
+
+    // 冷却到期时自动恢复；返回当前是否处于冷却
+    fn in_cooldown(&mut self) -> bool {
+        match self.cooldown_until {
+            Some(until) if Instant::now() >= until => {
+                out!("[tilt] ✅ 冷却结束，恢复正常部署");
+                self.cooldown_until = None;
+                self.losing_streak = 0;
+                self.recent_losses.clear();
+                notify_reward_event(&RewardEvent::new("cooldown_end"));
+                false
+            }
+            Some(_) => true,
+            None => false,
+        }
+    }
+
+    // 冷却中且未设置缩减系数时暂停，返回剩余秒数
+    fn pause_remaining(&mut self) -> Option<u64> {
+        if self.in_cooldown() && self.reduce_factor.is_none() {
+            self.cooldown_until
+                .map(|until| until.saturating_duration_since(Instant::now()).as_secs())
+        } else {
+            None
+        }
+    }
+
+    // 冷却中的下注缩减系数
+    fn bet_factor(&mut self) -> f64 {
+        let in_cooldown = self.in_cooldown();
+        match self.reduce_factor {
+            Some(factor) if in_cooldown => factor,
+            _ => 1.0,
+        }
+    }
+}
+
+// ============ 新增：资金对账 ============
+// 设置 RECONCILE_MAX_DRIFT_SOL 后，每次部署前核对「钱包余额 + 可领 SOL」与预期值：
+// 预期值 = 基准 - 部署花费 - 交易基础费（每笔 5000 lamports）+ checkpoint 入账的 SOL 奖励。
+// 实际值比预期少超过阈值时告警并通知（RECONCILE_HALT=true 时停止 auto_mine），用于发现 claim 未上链、资金被意外转走等情况；
+// 比预期多（如手动充值）时只记录并重设基准。优先费不计入预期，由阈值覆盖
+const BASE_TX_FEE_LAMPORTS: u64 = 5000;
+
+// 单独提交上一轮的 checkpoint（不随部署），成功时记入奖励日志与资金对账；
+// 失败时（例如 round 还未结束或已过期）只打印警告
+async fn submit_checkpoint(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+    authority: Pubkey,
+    miner_before: &Miner,
+    reconciler: Option<&mut BalanceReconciler>,
+) -> bool {
+    out!("[auto] 正在执行 checkpoint...");
+    let ix = ore_api::sdk::checkpoint(authority, authority, miner_before.round_id);
+    match submit_transaction(rpc, payer, &[ix]).await {
+        Ok(sig) => {
+            out!("[auto] ✅ Checkpoint 成功！交易签名: {}", sig);
+            let credited = log_checkpoint_rewards(rpc, authority, miner_before, &sig).await;
+            if let Some(reconciler) = reconciler {
+                reconciler.record_tx_fee();
+                reconciler.record_credit(credited.unwrap_or(0));
+            }
+            true
+        }
+        Err(e) => {
+            out!("[auto] ⚠️  Checkpoint 失败（可能 round 还未结束或已过期）: {:?}", e);
+            false
+        }
+    }
+}
+
+struct BalanceReconciler {
+    max_drift_lamports: u64,
+    halt: bool,
+    baseline: Option<u64>,
+    spent: u64,
+    credited: u64,
+}
+
+impl BalanceReconciler {
+    fn from_env() -> Option<Self> {
+        let max_drift_sol: f64 = std::env::var("RECONCILE_MAX_DRIFT_SOL").ok()?.parse().ok()?;
+        Some(Self {
+            max_drift_lamports: sol_to_lamports(max_drift_sol),
+            halt: env_flag("RECONCILE_HALT"),
+            baseline: None,
+            spent: 0,
+            credited: 0,
+        })
+    }
+
+    // 一笔已上链的部署交易
+    fn record_deploy(&mut self, cost_lamports: u64) {
+        self.spent += cost_lamports + BASE_TX_FEE_LAMPORTS;
+    }
+
+    // 单独提交的 checkpoint 交易
+    fn record_tx_fee(&mut self) {
+        self.spent += BASE_TX_FEE_LAMPORTS;
+    }
+
+    fn record_credit(&mut self, lamports: u64) {
+        self.credited += lamports;
+    }
+
+    fn rebase(&mut self, actual: u64) {
+        self.baseline = Some(actual);
+        self.spent = 0;
+        self.credited = 0;
+    }
+
+    // 返回 Err 表示需要停止
+    async fn check(&mut self, rpc: &RpcClient, authority: Pubkey, round_id: u64) -> Result<(), String> {
+        let Some(actual) = wallet_value_lamports(rpc, authority).await else {
+            out!("[reconcile] ⚠️  无法读取钱包余额，跳过本次对账");
+            return Ok(());
+        };
+        let Some(baseline) = self.baseline else {
+            out!("[reconcile] 对账基准: {}（钱包 + 可领）", fmt_sol(actual));
+            self.rebase(actual);
+            return Ok(());
+        };
+        let expected = (baseline + self.credited).saturating_sub(self.spent);
+        if actual + self.max_drift_lamports < expected {
+            let message = format!(
+                "资金对账偏差：预期 {}，实际 {}，缺少 {}（阈值 {}）",
+                fmt_sol(expected),
+                fmt_sol(actual),
+                fmt_sol(expected - actual),
+                fmt_sol(self.max_drift_lamports)
+            );
+            out!("[reconcile] ⚠️  {}", message);
+            notify_reward_event(&RewardEvent {
+                round: Some(round_id),
+                kind: Some(message.clone()),
+                ..RewardEvent::new("balance_drift")
+            });
+            if self.halt {
+                return Err(message);
+            }
+            self.rebase(actual);
+        } else if actual > expected + self.max_drift_lamports {
+            out!(
+                "[reconcile] 余额比预期多 {}（可能有充值），重设对账基准",
+                fmt_sol(actual - expected)
+            );
+            self.rebase(actual);
+        }
+        Ok(())
+    }
+}
+
+// ============ 新增：分钱包部署（split-wallet） ============
+// SPLIT_WALLETS="a.json,b.json,c.json"：auto_mine 每轮随机选一个钱包部署（不连续两轮使用同一个），
+// 由该钱包自己签名并支付手续费，单个 miner 账户上看不出完整的下注规律。主钱包（KEYPAIR）只用 split_fund 补充资金。
+//   SPLIT_AMOUNT_JITTER_PCT  每轮下注金额随机浮动 ±N%（默认 10）
+//   SPLIT_DELAY_MAX_MS       提交前随机等待 0 ~ N 毫秒（默认 1500，危险区间不等待）
+//   SPLIT_FUND_SOL           split_fund 把每个钱包补足到约该余额（同样随机浮动）
+// split_report 汇总所有钱包的余额、可领取 SOL / ORE 与部署记录。不能与 AUTHORITY_KEYPAIR 同时使用
+struct SplitWallets {
+    wallets: Vec<solana_sdk::signer::keypair::Keypair>,
+    amount_jitter: f64,
+    delay_max_ms: u64,
+    last: Option<usize>,
+    // 不使用 RNG_SEED：选择与浮动不应可复现
+    rng: SplitMix64,
+}
+
+impl SplitWallets {
+    fn from_env() -> Result<Option<Self>, anyhow::Error> {
+        let wallets = read_wallet_files("SPLIT_WALLETS")?;
+        if wallets.is_empty() {
+            return Ok(None);
+        }
+        if std::env::var("AUTHORITY_KEYPAIR").is_ok() {
+            return Err(CommandError::config("SPLIT_WALLETS 不能与 AUTHORITY_KEYPAIR 同时使用").into());
+        }
+        Ok(Some(Self {
+            wallets,
+            amount_jitter: env_f64("SPLIT_AMOUNT_JITTER_PCT", 10.0).clamp(0.0, 90.0) / 100.0,
+            delay_max_ms: std::env::var("SPLIT_DELAY_MAX_MS")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(1500),
+            last: None,
+            rng: SplitMix64(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos() as u64,
+            ),
+        }))
+    }
+
+    // 本轮使用的钱包序号（钱包多于一个时不与上一轮相同）
+    fn next_index(&mut self) -> usize {
+        let n = self.wallets.len() as u64;
+        let index = match self.last {
+            Some(last) if n > 1 => ((last as u64 + 1 + self.rng.next_u64() % (n - 1)) % n) as usize,
+            _ => (self.rng.next_u64() % n) as usize,
+        };
+        self.last = Some(index);
+        index
+    }
+
+    // 整轮使用同一浮动系数，金额相同的格子仍合并为一条 deploy 指令
+    fn jitter(&mut self, amount: u64) -> u64 {
+        let factor = 1.0 + (self.rng.next_f64() * 2.0 - 1.0) * self.amount_jitter;
+        (amount as f64 * factor) as u64
+    }
+
+    fn delay(&mut self) -> Duration {
+        Duration::from_millis((self.rng.next_f64() * self.delay_max_ms as f64) as u64)
+    }
+}
+
+fn split_wallets_or_err() -> Result<SplitWallets, anyhow::Error> {
+    SplitWallets::from_env()?.ok_or_else(|| CommandError::config("SPLIT_WALLETS 未设置").into())
+}
+
+async fn split_fund(rpc: &RpcClient, payer: &dyn TxSigner) -> Result<(), anyhow::Error> {
+    let mut split = split_wallets_or_err()?;
+    let target = sol_to_lamports(env_f64("SPLIT_FUND_SOL", 0.0));
+    if target == 0 {
+        return Err(CommandError::config("SPLIT_FUND_SOL 未设置或为 0").into());
+    }
+    let dry_run = env_flag("DRY_RUN");
+    let mut total = 0u64;
+    let mut progress = Progress::new("split_fund", split.wallets.len() as u64);
+    for i in 0..split.wallets.len() {
+        progress.set(i as u64);
+        let wallet = Signer::pubkey(&split.wallets[i]);
+        let balance = rpc.get_balance(&wallet).await?;
+        let wallet_target = split.jitter(target);
+        if balance >= wallet_target {
+            progress.println(&format!("[split] {} 余额 {}，无需补充", wallet, fmt_sol(balance)));
+            continue;
+        }
+        let amount = wallet_target - balance;
+        progress.println(&format!(
+            "[split] {} 余额 {} -> 补充 {}",
+            wallet,
+            fmt_sol(balance),
+            fmt_sol(amount)
+        ));
+        total += amount;
+        if !dry_run {
+            let ix = solana_sdk::system_instruction::transfer(&payer.pubkey(), &wallet, amount);
+            let sig = submit_transaction(rpc, payer, &[ix]).await?;
+            progress.println(&format!("[split] ✅ 交易签名: {}", sig));
+        }
+    }
+    progress.set(split.wallets.len() as u64);
+    progress.finish();
+    if total == 0 {
+        return Err(CommandError::nothing_to_do("[split] 所有钱包余额充足").into());
+    }
+    out!(
+        "[split] {}合计补充 {}",
+        if dry_run { "预览模式（DRY_RUN），" } else { "" },
+        fmt_sol(total)
+    );
+    Ok(())
+}
+
+async fn split_report(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let split = split_wallets_or_err()?;
+    let events: Vec<RewardEvent> = read_reward_events()
+        .into_iter()
+        .filter(|e| e.event == "deploy")
+        .collect();
+    out!(
+        "{:<44} {:>12} {:>12} {:>12} {:>8} {:>12}",
+        "wallet", "balance SOL", "claim SOL", "claim ORE", "rounds", "cost SOL"
+    );
+    let (mut balance_total, mut sol_total, mut ore_total, mut rounds_total, mut cost_total) = (0u64, 0u64, 0u64, 0usize, 0u64);
+    for wallet in split.wallets.iter() {
+        let pubkey = Signer::pubkey(wallet);
+        let balance = rpc.get_balance(&pubkey).await.unwrap_or(0);
+        let (claim_sol, claim_ore) = match get_miner(rpc, pubkey).await {
+            Ok(miner) => {
+                let view = MinerView::new(miner);
+                (view.claimable_sol_lamports(), view.claimable_ore_amount())
+            }
+            Err(_) => (0, 0),
+        };
+        let key = pubkey.to_string();
+        let deploys: Vec<&RewardEvent> = events.iter().filter(|e| e.wallet.as_deref() == Some(key.as_str())).collect();
+        let cost: u64 = deploys.iter().filter_map(|e| e.cost_lamports).sum();
+        out!(
+            "{:<44} {:>12.6} {:>12.6} {:>12.4} {:>8} {:>12.6}",
+            key,
+            lamports_to_sol(balance),
+            lamports_to_sol(claim_sol),
+            amount_to_ui_amount(claim_ore, TOKEN_DECIMALS),
+            deploys.len(),
+            lamports_to_sol(cost)
+        );
+        balance_total += balance;
+        sol_total += claim_sol;
+        ore_total += claim_ore;
+        rounds_total += deploys.len();
+        cost_total += cost;
+    }
+    out!(
+        "{:<44} {:>12.6} {:>12.6} {:>12.4} {:>8} {:>12.6}",
+        "total",
+        lamports_to_sol(balance_total),
+        lamports_to_sol(sol_total),
+        amount_to_ui_amount(ore_total, TOKEN_DECIMALS),
+        rounds_total,
+        lamports_to_sol(cost_total)
+    );
+    Ok(())
+}
+
+// ============ 新增：多钱包并行挖矿（fleet） ============
+// COMMAND=fleet，FLEET_WALLETS="a.json,b.json"：一个数据源统一读取 Board / Clock / Round，
+// 通过每个 worker 一个 watch channel 分发给各钱包独立的策略 worker，RPC 读取量不随钱包数量增加。
+// channel 只保留最新一份快照：worker 处理不过来时旧快照被覆盖（计入 dropped），worker 总是基于最新快照决策。
+// 每个 worker 使用与 auto_mine 相同的策略配置（DeployDecider）与相同的部署闸门：每个钱包各持有一把实例锁，
+// 远程停止开关、亏损冷却（TILT_*）、奖池比例上限（MAX_POT_FRACTION）逐个 worker 生效；用自己的钱包签名、支付并部署；
+// 每 FLEET_HEALTH_SECS（默认 30）秒打印各 worker 健康状态，超过 3 个轮询周期没有处理快照的 worker 标记为 stalled
+#[derive(Debug, Clone, Copy)]
+struct FeedSnapshot {
+    // 发布序号，worker 据此统计被覆盖的快照数
+    seq: u64,
+    slot: u64,
+    round_id: u64,
+    end_slot: u64,
+    deployed: [u64; 25],
+    miners: u64,
+}
+
+#[derive(Debug, Default)]
+struct WorkerHealth {
+    wallet: String,
+    last_round: Option<u64>,
+    last_seen: Option<Instant>,
+    snapshots: u64,
+    dropped: u64,
+    decisions: u64,
+    deploys: u64,
+    failures: u64,
+    last_error: Option<String>,
+    stopped: bool,
+}
+
+type FleetHealth = std::sync::Arc<std::sync::Mutex<Vec<WorkerHealth>>>;
+
+fn read_wallet_files(key: &str) -> Result<Vec<solana_sdk::signer::keypair::Keypair>, anyhow::Error> {
+    let mut wallets = vec![];
+    for path in env_list::<String>(key, &[]).iter() {
+        let wallet = read_keypair_file(path)
+            .map_err(|e| CommandError::config(format!("无法读取 {} 密钥文件 {}: {}", key, path, e)))?;
+        wallets.push(wallet);
+    }
+    Ok(wallets)
+}
+
+fn print_fleet_health(health: &FleetHealth, poll: Duration) {
+    let Ok(workers) = health.lock() else {
+        return;
+    };
+    out!("[fleet] worker 状态：");
+    for (i, w) in workers.iter().enumerate() {
+        let status = if w.stopped {
+            "stopped"
+        } else if w.last_seen.is_some_and(|t| t.elapsed() > poll * 3) {
+            "stalled"
+        } else {
+            "ok"
+        };
+        out!(
+            "  #{} {} {} round={} snapshots={} dropped={} decisions={} deploys={} failures={}{}",
+            i,
+            w.wallet,
+            status,
+            w.last_round.map(|r| r.to_string()).unwrap_or("-".to_string()),
+            w.snapshots,
+            w.dropped,
+            w.decisions,
+            w.deploys,
+            w.failures,
+            w.last_error
+                .as_ref()
+                .map(|e| format!(" last_error={}", e))
+                .unwrap_or_default()
+        );
+    }
+}
+
+async fn fleet_worker(
+    index: usize,
+    rpc: std::sync::Arc<RpcClient>,
+    wallet: solana_sdk::signer::keypair::Keypair,
+    decider: DeployDecider,
+    start_before_seconds: f64,
+    mut feed: tokio::sync::watch::Receiver<Option<FeedSnapshot>>,
+    health: FleetHealth,
+) {
+    let authority = Signer::pubkey(&wallet);
+    let mut inflow = InflowTracker::new(decider.velocity_samples);
+    // 配置已在 fleet 启动时校验
+    let mut competition = CompetitionGuard::from_env().ok().flatten();
+    let mut tilt = TiltGuard::from_env();
+    let mut deployed_round: Option<u64> = None;
+    // 已部署、等待开奖的回合：(round, 格子, 花费)，开奖后计入亏损冷却
+    let mut pending: Option<(u64, Vec<usize>, u64)> = None;
+    // 每轮只打印一次暂停原因
+    let mut gated_round: Option<u64> = None;
+    let mut last_seq: Option<u64> = None;
+    let update = |f: &dyn Fn(&mut WorkerHealth)| {
+        if let Ok(mut workers) = health.lock() {
+            f(&mut workers[index]);
+        }
+    };
+    while feed.changed().await.is_ok() {
+        let Some(snapshot) = *feed.borrow_and_update() else {
+            continue;
+        };
+        let overwritten = last_seq.map(|last| snapshot.seq.saturating_sub(last + 1)).unwrap_or(0);
+        last_seq = Some(snapshot.seq);
+        update(&|w| {
+            w.snapshots += 1;
+            w.dropped += overwritten;
+            w.last_round = Some(snapshot.round_id);
+            w.last_seen = Some(Instant::now());
+        });
+        inflow.push(snapshot.round_id, snapshot.slot, snapshot.deployed);
+        let settled = match &pending {
+            Some((round_id, squares, cost)) if snapshot.round_id > *round_id => {
+                match get_round(&rpc, *round_id).await.ok().and_then(|r| RoundResult::from_round(&r)) {
+                    Some(result) => Some(Some((*round_id, squares.contains(&(result.winning_square as usize)), *cost))),
+                    // 开奖结果迟迟读不到时放弃该回合，不再每次快照重复读取
+                    None if snapshot.round_id > *round_id + 1 => Some(None),
+                    None => None,
+                }
+            }
+            _ => None,
+        };
+        if let Some(result) = settled {
+            if let Some((round_id, won, cost)) = result {
+                tilt.record(round_id, won, cost);
+            }
+            pending = None;
+        }
+        let slots_remaining = snapshot.end_slot.saturating_sub(snapshot.slot);
+        // 与 auto_mine 一致：剩余不足约 2 秒时不再提交
+        if deployed_round == Some(snapshot.round_id)
+            || slots_to_secs(slots_remaining) > start_before_seconds
+            || slots_remaining <= 5
+        {
+            continue;
+        }
+        let gate = if let Some(remaining) = tilt.pause_remaining() {
+            Some(format!("冷却中，约 {} 秒后恢复", remaining))
+        } else if kill_switch_engaged() {
+            Some("远程停止开关已开启".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = gate {
+            if gated_round != Some(snapshot.round_id) {
+                gated_round = Some(snapshot.round_id);
+                out!("[fleet#{}] {}，本轮 (round={}) 不部署", index, reason, snapshot.round_id);
+            }
+            continue;
+        }
+        out!("[fleet#{}] round={} 开始决策", index, snapshot.round_id);
+        let velocity = inflow.velocities();
+        let bet_factor = tilt.bet_factor()
+            * competition
+                .as_mut()
+                .map(|c| c.assess(snapshot.round_id, velocity.iter().sum(), snapshot.miners))
+                .unwrap_or(1.0);
+        let (plan, _) = decider.plan(
+            snapshot.round_id,
+            &snapshot.deployed,
+            &velocity,
+            &inflow.twap(),
+            bet_factor,
+            slots_to_secs(slots_remaining),
+        );
+        update(&|w| w.decisions += 1);
+        let Some(plan) = plan.and_then(|plan| decider.guard_pot(plan, &snapshot.deployed)) else {
+            continue;
+        };
+
+        let mut ixs = vec![];
+        if let Ok(miner) = get_miner_with_commitment(&rpc, authority, commitment_for(OpClass::Strategy)).await {
+            if miner.round_id < snapshot.round_id && miner.checkpoint_id < miner.round_id {
+                ixs.push(ore_api::sdk::checkpoint(authority, authority, miner.round_id));
+            }
+        }
+        for (amount, squares) in group_deploy_plan(&plan) {
+            ixs.push(ore_api::sdk::deploy(authority, authority, amount, snapshot.round_id, squares));
+        }
+        let cost: u64 = plan.iter().sum();
+        ixs.extend(memo_instruction(&[
+            ("round", snapshot.round_id.to_string()),
+            ("strategy", "fleet".to_string()),
+            ("cost", cost.to_string()),
+            ("squares", plan.iter().filter(|&&v| v > 0).count().to_string()),
+        ]));
+        match submit_transaction(&rpc, &wallet, &ixs).await {
+            Ok(sig) => {
+                out!(
+                    "[fleet#{}] ✅ round={} 部署 {}，交易签名: {}",
+                    index,
+                    snapshot.round_id,
+                    fmt_sol(cost),
+                    sig
+                );
+                deployed_round = Some(snapshot.round_id);
+                pending = Some((snapshot.round_id, (0..25).filter(|&i| plan[i] > 0).collect(), cost));
+                update(&|w| w.deploys += 1);
+                append_reward_event(RewardEvent {
+                    round: Some(snapshot.round_id),
+                    algorithm: Some("fleet".to_string()),
+                    squares: Some(plan.iter().filter(|&&v| v > 0).count()),
+                    cost_lamports: Some(cost),
+                    tx: Some(sig.to_string()),
+                    wallet: Some(authority.to_string()),
+                    ..RewardEvent::new("deploy")
+                });
+            }
+            Err(e) => {
+                out!("[fleet#{}] ⚠️  部署失败: {:?}", index, e);
+                let message = e.to_string();
+                update(&|w| {
+                    w.failures += 1;
+                    w.last_error = Some(message.clone());
+                });
+            }
+        }
+    }
+    update(&|w| w.stopped = true);
+}
+
+async fn fleet(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let wallets = read_wallet_files("FLEET_WALLETS")?;
+    if wallets.is_empty() {
+        return Err(CommandError::config("FLEET_WALLETS 未设置").into());
+    }
+    if std::env::var("AUTHORITY_KEYPAIR").is_ok() {
+        return Err(CommandError::config("FLEET_WALLETS 不能与 AUTHORITY_KEYPAIR 同时使用").into());
+    }
+    let (amount_lamports, _, _, _, _) = read_auto_params_from_env();
+    if amount_lamports == 0 {
+        return Err(CommandError::config("[fleet] AMOUNT/AMOUNT_SOL 未设置或为 0，退出。").into());
+    }
+    CompetitionGuard::from_env()?;
+    let rpc_url = required_env("RPC")?;
+    let shared_rpc = std::sync::Arc::new(RpcClient::new_with_timeout_and_commitment(
+        rpc_url,
+        rpc_request_timeout(),
+        commitment_for(OpClass::Confirmation),
+    ));
+    let start_before_seconds = env_f64("START_BEFORE_SECONDS", 40.0);
+    let poll = Duration::from_millis(
+        std::env::var("FLEET_POLL_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(500),
+    );
+    let health_every = Duration::from_secs(
+        std::env::var("FLEET_HEALTH_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30),
+    );
+
+    let health: FleetHealth = std::sync::Arc::new(std::sync::Mutex::new(
+        wallets
+            .iter()
+            .map(|w| WorkerHealth {
+                wallet: Signer::pubkey(w).to_string(),
+                ..Default::default()
+            })
+            .collect(),
+    ));
+    // 与 auto_mine 相同的实例锁：每个钱包一把，与使用同一钱包的其他 auto_mine / fleet 互斥
+    let mut instance_locks = vec![];
+    for wallet in wallets.iter() {
+        instance_locks.push(acquire_instance_lock(rpc, Signer::pubkey(wallet)).await?);
+    }
+    install_stop_handler();
+    start_kill_switch_watch()?;
+    let mut senders = vec![];
+    let mut handles = vec![];
+    for (index, wallet) in wallets.into_iter().enumerate() {
+        let (tx, rx) = tokio::sync::watch::channel(None);
+        senders.push(tx);
+        handles.push(tokio::spawn(fleet_worker(
+            index,
+            shared_rpc.clone(),
+            wallet,
+            DeployDecider::from_env(SquareSelectionAlgorithm::from_env())?,
+            start_before_seconds,
+            rx,
+            health.clone(),
+        )));
+    }
+    out!(
+        "[fleet] {} 个 worker，共享数据源每 {} ms 读取一次",
+        senders.len(),
+        poll.as_millis()
+    );
+
+    let mut seq = 0u64;
+    let mut last_health = Instant::now();
+    while !stop_requested() {
+        let (board_result, clock_result) = tokio::join!(get_board_at(rpc, None), get_clock(rpc));
+        match (board_result, clock_result) {
+            (Ok((board, _)), Ok(clock)) => match get_round_at(rpc, board.round_id, None).await {
+                Ok((round, _)) if round.id == board.round_id => {
+                    seq += 1;
+                    let snapshot = FeedSnapshot {
+                        seq,
+                        slot: clock.slot,
+                        round_id: board.round_id,
+                        end_slot: board.end_slot,
+                        deployed: round.deployed,
+                        miners: round.count.iter().sum(),
+                    };
+                    for tx in senders.iter() {
+                        tx.send_replace(Some(snapshot));
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => out!("[fleet] ⚠️  读取 Round 失败: {:?}", e),
+            },
+            (Err(e), _) | (_, Err(e)) => out!("[fleet] ⚠️  读取 Board / Clock 失败: {:?}", e),
+        }
+        if last_health.elapsed() >= health_every {
+            print_fleet_health(&health, poll);
+            last_health = Instant::now();
+        }
+        sleep(poll).await;
+    }
+
+    drop(senders);
+    for handle in handles {
+        let _ = handle.await;
+    }
+    drop(instance_locks);
+    print_fleet_health(&health, poll);
+    Ok(())
+}
+
+// ============ 新增：触发时间与下注金额随机化 ============
+// 固定的 START_BEFORE_SECONDS 和下注金额容易被其他机器人学习并针对：
+//   TRIGGER_JITTER_SECS  每轮触发时间在 START_BEFORE_SECONDS ± N 秒内随机（每轮抽取一次，默认 0）
+//   AMOUNT_JITTER_PCT    每轮下注金额随机浮动 ±N%（整轮同一系数，默认 0）
+struct SubmissionJitter {
+    trigger_secs: f64,
+    amount_pct: f64,
+    // 当前回合抽取的触发偏移
+    round_offset: Option<(u64, f64)>,
+    rng: SplitMix64,
+}
+
+impl SubmissionJitter {
+    fn from_env() -> Self {
+        Self {
+            trigger_secs: env_f64("TRIGGER_JITTER_SECS", 0.0).max(0.0),
+            amount_pct: env_f64("AMOUNT_JITTER_PCT", 0.0).clamp(0.0, 50.0),
+            round_offset: None,
+            rng: SplitMix64(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos() as u64,
+            ),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.trigger_secs > 0.0 || self.amount_pct > 0.0
+    }
+
+    // 本轮的触发阈值（秒），同一回合内保持不变
+    fn trigger_before(&mut self, round_id: u64, start_before_seconds: f64) -> f64 {
+        if self.trigger_secs <= 0.0 {
+            return start_before_seconds;
+        }
+        let offset = match self.round_offset {
+            Some((id, offset)) if id == round_id => offset,
+            _ => {
+                let offset = (self.rng.next_f64() * 2.0 - 1.0) * self.trigger_secs;
+                self.round_offset = Some((round_id, offset));
+                out!(
+                    "[jitter] round={} 触发时间 {:.2}s（{:+.2}s）",
+                    round_id,
+                    start_before_seconds + offset,
+                    offset
+                );
+                offset
+            }
+        };
+        (start_before_seconds + offset).max(0.0)
+    }
+
+    fn perturb(&mut self, plan: [u64; 25]) -> [u64; 25] {
+        if self.amount_pct <= 0.0 {
+            return plan;
+        }
+        let factor = 1.0 + (self.rng.next_f64() * 2.0 - 1.0) * self.amount_pct / 100.0;
+        out!("[jitter] 下注金额 ×{:.4}", factor);
+        plan.map(|v| (v as f64 * factor) as u64)
+    }
+}
+
+// ============ 新增：挖矿循环分阶段耗时 ============
+// auto_mine 每个阶段的耗时：fetch（读取 Board / Clock / Round）、decide（选格决策）、
+// build（组装部署指令）、sign（签名）、send（发送并确认）。最近一次耗时以 ore_phase_*_ms 指标导出；
+// PROFILE_TIMING=true 时每次部署后打印各阶段耗时，结束时打印 p50 / p95 / 最大值汇总
+#[derive(Debug, Clone, Copy)]
+enum LoopPhase {
+    Fetch,
+    Decide,
+    Build,
+    Sign,
+    Send,
+}
+
+impl LoopPhase {
+    const ALL: [LoopPhase; 5] = [
+        LoopPhase::Fetch,
+        LoopPhase::Decide,
+        LoopPhase::Build,
+        LoopPhase::Sign,
+        LoopPhase::Send,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            LoopPhase::Fetch => "fetch",
+            LoopPhase::Decide => "decide",
+            LoopPhase::Build => "build",
+            LoopPhase::Sign => "sign",
+            LoopPhase::Send => "send",
+        }
+    }
+
+    fn metric(&self) -> &'static Metric {
+        match self {
+            LoopPhase::Fetch => &METRIC_PHASE_FETCH_MS,
+            LoopPhase::Decide => &METRIC_PHASE_DECIDE_MS,
+            LoopPhase::Build => &METRIC_PHASE_BUILD_MS,
+            LoopPhase::Sign => &METRIC_PHASE_SIGN_MS,
+            LoopPhase::Send => &METRIC_PHASE_SEND_MS,
+        }
+    }
+}
+
+// 每个阶段的耗时样本（微秒），仅 PROFILE_TIMING 开启时收集
+static PHASE_SAMPLES: std::sync::Mutex<[Vec<u64>; 5]> =
+    std::sync::Mutex::new([Vec::new(), Vec::new(), Vec::new(), Vec::new(), Vec::new()]);
+
+fn record_phase(phase: LoopPhase, elapsed: Duration) {
+    phase.metric().set(elapsed.as_millis() as u64);
+    if env_flag("PROFILE_TIMING") {
+        if let Ok(mut samples) = PHASE_SAMPLES.lock() {
+            samples[phase as usize].push(elapsed.as_micros() as u64);
+        }
+    }
+}
+
+// 最近一次各阶段耗时
+fn print_phase_timing() {
+    let parts: Vec<String> = LoopPhase::ALL
+        .iter()
+        .map(|phase| format!("{}={}ms", phase.name(), phase.metric().get()))
+        .collect();
+    out!("[timing] {}", parts.join(" "));
+}
+
+fn print_phase_summary() {
+    let Ok(samples) = PHASE_SAMPLES.lock() else {
+        return;
+    };
+    out!("[timing] 分阶段耗时汇总（ms）:");
+    out!("  {:<8} {:>8} {:>10} {:>10} {:>10}", "phase", "samples", "p50", "p95", "max");
+    for phase in LoopPhase::ALL {
+        let mut values = samples[phase as usize].clone();
+        if values.is_empty() {
+            continue;
+        }
+        values.sort_unstable();
+        let percentile = |p: f64| values[((values.len() - 1) as f64 * p).round() as usize] as f64 / 1000.0;
+        out!(
+            "  {:<8} {:>8} {:>10.2} {:>10.2} {:>10.2}",
+            phase.name(),
+            values.len(),
+            percentile(0.5),
+            percentile(0.95),
+            percentile(1.0)
+        );
+    }
+}
+
+// ============ 新增：实例锁 ============
+// 防止误启动两个 auto_mine 使用同一个 authority 重复部署：
+//   1) 本地锁文件 ore.<authority>.lock（pid + 心跳，每 30 秒刷新）：用 create_new 创建，已存在即说明有持有者；
+//      心跳超过 120 秒或进程已不存在视为残留锁。接管时先把残留锁 rename 到本进程独有的路径，rename 只有一个进程能成功，
+//      再确认移走的正是判断为残留的那份内容（否则说明别的实例刚接管，放回原处并报错），然后重新 create_new；
+//   2) 链上检查：最近 INSTANCE_CHECK_SECS 秒内（默认 60，0 关闭）有其他进程的部署交易——authority 发出的带本程序部署 memo
+//      （round=）的交易，或 authority 签名、写入其 miner 账户的交易——说明可能有其他主机上的实例在运行。
+//      检查在本进程发出任何交易之前进行，因此命中的交易都来自其他进程；转账、他人代为 checkpoint 等不算。
+// 确认需要多实例（如不同策略分开部署）时设置 ALLOW_MULTI_INSTANCE=true 跳过检查
+const INSTANCE_LOCK_STALE_SECS: u64 = 120;
+// 心跳原地覆盖写入，补齐到固定长度避免截断时读到半份内容（serde_json 忽略末尾空白）
+const INSTANCE_LOCK_RECORD_LEN: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct InstanceLockInfo {
+    pid: u32,
+    started_at: u64,
+    heartbeat: u64,
+}
+
+// 持有期间保持锁文件，退出 auto_mine 时删除；只删除仍属于本进程（pid 与 started_at 一致）的锁文件
+struct InstanceLock {
+    path: String,
+    pid: u32,
+    started_at: u64,
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        if read_instance_lock(&self.path).is_some_and(|info| info.pid == self.pid && info.started_at == self.started_at) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn read_instance_lock(path: &str) -> Option<InstanceLockInfo> {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str::<InstanceLockInfo>(&s).ok())
+}
+
+fn write_instance_lock(file: &mut fs::File, info: &InstanceLockInfo) -> Result<(), anyhow::Error> {
+    let mut record = serde_json::to_string(info)?;
+    if record.len() < INSTANCE_LOCK_RECORD_LEN {
+        record.push_str(&" ".repeat(INSTANCE_LOCK_RECORD_LEN - record.len()));
+    }
+    io::Seek::seek(file, io::SeekFrom::Start(0))?;
+    file.write_all(record.as_bytes())?;
+    file.flush()?;
+    Ok(())
+}
+
+// 锁文件是否仍被持有：内容无法解析（刚创建尚未写入）时按文件修改时间判断
+fn instance_lock_held(path: &str, existing: Option<&InstanceLockInfo>) -> bool {
+    match existing {
+        Some(info) => {
+            let fresh = unix_now().saturating_sub(info.heartbeat) <= INSTANCE_LOCK_STALE_SECS;
+            // 能检查进程时（Linux /proc）以进程是否存在为准
+            let proc_dir = std::path::Path::new("/proc");
+            let alive = !proc_dir.exists() || proc_dir.join(info.pid.to_string()).exists();
+            fresh && alive
+        }
+        None => fs::metadata(path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| SystemTime::now().duration_since(t).ok())
+            .is_none_or(|age| age.as_secs() <= INSTANCE_LOCK_STALE_SECS),
+    }
+}
+
+// 接管残留锁（比较并交换）：只有 rename 成功、且移走的内容与判断时读到的一致，才算接管成功
+fn take_over_stale_lock(path: &str, expected: Option<&InstanceLockInfo>) -> Result<bool, anyhow::Error> {
+    let aside = format!("{}.stale.{}", path, std::process::id());
+    match fs::rename(path, &aside) {
+        Ok(()) => {}
+        // 已被其他进程移走或删除，直接重试 create_new
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+        Err(e) => return Err(e.into()),
+    }
+    let moved = read_instance_lock(&aside);
+    if expected.is_some() && moved.as_ref() != expected {
+        // 判断之后有其他实例抢先接管并写入了新锁：放回原处（hard_link 在目标已存在时失败，不会覆盖）
+        let _ = fs::hard_link(&aside, path);
+        let _ = fs::remove_file(&aside);
+        return Ok(false);
+    }
+    let _ = fs::remove_file(&aside);
+    Ok(true)
+}
+
+// 最近 check_secs 秒内其他进程的部署交易
+async fn recent_deploy_signature(rpc: &RpcClient, authority: Pubkey, check_secs: u64) -> Result<Option<(String, i64)>, anyhow::Error> {
+    let now = chain_now() as i64;
+    let recent = |s: &solana_client::rpc_response::RpcConfirmedTransactionStatusWithSignature| {
+        s.err.is_none() && s.block_time.is_some_and(|t| now - t <= check_secs as i64)
+    };
+    for s in rpc.get_signatures_for_address(&authority).await?.iter().take(10) {
+        let deploy_memo = s
+            .memo
+            .as_deref()
+            .and_then(parse_bot_memo)
+            .is_some_and(|fields| fields.contains_key("round"));
+        if recent(s) && deploy_memo {
+            return Ok(Some((s.signature.clone(), now - s.block_time.unwrap_or(now))));
+        }
+    }
+    for s in rpc.get_signatures_for_address(&miner_pda(authority).0).await?.iter().take(10) {
+        if !recent(s) {
+            continue;
+        }
+        let Ok(signature) = solana_sdk::signature::Signature::from_str(&s.signature) else {
+            continue;
+        };
+        if fetch_transaction_signers(rpc, &signature).await?.contains(&authority) {
+            return Ok(Some((s.signature.clone(), now - s.block_time.unwrap_or(now))));
+        }
+    }
+    Ok(None)
+}
+
+async fn acquire_instance_lock(rpc: &RpcClient, authority: Pubkey) -> Result<Option<InstanceLock>, anyhow::Error> {
+    if env_flag("ALLOW_MULTI_INSTANCE") {
+        out!("[lock] ALLOW_MULTI_INSTANCE 已开启，跳过实例检查");
+        return Ok(None);
+    }
+    let override_hint = "确认需要多实例时设置 ALLOW_MULTI_INSTANCE=true";
+    let path = state_path(&format!("ore.{}.lock", authority));
+    let held_error = |pid: Option<u32>| -> anyhow::Error {
+        CommandError::config(format!(
+            "[lock] 另一个实例（pid {}）正在使用 authority {}（锁文件 {}）；{}",
+            pid.map(|p| p.to_string()).unwrap_or_else(|| "?".to_string()),
+            authority,
+            path,
+            override_hint
+        ))
+        .into()
+    };
+
+    let check_secs: u64 = std::env::var("INSTANCE_CHECK_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(60);
+    if check_secs > 0 {
+        match recent_deploy_signature(rpc, authority, check_secs).await {
+            Ok(Some((signature, age))) => {
+                return Err(CommandError::config(format!(
+                    "[lock] authority {} 在 {} 秒内有其他进程的部署交易 {}，可能有其他实例在运行；{}",
+                    authority, age, signature, override_hint
+                ))
+                .into());
+            }
+            Ok(None) => {}
+            Err(e) => out!("[lock] ⚠️  链上活动检查失败: {:?}，仅使用本地锁", e),
+        }
+    }
+
+    let pid = std::process::id();
+    let started_at = unix_now();
+    let mut file = None;
+    for _ in 0..3 {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        match options.open(&path) {
+            Ok(f) => {
+                file = Some(f);
+                break;
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                let existing = read_instance_lock(&path);
+                if instance_lock_held(&path, existing.as_ref()) {
+                    return Err(held_error(existing.map(|info| info.pid)));
+                }
+                out!(
+                    "[lock] 发现残留锁文件（pid {}），接管",
+                    existing.as_ref().map(|info| info.pid.to_string()).unwrap_or_else(|| "?".to_string())
+                );
+                if !take_over_stale_lock(&path, existing.as_ref())? {
+                    return Err(held_error(read_instance_lock(&path).map(|info| info.pid)));
+                }
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+    let Some(mut file) = file else {
+        return Err(held_error(read_instance_lock(&path).map(|info| info.pid)));
+    };
+    write_instance_lock(&mut file, &InstanceLockInfo { pid, started_at, heartbeat: started_at })?;
+    out!("[lock] 已获取实例锁 {}（pid {}）", path, pid);
+    let heartbeat_path = path.clone();
+    tokio::spawn(async move {
+        loop {
+            sleep(Duration::from_secs(30)).await;
+            // 锁文件被删除（已退出）或被其他实例接管时停止心跳；写入走已打开的句柄，不会覆盖别人的锁文件
+            let ours = read_instance_lock(&heartbeat_path).is_some_and(|info| info.pid == pid && info.started_at == started_at);
+            if !ours {
+                break;
+            }
+            let _ = write_instance_lock(&mut file, &InstanceLockInfo { pid, started_at, heartbeat: unix_now() });
+        }
+    });
+    Ok(Some(InstanceLock { path, pid, started_at }))
+}
+
+// ============ 新增：运行边界 ============
+// MAX_LOOPS 统计的是观察到的回合切换次数；另外提供三个明确的边界，任一满足即退出：
+//   MAX_ROUNDS=N        实际部署的回合数
+//   MAX_DURATION=6h     运行时长（支持 s / m / h / d 后缀，无后缀为秒）
+//   RUN_UNTIL=...       截止时间：unix 时间戳，或 UTC 时间 2025-01-31T08:00[:00][Z]
+// 设置了以上任一边界且未显式设置 MAX_LOOPS 时，不再使用 MAX_LOOPS 的默认值 100
+struct RunBounds {
+    max_loops: Option<usize>,
+    max_rounds: Option<usize>,
+    started: Instant,
+    max_duration: Option<Duration>,
+    until: Option<u64>,
+}
+
+fn parse_duration_secs(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (number, unit) = match s.char_indices().last()? {
+        (i, c) if c.is_ascii_alphabetic() => (&s[..i], c.to_ascii_lowercase()),
+        _ => (s, 's'),
+    };
+    let value: f64 = number.trim().parse().ok()?;
+    let multiplier = match unit {
+        's' => 1.0,
+        'm' => 60.0,
+        'h' => 3600.0,
+        'd' => 86400.0,
+        _ => return None,
+    };
+    (value >= 0.0).then_some((value * multiplier) as u64)
+}
+
+// unix 时间戳或 UTC 日期时间（YYYY-MM-DD[THH:MM[:SS]][Z]）
+fn parse_utc_timestamp(s: &str) -> Option<u64> {
+    let s = s.trim().trim_end_matches('Z');
+    if let Ok(ts) = s.parse::<u64>() {
+        return Some(ts);
+    }
+    let (date, time) = s.split_once(['T', ' ']).unwrap_or((s, "00:00:00"));
+    let mut date_parts = date.split('-').map(|p| p.parse::<i64>().ok());
+    let (year, month, day) = (date_parts.next()??, date_parts.next()??, date_parts.next()??);
+    let mut time_parts = time.split(':').map(|p| p.parse::<i64>().ok());
+    let hour = time_parts.next().flatten().unwrap_or(0);
+    let minute = time_parts.next().flatten().unwrap_or(0);
+    let second = time_parts.next().flatten().unwrap_or(0);
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    // days_from_civil（Howard Hinnant 算法，与 utc_date 互逆）
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    u64::try_from(days * 86400 + hour * 3600 + minute * 60 + second).ok()
+}
+
+impl RunBounds {
+    fn from_env(max_loops: usize) -> Result<Self, anyhow::Error> {
+        let max_rounds = match std::env::var("MAX_ROUNDS") {
+            Ok(v) => Some(
+                v.trim()
+                    .parse::<usize>()
+                    .map_err(|_| CommandError::config(format!("MAX_ROUNDS 无效: {}", v)))?,
+            ),
+            Err(_) => None,
+        };
+        let max_duration = match std::env::var("MAX_DURATION") {
+            Ok(v) => Some(Duration::from_secs(parse_duration_secs(&v).ok_or_else(|| {
+                CommandError::config(format!("MAX_DURATION 无效: {}（示例：90m、6h、1d）", v))
+            })?)),
+            Err(_) => None,
+        };
+        let until = match std::env::var("RUN_UNTIL") {
+            Ok(v) => Some(parse_utc_timestamp(&v).ok_or_else(|| {
+                CommandError::config(format!("RUN_UNTIL 无效: {}（unix 时间戳或 2025-01-31T08:00:00Z）", v))
+            })?),
+            Err(_) => None,
+        };
+        let explicit_bounds = max_rounds.is_some() || max_duration.is_some() || until.is_some();
+        let max_loops = if explicit_bounds && std::env::var("MAX_LOOPS").is_err() {
+            None
+        } else {
+            Some(max_loops)
+        };
+        Ok(Self {
+            max_loops,
+            max_rounds,
+            started: Instant::now(),
+            max_duration,
+            until,
+        })
+    }
+
+    fn describe(&self) -> String {
+        let mut parts = vec![];
+        if let Some(n) = self.max_loops {
+            parts.push(format!("回合切换 {} 次", n));
+        }
+        if let Some(n) = self.max_rounds {
+            parts.push(format!("部署 {} 轮", n));
+        }
+        if let Some(d) = self.max_duration {
+            parts.push(format!("运行 {} 秒", d.as_secs()));
+        }
+        if let Some(ts) = self.until {
+            parts.push(format!("截止 {} (unix {})", utc_date(ts), ts));
+        }
+        if parts.is_empty() {
+            "无限制".to_string()
+        } else {
+            parts.join(" / ")
+        }
+    }
+
+    // 返回触发的退出原因
+    fn reached(&self, loops_done: usize, rounds_played: usize) -> Option<&'static str> {
+        if self.max_loops.is_some_and(|n| loops_done >= n) {
+            return Some("max_loops");
+        }
+        if self.max_rounds.is_some_and(|n| rounds_played >= n) {
+            return Some("max_rounds");
+        }
+        if self.max_duration.is_some_and(|d| self.started.elapsed() >= d) {
+            return Some("max_duration");
+        }
+        let now = chain_now();
+        if self.until.is_some_and(|ts| now >= ts) {
+            return Some("run_until");
+        }
+        None
+    }
+}
+
+// ============ 新增：结算规则（if / then） ============
+// RULES 为分号分隔的规则，每条形如 "条件 => 动作1, 动作2"，auto_mine 在每轮结算时按顺序求值：
+//   条件：won / lost（本轮部署且命中 / 未中）、loss_streak>=N、win_streak>=N、motherlode（本轮开出 motherlode）、always
+//   动作：claim_sol（checkpoint 并立即领取 SOL）、strategy:<算法>（切换选格算法）、notify（发送通知，事件名 rule）、
+//         pause:<秒>（暂停部署）、stop（结束 auto_mine）
+// 例：RULES="won => claim_sol; loss_streak>=3 => strategy:optimized; motherlode => notify, pause:3600"
+#[derive(Debug, Clone, PartialEq)]
+enum RuleCondition {
+    Won,
+    Lost,
+    LossStreak(usize),
+    WinStreak(usize),
+    Motherlode,
+    Always,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum RuleAction {
+    ClaimSol,
+    Strategy(SquareSelectionAlgorithm),
+    Notify,
+    Pause(u64),
+    Stop,
+}
+
+#[derive(Debug, Clone)]
+struct Rule {
+    text: String,
+    condition: RuleCondition,
+    actions: Vec<RuleAction>,
+}
+
+fn parse_rule(text: &str) -> Result<Rule, String> {
+    let (condition, actions) = text.split_once("=>").ok_or("缺少 =>")?;
+    let condition = condition.trim();
+    let streak = |prefix: &str| -> Option<Result<usize, String>> {
+        let n = condition.strip_prefix(prefix)?.trim();
+        Some(n.parse::<usize>().map_err(|_| format!("无效的次数 {}", n)))
+    };
+    let condition = match condition {
+        "won" => RuleCondition::Won,
+        "lost" => RuleCondition::Lost,
+        "motherlode" => RuleCondition::Motherlode,
+        "always" => RuleCondition::Always,
+        _ => match (streak("loss_streak>="), streak("win_streak>=")) {
+            (Some(n), _) => RuleCondition::LossStreak(n?),
+            (_, Some(n)) => RuleCondition::WinStreak(n?),
+            _ => return Err(format!("未知条件 {}", condition)),
+        },
+    };
+    let mut parsed = vec![];
+    for action in actions.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        parsed.push(match action.split_once(':') {
+            None if action == "claim_sol" => RuleAction::ClaimSol,
+            None if action == "notify" => RuleAction::Notify,
+            None if action == "stop" => RuleAction::Stop,
+            Some(("strategy", name)) => RuleAction::Strategy(
+                SquareSelectionAlgorithm::parse(name).ok_or(format!("未知算法 {}", name))?,
+            ),
+            Some(("pause", secs)) => {
+                RuleAction::Pause(secs.trim().parse::<u64>().map_err(|_| format!("无效的秒数 {}", secs))?)
+            }
+            _ => return Err(format!("未知动作 {}", action)),
+        });
+    }
+    if parsed.is_empty() {
+        return Err("没有动作".to_string());
+    }
+    Ok(Rule {
+        text: text.trim().to_string(),
+        condition,
+        actions: parsed,
+    })
+}
+
+struct RuleEngine {
+    rules: Vec<Rule>,
+    win_streak: usize,
+    loss_streak: usize,
+    paused_until: Option<Instant>,
+    stopped: bool,
+}
+
+impl RuleEngine {
+    fn from_env() -> Result<Self, anyhow::Error> {
+        let mut rules = vec![];
+        for text in std::env::var("RULES").unwrap_or_default().split(';').filter(|s| !s.trim().is_empty()) {
+            let rule = parse_rule(text)
+                .map_err(|e| CommandError::config(format!("RULES 中的规则 \"{}\" 无效: {}", text.trim(), e)))?;
+            out!("[rules] {}", rule.text);
+            rules.push(rule);
+        }
+        Ok(Self {
+            rules,
+            win_streak: 0,
+            loss_streak: 0,
+            paused_until: None,
+            stopped: false,
+        })
+    }
+
+    // 结算时调用：won 为 None 表示本轮未部署（不影响连胜 / 连败）。返回需要执行的动作及触发的规则
+    fn settle(&mut self, result: &RoundResult, won: Option<bool>) -> Vec<(String, RuleAction)> {
+        match won {
+            Some(true) => {
+                self.win_streak += 1;
+                self.loss_streak = 0;
+            }
+            Some(false) => {
+                self.loss_streak += 1;
+                self.win_streak = 0;
+            }
+            None => {}
+        }
+        let mut fired = vec![];
+        for rule in self.rules.iter() {
+            let matched = match rule.condition {
+                RuleCondition::Won => won == Some(true),
+                RuleCondition::Lost => won == Some(false),
+                RuleCondition::LossStreak(n) => won == Some(false) && self.loss_streak >= n,
+                RuleCondition::WinStreak(n) => won == Some(true) && self.win_streak >= n,
+                RuleCondition::Motherlode => result.motherlode > 0,
+                RuleCondition::Always => true,
+            };
+            if matched {
+                out!("[rules] round={} 触发规则：{}", result.round_id, rule.text);
+                fired.extend(rule.actions.iter().map(|a| (rule.text.clone(), a.clone())));
+            }
+        }
+        fired
+    }
+
+    fn pause(&mut self, secs: u64) {
+        self.paused_until = Some(Instant::now() + Duration::from_secs(secs));
+    }
+
+    // 暂停中返回剩余秒数
+    fn pause_remaining(&mut self) -> Option<u64> {
+        let until = self.paused_until?;
+        let now = Instant::now();
+        if now >= until {
+            self.paused_until = None;
+            return None;
+        }
+        Some((until - now).as_secs())
+    }
+}
+
+// checkpoint 刚结算的回合并领取 SOL（奖励在 checkpoint 后才记入 Miner）
+async fn rule_claim_sol(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+    authority: Pubkey,
+    round_id: u64,
+) -> Result<(), anyhow::Error> {
+    if env_flag("DRY_RUN") {
+        out!("[rules] 预览模式（DRY_RUN），不领取 SOL");
+        return Ok(());
+    }
+    let mut ixs = vec![
+        ore_api::sdk::checkpoint(payer.pubkey(), authority, round_id),
+        ore_api::sdk::claim_sol(authority),
+    ];
+    ixs.extend(memo_instruction(&[("claim", "sol".to_string())]));
+    let sig = submit_transaction(rpc, payer, &ixs).await?;
+    print_claim_receipt(rpc, "sol", &sig);
+    Ok(())
+}
+
+// ============ 新增：会话汇总 ============
+// auto_mine 结束时（达到运行边界 / Ctrl-C）打印汇总，并追加一行 JSON 到 ore.sessions.jsonl
+const SESSION_LOG_FILE: &str = "ore.sessions.jsonl";
+
+static STOP_REQUESTED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+// 第一次 Ctrl-C 请求在当前步骤结束后退出（以便输出汇总），第二次立即退出
+fn install_stop_handler() {
+    tokio::spawn(async {
+        while tokio::signal::ctrl_c().await.is_ok() {
+            if STOP_REQUESTED.swap(true, std::sync::atomic::Ordering::Relaxed) {
+                out!("[auto] 再次收到 Ctrl-C，立即退出");
+                std::process::exit(130);
+            }
+            out!("[auto] 收到 Ctrl-C，将在当前步骤结束后输出会话汇总并退出（再按一次立即退出）");
+        }
+    });
+}
+
+fn stop_requested() -> bool {
+    STOP_REQUESTED.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+// 批量命令（checkpoint_all / close_all / backfill）的取消与超时：Ctrl-C 或超过 COMMAND_TIMEOUT_SECS 时
+// 设置 STOP_REQUESTED，循环在当前批次 / 分页结束后停止并保留已完成的进度；单次 RPC 请求超时见 rpc_request_timeout
+fn install_cancellation(label: &'static str) {
+    install_stop_handler();
+    let Some(secs) = std::env::var("COMMAND_TIMEOUT_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .filter(|s| *s > 0)
+    else {
+        return;
+    };
+    tokio::spawn(async move {
+        sleep(Duration::from_secs(secs)).await;
+        if !STOP_REQUESTED.swap(true, std::sync::atomic::Ordering::Relaxed) {
+            out!("[{}] 已运行 {} 秒（COMMAND_TIMEOUT_SECS），当前步骤结束后停止", label, secs);
+        }
+    });
+}
+
+// 单次 RPC 请求超时（RPC_TIMEOUT_SECS，默认 30 秒）
+fn rpc_request_timeout() -> Duration {
+    Duration::from_secs(
+        std::env::var("RPC_TIMEOUT_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(30)
+            .max(1),
+    )
+}
+
+// 批量操作进度条（stderr，带 ETA），用于 checkpoint_all / close_all / backfill / split_fund：
+// QUIET、OUTPUT=json、PROGRESS=false 或 stderr 不是终端时不显示
+struct Progress {
+    label: &'static str,
+    total: u64,
+    done: u64,
+    started: Instant,
+    last_draw: Option<Instant>,
+    enabled: bool,
+}
+
+impl Progress {
+    fn new(label: &'static str, total: u64) -> Self {
+        use std::io::IsTerminal;
+        let json_output = std::env::var("OUTPUT").map(|o| o == "json").unwrap_or(false);
+        let enabled = !env_flag("QUIET")
+            && !json_output
+            && (std::env::var("PROGRESS").is_err() || env_flag("PROGRESS"))
+            && io::stderr().is_terminal();
+        Self {
+            label,
+            total,
+            done: 0,
+            started: Instant::now(),
+            last_draw: None,
+            enabled,
+        }
+    }
+
+    fn inc(&mut self, n: u64) {
+        self.set(self.done + n);
+    }
+
+    fn set(&mut self, done: u64) {
+        self.done = done.min(self.total);
+        // 最多每 100ms 重绘一次，完成时总是重绘
+        if self.done < self.total && self.last_draw.is_some_and(|t| t.elapsed() < Duration::from_millis(100)) {
+            return;
+        }
+        self.draw();
+    }
+
+    fn draw(&mut self) {
+        if !self.enabled {
+            return;
+        }
+        self.last_draw = Some(Instant::now());
+        const WIDTH: u64 = 30;
+        let filled = (self.done * WIDTH).checked_div(self.total).unwrap_or(WIDTH);
+        let elapsed = self.started.elapsed().as_secs_f64();
+        let eta = if self.done > 0 && self.done < self.total {
+            let secs = (elapsed / self.done as f64 * (self.total - self.done) as f64) as u64;
+            format!("ETA {:02}:{:02}", secs / 60, secs % 60)
+        } else {
+            format!("用时 {:.0}s", elapsed)
+        };
+        eprint!(
+            "\r\x1b[2K[{}] [{}{}] {}/{} {}",
+            self.label,
+            "#".repeat(filled as usize),
+            "-".repeat((WIDTH - filled) as usize),
+            self.done,
+            self.total,
+            eta
+        );
+        let _ = io::stderr().flush();
+    }
+
+    // 输出一行普通日志，不打断进度条
+    fn println(&mut self, line: &str) {
+        if self.enabled {
+            eprint!("\r\x1b[2K");
+        }
+        out!("{}", line);
+        if self.enabled {
+            self.draw();
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.enabled && self.last_draw.is_some() {
+            self.draw();
+            eprintln!();
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+struct SessionSummary {
+    started_at: u64,
+    ended_at: u64,
+    exit_reason: String,
+    strategy: Vec<String>,
+    rounds_observed: usize,
+    rounds_played: usize,
+    rounds_skipped: usize,
+    // 未部署回合的原因 -> 回合数
+    skip_reasons: BTreeMap<String, usize>,
+    total_spent_lamports: u64,
+    // 协议收取的部署费（1%）估算
+    protocol_fee_lamports: u64,
+    wins: usize,
+    losses: usize,
+    // 钱包余额 + 可领 SOL，差值即为本次会话的已实现盈亏（含交易费）
+    start_value_lamports: Option<u64>,
+    end_value_lamports: Option<u64>,
+    realized_pnl_lamports: Option<i64>,
+}
+
+struct SessionTracker {
+    summary: SessionSummary,
+    current_round: Option<u64>,
+    played_current: bool,
+    last_skip: Option<&'static str>,
+}
+
+impl SessionTracker {
+    fn new(strategy: Vec<String>, start_value_lamports: Option<u64>) -> Self {
+        Self {
+            summary: SessionSummary {
+                started_at: chain_now(),
+                strategy,
+                start_value_lamports,
+                ..Default::default()
+            },
+            current_round: None,
+            played_current: false,
+            last_skip: None,
+        }
+    }
+
+    fn observe(&mut self, round_id: u64) {
+        if self.current_round != Some(round_id) {
+            self.finish_current();
+            self.current_round = Some(round_id);
+            self.summary.rounds_observed += 1;
+        }
+    }
+
+    // 记录本轮最近一次未部署的原因（以回合结束时的最后一次为准）
+    fn skip(&mut self, reason: &'static str) {
+        self.last_skip = Some(reason);
+    }
+
+    fn played(&mut self, cost_lamports: u64) {
+        self.played_current = true;
+        self.summary.rounds_played += 1;
+        self.summary.total_spent_lamports += cost_lamports;
+        self.summary.protocol_fee_lamports += (cost_lamports as f64 * BACKTEST_ADMIN_FEE) as u64;
+    }
+
+    fn settled(&mut self, won: bool) {
+        if won {
+            self.summary.wins += 1;
+        } else {
+            self.summary.losses += 1;
+        }
+    }
+
+    fn finish_current(&mut self) {
+        if self.current_round.is_some() && !self.played_current {
+            self.summary.rounds_skipped += 1;
+            let reason = self.last_skip.unwrap_or("未进入部署窗口");
+            *self.summary.skip_reasons.entry(reason.to_string()).or_default() += 1;
+        }
+        self.played_current = false;
+        self.last_skip = None;
+    }
+
+    fn finish(mut self, exit_reason: &str, end_value_lamports: Option<u64>) -> SessionSummary {
+        self.finish_current();
+        let mut summary = self.summary;
+        summary.ended_at = chain_now();
+        summary.exit_reason = exit_reason.to_string();
+        summary.end_value_lamports = end_value_lamports;
+        if let (Some(start), Some(end)) = (summary.start_value_lamports, end_value_lamports) {
+            summary.realized_pnl_lamports = Some(end as i64 - start as i64);
+        }
+        summary
+    }
+}
+
+// 钱包余额 + 可领 SOL
+async fn wallet_value_lamports(rpc: &RpcClient, authority: Pubkey) -> Option<u64> {
+    let balance = rpc.get_balance(&authority).await.ok()?;
+    let claimable = get_miner(rpc, authority).await.map(|m| m.rewards_sol).unwrap_or(0);
+    Some(balance + claimable)
+}
+
+fn print_session_summary(summary: &SessionSummary) {
+    out!("[auto] ===== 会话汇总 =====");
+    out!(
+        "[auto] 运行时长 {} 秒，结束原因: {}",
+        summary.ended_at.saturating_sub(summary.started_at),
+        summary.exit_reason
+    );
+    for label in summary.strategy.iter() {
+        out!("[auto] 策略: {}", label);
+    }
+    out!(
+        "[auto] 观察回合 {}，部署 {}，跳过 {}",
+        summary.rounds_observed, summary.rounds_played, summary.rounds_skipped
+    );
+    for (reason, count) in summary.skip_reasons.iter() {
+        out!("[auto]   跳过原因 {}: {} 轮", reason, count);
+    }
+    out!(
+        "[auto] 总花费 {}，协议费约 {}，命中 {} 轮 / 未中 {} 轮",
+        fmt_sol(summary.total_spent_lamports),
+        fmt_sol(summary.protocol_fee_lamports),
+        summary.wins,
+        summary.losses
+    );
+    match summary.realized_pnl_lamports {
+        Some(pnl) => out!(
+            "[auto] 已实现盈亏（钱包 + 可领 SOL，含交易费）: {}{}",
+            if pnl < 0 { "-" } else { "+" },
+            fmt_sol(pnl.unsigned_abs())
+        ),
+        None => out!("[auto] 已实现盈亏: 无法读取钱包余额"),
+    }
+    if let Ok(line) = serde_json::to_string(summary) {
+        if let Ok(mut file) = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(state_path(SESSION_LOG_FILE))
+        {
+            let _ = writeln!(file, "{}", line);
+        }
+    }
+}
+
+// ============ 新增：策略预热期 ============
+// WARMUP_ROUNDS=N 时，新策略（策略描述与上次预热不同）先只观察 N 个回合：照常记录快照、计算部署计划，但不下注。
+// 每轮以进入部署窗口后最后一次计算的计划为准，回合结束后按开奖结果用回测模型估算假设盈亏。
+// 满 N 轮后输出预热报告（终端 + ore.warmup_report.json + 告警），随后同一会话内开始正式部署；
+// 进度保存在 ore.warmup.json，进程重启后继续累计，同一策略预热完成后不再重复
+const WARMUP_FILE: &str = "ore.warmup.json";
+const WARMUP_REPORT_FILE: &str = "ore.warmup_report.json";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct WarmupRound {
+    round_id: u64,
+    secs_remaining: f64,
+    // 决策时看到的部署分布
+    decision: [u64; 25],
+    // 策略给出的计划（None = 本轮不会部署）
+    plan: Option<[u64; 25]>,
+    // 回合结束后填入
+    winning_square: Option<u64>,
+    settled: Option<[u64; 25]>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WarmupState {
+    strategy: Vec<String>,
+    target_rounds: usize,
+    started_at: u64,
+    completed_at: Option<u64>,
+    rounds: Vec<WarmupRound>,
+}
+
+#[derive(Debug, Serialize)]
+struct WarmupReport {
+    strategy: Vec<String>,
+    rounds_observed: usize,
+    rounds_settled: usize,
+    rounds_would_play: usize,
+    wins: usize,
+    // 按选中格子数 / 25 计算的随机命中期望
+    expected_wins: f64,
+    cost_lamports: u64,
+    payout_lamports: u64,
+    pnl_lamports: i64,
+    roi: f64,
+    // 决策后到回合结束的平均流入（占决策时总部署的比例）
+    late_inflow_ratio: f64,
+    // 开奖格子在决策时的部署量：中位数，以及低于 THRESHOLD_SOL 的比例
+    winning_decision_median_sol: f64,
+    threshold_sol: f64,
+    winning_below_threshold_ratio: f64,
+}
+
+struct Warmup {
+    state: WarmupState,
+}
+
+impl Warmup {
+    fn from_env(strategy: &[String]) -> Option<Self> {
+        let target_rounds: usize = std::env::var("WARMUP_ROUNDS")
+            .ok()
+            .and_then(|s| s.parse().ok())
+            .filter(|n| *n > 0)?;
+        let saved = fs::read_to_string(state_path(WARMUP_FILE))
+            .ok()
+            .and_then(|s| serde_json::from_str::<WarmupState>(&s).ok())
+            .filter(|state| state.strategy == strategy);
+        let state = match saved {
+            Some(state) if state.completed_at.is_some() => {
+                out!("[warmup] 当前策略已完成预热（{} 轮），直接部署", state.rounds.len());
+                return None;
+            }
+            Some(mut state) => {
+                state.target_rounds = target_rounds;
+                state
+            }
+            None => WarmupState {
+                strategy: strategy.to_vec(),
+                target_rounds,
+                started_at: chain_now(),
+                ..Default::default()
+            },
+        };
+        out!(
+            "[warmup] 预热期：观察 {} 轮后开始部署（已观察 {} 轮），期间不下注",
+            state.target_rounds,
+            state.rounds.len()
+        );
+        Some(Self { state })
+    }
+
+    fn active(&self) -> bool {
+        self.state.completed_at.is_none()
+    }
+
+    // 记录本轮的决策（同一回合多次调用时保留最后一次）
+    fn observe(&mut self, round_id: u64, secs_remaining: f64, decision: [u64; 25], plan: Option<[u64; 25]>) {
+        let observation = WarmupRound {
+            round_id,
+            secs_remaining,
+            decision,
+            plan,
+            winning_square: None,
+            settled: None,
+        };
+        match self.state.rounds.iter_mut().find(|r| r.round_id == round_id) {
+            Some(existing) => *existing = observation,
+            None => {
+                self.state.rounds.push(observation);
+                out!(
+                    "[warmup] 观察 round={}（{}/{}），本轮不部署",
+                    round_id,
+                    self.state.rounds.len(),
+                    self.state.target_rounds
+                );
+            }
+        }
+        self.save();
+    }
+
+    // 回合结束：填入开奖结果；观察满 N 轮时结束预热并返回报告
+    fn settle(&mut self, result: Option<&RoundResult>, round_id: u64) -> Option<WarmupReport> {
+        if let Some(result) = result {
+            if let Some(observed) = self.state.rounds.iter_mut().find(|r| r.round_id == result.round_id) {
+                observed.winning_square = Some(result.winning_square);
+                observed.settled = Some(result.deployed);
+            }
+        }
+        let done = self.active()
+            && self.state.rounds.len() >= self.state.target_rounds
+            && self.state.rounds.iter().all(|r| r.round_id <= round_id);
+        if done {
+            self.state.completed_at = Some(chain_now());
+        }
+        self.save();
+        done.then(|| self.report())
+    }
+
+    fn report(&self) -> WarmupReport {
+        let threshold_sol = read_auto_params_from_env().1;
+        let mut report = WarmupReport {
+            strategy: self.state.strategy.clone(),
+            rounds_observed: self.state.rounds.len(),
+            rounds_settled: 0,
+            rounds_would_play: 0,
+            wins: 0,
+            expected_wins: 0.0,
+            cost_lamports: 0,
+            payout_lamports: 0,
+            pnl_lamports: 0,
+            roi: 0.0,
+            late_inflow_ratio: 0.0,
+            winning_decision_median_sol: 0.0,
+            threshold_sol,
+            winning_below_threshold_ratio: 0.0,
+        };
+        let mut late_inflow = Vec::new();
+        let mut winning_decision = Vec::new();
+        for round in self.state.rounds.iter() {
+            let (Some(winning_square), Some(settled)) = (round.winning_square, round.settled) else {
+                continue;
+            };
+            let winning_square = winning_square as usize;
+            report.rounds_settled += 1;
+            let decision_total: u64 = round.decision.iter().sum();
+            if decision_total > 0 {
+                late_inflow.push(settled.iter().sum::<u64>().saturating_sub(decision_total) as f64 / decision_total as f64);
+            }
+            winning_decision.push(lamports_to_sol(round.decision[winning_square]));
+
+            let Some(plan) = round.plan else {
+                continue;
+            };
+            report.rounds_would_play += 1;
+            report.cost_lamports += plan.iter().sum::<u64>();
+            report.expected_wins += plan.iter().filter(|v| **v > 0).count() as f64 / 25.0;
+            if plan[winning_square] > 0 {
+                report.wins += 1;
+                // 与回测一致：部署扣 1%，失败池扣 10% 后按中奖格子份额分配
+                let stakes = plan.map(|v| (v as f64 * (1.0 - BACKTEST_ADMIN_FEE)) as u64);
+                let with_stakes: Vec<u64> = settled.iter().zip(stakes.iter()).map(|(s, m)| s + m).collect();
+                let losing_pool: u64 = with_stakes
+                    .iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i != winning_square)
+                    .map(|(_, v)| *v)
+                    .sum();
+                let share = stakes[winning_square] as f64 / with_stakes[winning_square] as f64;
+                report.payout_lamports +=
+                    stakes[winning_square] + (losing_pool as f64 * (1.0 - BACKTEST_VAULT_FEE) * share) as u64;
+            }
+        }
+        report.pnl_lamports = report.payout_lamports as i64 - report.cost_lamports as i64;
+        if report.cost_lamports > 0 {
+            report.roi = report.pnl_lamports as f64 / report.cost_lamports as f64;
+        }
+        if !late_inflow.is_empty() {
+            report.late_inflow_ratio = late_inflow.iter().sum::<f64>() / late_inflow.len() as f64;
+        }
+        if !winning_decision.is_empty() {
+            report.winning_below_threshold_ratio =
+                winning_decision.iter().filter(|v| **v < threshold_sol).count() as f64 / winning_decision.len() as f64;
+            winning_decision.sort_by(|a, b| a.total_cmp(b));
+            report.winning_decision_median_sol = winning_decision[winning_decision.len() / 2];
+        }
+        report
+    }
+
+    fn save(&self) {
+        if let Ok(json) = serde_json::to_string(&self.state) {
+            let _ = fs::write(state_path(WARMUP_FILE), json);
+        }
+    }
+}
+
+fn print_warmup_report(report: &WarmupReport) {
+    out!("[warmup] ===== 预热报告 =====");
+    for label in report.strategy.iter() {
+        out!("[warmup] 策略: {}", label);
+    }
+    out!(
+        "[warmup] 观察 {} 轮（已开奖 {} 轮），其中策略会部署 {} 轮",
+        report.rounds_observed, report.rounds_settled, report.rounds_would_play
+    );
+    out!(
+        "[warmup] 假设命中 {} 轮（随机期望 {:.1} 轮），花费 {}，回收 {}，盈亏 {}（ROI {:+.2}%，未计 ORE 与交易费）",
+        report.wins,
+        report.expected_wins,
+        fmt_sol(report.cost_lamports),
+        fmt_sol(report.payout_lamports),
+        fmt_sol_signed(report.pnl_lamports),
+        report.roi * 100.0
+    );
+    out!(
+        "[warmup] 决策后平均流入 {:+.1}%（决策时的分布{}）",
+        report.late_inflow_ratio * 100.0,
+        if report.late_inflow_ratio > 0.2 { "与最终分布差异较大，可考虑开启 LATE_INFLOW 或推迟 START_BEFORE_SECONDS" } else { "基本代表最终分布" }
+    );
+    out!(
+        "[warmup] 开奖格子决策时部署量中位数 {:.4} SOL，{:.0}% 低于 THRESHOLD_SOL={}",
+        report.winning_decision_median_sol,
+        report.winning_below_threshold_ratio * 100.0,
+        report.threshold_sol
+    );
+    if let Ok(json) = serde_json::to_string_pretty(report) {
+        let _ = fs::write(state_path(WARMUP_REPORT_FILE), json);
+    }
+    notify_alert(
+        "warmup",
+        &format!(
+            "预热完成：观察 {} 轮，假设部署 {} 轮，命中 {} 轮，假设盈亏 {}；开始正式部署",
+            report.rounds_observed,
+            report.rounds_would_play,
+            report.wins,
+            fmt_sol_signed(report.pnl_lamports)
+        ),
+    );
+}
+
+async fn auto_mine(
+    primary_rpc: &RpcClient,
+    payer: &dyn TxSigner,
+    algorithm: SquareSelectionAlgorithm,
+) -> Result<(), anyhow::Error> {
+    let (amount_lamports, _, _, _, max_loops) = read_auto_params_from_env();
+    let authority = mining_authority(payer).pubkey();
+    if amount_lamports == 0 {
+        return Err(CommandError::config("[auto] AMOUNT/AMOUNT_SOL 未设置或为 0，退出。").into());
+    }
+    // REPLAY=<文件>：按录制数据重放决策，不连接链上
+    if let Ok(path) = std::env::var("REPLAY") {
+        return replay_session(&path, algorithm);
+    }
+    let replay_record = std::env::var("REPLAY_RECORD").ok().filter(|p| !p.is_empty());
+    let _instance_lock = acquire_instance_lock(primary_rpc, authority).await?;
+
+    let mut decider = DeployDecider::from_env(algorithm)?;
+    let mut rules = RuleEngine::from_env()?;
+    if !decider.constraints.is_empty() {
+        out!(
+            "[auto] 固定格子: {:?}，排除格子: {:?}",
+            decider.constraints.pinned, decider.constraints.excluded
+        );
+    }
+    if let Some(members) = &decider.ensemble {
+        out!("[auto] 多策略组合模式：");
+        for m in members {
+            out!("[auto]   {:.0}% {}", m.allocation_pct, m.params.label());
+        }
+    }
+
+    let mut processed_round: Option<u64> = None;
+    // 优先费预测：已告警 / 已放弃部署的回合
+    let mut fee_alerted_round: Option<u64> = None;
+    let mut fee_skipped_round: Option<u64> = None;
+    // 远程停止开关开启期间已尝试 checkpoint 的回合
+    let mut halt_checkpoint_round: Option<u64> = None;
+    // 保存本轮部署信息：round_id -> (部署的格子, 花费 SOL)
+    let mut round_deployment_info: Option<(u64, Vec<usize>, u64)> = None;
+    let mut loops_done: usize = 0;
+    let mut total_spent: u128 = 0;
+
+    let mut inflow = InflowTracker::new(decider.velocity_samples);
+    let mut competition = CompetitionGuard::from_env()?;
+
+    // ATOMIC_CHECKPOINT=true：checkpoint(上一轮) + deploy(本轮) 放在同一笔交易中。
+    // 默认关闭：合并后 checkpoint 只随部署提交，本轮跳过或部署失败时会一直推迟
+    let atomic_checkpoint = env_flag("ATOMIC_CHECKPOINT");
+
+    // 持久化记录已部署轮次，避免重复部署
+    const LAST_DEPLOYED_ROUND_FILE: &str = "ore.last_deployed_round";
+    let read_last_deployed_round = || -> Option<u64> {
+        fs::read_to_string(state_path(LAST_DEPLOYED_ROUND_FILE))
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+    };
+    let write_last_deployed_round = |round_id: u64| {
+        let _ = fs::write(state_path(LAST_DEPLOYED_ROUND_FILE), round_id.to_string());
+    };
+    let clear_last_deployed_round = || {
+        let _ = std::fs::remove_file(state_path(LAST_DEPLOYED_ROUND_FILE));
+    };
+
+    // 上一次循环看到的回合，用于与 board/clock 并发预取 Round
+    let mut last_round_id: Option<u64> = None;
+
+    let mut watchdog = SlotLagWatchdog::from_env();
+    let mut blackout = BlackoutMonitor::from_env();
+    let mut tilt = TiltGuard::from_env();
+    let mut top_miner = TopMinerTracker::new(authority);
+    let mut reconciler = BalanceReconciler::from_env();
+    let mut split = SplitWallets::from_env()?;
+    let mut jitter = SubmissionJitter::from_env();
+    if jitter.is_enabled() {
+        out!(
+            "[jitter] 触发时间 ±{}s，下注金额 ±{}%",
+            jitter.trigger_secs, jitter.amount_pct
+        );
+    }
+    if let Some(split) = &split {
+        out!("[split] 分钱包模式：{} 个钱包轮换部署", split.wallets.len());
+        if reconciler.take().is_some() {
+            out!("[split] 部署资金不经过主钱包，资金对账已关闭");
+        }
+    }
+
+    let strategy_labels = decider.labels();
+    let mut session = SessionTracker::new(
+        strategy_labels.clone(),
+        wallet_value_lamports(primary_rpc, authority).await,
+    );
+    let mut warmup = Warmup::from_env(&strategy_labels);
+    install_stop_handler();
+    start_kill_switch_watch()?;
+    let bounds = RunBounds::from_env(max_loops)?;
+    out!("[auto] 运行边界: {}", bounds.describe());
+    let exit_reason;
+
+    start_metrics_exporters(authority.to_string());
+    spawn_expiry_guard(authority);
+    spawn_slot_hash_recorder();
+    spawn_fee_forecaster();
+
+    loop {
+        if let Some(reason) = bounds.reached(loops_done, session.summary.rounds_played) {
+            exit_reason = reason;
+            break;
+        }
+        if stop_requested() {
+            exit_reason = "ctrl_c";
+            break;
+        }
+        if rules.stopped {
+            exit_reason = "rule_stop";
+            break;
+        }
+
+        // 主 RPC 落后时不基于过期数据部署（必要时已切换到参考节点）
+        if watchdog.check(primary_rpc).await {
+            session.skip("RPC 落后或不可用");
+            sleep(Duration::from_secs(1)).await;
+            continue;
+        }
+        let rpc = watchdog.active(primary_rpc);
+
+        // board / clock / 上一轮次的 round 并发读取，缩短最后几秒的决策延迟
+        let loop_started = Instant::now();
+        let (board_result, clock_result, prefetched_round) = tokio::join!(
+            get_board_at(rpc, None),
+            get_clock(rpc),
+            async {
+                match last_round_id {
+                    Some(id) => get_round_at(rpc, id, None).await.ok(),
+                    None => None,
+                }
+            }
+        );
+        let fetch_ms = loop_started.elapsed().as_millis();
+        record_phase(LoopPhase::Fetch, loop_started.elapsed());
+
+        // 使用重试机制处理 RPC 错误，避免因网络问题导致程序崩溃
+        let (board, board_context_slot) = match board_result {
+            Ok(b) => b,
+            Err(e) => {
+                if blackout.failure("Board", &e) {
+                    blackout.wait_for_recovery(&watchdog.endpoints(primary_rpc)).await;
+                    continue;
+                }
+                out!("[auto] ⚠️  读取 Board 失败: {:?}，等待 2 秒后重试...", e);
+                sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        };
+
+        let clock = match clock_result {
+            Ok(c) => c,
+            Err(e) => {
+                if blackout.failure("Clock", &e) {
+                    blackout.wait_for_recovery(&watchdog.endpoints(primary_rpc)).await;
+                    continue;
+                }
+                out!("[auto] ⚠️  读取 Clock 失败: {:?}，等待 2 秒后重试...", e);
+                sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        };
+        if let Some(missed) = blackout.success(board.round_id) {
+            for _ in 0..missed {
+                session.skip("RPC 断连");
+            }
+        }
+        let current_slot = clock.slot;
+        last_round_id = Some(board.round_id);
+        METRIC_ROUND_ID.set(board.round_id);
+        session.observe(board.round_id);
+
+        // 数据一致性验证：确保 Board 和 Clock 数据是有效的
+        let board_view = BoardView::new(board, current_slot);
+        if !board_view.is_valid() {
+            out!("[auto] ⚠️  警告：Board 数据异常 (start_slot={} >= end_slot={})，等待 2 秒后重试...",
+                board.start_slot, board.end_slot);
+            sleep(Duration::from_secs(2)).await;
+            continue;
+        }
+
+        // 与 print_board 保持一致（BoardView）
+        let slot_diff = board_view.slots_remaining();
+        let secs_left = board_view.secs_remaining();
+
+        // 输出状态
+        out!(
+            "[auto] round={} 剩余 {} slots ({:.2}s)，等待触发阈值（< START_BEFORE_SECONDS）",
+            board.round_id, slot_diff, secs_left
+        );
+
+        let start_before_seconds: f64 = std::env::var("START_BEFORE_SECONDS")
+            .ok()
+            .and_then(|s| s.parse::<f64>().ok())
+            .unwrap_or(40.0);
+        let start_before_seconds = jitter.trigger_before(board.round_id, start_before_seconds);
+
+        if secs_left <= start_before_seconds {
+            // 读取持久化记录，避免同一轮次重复部署（即使进程重启）
+            let persisted_last = read_last_deployed_round();
+            if processed_round == Some(board.round_id) || persisted_last == Some(board.round_id) {
+                // 已成功部署过该回合，等待下一回合，跳过所有读取和判定
+                if let Some((round_id, squares, cost_lamports)) = &round_deployment_info {
+                    if *round_id == board.round_id {
+                        out!("[auto] 本轮 (round={}) 已部署完成：{} 个格子，花费 {}，等待下一轮...", 
+                            board.round_id, squares.len(), fmt_sol(*cost_lamports));
+                    } else {
+                        out!("[auto] 本轮 (round={}) 已部署完成，等待下一轮...", board.round_id);
+                    }
+                } else {
+                    out!("[auto] 本轮 (round={}) 已部署完成，等待下一轮...", board.round_id);
+                }
+            } else if fee_skipped_round == Some(board.round_id) {
+                out!("[fee] 本轮 (round={}) 预测手续费超过期望收益，已跳过，等待下一轮...", board.round_id);
+                session.skip("优先费过高");
+            } else if let Some(remaining) = tilt.pause_remaining() {
+                out!("[tilt] 冷却中，本轮 (round={}) 不部署，约 {} 秒后恢复", board.round_id, remaining);
+                session.skip("亏损冷却");
+            } else if kill_switch_engaged() {
+                out!("[kill] 远程停止开关已开启，本轮 (round={}) 不部署", board.round_id);
+                session.skip("远程停止开关");
+                // 停止部署期间仍然结算上一轮，避免奖励过期（分钱包模式由过期保护 EXPIRY_GUARD 兜底）
+                if split.is_none() && halt_checkpoint_round != Some(board.round_id) {
+                    halt_checkpoint_round = Some(board.round_id);
+                    if let Ok(miner) = get_miner_with_commitment(rpc, authority, commitment_for(OpClass::Strategy)).await {
+                        if miner.round_id < board.round_id && miner.checkpoint_id < miner.round_id {
+                            submit_checkpoint(rpc, payer, authority, &miner, reconciler.as_mut()).await;
+                        }
+                    }
+                }
+            } else if let Some(remaining) = rules.pause_remaining() {
+                out!("[rules] 规则暂停中，本轮 (round={}) 不部署，约 {} 秒后恢复", board.round_id, remaining);
+                session.skip("规则暂停");
+            } else {
+                // 未成功部署，继续读取棋盘格并判定
+                // 获取当前回合部署分布（使用重试机制）
+                // 预取的 round 与当前回合一致时直接复用，否则补一次读取
+                let round_result = match prefetched_round.filter(|(r, _)| r.id == board.round_id) {
+                    Some(r) => Ok(r),
+                    None => get_round_at(rpc, board.round_id, None).await,
+                };
+                let round = match round_result {
+                    Ok((r, round_context_slot)) => {
+                        // 立即验证 round_id 一致性，避免使用过时的 Round 数据：
+                        // 按 context slot 判断哪一方过期，只重新读取过期的一方
+                        if r.id != board.round_id {
+                            out!("[auto] ⚠️  Round ID 不一致 (board.round_id={}, round.id={})，尝试按 context slot 修正...", board.round_id, r.id);
+                            match resolve_round_mismatch(rpc, (board, board_context_slot), (r, round_context_slot)).await {
+                                Some((resolved_board, resolved_round)) if resolved_board.round_id == board.round_id => {
+                                    out!("[auto] ✅ 已修正 Round 数据 (round={})", resolved_round.id);
+                                    resolved_round
+                                }
+                                Some((resolved_board, _)) => {
+                                    out!("[auto] 轮次已切换 {} -> {}，重新开始判定", board.round_id, resolved_board.round_id);
+                                    continue;
+                                }
+                                None => {
+                                    out!("[auto] ⚠️  Round ID 仍不一致，可能是新回合刚启动，等待 1 秒后重试...");
+                                    sleep(Duration::from_secs(1)).await;
+                                    continue;
+                                }
+                            }
+                        } else {
+                            r
+                        }
+                    }
+                    Err(e) => {
+                        out!("[auto] ⚠️  读取 Round {} 失败: {:?}，等待 1 秒后重试...", board.round_id, e);
+                        sleep(Duration::from_secs(1)).await;
+                        continue;
+                    }
+                };
+                
+                // 输出调试信息：显示当前 slot 和数据获取时间
+                out!(
+                    "[auto] 数据获取时间: slot={}, 当前回合: {}，读取耗时 {} ms",
+                    current_slot, board.round_id, fetch_ms
+                );
+
+                top_miner.observe(&round);
+                if let Some(twap) = inflow.push(round.id, current_slot, round.deployed) {
+                    append_round_twap(&twap);
+                }
+                // RECORD_SNAPSHOTS 开启时记录观察到的部署分布（供归档 / 回测）
+                if env_flag("RECORD_SNAPSHOTS") || warmup.as_ref().is_some_and(|w| w.active()) {
+                    append_board_snapshot(&BoardSnapshot {
+                        timestamp: chain_now(),
+                        slot: current_slot,
+                        round_id: round.id,
+                        deployed: round.deployed,
+                        end_slot: Some(board.end_slot),
+                    });
+                }
+                let velocity = inflow.velocities();
+                let bet_factor = tilt.bet_factor()
+                    * competition
+                        .as_mut()
+                        .map(|c| c.assess(round.id, velocity.iter().sum(), round.count.iter().sum()))
+                        .unwrap_or(1.0);
+                let decide_started = Instant::now();
+                let secs_remaining = slots_to_secs(board.end_slot.saturating_sub(current_slot));
+                let (plan, explain) = decider.plan(
+                    round.id,
+                    &round.deployed,
+                    &velocity,
+                    &inflow.twap(),
+                    bet_factor,
+                    secs_remaining,
+                );
+                record_phase(LoopPhase::Decide, decide_started.elapsed());
+                append_decision_explanation(&explain);
+                if let Some(path) = &replay_record {
+                    append_replay_frame(path, &ReplayFrame {
+                        timestamp: chain_now(),
+                        slot: current_slot,
+                        board_start_slot: board.start_slot,
+                        board_end_slot: board.end_slot,
+                        round_id: round.id,
+                        deployed: round.deployed,
+                        bet_factor,
+                        strategy: strategy_labels.clone(),
+                        plan,
+                    });
+                }
+                if plan.is_none() {
+                    session.skip("策略未选中格子");
+                }
+                // 预热期只记录计划，不部署
+                let plan = match warmup.as_mut() {
+                    Some(warmup) if warmup.active() => {
+                        warmup.observe(round.id, secs_remaining, round.deployed, plan);
+                        session.skip("预热观察");
+                        None
+                    }
+                    _ => plan,
+                };
+
+                if let Some(plan) = plan {
+                        let picked: Vec<usize> = (0..25).filter(|&i| plan[i] > 0).collect();
+                        out!("[auto] 选中格子: {:?}", picked);
+
+                        // 部署前资金对账
+                        if let Some(reconciler) = reconciler.as_mut() {
+                            if let Err(message) = reconciler.check(rpc, authority, board.round_id).await {
+                                out!("[auto] 资金对账未通过（RECONCILE_HALT），停止挖矿: {}", message);
+                                exit_reason = "balance_drift";
+                                break;
+                            }
+                        }
+                        
+                        // 部署前检查是否需要 checkpoint
+                        // 重要：只有在满足以下条件时才执行 checkpoint：
+                        // 1. miner 所在的 round_id < 当前 board 的 round_id
+                        // 2. miner 尚未 checkpoint 到该 round
+                        // 3. 当前轮次还有充足时间部署
+                        let mut did_checkpoint = false;
+                        // ATOMIC_CHECKPOINT 开启时，checkpoint 指令暂存，随部署交易一起提交
+                        let mut pending_checkpoint: Option<(Instruction, Miner)> = None;
+                        // 分钱包模式下主钱包不部署，由本轮的部署钱包在部署交易中自行 checkpoint
+                        if split.is_none() {
+                            match get_miner_with_commitment(rpc, authority, commitment_for(OpClass::Strategy)).await {
+                                Ok(miner) => {
+                                    let miner_before = miner;
+                                    // 修复：更严格的 checkpoint 条件检查
+                                    // 只有当 miner 完全处于旧轮次时才需要 checkpoint
+                                    if miner.round_id < board.round_id && miner.checkpoint_id < miner.round_id {
+                                        out!("[auto] 检测到需要 checkpoint：miner.round_id={}, checkpoint_id={}, 当前 round_id={}",
+                                            miner.round_id, miner.checkpoint_id, board.round_id);
+                                        let checkpoint_ix = ore_api::sdk::checkpoint(
+                                            authority,
+                                            authority,
+                                            miner.round_id,
+                                        );
+                                        if atomic_checkpoint {
+                                            out!("[auto] checkpoint 将与本轮部署合并为同一笔交易提交");
+                                            pending_checkpoint = Some((checkpoint_ix, miner_before));
+                                        } else {
+                                            did_checkpoint = submit_checkpoint(rpc, payer, authority, &miner_before, reconciler.as_mut()).await;
+                                            if !did_checkpoint {
+                                                // 如果部署时仍然失败，会在部署阶段报错
+                                                out!("[auto] 尝试继续部署...");
+                                            }
+                                        }
+                                    } else if miner.round_id == board.round_id && miner.checkpoint_id < miner.round_id {
+                                        // 同一轮但未 checkpoint，这种情况不需要 checkpoint，可以直接部署
+                                        out!("[auto] Miner 已在当前轮次，无需 checkpoint，直接部署");
+                                    }
+                                }
+                                Err(e) => {
+                                    out!("[auto] 警告：无法读取 Miner 账户: {:?}，继续尝试部署", e);
+                                }
+                            }
+                        }
+                        // 刚刚执行了 checkpoint 时不再跳过本轮：下面会重新读取最新的 board/round，
+                        // 在同一循环内紧接着部署到当前轮次，避免因结算上一轮而错过本轮
+                        if did_checkpoint {
+                            out!("[auto] 已完成上一轮 checkpoint，继续在本轮部署...");
+                        }
+                        
+                        // 部署前再次验证 Board/Round 一致性，并尽量使用最新快照，降低竞态
+                        // （三者并发读取；round 按当前回合读取，随后校验 ID 一致）
+                        let refetch_started = Instant::now();
+                        let (latest_board_result, latest_round_result, latest_clock_result) = tokio::join!(
+                            get_board_at(rpc, None),
+                            get_round_at(rpc, board.round_id, None),
+                            get_clock(rpc)
+                        );
+                        record_phase(LoopPhase::Fetch, refetch_started.elapsed());
+                        let (latest_board, latest_board_context_slot) = match latest_board_result {
+                            Ok(b) => b,
+                            Err(e) => {
+                                out!("[auto] 警告：读取 Board 失败: {:?}，跳过本次部署", e);
+                                continue;
+                            }
+                        };
 
                         // 验证Round ID是否变化（说明轮次已经结束或转移）
                         if latest_board.round_id != board.round_id {
-                            println!("[auto] ⚠️  轮次已变化！检测到新轮次 {} -> {}，跳过本次部署，等待下一轮", board.round_id, latest_board.round_id);
+                            out!("[auto] ⚠️  轮次已变化！检测到新轮次 {} -> {}，跳过本次部署，等待下一轮", board.round_id, latest_board.round_id);
                             // 重置为新轮次，让主循环检测到变化
                             processed_round = None;
                             round_deployment_info = None;
@@ -790,663 +8061,5809 @@ async fn auto_mine(
                             continue;
                         }
 
-                        let latest_round = match get_round(rpc, latest_board.round_id).await {
-                            Ok(r) => r,
-                            Err(e) => {
-                                println!("[auto] 警告：Round 账户 {} 无法读取: {:?}，跳过本次部署", latest_board.round_id, e);
-                                continue;
-                            }
-                        };
-                        if latest_round.id != latest_board.round_id {
-                            println!("[auto] 警告：Board/Round ID不一致 (board.round_id={}, round.id={})，可能正在轮次切换，跳过本次部署", latest_board.round_id, latest_round.id);
-                            continue;
-                        }
+                        let (latest_round, latest_round_context_slot) = match latest_round_result {
+                            Ok(r) => r,
+                            Err(e) => {
+                                out!("[auto] 警告：Round 账户 {} 无法读取: {:?}，跳过本次部署", latest_board.round_id, e);
+                                continue;
+                            }
+                        };
+                        if latest_round.id != latest_board.round_id {
+                            out!("[auto] 警告：Board/Round ID不一致 (board.round_id={}, round.id={})，尝试按 context slot 修正...", latest_board.round_id, latest_round.id);
+                            match resolve_round_mismatch(
+                                rpc,
+                                (latest_board, latest_board_context_slot),
+                                (latest_round, latest_round_context_slot),
+                            )
+                            .await
+                            {
+                                Some((resolved_board, _)) if resolved_board.round_id == board.round_id => {
+                                    out!("[auto] ✅ Board/Round 已一致，继续部署");
+                                }
+                                _ => {
+                                    out!("[auto] 警告：Board/Round 仍不一致，可能正在轮次切换，跳过本次部署");
+                                    continue;
+                                }
+                            }
+                        }
+
+                        let current_slot_for_check = match latest_clock_result {
+                            Ok(c) => c.slot,
+                            Err(e) => {
+                                out!("[auto] 警告：读取 Clock 失败（检查回合结束）: {:?}，跳过本次部署", e);
+                                continue;
+                            }
+                        };
+
+                        // 检查轮次是否即将结束
+                        let slots_remaining = if latest_board.end_slot > current_slot_for_check {
+                            latest_board.end_slot - current_slot_for_check
+                        } else {
+                            0
+                        };
+
+                        // 定义两个阈值：
+                        // - danger_zone_slots (约6秒): 在这个时间内，只进行单次快速提交，不重试
+                        // - buffer_slots (约2秒): 这个时间内不再尝试提交
+                        let danger_zone_slots = 15u64;  // ~6秒 (15 * 0.4秒)
+                        let buffer_slots = 5u64;        // ~2秒 (5 * 0.4秒)
+
+                        if slots_remaining <= buffer_slots {
+                            session.skip("剩余时间不足");
+                            out!("[auto] ⚠️  轮次即将结束：剩余 {} slots (~{:.1}s，< {:.1}s 缓冲)，跳过本次部署以避免交易过期",
+                                slots_remaining, slots_remaining as f64 * 0.4, buffer_slots as f64 * 0.4);
+                            continue;
+                        }
+
+                        if latest_board.end_slot <= current_slot_for_check {
+                            out!("[auto] ⚠️  当前回合已结束，跳过本次部署");
+                            continue;
+                        }
+
+                        // 判断是否处于危险区间（轮次剩余时间很短）
+                        let is_danger_zone = slots_remaining <= danger_zone_slots;
+                        if is_danger_zone {
+                            out!("[auto] ⚠️  进入危险区间：轮次剩余 {:.1}s (~{} slots)，将进行单次快速提交（不重试）",
+                                slots_remaining as f64 * 0.4, slots_remaining);
+                        }
+                        
+                        // 部署前记录关键信息
+                        out!("[auto] 准备部署到轮次 {}，剩余时间约 {:.2}s，格子: {:?}",
+                            latest_board.round_id,
+                            (latest_board.end_slot as f64 - current_slot_for_check as f64) * 0.4,
+                            picked);
+                        out!(
+                            "[auto] 决策耗时 {} ms（首轮读取 {} ms）",
+                            loop_started.elapsed().as_millis(),
+                            fetch_ms
+                        );
+                        METRIC_DECISION_LATENCY_MS.set(loop_started.elapsed().as_millis() as u64);
+
+                        // AMOUNT_JITTER_PCT：下注金额随机浮动
+                        let mut plan = jitter.perturb(plan);
+                        // 分钱包模式：本轮由随机选中的钱包部署，金额随机浮动，提交前随机等待
+                        let mut split_index = None;
+                        if let Some(split) = split.as_mut() {
+                            let index = split.next_index();
+                            plan = plan.map(|v| if v > 0 { split.jitter(v) } else { 0 });
+                            let delay = split.delay();
+                            out!(
+                                "[split] 本轮部署钱包 {}，金额 {}",
+                                Signer::pubkey(&split.wallets[index]),
+                                fmt_sol(plan.iter().sum())
+                            );
+                            if !is_danger_zone && !delay.is_zero() {
+                                out!("[split] 随机等待 {} ms 后提交", delay.as_millis());
+                                sleep(delay).await;
+                            }
+                            split_index = Some(index);
+                        }
+                        // MAX_POT_FRACTION 按随机化后的最终金额与最新奖池检查
+                        let Some(plan) = decider.guard_pot(plan, &latest_round.deployed) else {
+                            session.skip("奖池上限");
+                            continue;
+                        };
+                        let deployer: &dyn TxSigner = match (&split, split_index) {
+                            (Some(split), Some(index)) => &split.wallets[index],
+                            _ => payer,
+                        };
+                        let deploy_authority = if split_index.is_some() { deployer.pubkey() } else { authority };
+
+                        // 金额相同的格子合并为一条 deploy 指令
+                        let build_started = Instant::now();
+                        let deploy_groups = group_deploy_plan(&plan);
+
+                        // 改进错误处理：不 panic，记录错误并继续
+                        let this_round_cost: u128 = plan.iter().map(|&v| v as u128).sum();
+                        let this_round_cost_u64 =
+                            this_round_cost.min(u64::MAX as u128) as u64;
+
+                        // 根据轮次剩余时间选择提交策略
+                        // 危险区间（剩余时间少于6秒）：单次快速提交，不重试
+                        // 安全区间：有重试的提交
+                        let mut deploy_ixs = Vec::with_capacity(deploy_groups.len() + 1);
+                        if let Some((checkpoint_ix, _)) = &pending_checkpoint {
+                            deploy_ixs.push(checkpoint_ix.clone());
+                        }
+                        if split_index.is_some() {
+                            if let Ok(miner) = get_miner_with_commitment(rpc, deploy_authority, commitment_for(OpClass::Strategy)).await {
+                                if miner.round_id < latest_board.round_id && miner.checkpoint_id < miner.round_id {
+                                    deploy_ixs.push(ore_api::sdk::checkpoint(deploy_authority, deploy_authority, miner.round_id));
+                                }
+                            }
+                        }
+                        for (amount, squares) in deploy_groups.iter() {
+                            deploy_ixs.push(ore_api::sdk::deploy(
+                                deploy_authority,
+                                deploy_authority,
+                                *amount,
+                                latest_board.round_id,
+                                *squares,
+                            ));
+                        }
+                        deploy_ixs.extend(memo_instruction(&[
+                            ("round", latest_board.round_id.to_string()),
+                            ("strategy", match &decider.ensemble {
+                                Some(_) => "ensemble".to_string(),
+                                None => algorithm.name().to_string(),
+                            }),
+                            ("cost", this_round_cost_u64.to_string()),
+                            ("squares", picked.len().to_string()),
+                        ]));
+                        record_phase(LoopPhase::Build, build_started.elapsed());
+                        // 手续费把本轮的非负期望收益变为负时告警（FEE_FORECAST_SKIP 时跳过）；
+                        // 只有危险区间的单次提交使用预测优先费，其余提交按 COMPUTE_UNIT_PRICE 计算
+                        if let Some(forecast) = fee_forecast() {
+                            let signatures = if deploy_authority == deployer.pubkey() { 1 } else { 2 };
+                            let configured_fee = expected_tx_fee_lamports(signatures);
+                            let fee = if is_danger_zone {
+                                tx_fee_lamports(signatures, forecast.cu_price).max(configured_fee)
+                            } else {
+                                configured_fee
+                            };
+                            let edge = plan_expected_value(&plan, &latest_round.deployed, forecast.ore_price_sol);
+                            if edge >= 0.0 && edge - (fee as f64) < 0.0 {
+                                let message = format!(
+                                    "round {} {}手续费 {} 超过期望收益 {}",
+                                    latest_board.round_id,
+                                    if is_danger_zone {
+                                        format!("危险区间预测优先费 {} microlamports/CU，", forecast.cu_price)
+                                    } else {
+                                        String::new()
+                                    },
+                                    fmt_sol(fee),
+                                    fmt_sol_signed(edge as i64)
+                                );
+                                // 每轮只告警一次
+                                if fee_alerted_round != Some(latest_board.round_id) {
+                                    fee_alerted_round = Some(latest_board.round_id);
+                                    out!("[fee] ⚠️  {}", message);
+                                    notify_alert("fee_forecast", &message);
+                                }
+                                if env_flag("FEE_FORECAST_SKIP") {
+                                    session.skip("优先费过高");
+                                    fee_skipped_round = Some(latest_board.round_id);
+                                    continue;
+                                }
+                            }
+                        }
+                        // 本次签出的所有部署交易签名，提交报错时据此核对是否已上链
+                        let mut sent = vec![];
+                        let submit_result = if is_danger_zone {
+                            out!("[auto] 💨 危险区间：采用快速单次提交！");
+                            submit_transaction_danger_zone_no_retry(rpc, deployer, &deploy_ixs, &mut sent).await
+                        } else {
+                            submit_transaction_tracked(rpc, deployer, deploy_authority, &deploy_ixs, &mut sent).await
+                        };
+                        if env_flag("PROFILE_TIMING") {
+                            print_phase_timing();
+                        }
+                        // 提交报错但部署可能已落地（例如确认超时）：按已部署处理，避免下次循环重复部署
+                        let submit_result = match submit_result {
+                            Err(e) => match find_landed_deploy(rpc, deploy_authority, latest_board.round_id, &sent).await {
+                                Some(LandedDeploy::Sent(sig)) => {
+                                    out!("[auto] ⚠️  提交报错 ({:?})，但本次发出的部署 {} 已上链，不再重试", e, sig);
+                                    Ok(sig)
+                                }
+                                Some(LandedDeploy::Other) => {
+                                    out!(
+                                        "[auto] ⚠️  提交报错 ({:?})，Miner 已有本轮部署但不是本次发出的交易，本轮不再部署",
+                                        e
+                                    );
+                                    session.skip("已有其他部署");
+                                    processed_round = Some(latest_board.round_id);
+                                    write_last_deployed_round(latest_board.round_id);
+                                    continue;
+                                }
+                                None => Err(e),
+                            },
+                            ok => ok,
+                        };
+
+                        match submit_result {
+                            Ok(sig) => {
+                                out!("[auto] ✅ 部署成功！交易签名: {}", sig);
+                                if let Some((_, miner_before)) = &pending_checkpoint {
+                                    out!("[auto] ✅ 上一轮 (round={}) checkpoint 已随部署交易完成", miner_before.round_id);
+                                    let credited = log_checkpoint_rewards(rpc, authority, miner_before, &sig).await;
+                                    if let Some(reconciler) = reconciler.as_mut() {
+                                        reconciler.record_credit(credited.unwrap_or(0));
+                                    }
+                                }
+                                if decider.ensemble.is_some() {
+                                    out!("[auto] 本次部署花费: {} ({} 个格子，{} 条 deploy 指令)",
+                                        fmt_sol(this_round_cost_u64),
+                                        picked.len(),
+                                        deploy_groups.len());
+                                } else {
+                                    out!("[auto] 本次部署花费: {} ({} 个格子 × {:.6} SOL/格子)",
+                                        fmt_sol(this_round_cost_u64),
+                                        picked.len(),
+                                        lamports_to_sol(amount_lamports));
+                                }
+                                total_spent += this_round_cost;
+                                session.played(this_round_cost_u64);
+                                if let Some(reconciler) = reconciler.as_mut() {
+                                    reconciler.record_deploy(this_round_cost_u64);
+                                }
+                                // 只有成功部署后，才标记为已处理，后续等待下一轮
+                                processed_round = Some(latest_board.round_id);
+                                // 保存本轮部署信息，用于后续循环显示
+                                round_deployment_info =
+                                    Some((latest_board.round_id, picked.clone(), this_round_cost_u64));
+
+                                let algo_label = match &decider.ensemble {
+                                    Some(_) => "ensemble",
+                                    None => algorithm.name(),
+                                };
+                                append_reward_event(RewardEvent {
+                                    round: Some(latest_board.round_id),
+                                    algorithm: Some(algo_label.to_string()),
+                                    squares: Some(picked.len()),
+                                    cost_lamports: Some(this_round_cost_u64),
+                                    tx: Some(sig.to_string()),
+                                    wallet: split_index.map(|_| deploy_authority.to_string()),
+                                    ..RewardEvent::new("deploy")
+                                });
+
+                                // 写入持久化记录（避免同轮次重复部署）
+                                write_last_deployed_round(latest_board.round_id);
+
+                                // 输出收益信息（部署后校验使用 confirmed 级别，避免读到被回滚的数据）
+                                let commitment = commitment_for(OpClass::Confirmation);
+                                if let Ok(miner) = get_miner_with_commitment(rpc, deploy_authority, commitment).await {
+                                    out!(
+                                        "[auto] 累计花费 {}，当前可领 ORE: {}，SOL: {}",
+                                        fmt_sol(total_spent as u64),
+                                        fmt_ore(miner.rewards_ore + miner.refined_ore),
+                                        fmt_sol(miner.rewards_sol),
+                                    );
+                                }
+                                out!("[auto] 本轮已部署完成，等待下一轮...");
+                            }
+                            Err(e) => {
+                                out!("[auto] ⚠️  部署失败: {:?}", e);
+                                METRIC_DEPLOY_FAILURES.inc_by(1);
+                                session.skip("部署失败");
+                                out!("[auto] 可能原因：Round 账户数据无效、账户未初始化、或网络问题。将重试。");
+                                // 不设置 processed_round，下次循环继续尝试
+                                // 重要：使用 latest_board.round_id 而非 board.round_id，确保轮次一致
+                            }
+                        }
+                } else {
+                    // 未选中任何格子，继续尝试
+                    // 注意：不设置 processed_round，下次循环继续尝试读取和判定
+                }
+            }
+        }
+
+        sleep(Duration::from_millis(500)).await;
+
+        // 重新获取最新的 board 和 clock，检查是否进入新轮次（使用重试机制）
+        let (new_board_result, new_clock_result) = tokio::join!(get_board(rpc), get_clock(rpc));
+        let new_board = match new_board_result {
+            Ok(b) => b,
+            Err(e) => {
+                if blackout.failure("Board", &e) {
+                    blackout.wait_for_recovery(&watchdog.endpoints(primary_rpc)).await;
+                    continue;
+                }
+                out!("[auto] ⚠️  读取 Board 失败（检查新轮次）: {:?}，等待 2 秒后重试...", e);
+                sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        };
+
+        let new_clock = match new_clock_result {
+            Ok(c) => c,
+            Err(e) => {
+                if blackout.failure("Clock", &e) {
+                    blackout.wait_for_recovery(&watchdog.endpoints(primary_rpc)).await;
+                    continue;
+                }
+                out!("[auto] ⚠️  读取 Clock 失败（检查新轮次）: {:?}，等待 2 秒后重试...", e);
+                sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        };
+
+        // 检查轮次是否变化
+        if new_board.round_id != board.round_id {
+            // 轮次已经变化，这是正常的轮次切换
+            out!("[auto] ✅ 检测到新轮次：{} -> {}", board.round_id, new_board.round_id);
+            let settled = record_round_settled(rpc, board.round_id).await;
+            if let Some(result) = &settled {
+                top_miner.settled(result);
+            }
+            if let Some(report) = warmup.as_mut().and_then(|w| w.settle(settled.as_ref(), board.round_id)) {
+                print_warmup_report(&report);
+                out!("[warmup] 预热完成，从下一轮开始正式部署");
+            }
+            let mut played: Option<bool> = None;
+            if let (Some(result), Some((round_id, squares, cost_lamports))) = (&settled, &round_deployment_info) {
+                if *round_id == board.round_id {
+                    let won = squares.contains(&(result.winning_square as usize));
+                    out!(
+                        "[auto] round={} 开奖格子 #{}，{}",
+                        round_id,
+                        result.winning_square,
+                        if won { "已命中" } else { "未命中" }
+                    );
+                    tilt.record(*round_id, won, *cost_lamports);
+                    notify_round_result(result, squares, won);
+                    session.settled(won);
+                    played = Some(won);
+                }
+            }
+            if let Some(result) = &settled {
+                for (rule, action) in rules.settle(result, played) {
+                    match action {
+                        RuleAction::ClaimSol => {
+                            if let Err(e) = rule_claim_sol(rpc, payer, authority, result.round_id).await {
+                                out!("[rules] ⚠️  领取 SOL 失败: {}", e);
+                            }
+                        }
+                        RuleAction::Strategy(algorithm) => {
+                            if decider.ensemble.is_some() {
+                                out!("[rules] ⚠️  多策略组合模式下不切换算法");
+                            } else if decider.algorithm != algorithm {
+                                out!("[rules] 切换选格算法：{} -> {}", decider.algorithm.name(), algorithm.name());
+                                decider.algorithm = algorithm;
+                            }
+                        }
+                        RuleAction::Notify => notify_alert(
+                            "rule",
+                            &format!(
+                                "round {} 触发规则 \"{}\"：开奖格子 #{}，motherlode {}",
+                                result.round_id,
+                                rule,
+                                result.winning_square,
+                                fmt_ore(result.motherlode)
+                            ),
+                        ),
+                        RuleAction::Pause(secs) => {
+                            out!("[rules] 暂停部署 {} 秒", secs);
+                            rules.pause(secs);
+                        }
+                        RuleAction::Stop => {
+                            out!("[rules] 规则要求停止，结束 auto_mine");
+                            rules.stopped = true;
+                        }
+                    }
+                }
+            }
+            emit_round_started(&new_board);
+            loops_done += 1;
+            processed_round = None;
+            round_deployment_info = None; // 清除上一轮的部署信息
+            // 清除持久化记录，允许新轮次重新部署
+            clear_last_deployed_round();
+        } else if new_clock.slot >= board.end_slot {
+            // slot 已经超过或等于 end_slot，但 round_id 还没变化
+            // 这可能表示：
+            // 1. 轮次正在重置过程中
+            // 2. Board 账户还未更新
+            // 3. 出现了网络延迟
+            // 最安全的做法是再等一会，然后重新检查
+            out!("[auto] ⚠️  当前 slot {} >= end_slot {}，轮次可能正在切换，等待状态更新...", new_clock.slot, board.end_slot);
+            // 如果 processed_round 已设置，则等待下一个轮次；否则继续尝试
+            if processed_round.is_some() {
+                // 已经部署过，等待轮次变化
+                out!("[auto] 已在本轮部署，等待新轮次到来...");
+                sleep(Duration::from_secs(3)).await;
+            }
+        }
+    }
+
+    out!(
+        "[auto] 结束。总花费约 {}",
+        fmt_sol(total_spent as u64)
+    );
+    if env_flag("PROFILE_TIMING") {
+        print_phase_summary();
+    }
+    let end_value = wallet_value_lamports(primary_rpc, authority).await;
+    print_session_summary(&session.finish(exit_reason, end_value));
+    Ok(())
+}
+
+// ============ 新增：交互式菜单 ============
+
+async fn interactive_menu(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    // 显示当前奖励
+    let miner = get_miner(rpc, payer.pubkey()).await.ok();
+    if let Some(m) = &miner {
+        let view = MinerView::new(*m);
+        out!(
+            "当前可领：SOL {:.6}，ORE {}",
+            view.claimable_sol(),
+            view.claimable_ore()
+        );
+        if let Some(warning) = pending_checkpoint_warning(rpc, m).await {
+            out!("⚠️  {}", warning);
+        }
+    }
+    out!("请选择：");
+    out!("1) 按预设自动挖矿（阈值算法）");
+    out!("2) 按预设自动挖矿（最优化算法）");
+    out!("3) claim 所有 SOL");
+    out!("4) claim 所有 ORE");
+    out!("5) 查询账户状态（余额/是否为矿工/可领取）");
+    out!("6) 浏览历史回合");
+    out_inline!("输入选项序号并回车: ");
+    let _ = io::stdout().flush();
+    let mut line = String::new();
+    let _ = io::stdin().read_line(&mut line);
+    let choice = line.trim();
+
+    match choice {
+        "1" => {
+            auto_mine(rpc, payer, SquareSelectionAlgorithm::Threshold).await?;
+        }
+        "2" => {
+            auto_mine(rpc, payer, SquareSelectionAlgorithm::Optimized).await?;
+        }
+        "3" => {
+            if let Some(m) = &miner {
+                let sol_amt = lamports_to_sol(m.rewards_sol);
+                if sol_amt <= 0.0 {
+                    out!("当前可领 SOL 为 0，已取消。");
+                    return Ok(());
+                }
+                out!("当前可领 SOL {:.6}。输入 y 确认领取，其他任意键取消：", sol_amt);
+                let mut c = String::new();
+                let _ = io::stdin().read_line(&mut c);
+                if c.trim().to_lowercase() != "y" { out!("已取消。"); return Ok(()); }
+            }
+            let ix_sol = ore_api::sdk::claim_sol(payer.pubkey());
+            let sig = submit_transaction(rpc, payer, &[ix_sol]).await?;
+            print_claim_receipt(rpc, "sol", &sig);
+        }
+        "4" => {
+            if let Some(m) = &miner {
+                let ore_amount = amount_to_ui_amount(m.rewards_ore + m.refined_ore, TOKEN_DECIMALS);
+                if ore_amount <= 0.0 {
+                    out!("当前可领 ORE 为 0，已取消。");
+                    return Ok(());
+                }
+                out!("当前可领 ORE {}。输入 y 确认领取，其他任意键取消：", ore_amount);
+                let mut c = String::new();
+                let _ = io::stdin().read_line(&mut c);
+                if c.trim().to_lowercase() != "y" { out!("已取消。"); return Ok(()); }
+            }
+            let ix_ore = ore_api::sdk::claim_ore(payer.pubkey());
+            let sig = submit_transaction(rpc, payer, &[ix_ore]).await?;
+            print_claim_receipt(rpc, "ore", &sig);
+        }
+        "5" => {
+            query_account_status(rpc, payer).await?;
+        }
+        "6" => {
+            browse_rounds()?;
+        }
+        _ => out!("已取消。"),
+    }
+
+    Ok(())
+}
+
+async fn query_account_status(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    out!("[status] 开始查询账户状态...");
+    let address = payer.pubkey();
+    // 基本网络连通与钱包 SOL 余额
+    match rpc.get_balance(&address).await {
+        Ok(lamports) => {
+            out!("钱包地址: {}", address);
+            out!("钱包余额: {}", fmt_sol(lamports));
+        }
+        Err(e) => {
+            out!("[error] 无法读取钱包余额: {}", e);
+            out!("可能原因：RPC 不可用/网络不匹配。");
+            return Ok(());
+        }
+    }
+
+    // 读取 ORE 配置与当前回合，验证网络是否存在程序状态
+    match get_board(rpc).await {
+        Ok(board) => {
+            let slot = get_clock(rpc).await.map(|c| c.slot).unwrap_or(0);
+            let view = BoardView::new(board, slot);
+            out!("当前回合: {}，距结束约 {:.2}s", view.round_id(), view.secs_remaining());
+        }
+        Err(e) => {
+            out!("[warn] 读取 ORE Board 失败: {}", e);
+        }
+    }
+
+    // Miner 账户与可领取
+    match get_miner(rpc, address).await {
+        Ok(miner) => {
+            let view = MinerView::new(miner);
+            out!("矿工账户: 存在");
+            out!("可领取 ORE: {}", view.claimable_ore());
+            out!("可领取 SOL: {:.6}", view.claimable_sol());
+            out!("当前回合ID: {}，checkpoint到: {}", miner.round_id, miner.checkpoint_id);
+            // 最近参与回合中我在各格子的占比
+            if let Ok(round) = get_round(rpc, miner.round_id).await {
+                let round_view = RoundView::new(round, 0).with_miner(&miner);
+                let shares: Vec<String> = (0..25)
+                    .filter(|i| view.miner.deployed[*i] > 0)
+                    .map(|i| {
+                        format!(
+                            "#{}={:.6} SOL ({:.2}%)",
+                            i,
+                            round_view.square_sol(i),
+                            round_view.my_share(i) * 100.0
+                        )
+                    })
+                    .collect();
+                if !shares.is_empty() {
+                    out!(
+                        "round {} 我的部署: {}，占比: {}",
+                        round_view.id(),
+                        fmt_sol(round_view.my_deployed_lamports()),
+                        shares.join(" ")
+                    );
+                }
+            }
+            if let Some(warning) = pending_checkpoint_warning(rpc, &miner).await {
+                out!("⚠️  {}", warning);
+            }
+            if !view.has_claimable() {
+                out!("提示：当前无可领取奖励。如刚部署，请在回合结束后执行 checkpoint 再领取。");
+            }
+        }
+        Err(_) => {
+            out!("矿工账户: 不存在 (未注册/未初始化)。你需要先成功部署一次来创建 Miner 账户。");
+        }
+    }
+
+    Ok(())
+}
+
+// ============ 新增：历史回合浏览 ============
+// 交互式菜单第 6 项：翻阅 ore.round_results.jsonl 中已归档的回合（新的在前），
+// 每行显示日期、开奖格子、我的参与和 PnL；输入回合号查看该回合的最终分布，输入日期筛选。
+// 我的格子来自决策日志，花费 / 奖励来自奖励日志（checkpoint 事件的 round 为参与的回合），
+// 日期优先取我的部署时间，其次取该回合第一张棋盘快照
+const ROUND_BROWSER_PAGE: usize = 10;
+
+struct BrowsedRound {
+    result: RoundResult,
+    ts: Option<u64>,
+    my_squares: Vec<usize>,
+    cost_lamports: u64,
+    reward_sol: u64,
+    reward_ore: u64,
+}
+
+impl BrowsedRound {
+    fn participated(&self) -> bool {
+        self.cost_lamports > 0 || !self.my_squares.is_empty()
+    }
+
+    fn pnl_lamports(&self) -> i64 {
+        self.reward_sol as i64 - self.cost_lamports as i64
+    }
+
+    fn date(&self) -> String {
+        self.ts.map(utc_date).unwrap_or("-".to_string())
+    }
+}
+
+fn load_browsed_rounds() -> Vec<BrowsedRound> {
+    let mut ts: HashMap<u64, u64> = HashMap::new();
+    for snapshot in read_board_snapshots() {
+        ts.entry(snapshot.round_id).or_insert(snapshot.timestamp);
+    }
+    let mut squares: HashMap<u64, Vec<usize>> = HashMap::new();
+    for decision in fs::read_to_string(state_path(DECISION_LOG_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<DecisionExplanation>(line).ok())
+        .filter(|d| !d.picked.is_empty())
+    {
+        squares.insert(decision.round_id, decision.picked.iter().map(|(s, _)| *s).collect());
+    }
+    let mut cost: HashMap<u64, u64> = HashMap::new();
+    let mut rewards: HashMap<u64, (u64, u64)> = HashMap::new();
+    for event in read_reward_events() {
+        let Some(round) = event.round else {
+            continue;
+        };
+        match event.event.as_str() {
+            "deploy" => {
+                *cost.entry(round).or_default() += event.cost_lamports.unwrap_or(0);
+                ts.insert(round, event.ts);
+            }
+            "checkpoint" => {
+                let entry = rewards.entry(round).or_default();
+                entry.0 += event.delta_sol_lamports.unwrap_or(0);
+                entry.1 += event.delta_rewards_ore.unwrap_or(0) + event.delta_refined_ore.unwrap_or(0);
+            }
+            _ => {}
+        }
+    }
+
+    let mut rounds: Vec<BrowsedRound> = read_round_results()
+        .into_iter()
+        .map(|result| {
+            let id = result.round_id;
+            let (reward_sol, reward_ore) = rewards.get(&id).copied().unwrap_or_default();
+            BrowsedRound {
+                ts: ts.get(&id).copied(),
+                my_squares: squares.remove(&id).unwrap_or_default(),
+                cost_lamports: cost.get(&id).copied().unwrap_or(0),
+                reward_sol,
+                reward_ore,
+                result,
+            }
+        })
+        .collect();
+    rounds.reverse();
+    rounds
+}
+
+fn print_browsed_round_row(round: &BrowsedRound) {
+    let mine = if round.participated() {
+        format!(
+            "{:>2} 格 {:>10.6} SOL {:>+11.6} SOL {:>8.4} ORE",
+            round.my_squares.len(),
+            lamports_to_sol(round.cost_lamports),
+            round.pnl_lamports() as f64 / 1e9,
+            amount_to_ui_amount(round.reward_ore, TOKEN_DECIMALS)
+        )
+    } else {
+        "未参与".to_string()
+    };
+    out!(
+        "  {:>8} {:<10} #{:<2} {:>12.6} SOL  {}",
+        round.result.round_id,
+        round.date(),
+        round.result.winning_square,
+        lamports_to_sol(round.result.total_deployed),
+        mine
+    );
+}
+
+fn print_browsed_round_detail(round: &BrowsedRound) {
+    let result = &round.result;
+    out!();
+    out!("Round {}（{}）", result.round_id, round.date());
+    out!("  开奖格子: #{}", result.winning_square);
+    out!("  总部署: {}", fmt_sol(result.total_deployed));
+    out!("  总奖金: {}", fmt_sol(result.total_winnings));
+    out!("  top miner: {}", result.top_miner);
+    out!("  最终分布（★ 开奖格子，✓ 我部署的格子）:");
+    for row in 0..5 {
+        let cells: Vec<String> = (0..5)
+            .map(|col| {
+                let square = row * 5 + col;
+                let mark = if square as u64 == result.winning_square {
+                    "★"
+                } else if round.my_squares.contains(&square) {
+                    "✓"
+                } else {
+                    " "
+                };
+                format!("#{:<2}{} {:>9.4}", square, mark, lamports_to_sol(result.deployed[square]))
+            })
+            .collect();
+        out!("  {}", cells.join("  "));
+    }
+    if round.participated() {
+        let won = round.my_squares.contains(&(result.winning_square as usize));
+        out!(
+            "  我的参与: {} 格 {:?}，花费 {}，{}",
+            round.my_squares.len(),
+            round.my_squares,
+            fmt_sol(round.cost_lamports),
+            if won { "已命中" } else { "未命中" }
+        );
+        out!(
+            "  PnL: {}，+{}",
+            fmt_sol_signed(round.pnl_lamports()),
+            fmt_ore(round.reward_ore)
+        );
+    } else {
+        out!("  我未参与本回合");
+    }
+    out!();
+}
+
+fn browse_rounds() -> Result<(), anyhow::Error> {
+    let all = load_browsed_rounds();
+    if all.is_empty() {
+        return Err(CommandError::nothing_to_do(format!(
+            "{} 中没有回合记录，运行 auto_mine / follow 或 archive_import 后再浏览",
+            state_path(ROUND_RESULTS_FILE)
+        ))
+        .into());
+    }
+    let mut date_filter: Option<String> = None;
+    let mut page = 0;
+    loop {
+        let rounds: Vec<&BrowsedRound> = all
+            .iter()
+            .filter(|r| date_filter.as_ref().is_none_or(|d| r.date() == *d))
+            .collect();
+        let pages = rounds.len().div_ceil(ROUND_BROWSER_PAGE).max(1);
+        page = page.min(pages - 1);
+        out!();
+        out!(
+            "历史回合 第 {}/{} 页（共 {} 个{}）",
+            page + 1,
+            pages,
+            rounds.len(),
+            date_filter.as_ref().map(|d| format!("，日期 {}", d)).unwrap_or_default()
+        );
+        out!("  {:>8} {:<10} {:<3} {:>16}  我的参与 / PnL", "round", "date", "win", "total");
+        for round in rounds.iter().skip(page * ROUND_BROWSER_PAGE).take(ROUND_BROWSER_PAGE) {
+            print_browsed_round_row(round);
+        }
+        out_inline!("n 下一页 / p 上一页 / 回合号 查看详情 / YYYY-MM-DD 按日期筛选 / a 全部 / q 返回: ");
+        let _ = io::stdout().flush();
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            return Ok(());
+        }
+        match line.trim() {
+            "q" | "" => return Ok(()),
+            "n" => page += 1,
+            "p" => page = page.saturating_sub(1),
+            "a" => {
+                date_filter = None;
+                page = 0;
+            }
+            input if input.len() == 10 && input.as_bytes()[4] == b'-' => {
+                date_filter = Some(input.to_string());
+                page = 0;
+            }
+            input => match input.parse::<u64>() {
+                Ok(id) => match all.iter().find(|r| r.result.round_id == id) {
+                    Some(round) => print_browsed_round_detail(round),
+                    None => out!("没有回合 {} 的记录", id),
+                },
+                Err(_) => out!("无法识别的输入: {}", input),
+            },
+        }
+    }
+}
+
+// ============ 新增：doctor（矿工账户体检与修复） ============
+
+// 未 checkpoint 的回合在 expires_at 前 12 小时进入费用收取期，期间任何人都可代为 checkpoint 并收取费用
+fn describe_fee_window(expires_at: u64, slot: u64) -> String {
+    match FeeWindow::at(expires_at, slot) {
+        FeeWindow::Expired => "该回合已过期，奖励可能已无法领取".to_string(),
+        FeeWindow::Open { slots_until_expiry } => format!(
+            "已进入费用收取期，其他人可代为 checkpoint 并收取费用（距过期约 {:.1}h）",
+            slots_to_secs(slots_until_expiry) / 3600.0
+        ),
+        FeeWindow::Closed { slots_until_open } => format!(
+            "距进入费用收取期约 {:.1}h",
+            slots_to_secs(slots_until_open) / 3600.0
+        ),
+    }
+}
+
+const FEE_WINDOW_ALERT_FILE: &str = "ore.fee_window_alert";
+
+// 费用收取期告警（事件名 fee_window）：同一回合每个阶段（进入收取期 / 已过期）只推送一次，
+// 最近一次推送记录在 ore.fee_window_alert，重复运行 miner 等命令不会重复告警
+fn alert_fee_window(round_id: u64, window: FeeWindow, text: &str) {
+    let stage = match window {
+        FeeWindow::Open { .. } => "open",
+        FeeWindow::Expired => "expired",
+        FeeWindow::Closed { .. } => return,
+    };
+    let key = format!("{}:{}", round_id, stage);
+    let path = state_path(FEE_WINDOW_ALERT_FILE);
+    if fs::read_to_string(&path).is_ok_and(|last| last.trim() == key) {
+        return;
+    }
+    let _ = fs::write(&path, &key);
+    notify_alert("fee_window", text);
+}
+
+// 矿工存在未 checkpoint 的旧回合时，返回该回合的费用收取期提示（并按阶段推送告警）
+async fn pending_checkpoint_warning(
+    rpc: &RpcClient,
+    miner: &Miner,
+) -> Option<String> {
+    let board = get_board(rpc).await.ok()?;
+    if !MinerView::new(*miner).needs_checkpoint(board.round_id) {
+        return None;
+    }
+    let clock = get_clock(rpc).await.ok()?;
+    let round = get_round(rpc, miner.round_id).await.ok()?;
+    let warning = format!(
+        "round {} 尚未 checkpoint：{}",
+        miner.round_id,
+        describe_fee_window(round.expires_at, clock.slot)
+    );
+    alert_fee_window(miner.round_id, FeeWindow::at(round.expires_at, clock.slot), &warning);
+    Some(warning)
+}
+
+fn confirm(prompt: &str) -> bool {
+    out_inline!("{} 输入 y 确认，其他任意键跳过: ", prompt);
+    let _ = io::stdout().flush();
+    let mut c = String::new();
+    let _ = io::stdin().read_line(&mut c);
+    c.trim().to_lowercase() == "y"
+}
+
+async fn doctor(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let authority = payer.pubkey();
+    let board = get_board(rpc).await?;
+    let clock = get_clock(rpc).await?;
+    let mut issues = 0;
+
+    // 1. 上一轮未 checkpoint（以及是否即将进入费用收取期）
+    match get_miner(rpc, authority).await {
+        Ok(miner) => {
+            if miner.checkpoint_id < miner.round_id && miner.round_id < board.round_id {
+                issues += 1;
+                out!(
+                    "[doctor] ⚠️  round {} 尚未 checkpoint（checkpoint_id={}）",
+                    miner.round_id, miner.checkpoint_id
+                );
+                if let Ok(round) = get_round(rpc, miner.round_id).await {
+                    let description = describe_fee_window(round.expires_at, clock.slot);
+                    out!("[doctor]    {}", description);
+                    alert_fee_window(
+                        miner.round_id,
+                        FeeWindow::at(round.expires_at, clock.slot),
+                        &format!("round {} 尚未 checkpoint：{}", miner.round_id, description),
+                    );
+                }
+                if confirm("[doctor] 现在提交 checkpoint？") {
+                    let ix = ore_api::sdk::checkpoint(authority, authority, miner.round_id);
+                    submit_transaction(rpc, payer, &[ix]).await?;
+                }
+            }
+            let view = MinerView::new(miner);
+            if view.has_claimable() {
+                out!(
+                    "[doctor] 可领取：SOL {:.6}，ORE {}",
+                    view.claimable_sol(),
+                    view.claimable_ore()
+                );
+            }
+        }
+        Err(_) => {
+            out!("[doctor] 矿工账户不存在（尚未部署过），跳过矿工检查");
+        }
+    }
+
+    // 2. ORE 代币账户（claim_ore 需要）
+    let ore_ata = get_associated_token_address(&authority, &MINT_ADDRESS);
+    if rpc.get_account(&ore_ata).await.is_err() {
+        issues += 1;
+        out!("[doctor] ⚠️  缺少 ORE 代币账户 (ATA): {}", ore_ata);
+        if confirm("[doctor] 现在创建 ORE ATA？") {
+            let ix = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &authority,
+                &authority,
+                &MINT_ADDRESS,
+                &spl_token::ID,
+            );
+            submit_transaction(rpc, payer, &[ix]).await?;
+        }
+    }
+
+    if issues == 0 {
+        out!("[doctor] ✅ 未发现问题");
+    }
+    Ok(())
+}
+
+// ============ 新增：init_miner（新钱包首次部署前置检查） ============
+// 新钱包第一次 deploy 时才会创建 Miner 账户，余额不够租金 / ATA 缺失时报错很难看懂。
+// 这里逐项检查并汇总首次部署的全部成本：Miner 租金 + ATA 租金 + 下注额 + checkpoint 费 + 手续费，
+// 再加上钱包自身的免租最低余额；ORE ATA 可当场创建（Miner 账户只能由第一次 deploy 创建）
+async fn init_miner(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let authority = mining_authority(payer).pubkey();
+    let amount: u64 = std::env::var("AMOUNT")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(0);
+    let squares: u64 = std::env::var("PICK_SQUARES")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(5);
+    out!("[init] authority: {}", authority);
+
+    // 1. Miner 账户：已存在则不再需要租金
+    let miner_address = miner_pda(authority).0;
+    let miner_rent = match get_miner(rpc, authority).await {
+        Ok(_) => {
+            out!("[init] ✅ 矿工账户已存在: {}", miner_address);
+            0
+        }
+        Err(_) => {
+            let rent = rpc
+                .get_minimum_balance_for_rent_exemption(account_data_size::<Miner>() as usize)
+                .await?;
+            out!(
+                "[init] 矿工账户不存在，首次 deploy 时创建: {}（租金 {}）",
+                miner_address,
+                fmt_sol(rent)
+            );
+            rent
+        }
+    };
+
+    // 2. ORE ATA（claim_ore 需要），缺失时可当场创建
+    let ore_ata = get_associated_token_address(&authority, &MINT_ADDRESS);
+    let mut ata_rent = 0;
+    if rpc.get_account(&ore_ata).await.is_ok() {
+        out!("[init] ✅ ORE 代币账户 (ATA) 已存在: {}", ore_ata);
+    } else {
+        use solana_sdk::program_pack::Pack;
+        let rent = rpc
+            .get_minimum_balance_for_rent_exemption(spl_token::state::Account::LEN)
+            .await?;
+        out!(
+            "[init] ⚠️  缺少 ORE 代币账户 (ATA): {}（租金 {}）",
+            ore_ata,
+            fmt_sol(rent)
+        );
+        if !env_flag("DRY_RUN") && confirm("[init] 现在创建 ORE ATA？") {
+            let ix = spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &payer.pubkey(),
+                &authority,
+                &MINT_ADDRESS,
+                &spl_token::ID,
+            );
+            submit_transaction(rpc, payer, &[ix]).await?;
+            out!("[init] ✅ ORE ATA 已创建");
+        } else {
+            ata_rent = rent;
+        }
+    }
+
+    // 3. 首次部署成本与余额
+    let signatures = if authority == payer.pubkey() { 1 } else { 2 };
+    let stake = amount * squares;
+    let fee = expected_tx_fee_lamports(signatures);
+    let wallet_floor = rpc.get_minimum_balance_for_rent_exemption(0).await?;
+    let total = miner_rent + ata_rent + stake + CHECKPOINT_FEE + fee + wallet_floor;
+    let balance = rpc.get_balance(&payer.pubkey()).await?;
+
+    out!("[init] 首次部署成本（AMOUNT={} lamports × {} 格）:", amount, squares);
+    out!("[init]   Miner 租金       {}", fmt_sol(miner_rent));
+    out!("[init]   ORE ATA 租金     {}", fmt_sol(ata_rent));
+    out!("[init]   下注             {}", fmt_sol(stake));
+    out!("[init]   checkpoint 费    {}", fmt_sol(CHECKPOINT_FEE));
+    out!("[init]   预期手续费       {}", fmt_sol(fee));
+    out!("[init]   钱包最低余额     {}", fmt_sol(wallet_floor));
+    out!("[init]   合计             {}", fmt_sol(total));
+    out!("[init] 当前余额 {} ({})", fmt_sol(balance), payer.pubkey());
+
+    if amount == 0 {
+        out!("[init] ⚠️  未设置 AMOUNT，下注成本按 0 计算");
+    }
+    if balance < total {
+        return Err(CommandError::config(format!(
+            "余额不足：还需要 {} 才能完成首次部署",
+            fmt_sol(total - balance)
+        ))
+        .into());
+    }
+    out!("[init] ✅ 首次部署的前置条件已满足");
+    Ok(())
+}
+
+// ============ 新增：垃圾代币（dusting）防护 ============
+// 挖矿钱包地址公开且活跃，常被空投垃圾代币 / NFT（诱导去钓鱼网站授权）。只有别人替钱包创建的代币账户
+// （账户最早一笔交易不是由手续费账户或 authority 签名）才视为疑似垃圾代币；ORE、wSOL、Meteora 池 LP、
+// TOKEN_ALLOWLIST 中的 mint 以及可能符合 Seeker 条件的 Token-2022 NFT（数量 1、精度 0）始终保留。
+// rent 报告中不列出、只提示数量。COMMAND=cleanup 默认只列出；CLEANUP_BURN=true 时逐个确认后 burn 并关闭账户，
+// 租金退回手续费支付账户（Token-2022 账户只列出）；DRY_RUN 只列出。不会与这些代币的任何链接或合约交互
+fn is_kept_mint(mint: &Pubkey, lp_mint: Option<&Pubkey>) -> bool {
+    *mint == MINT_ADDRESS
+        || *mint == spl_token::native_mint::ID
+        || lp_mint == Some(mint)
+        || env_list::<String>("TOKEN_ALLOWLIST", &[])
+            .iter()
+            .any(|allowed| allowed.trim() == mint.to_string())
+}
+
+// 代币账户是否由钱包自己创建：取该地址最早的一笔交易，看签名者中是否有钱包。
+// 历史超过一页（1000 笔）时无法确定最早一笔，按钱包自己创建处理
+async fn created_by_wallet(rpc: &RpcClient, account: &Pubkey, wallets: &[Pubkey]) -> Result<bool, anyhow::Error> {
+    let signatures = rpc.get_signatures_for_address(account).await?;
+    if signatures.len() >= 1000 {
+        return Ok(true);
+    }
+    let Some(first) = signatures.last() else {
+        return Ok(true);
+    };
+    let signers = fetch_transaction_signers(rpc, &solana_sdk::signature::Signature::from_str(&first.signature)?).await?;
+    Ok(signers.iter().any(|s| wallets.contains(s)))
+}
+
+struct DustAccount {
+    address: Pubkey,
+    mint: Pubkey,
+    program_id: Pubkey,
+    amount: u64,
+    lamports: u64,
+}
+
+// authority 名下所有疑似垃圾代币账户；读取创建交易失败的账户按钱包自己创建处理
+async fn find_dust_accounts(rpc: &RpcClient, payer: &Pubkey, authority: &Pubkey) -> Result<Vec<DustAccount>, anyhow::Error> {
+    let lp_mint = get_meteora_reserves(rpc, known_address("METEORA_POOL", DEFAULT_METEORA_POOL))
+        .await
+        .ok()
+        .map(|reserves| reserves.pool.lp_mint);
+    let wallets = [*payer, *authority];
+    let mut dust = vec![];
+    for program_id in [spl_token::ID, TOKEN_2022_PROGRAM_ID] {
+        let token_accounts = rpc
+            .get_token_accounts_by_owner(
+                authority,
+                solana_client::rpc_request::TokenAccountsFilter::ProgramId(program_id),
+            )
+            .await?;
+        for keyed in token_accounts {
+            let solana_account_decoder::UiAccountData::Json(parsed) = keyed.account.data else {
+                continue;
+            };
+            let info = &parsed.parsed["info"];
+            let amount = info["tokenAmount"]["amount"]
+                .as_str()
+                .and_then(|a| a.parse::<u64>().ok())
+                .unwrap_or(0);
+            let decimals = info["tokenAmount"]["decimals"].as_u64().unwrap_or(u64::MAX);
+            let Ok(mint) = Pubkey::from_str(info["mint"].as_str().unwrap_or_default()) else {
+                continue;
+            };
+            if amount == 0 || is_kept_mint(&mint, lp_mint.as_ref()) {
+                continue;
+            }
+            // 可能符合 Seeker 条件的 NFT（见 seeker_status）
+            if program_id == TOKEN_2022_PROGRAM_ID && amount == 1 && decimals == 0 {
+                continue;
+            }
+            let address = Pubkey::from_str(&keyed.pubkey)?;
+            if created_by_wallet(rpc, &address, &wallets).await.unwrap_or(true) {
+                continue;
+            }
+            dust.push(DustAccount {
+                address,
+                mint,
+                program_id,
+                amount,
+                lamports: keyed.account.lamports,
+            });
+        }
+    }
+    Ok(dust)
+}
+
+async fn cleanup(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let authority = mining_authority(payer).pubkey();
+    let dust = find_dust_accounts(rpc, &payer.pubkey(), &authority).await?;
+    if dust.is_empty() {
+        return Err(CommandError::nothing_to_do(format!("{} 没有疑似垃圾代币账户", authority)).into());
+    }
+    out!("Dust ({})", authority);
+    for (i, d) in dust.iter().enumerate() {
+        out!(
+            "  {:>2}. {} mint {} 数量 {}{} 租金 {}",
+            i + 1,
+            d.address,
+            d.mint,
+            d.amount,
+            if d.program_id == TOKEN_2022_PROGRAM_ID { "（Token-2022，只列出）" } else { "" },
+            fmt_sol(d.lamports)
+        );
+    }
+    let reclaimable: u64 = dust
+        .iter()
+        .filter(|d| d.program_id == spl_token::ID)
+        .map(|d| d.lamports)
+        .sum();
+    out!("  reclaimable: {}", fmt_sol(reclaimable));
+    if env_flag("DRY_RUN") {
+        return Ok(());
+    }
+    if !env_flag("CLEANUP_BURN") {
+        out!("  默认只列出；设置 CLEANUP_BURN=true 后逐个确认销毁并关闭（需要保留的 mint 请加入 TOKEN_ALLOWLIST）");
+        return Ok(());
+    }
+
+    let mut selected = vec![];
+    for d in dust.iter().filter(|d| d.program_id == spl_token::ID) {
+        if confirm(&format!("[cleanup] 销毁 {} 个 {} 并关闭账户 {}？", d.amount, d.mint, d.address)) {
+            selected.push(d);
+        }
+    }
+    if selected.is_empty() {
+        return Ok(());
+    }
+
+    // 每个账户 burn + close 两条指令，每笔交易处理 5 个账户
+    let (mut closed, mut failed) = (0usize, 0usize);
+    for batch in selected.chunks(5) {
+        let mut ixs = vec![];
+        for d in batch {
+            ixs.push(spl_token::instruction::burn(
+                &spl_token::ID,
+                &d.address,
+                &d.mint,
+                &authority,
+                &[],
+                d.amount,
+            )?);
+            ixs.push(spl_token::instruction::close_account(
+                &spl_token::ID,
+                &d.address,
+                &payer.pubkey(),
+                &authority,
+                &[],
+            )?);
+        }
+        match submit_transaction(rpc, payer, &ixs).await {
+            Ok(sig) => {
+                closed += batch.len();
+                out!("[cleanup] ✅ 已关闭 {} 个账户: {}", batch.len(), sig);
+            }
+            Err(e) => {
+                failed += batch.len();
+                out!("[cleanup] ❌ 关闭失败（{} 个账户）: {}", batch.len(), e);
+            }
+        }
+    }
+    out!("[cleanup] 完成：关闭 {} 个，失败 {} 个", closed, failed);
+    Ok(())
+}
+
+// ============ 新增：rent（账户租金与回收） ============
+// 列出钱包为之支付租金的账户：Miner / Stake / Automation / nonce / SPL 代币账户，
+// 标出哪些可以关闭回收租金，并逐个确认后关闭（DRY_RUN 时只列出）。
+// Miner 与 Stake 没有关闭指令；Automation 以默认 executor 调用 automate 即关闭；
+// 余额为 0 的代币账户可 close_account（ORE ATA 保留给 claim_ore）；nonce 账户全额提取即关闭
+struct RentAccount {
+    kind: &'static str,
+    address: Pubkey,
+    lamports: u64,
+    close: Option<solana_sdk::instruction::Instruction>,
+    note: String,
+}
+
+#[derive(Debug, Serialize)]
+struct RentAccountResult {
+    kind: &'static str,
+    address: String,
+    lamports: u64,
+    closable: bool,
+    note: String,
+}
+
+// closed 为本次确认关闭的交易签名
+#[derive(Debug, Serialize)]
+struct RentResult {
+    authority: String,
+    accounts: Vec<RentAccountResult>,
+    total: u64,
+    reclaimable: u64,
+    ignored_dust: usize,
+    closed: Vec<String>,
+}
+
+async fn rent(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<RentResult, anyhow::Error> {
+    let authority = mining_authority(payer).pubkey();
+    let mut accounts = vec![];
+
+    // 1. ORE 程序账户
+    let ore_accounts = [
+        ("miner", miner_pda(authority).0),
+        ("stake", ore_api::state::stake_pda(authority).0),
+        ("automation", automation_pda(authority).0),
+    ];
+    for (kind, address) in ore_accounts {
+        let Ok(account) = rpc.get_account(&address).await else {
+            continue;
+        };
+        let (close, note) = match kind {
+            "automation" => (
+                Some(ore_api::sdk::automate(authority, 0, 0, Pubkey::default(), 0, 0, 0)),
+                "关闭后停止自动部署，剩余存款一并退回".to_string(),
+            ),
+            _ => (None, "程序不支持关闭".to_string()),
+        };
+        accounts.push(RentAccount {
+            kind,
+            address,
+            lamports: account.lamports,
+            close,
+            note,
+        });
+    }
+
+    // 2. SPL 代币账户
+    let token_accounts = rpc
+        .get_token_accounts_by_owner(
+            &authority,
+            solana_client::rpc_request::TokenAccountsFilter::ProgramId(spl_token::ID),
+        )
+        .await?;
+    let ore_ata = get_associated_token_address(&authority, &MINT_ADDRESS);
+    let dust_accounts: Vec<Pubkey> = find_dust_accounts(rpc, &payer.pubkey(), &authority)
+        .await?
+        .iter()
+        .map(|d| d.address)
+        .collect();
+    let mut dust = 0usize;
+    for keyed in token_accounts {
+        let address = Pubkey::from_str(&keyed.pubkey)?;
+        let solana_account_decoder::UiAccountData::Json(parsed) = keyed.account.data else {
+            continue;
+        };
+        let info = &parsed.parsed["info"];
+        let amount = info["tokenAmount"]["amount"].as_str().unwrap_or("0");
+        let mint = info["mint"].as_str().unwrap_or_default();
+        // 疑似垃圾代币不列出（见 cleanup）
+        if dust_accounts.contains(&address) {
+            dust += 1;
+            continue;
+        }
+        let (close, note) = if address == ore_ata {
+            (None, "ORE ATA，claim_ore 需要".to_string())
+        } else if amount == "0" {
+            (
+                Some(spl_token::instruction::close_account(
+                    &spl_token::ID,
+                    &address,
+                    &payer.pubkey(),
+                    &authority,
+                    &[],
+                )?),
+                format!("mint {}，余额为 0", mint),
+            )
+        } else {
+            (None, format!("mint {}，余额 {}（需先转出）", mint, amount))
+        };
+        accounts.push(RentAccount {
+            kind: "token",
+            address,
+            lamports: keyed.account.lamports,
+            close,
+            note,
+        });
+    }
+
+    // 3. nonce 账户（authority 位于偏移 8）
+    let nonce_filters = vec![
+        RpcFilterType::DataSize(80),
+        RpcFilterType::Memcmp(Memcmp::new_base58_encoded(8, &payer.pubkey().to_bytes())),
+    ];
+    match fetch_program_accounts_once(rpc, solana_sdk::system_program::ID, nonce_filters, None).await {
+        Ok(nonces) => {
+            for (address, _) in nonces {
+                let lamports = rpc.get_balance(&address).await.unwrap_or(0);
+                accounts.push(RentAccount {
+                    kind: "nonce",
+                    address,
+                    lamports,
+                    close: Some(solana_sdk::system_instruction::withdraw_nonce_account(
+                        &address,
+                        &payer.pubkey(),
+                        &payer.pubkey(),
+                        lamports,
+                    )),
+                    note: "全额提取即关闭".to_string(),
+                });
+            }
+        }
+        Err(e) => out!("[rent] ⚠️  查询 nonce 账户失败（RPC 可能不支持该 getProgramAccounts）: {}", e),
+    }
+
+    if accounts.is_empty() {
+        return Err(CommandError::nothing_to_do(format!("{} 没有需要支付租金的账户", authority)).into());
+    }
+
+    out!("Rent ({})", authority);
+    for (i, a) in accounts.iter().enumerate() {
+        out!(
+            "  {:>2}. {:<10} {} {} {} {}",
+            i + 1,
+            a.kind,
+            a.address,
+            fmt_sol(a.lamports),
+            if a.close.is_some() { "[可关闭]" } else { "" },
+            a.note
+        );
+    }
+    let total: u64 = accounts.iter().map(|a| a.lamports).sum();
+    let reclaimable: u64 = accounts
+        .iter()
+        .filter(|a| a.close.is_some())
+        .map(|a| a.lamports)
+        .sum();
+    out!("  total: {}", fmt_sol(total));
+    out!("  reclaimable: {}", fmt_sol(reclaimable));
+    if dust > 0 {
+        out!("  已忽略 {} 个疑似垃圾代币账户（COMMAND=cleanup 查看）", dust);
+    }
+    let mut result = RentResult {
+        authority: authority.to_string(),
+        accounts: accounts
+            .iter()
+            .map(|a| RentAccountResult {
+                kind: a.kind,
+                address: a.address.to_string(),
+                lamports: a.lamports,
+                closable: a.close.is_some(),
+                note: a.note.clone(),
+            })
+            .collect(),
+        total,
+        reclaimable,
+        ignored_dust: dust,
+        closed: vec![],
+    };
+
+    if env_flag("DRY_RUN") || reclaimable == 0 {
+        return Ok(result);
+    }
+
+    // 逐个确认关闭
+    for a in accounts {
+        let Some(ix) = a.close else {
+            continue;
+        };
+        let prompt = format!(
+            "[rent] 关闭 {} {}，回收 {}？",
+            a.kind,
+            a.address,
+            fmt_sol(a.lamports)
+        );
+        if !confirm(&prompt) {
+            continue;
+        }
+        match submit_transaction(rpc, payer, &[ix]).await {
+            Ok(sig) => {
+                out!("[rent] ✅ 已关闭 {}: {}", a.address, sig);
+                result.closed.push(sig.to_string());
+            }
+            Err(e) => out!("[rent] ❌ 关闭 {} 失败: {}", a.address, e),
+        }
+    }
+    Ok(result)
+}
+
+async fn claim_seeker(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let seeker_mint = known_address("SEEKER_MINT", DEFAULT_SEEKER_MINT);
+    let ix = ore_api::sdk::claim_seeker(payer.pubkey(), seeker_mint);
+    simulate_transaction(rpc, payer, &[ix]).await;
+    Ok(())
+}
+
+async fn set_admin(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    if let Some(format) = admin_payload_format()? {
+        let vault = multisig_vault()?;
+        // NEW_ADMIN 未设置时保持 admin 为金库本身
+        let new_admin = match std::env::var("NEW_ADMIN") {
+            Ok(v) => Pubkey::from_str(v.trim()).map_err(|_| CommandError::config(format!("NEW_ADMIN 无效: {}", v)))?,
+            Err(_) => vault,
+        };
+        let ix = ore_api::sdk::set_admin(vault, new_admin);
+        return print_admin_payload(rpc, format, vault, "set_admin", &[ix]).await;
+    }
+    let ix = ore_api::sdk::set_admin(payer.pubkey(), payer.pubkey());
+    submit_transaction(rpc, payer, &[ix]).await?;
+    Ok(())
+}
+
+async fn set_fee_collector(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    let fee_collector: Pubkey = parse_env("FEE_COLLECTOR")?;
+    if let Some(format) = admin_payload_format()? {
+        let vault = multisig_vault()?;
+        let ix = ore_api::sdk::set_fee_collector(vault, fee_collector);
+        return print_admin_payload(rpc, format, vault, "set_fee_collector", &[ix]).await;
+    }
+    let ix = ore_api::sdk::set_fee_collector(payer.pubkey(), fee_collector);
+    submit_transaction(rpc, payer, &[ix]).await?;
+    Ok(())
+}
+
+// ============ 新增：多签管理操作 ============
+// ADMIN_PAYLOAD=base58|base64 时，set_admin / set_fee_collector / bury 不使用本地密钥签名提交，
+// 而是以 MULTISIG_VAULT（Squads 金库地址，即链上 admin）作为签名者和手续费支付者，输出未签名交易，
+// 在 Squads 中通过 "Import transaction" 创建提案，由多签成员审批执行
+fn admin_payload_format() -> Result<Option<&'static str>, anyhow::Error> {
+    match std::env::var("ADMIN_PAYLOAD") {
+        Ok(v) => match v.trim() {
+            "base58" => Ok(Some("base58")),
+            "base64" => Ok(Some("base64")),
+            other => Err(CommandError::config(format!("ADMIN_PAYLOAD 无效: {}（base58 | base64）", other)).into()),
+        },
+        Err(_) => Ok(None),
+    }
+}
+
+fn multisig_vault() -> Result<Pubkey, anyhow::Error> {
+    let vault = std::env::var("MULTISIG_VAULT")
+        .map_err(|_| CommandError::config("ADMIN_PAYLOAD 模式需要设置 MULTISIG_VAULT（Squads 金库地址）"))?;
+    Pubkey::from_str(vault.trim())
+        .map_err(|_| CommandError::config(format!("MULTISIG_VAULT 无效: {}", vault)).into())
+}
+
+fn base64_encode(bytes: &[u8]) -> String {
+    const TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(TABLE[(n >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+// 输出以多签金库为手续费支付者的未签名交易
+async fn print_admin_payload(
+    rpc: &RpcClient,
+    format: &str,
+    vault: Pubkey,
+    label: &str,
+    instructions: &[Instruction],
+) -> Result<(), anyhow::Error> {
+    let blockhash = rpc.get_latest_blockhash().await?;
+    let message = solana_sdk::message::Message::new_with_blockhash(instructions, Some(&vault), &blockhash);
+    let transaction = Transaction::new_unsigned(message);
+    let bytes = bincode::serialize(&transaction)?;
+    let payload = match format {
+        "base64" => base64_encode(&bytes),
+        _ => solana_sdk::bs58::encode(&bytes).into_string(),
+    };
+    out!("[multisig] {}：签名者 / 手续费支付者 {}", label, vault);
+    for (i, ix) in instructions.iter().enumerate() {
+        out!(
+            "[multisig]   指令 {}: program={} accounts={} data={}",
+            i,
+            ix.program_id,
+            ix.accounts.len(),
+            solana_sdk::bs58::encode(&ix.data).into_string()
+        );
+    }
+    out!("[multisig] 未签名交易（{}），在 Squads 中通过 Import transaction 创建提案：", format);
+    out!("{}", payload);
+    Ok(())
+}
+
+async fn checkpoint(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<TxResult, anyhow::Error> {
+    let authority = parse_env_opt::<Pubkey>("AUTHORITY")?.unwrap_or(mining_authority(payer).pubkey());
+    let miner = get_miner(rpc, authority).await?;
+    let ix = ore_api::sdk::checkpoint(payer.pubkey(), authority, miner.round_id);
+    let sig = submit_transaction(rpc, payer, &[ix]).await?;
+    Ok(TxResult {
+        signatures: vec![sig.to_string()],
+    })
+}
+
+async fn checkpoint_all(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<TxResult, anyhow::Error> {
+    install_cancellation("checkpoint");
+    let clock = get_clock(rpc).await?;
+    let miners = get_miners(rpc).await?;
+    let mut expiry_slots = HashMap::new();
+    let mut ixs = vec![];
+    let mut targets = vec![];
+    let mut progress = Progress::new("checkpoint 扫描", miners.len() as u64);
+    for (i, (_address, miner)) in miners.iter().enumerate() {
+        if stop_requested() {
+            progress.finish();
+            return Err(anyhow::anyhow!("[checkpoint] 扫描中途取消（{}/{}），未提交任何交易", i, miners.len()));
+        }
+        progress.set(i as u64 + 1);
+        if miner.checkpoint_id < miner.round_id {
+            // Log the expiry slot for the round.
+            if !expiry_slots.contains_key(&miner.round_id) {
+                if let Ok(round) = get_round(rpc, miner.round_id).await {
+                    expiry_slots.insert(miner.round_id, round.expires_at);
+                }
+            }
+
+            // Get the expiry slot for the round.
+            let Some(expires_at) = expiry_slots.get(&miner.round_id) else {
+                continue;
+            };
+
+            // If we are in fee collection period, checkpoint the miner.
+            if clock.slot >= expires_at - TWELVE_HOURS_SLOTS {
+                progress.println(&format!(
+                    "[{}/{}] Checkpoint miner: {} ({} s)",
+                    i + 1,
+                    miners.len(),
+                    miner.authority,
+                    (expires_at - clock.slot) as f64 * 0.4
+                ));
+                ixs.push(ore_api::sdk::checkpoint(
+                    payer.pubkey(),
+                    miner.authority,
+                    miner.round_id,
+                ));
+                targets.push(CrankTarget::Checkpoint { authority: miner.authority });
+            }
+        }
+    }
+    progress.finish();
+    if ixs.is_empty() {
+        return Err(CommandError::nothing_to_do("没有处于费用收取期、需要 checkpoint 的矿工").into());
+    }
+
+    // Batch and submit the instructions.
+    submit_crank_batches(rpc, payer, "checkpoint", ixs, targets).await
+}
+
+// 逐批提交；取消后停止提交剩余批次。已上链的批次即为进度，再次运行时已完成的目标会被跳过
+async fn submit_crank_batches(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+    action: &str,
+    ixs: Vec<Instruction>,
+    targets: Vec<CrankTarget>,
+) -> Result<TxResult, anyhow::Error> {
+    let batches = pack_instructions(payer.pubkey(), ixs);
+    let mut result = TxResult::default();
+    let total = batches.len();
+    let mut targets = targets.into_iter();
+    let mut progress = Progress::new(if action == "close" { "close 提交" } else { "checkpoint 提交" }, total as u64);
+    progress.draw();
+    for (i, batch) in batches.into_iter().enumerate() {
+        if stop_requested() {
+            progress.finish();
+            return Err(anyhow::anyhow!(
+                "[{}] 已取消：完成 {}/{} 批，剩余部分再次运行时继续",
+                action,
+                i,
+                total
+            ));
+        }
+        let batch_targets: Vec<CrankTarget> = targets.by_ref().take(batch.len()).collect();
+        if let Some(sig) = submit_crank(rpc, payer, action, &batch, &batch_targets).await? {
+            result.signatures.push(sig.to_string());
+        }
+        progress.inc(1);
+    }
+    progress.finish();
+    Ok(result)
+}
+
+async fn close_all(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<TxResult, anyhow::Error> {
+    install_cancellation("close");
+    let rounds = get_rounds(rpc).await?;
+    let mut ixs = vec![];
+    let mut targets = vec![];
+    let clock = get_clock(rpc).await?;
+    for (_i, (_address, round)) in rounds.iter().enumerate() {
+        if clock.slot >= round.expires_at {
+            ixs.push(ore_api::sdk::close(
+                payer.pubkey(),
+                round.id,
+                round.rent_payer,
+            ));
+            targets.push(CrankTarget::Close { round_id: round.id });
+        }
+    }
+    if ixs.is_empty() {
+        return Err(CommandError::nothing_to_do("没有已过期、可关闭的 Round 账户").into());
+    }
+
+    // Batch and submit the instructions.
+    submit_crank_batches(rpc, payer, "close", ixs, targets).await
+}
+
+// ============ 新增：crank 收益核算 ============
+// reset / checkpoint_all / close_all 每笔交易记录到 ore.crank.jsonl：
+//   fee_lamports     交易费（基础费 + 优先费，取自交易 meta）
+//   earned_lamports  付款账户余额变化 + 交易费，即 reset 奖励、checkpoint 手续费、close 退回的租金
+// COMMAND=crank_report 按 UTC 日汇总收入、费用与 ROI（净收益 / 费用），用于调整抢 crank 的激进程度
+const CRANK_LOG_FILE: &str = "ore.crank.jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CrankRecord {
+    ts: u64,
+    action: String,
+    signature: String,
+    instructions: usize,
+    fee_lamports: u64,
+    earned_lamports: i64,
+}
+
+// 多个 cranker 抢同一动作时，后到的交易会在付费后失败。提交前与等待确认期间检查目标账户：
+//   reset       Board 的 round_id 已前进
+//   checkpoint  Miner 的 checkpoint_id 已追上 round_id
+//   close       Round 账户已不存在
+// 已被他人完成的指令在提交前剔除；已发出的交易在确认前发现目标全部完成时停止等待、不再重发
+// CRANK_CONFIRM_SECS（默认 60）内未确认时重新检查目标后重发，最多 3 次
+#[derive(Debug, Clone, Copy)]
+enum CrankTarget {
+    Reset { round_id: u64 },
+    Checkpoint { authority: Pubkey },
+    Close { round_id: u64 },
+}
+
+async fn crank_targets_pending(
+    rpc: &RpcClient,
+    targets: &[CrankTarget],
+) -> Result<Vec<bool>, anyhow::Error> {
+    let board = if targets.iter().any(|t| matches!(t, CrankTarget::Reset { .. })) {
+        Some(get_board(rpc).await?)
+    } else {
+        None
+    };
+    let addresses: Vec<Pubkey> = targets
+        .iter()
+        .filter_map(|target| match target {
+            CrankTarget::Reset { .. } => None,
+            CrankTarget::Checkpoint { authority } => Some(miner_pda(*authority).0),
+            CrankTarget::Close { round_id } => Some(round_pda(*round_id).0),
+        })
+        .collect();
+    let mut accounts = HashMap::new();
+    for chunk in addresses.chunks(100) {
+        let fetched = rpc
+            .get_multiple_accounts_with_commitment(chunk, CommitmentConfig::confirmed())
+            .await?
+            .value;
+        for (address, account) in chunk.iter().zip(fetched) {
+            if let Some(account) = account {
+                accounts.insert(*address, account.data);
+            }
+        }
+    }
+    Ok(targets
+        .iter()
+        .map(|target| match target {
+            CrankTarget::Reset { round_id } => board.map(|b| b.round_id == *round_id).unwrap_or(false),
+            CrankTarget::Checkpoint { authority } => accounts
+                .get(&miner_pda(*authority).0)
+                .and_then(|data| Miner::try_from_bytes(data).ok())
+                .map(|miner| miner.checkpoint_id < miner.round_id)
+                .unwrap_or(false),
+            CrankTarget::Close { round_id } => accounts.contains_key(&round_pda(*round_id).0),
+        })
+        .collect())
+}
+
+async fn submit_crank(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+    action: &str,
+    instructions: &[Instruction],
+    targets: &[CrankTarget],
+) -> Result<Option<solana_sdk::signature::Signature>, anyhow::Error> {
+    let confirm_secs: u64 = std::env::var("CRANK_CONFIRM_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(60);
+    let mut attempts = 0;
+    let signature = 'submit: loop {
+        let pending = crank_targets_pending(rpc, targets).await?;
+        let batch: Vec<Instruction> = instructions
+            .iter()
+            .zip(pending.iter())
+            .filter(|(_, pending)| **pending)
+            .map(|(ix, _)| ix.clone())
+            .collect();
+        if batch.is_empty() {
+            out!("[crank] {} 已全部被其他 cranker 完成，跳过提交", action);
+            return Ok(None);
+        }
+        if batch.len() < instructions.len() {
+            out!(
+                "[crank] {} 中 {} 条已被其他 cranker 完成，剔除后提交 {} 条",
+                action,
+                instructions.len() - batch.len(),
+                batch.len()
+            );
+        }
+
+        let signature = match submit_transaction_no_confirm(rpc, payer, &batch).await {
+            Ok(signature) => signature,
+            // 预检失败多半是刚被别人抢先，重新检查一次再决定是否报错
+            Err(e) => {
+                if !crank_targets_pending(rpc, targets).await?.contains(&true) {
+                    out!("[crank] {} 预检失败，目标已被其他 cranker 完成，跳过", action);
+                    return Ok(None);
+                }
+                return Err(e);
+            }
+        };
+        let deadline = Instant::now() + Duration::from_secs(confirm_secs);
+        while Instant::now() < deadline {
+            sleep(Duration::from_secs(2)).await;
+            if let Ok(statuses) = rpc.get_signature_statuses(&[signature]).await {
+                if let Some(Some(status)) = statuses.value.first() {
+                    if let Some(err) = &status.err {
+                        return Err(anyhow::anyhow!("{} 交易 {} 执行失败: {:?}", action, signature, err));
+                    }
+                    if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                        out!("[✓] 交易成功提交: {:?}", signature);
+                        break 'submit signature;
+                    }
+                }
+            }
+            if !crank_targets_pending(rpc, targets).await?.contains(&true) {
+                out!("[crank] {} 交易 {} 尚未确认，目标已被其他 cranker 完成，停止等待且不再重发", action, signature);
+                return Ok(None);
+            }
+        }
+        attempts += 1;
+        if attempts >= 3 {
+            return Err(anyhow::anyhow!("{} 交易 {} 次均未在 {} 秒内确认", action, attempts, confirm_secs));
+        }
+        out!("[crank] {} 交易 {} 未在 {} 秒内确认，重新检查目标后重发", action, signature, confirm_secs);
+    };
+    let config = solana_client::rpc_config::RpcTransactionConfig {
+        encoding: None,
+        commitment: Some(CommitmentConfig::confirmed()),
+        max_supported_transaction_version: Some(0),
+    };
+    // 交易刚确认时 getTransaction 可能还查不到，稍等重试
+    let mut meta = None;
+    for _ in 0..5 {
+        if let Ok(tx) = rpc.get_transaction_with_config(&signature, config).await {
+            meta = tx.transaction.meta;
+            break;
+        }
+        sleep(Duration::from_secs(2)).await;
+    }
+    let Some(meta) = meta else {
+        out!("[crank] ⚠️  无法读取交易 {} 的 meta，本笔不计入 {}", signature, state_path(CRANK_LOG_FILE));
+        return Ok(Some(signature));
+    };
+    // 付款账户固定为第 0 个账户
+    let pre = meta.pre_balances.first().copied().unwrap_or(0) as i64;
+    let post = meta.post_balances.first().copied().unwrap_or(0) as i64;
+    let record = CrankRecord {
+        ts: chain_now(),
+        action: action.to_string(),
+        signature: signature.to_string(),
+        instructions: instructions.len(),
+        fee_lamports: meta.fee,
+        earned_lamports: post - pre + meta.fee as i64,
+    };
+    out!(
+        "[crank] {} 收入 {}，费用 {}",
+        action,
+        fmt_sol_signed(record.earned_lamports),
+        fmt_sol(record.fee_lamports)
+    );
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(CRANK_LOG_FILE))
+    {
+        let _ = writeln!(file, "{}", serde_json::to_string(&record).unwrap_or_default());
+    }
+    Ok(Some(signature))
+}
+
+fn crank_report() -> Result<(), anyhow::Error> {
+    let records: Vec<CrankRecord> = fs::read_to_string(state_path(CRANK_LOG_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    if records.is_empty() {
+        return Err(CommandError::nothing_to_do(format!("{} 中没有 crank 记录", state_path(CRANK_LOG_FILE))).into());
+    }
+
+    // 日期 -> (交易数, 收入, 费用)；动作 -> 同上
+    let mut days: BTreeMap<String, (usize, i64, u64)> = BTreeMap::new();
+    let mut actions: BTreeMap<String, (usize, i64, u64)> = BTreeMap::new();
+    for record in records.iter() {
+        for entry in [
+            days.entry(utc_date(record.ts)).or_default(),
+            actions.entry(record.action.clone()).or_default(),
+        ] {
+            entry.0 += 1;
+            entry.1 += record.earned_lamports;
+            entry.2 += record.fee_lamports;
+        }
+    }
+    let roi = |earned: i64, fees: u64| {
+        if fees == 0 {
+            "-".to_string()
+        } else {
+            format!("{:+.1}%", (earned - fees as i64) as f64 / fees as f64 * 100.0)
+        }
+    };
+
+    out!("Crank ROI ({})", state_path(CRANK_LOG_FILE));
+    out!("  {:<10}  {:>5}  {:>12}  {:>12}  {:>12}  {:>9}", "date", "txs", "earned SOL", "fees SOL", "net SOL", "ROI");
+    for (date, (txs, earned, fees)) in days.iter() {
+        out!(
+            "  {:<10}  {:>5}  {:>12.6}  {:>12.6}  {:>12.6}  {:>9}",
+            date,
+            txs,
+            *earned as f64 / 1e9,
+            lamports_to_sol(*fees),
+            (*earned - *fees as i64) as f64 / 1e9,
+            roi(*earned, *fees)
+        );
+    }
+    out!();
+    out!("  By action:");
+    for (action, (txs, earned, fees)) in actions.iter() {
+        out!(
+            "    {:<16} {:>5} txs  earned {}  fees {}  ROI {}",
+            action,
+            txs,
+            fmt_sol_signed(*earned),
+            fmt_sol(*fees),
+            roi(*earned, *fees)
+        );
+    }
+    Ok(())
+}
+
+// ============ 新增：交易打包 ============
+// checkpoint_all / close_all 按交易序列化大小（PACKET_DATA_SIZE，1232 字节）与 CU 上限贪心装填指令，
+// 每笔交易装尽可能多的指令以摊薄基础费与优先费
+//   PACK_CU_PER_IX  每条指令的 CU 估算（默认 50,000），合计不超过 COMPUTE_UNIT_LIMIT
+// 目前只生成 legacy 交易，不使用地址查找表
+fn packed_transaction_size(payer: Pubkey, instructions: &[Instruction]) -> usize {
+    let mut all_instructions = vec![
+        ComputeBudgetInstruction::set_compute_unit_limit(0),
+        ComputeBudgetInstruction::set_compute_unit_price(0),
+    ];
+    all_instructions.extend_from_slice(instructions);
+    let message = solana_sdk::message::Message::new(&all_instructions, Some(&payer));
+    let transaction = Transaction {
+        signatures: vec![
+            solana_sdk::signature::Signature::default();
+            message.header.num_required_signatures as usize
+        ],
+        message,
+    };
+    bincode::serialized_size(&transaction).map(|s| s as usize).unwrap_or(usize::MAX)
+}
+
+fn pack_instructions(payer: Pubkey, instructions: Vec<Instruction>) -> Vec<Vec<Instruction>> {
+    let cu_per_ix: u64 = std::env::var("PACK_CU_PER_IX")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(50_000);
+    let compute_unit_limit: u64 = std::env::var("COMPUTE_UNIT_LIMIT")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(1_400_000);
+    let max_by_cu = (compute_unit_limit / cu_per_ix.max(1)).max(1) as usize;
+
+    let total = instructions.len();
+    let mut batches: Vec<Vec<Instruction>> = vec![];
+    let mut batch: Vec<Instruction> = vec![];
+    for ix in instructions {
+        batch.push(ix);
+        let too_big = packed_transaction_size(payer, &batch) > solana_sdk::packet::PACKET_DATA_SIZE;
+        if batch.len() > 1 && (too_big || batch.len() > max_by_cu) {
+            let ix = batch.pop().unwrap();
+            batches.push(std::mem::take(&mut batch));
+            batch.push(ix);
+        }
+    }
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+    out!(
+        "[pack] {} 条指令打包为 {} 笔交易（每笔最多 {} 条 / CU 上限 {}）",
+        total,
+        batches.len(),
+        batches.iter().map(|b| b.len()).max().unwrap_or(0),
+        max_by_cu
+    );
+    batches
+}
+
+// 储备以最小单位计（ORE 11 位小数，SOL 为 lamports），中间价为 SOL/ORE
+#[derive(Debug, Serialize)]
+struct PoolResult {
+    address: String,
+    lp_mint: String,
+    token_a_mint: String,
+    token_b_mint: String,
+    reserve_ore: u64,
+    reserve_sol: u64,
+    mid_price: f64,
+}
+
+async fn log_meteora_pool(rpc: &RpcClient) -> Result<PoolResult, anyhow::Error> {
+    let address = known_address("METEORA_POOL", DEFAULT_METEORA_POOL);
+    let reserves = get_meteora_reserves(rpc, address).await?;
+    let (pool, vault_a, vault_b) = (&reserves.pool, &reserves.vault_a, &reserves.vault_b);
+
+    out!("Pool");
+    out!("  address: {}", address);
+    out!("  lp_mint: {}", pool.lp_mint);
+    out!("  token_a_mint: {}", pool.token_a_mint);
+    out!("  token_b_mint: {}", pool.token_b_mint);
+    out!("  a_vault: {}", pool.a_vault);
+    out!("  b_vault: {}", pool.b_vault);
+    out!("  a_token_vault: {}", vault_a.token_vault);
+    out!("  b_token_vault: {}", vault_b.token_vault);
+    out!("  a_vault_lp_mint: {}", vault_a.lp_mint);
+    out!("  b_vault_lp_mint: {}", vault_b.lp_mint);
+    out!("  a_vault_lp: {}", pool.a_vault_lp);
+    out!("  b_vault_lp: {}", pool.b_vault_lp);
+    out!("  protocol_token_fee: {}", pool.protocol_token_b_fee);
+
+    // 实际储备、中间价与指定交易量的价格影响（PRICE_IMPACT_SOL，默认 1 SOL，买卖两个方向）
+    let (reserve_ore, reserve_sol) = reserves.ore_sol_reserves();
+    let mid_price = reserves.mid_price();
+    let trade_sol = env_f64("PRICE_IMPACT_SOL", 1.0);
+    out!();
+    out!("Liquidity");
+    out!("  reserve ORE: {:.4}", amount_to_ui_amount(reserve_ore, TOKEN_DECIMALS));
+    out!("  reserve SOL: {:.4}", lamports_to_sol(reserve_sol));
+    out!("  tvl: {:.4} SOL", lamports_to_sol(reserve_sol) * 2.0);
+    out!("  mid price: {:.6} SOL/ORE", mid_price);
+    if mid_price > 0.0 && trade_sol > 0.0 {
+        let sol_in = sol_to_lamports(trade_sol);
+        let ore_out = amount_to_ui_amount(reserves.quote(&SOL_MINT, sol_in), TOKEN_DECIMALS);
+        let ore_in = trade_sol / mid_price;
+        let sol_out = lamports_to_sol(reserves.quote(&MINT_ADDRESS, ui_amount_to_amount(ore_in, TOKEN_DECIMALS)));
+        let buy_impact = (trade_sol / ore_out.max(f64::MIN_POSITIVE) / mid_price - 1.0) * 100.0;
+        let sell_impact = (1.0 - sol_out / trade_sol) * 100.0;
+        out!("  buy {} SOL -> {:.4} ORE (impact {:.2}%, 含手续费)", trade_sol, ore_out, buy_impact);
+        out!("  sell {:.4} ORE -> {:.6} SOL (impact {:.2}%, 含手续费)", ore_in, sol_out, sell_impact);
+    }
+
+    // pool: *pool.key,
+    // user_source_token: *user_source_token.key,
+    // user_destination_token: *user_destination_token.key,
+    // a_vault: *a_vault.key,
+    // b_vault: *b_vault.key,
+    // a_token_vault: *a_token_vault.key,
+    // b_token_vault: *b_token_vault.key,
+    // a_vault_lp_mint: *a_vault_lp_mint.key,
+    // b_vault_lp_mint: *b_vault_lp_mint.key,
+    // a_vault_lp: *a_vault_lp.key,
+    // b_vault_lp: *b_vault_lp.key,
+    // protocol_token_fee: *protocol_token_fee.key,
+    // user: *user.key,
+    // vault_program: *vault_program.key,
+    // token_program: *token_program.key,
+
+    Ok(PoolResult {
+        address: address.to_string(),
+        lp_mint: pool.lp_mint.to_string(),
+        token_a_mint: pool.token_a_mint.to_string(),
+        token_b_mint: pool.token_b_mint.to_string(),
+        reserve_ore,
+        reserve_sol,
+        mid_price,
+    })
+}
+
+// ============ 新增：cashout（claim -> swap -> transfer） ============
+
+fn env_f64(key: &str, default: f64) -> f64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|s| s.parse::<f64>().ok())
+        .unwrap_or(default)
+}
+
+async fn get_token_balance(rpc: &RpcClient, token_account: &Pubkey) -> u64 {
+    rpc.get_token_account_balance(token_account)
+        .await
+        .ok()
+        .and_then(|b| b.amount.parse::<u64>().ok())
+        .unwrap_or(0)
+}
+
+// cashout 各步骤的实际（DRY_RUN 时为预计）数量，SOL 以 lamports、ORE 以最小单位计
+#[derive(Debug, Default, Serialize)]
+struct CashoutResult {
+    dry_run: bool,
+    claimed_sol: u64,
+    claimed_ore: u64,
+    swapped_ore: u64,
+    swap_minimum_out: u64,
+    transferred_sol: u64,
+    destination: Option<String>,
+    signatures: Vec<String>,
+}
+
+async fn cashout(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<CashoutResult, anyhow::Error> {
+    let dry_run = env_flag("DRY_RUN");
+    let min_claim_sol = sol_to_lamports(env_f64("CASHOUT_MIN_CLAIM_SOL", 0.0));
+    let min_claim_ore = ui_amount_to_amount(env_f64("CASHOUT_MIN_CLAIM_ORE", 0.0), TOKEN_DECIMALS);
+    let min_swap_ore = ui_amount_to_amount(env_f64("CASHOUT_MIN_SWAP_ORE", 0.0), TOKEN_DECIMALS);
+    let min_transfer_sol = sol_to_lamports(env_f64("CASHOUT_MIN_TRANSFER_SOL", 0.0));
+    let slippage_bps: u64 = std::env::var("CASHOUT_SLIPPAGE_BPS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(100)
+        .min(DENOMINATOR_BPS);
+    let destination: Option<Pubkey> = parse_env_opt("CASHOUT_DESTINATION")?;
+
+    let (claimable_sol, claimable_ore) = match get_miner(rpc, payer.pubkey()).await {
+        Ok(m) => (m.rewards_sol, m.rewards_ore + m.refined_ore),
+        Err(_) => (0, 0),
+    };
+    let claim_sol = claimable_sol > 0 && claimable_sol >= min_claim_sol;
+    let claim_ore = claimable_ore > 0 && claimable_ore >= min_claim_ore;
+
+    out!("[cashout] {}", if dry_run { "预览模式（DRY_RUN），不会发送交易" } else { "开始执行" });
+    out!(
+        "[cashout] 1) claim_sol: 可领 {} -> {}",
+        fmt_sol(claimable_sol),
+        if claim_sol { "领取" } else { "跳过（低于阈值）" }
+    );
+    out!(
+        "[cashout] 2) claim_ore: 可领 {} -> {}",
+        fmt_ore(claimable_ore),
+        if claim_ore { "领取" } else { "跳过（低于阈值）" }
+    );
+
+    let mut result = CashoutResult {
+        dry_run,
+        claimed_sol: if claim_sol { claimable_sol } else { 0 },
+        ..Default::default()
+    };
+
+    // 领取前的钱包 ORE 余额：默认只兑换本次领取到的部分，不动钱包里原有的 ORE
+    let ore_ata = get_associated_token_address(&payer.pubkey(), &MINT_ADDRESS);
+    let ore_before = get_token_balance(rpc, &ore_ata).await;
+
+    // 执行 claim（SOL 与 ORE 合并为同一笔交易）
+    if !dry_run && (claim_sol || claim_ore) {
+        let mut ixs = vec![];
+        if claim_sol {
+            ixs.push(ore_api::sdk::claim_sol(payer.pubkey()));
+        }
+        if claim_ore {
+            ixs.push(ore_api::sdk::claim_ore(payer.pubkey()));
+        }
+        let kind = match (claim_sol, claim_ore) {
+            (true, true) => "sol+ore",
+            (true, false) => "sol",
+            _ => "ore",
+        };
+        ixs.extend(memo_instruction(&[("claim", kind.to_string())]));
+        let sig = submit_transaction(rpc, payer, &ixs).await?;
+        print_claim_receipt(rpc, kind, &sig);
+        result.signatures.push(sig.to_string());
+    }
+
+    // 3) swap：本次领取到的 ORE（领取后余额 - 领取前余额）换成 SOL；CASHOUT_SWAP_ALL=true 时兑换钱包内全部 ORE
+    let (wallet_ore, claimed_ore) = if dry_run {
+        (ore_before + if claim_ore { claimable_ore } else { 0 }, if claim_ore { claimable_ore } else { 0 })
+    } else {
+        let ore_after = get_token_balance(rpc, &ore_ata).await;
+        (ore_after, ore_after.saturating_sub(ore_before))
+    };
+    result.claimed_ore = claimed_ore;
+    let swap_in = if env_flag("CASHOUT_SWAP_ALL") { wallet_ore } else { claimed_ore };
+    let reserves = get_meteora_reserves(rpc, known_address("METEORA_POOL", DEFAULT_METEORA_POOL)).await?;
+    let expected_out = reserves.quote(&MINT_ADDRESS, swap_in);
+    let minimum_out = expected_out - expected_out * slippage_bps / DENOMINATOR_BPS;
+    let do_swap = swap_in > 0 && swap_in >= min_swap_ore;
+    out!(
+        "[cashout] 3) swap: {} -> 预计 {}（滑点 {} bps，最少 {}）-> {}",
+        fmt_ore(swap_in),
+        fmt_sol(expected_out),
+        slippage_bps,
+        fmt_sol(minimum_out),
+        if do_swap { "兑换" } else { "跳过（低于阈值）" }
+    );
+    if !dry_run && do_swap {
+        let swap_ix = build_meteora_swap_ix(&reserves, payer.pubkey(), &MINT_ADDRESS, swap_in, minimum_out);
+        let wsol_ata = get_associated_token_address(&payer.pubkey(), &SOL_MINT);
+        let ixs = [
+            spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+                &payer.pubkey(),
+                &payer.pubkey(),
+                &SOL_MINT,
+                &spl_token::ID,
+            ),
+            swap_ix,
+            // 关闭 wSOL 账户，换得的 SOL 回到钱包
+            spl_token::instruction::close_account(
+                &spl_token::ID,
+                &wsol_ata,
+                &payer.pubkey(),
+                &payer.pubkey(),
+                &[],
+            )?,
+        ];
+        let sig = submit_transaction(rpc, payer, &ixs).await?;
+        result.signatures.push(sig.to_string());
+    }
+    if do_swap {
+        result.swapped_ore = swap_in;
+        result.swap_minimum_out = minimum_out;
+    }
+
+    if !dry_run && !claim_sol && !claim_ore && !do_swap {
+        return Err(CommandError::nothing_to_do("[cashout] 所有步骤均低于阈值，无需执行").into());
+    }
+
+    // 4) transfer：本次领取 + 兑换所得（按最少到账数量计算，保守）
+    let mut transfer_amount = 0u64;
+    if claim_sol {
+        transfer_amount += claimable_sol;
+    }
+    if do_swap {
+        transfer_amount += minimum_out;
+    }
+    match destination {
+        Some(dest) if transfer_amount > 0 && transfer_amount >= min_transfer_sol => {
+            out!(
+                "[cashout] 4) transfer: {} -> {}",
+                fmt_sol(transfer_amount),
+                dest
+            );
+            if !dry_run {
+                let ix = solana_sdk::system_instruction::transfer(&payer.pubkey(), &dest, transfer_amount);
+                let sig = submit_transaction(rpc, payer, &[ix]).await?;
+                result.signatures.push(sig.to_string());
+            }
+            result.transferred_sol = transfer_amount;
+            result.destination = Some(dest.to_string());
+        }
+        Some(_) => out!(
+            "[cashout] 4) transfer: {} -> 跳过（低于阈值）",
+            fmt_sol(transfer_amount)
+        ),
+        None => out!("[cashout] 4) transfer: 未设置 CASHOUT_DESTINATION，跳过"),
+    }
+
+    out!(
+        "[cashout] {}合计到手约 {}",
+        if dry_run { "预计" } else { "" },
+        fmt_sol(transfer_amount)
+    );
+    Ok(result)
+}
+
+// ============ 新增：ORE/SOL 流动性（Meteora） ============
+// lp_add     按池子当前比例存入 LP_ORE 个 ORE 及等值 SOL（SOL 自动包装为 wSOL，剩余部分关闭账户退回）
+// lp_remove  赎回 LP_AMOUNT 个池子 LP（未设置或 all 时全部赎回），按比例取回 ORE 与 SOL
+// 两者都按 LP_SLIPPAGE_BPS（默认 100）设置最多存入 / 最少取回数量，DRY_RUN=true 只预览
+fn lp_slippage_bps() -> u64 {
+    std::env::var("LP_SLIPPAGE_BPS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(100)
+        .min(DENOMINATOR_BPS)
+}
+
+// 持仓 LP 按池子储备换算成 (ORE, SOL)
+fn lp_position_value(reserves: &MeteoraReserves, lp_amount: u64, lp_supply: u64) -> (u64, u64) {
+    if lp_supply == 0 {
+        return (0, 0);
+    }
+    let (reserve_ore, reserve_sol) = reserves.ore_sol_reserves();
+    (
+        (reserve_ore as u128 * lp_amount as u128 / lp_supply as u128) as u64,
+        (reserve_sol as u128 * lp_amount as u128 / lp_supply as u128) as u64,
+    )
+}
+
+async fn lp_add(rpc: &RpcClient, payer: &dyn TxSigner) -> Result<(), anyhow::Error> {
+    let dry_run = env_flag("DRY_RUN");
+    let ore_in = ui_amount_to_amount(env_f64("LP_ORE", 0.0), TOKEN_DECIMALS);
+    if ore_in == 0 {
+        return Err(CommandError::config("请设置 LP_ORE（存入的 ORE 数量）").into());
+    }
+    let slippage_bps = lp_slippage_bps();
+    let user = payer.pubkey();
+    let reserves = get_meteora_reserves(rpc, known_address("METEORA_POOL", DEFAULT_METEORA_POOL)).await?;
+    let pool = &reserves.pool;
+    let (reserve_ore, reserve_sol) = reserves.ore_sol_reserves();
+    if reserve_ore == 0 || reserve_sol == 0 {
+        return Err(anyhow::anyhow!("池子储备为空，无法按比例存入"));
+    }
+    let lp_supply = rpc.get_token_supply(&pool.lp_mint).await?.amount.parse::<u64>()?;
+    let wallet_ore = get_token_balance(rpc, &get_associated_token_address(&user, &MINT_ADDRESS)).await;
+    if wallet_ore < ore_in {
+        return Err(CommandError::config(format!(
+            "钱包 ORE 不足：需要 {}，现有 {}",
+            amount_to_ui_amount(ore_in, TOKEN_DECIMALS),
+            amount_to_ui_amount(wallet_ore, TOKEN_DECIMALS)
+        ))
+        .into());
+    }
+
+    // 按 ORE 占比计算可铸造的池子 LP，再反推需要的 SOL；最多存入量加上滑点余量
+    let pool_token_amount = (lp_supply as u128 * ore_in as u128 / reserve_ore as u128) as u64;
+    let sol_in = (reserve_sol as u128 * pool_token_amount as u128 / lp_supply.max(1) as u128) as u64;
+    let max_ore = ore_in + ore_in * slippage_bps / DENOMINATOR_BPS;
+    let max_sol = sol_in + sol_in * slippage_bps / DENOMINATOR_BPS;
+    out!(
+        "[lp] 存入约 {} + {}，获得 {} 池子 LP（滑点 {} bps，最多 {} / {}）",
+        fmt_ore(ore_in),
+        fmt_sol(sol_in),
+        pool_token_amount,
+        slippage_bps,
+        fmt_ore(max_ore),
+        fmt_sol(max_sol)
+    );
+    if dry_run {
+        out!("[lp] 预览模式（DRY_RUN），不会发送交易");
+        return Ok(());
+    }
+
+    let (max_a, max_b) = if pool.token_a_mint == MINT_ADDRESS { (max_ore, max_sol) } else { (max_sol, max_ore) };
+    let wsol_ata = get_associated_token_address(&user, &SOL_MINT);
+    let add_ix = meteora_pools_sdk::instructions::AddBalanceLiquidityBuilder::new()
+        .pool(reserves.address)
+        .lp_mint(pool.lp_mint)
+        .user_pool_lp(get_associated_token_address(&user, &pool.lp_mint))
+        .a_vault_lp(pool.a_vault_lp)
+        .b_vault_lp(pool.b_vault_lp)
+        .a_vault(pool.a_vault)
+        .b_vault(pool.b_vault)
+        .a_vault_lp_mint(reserves.vault_a.lp_mint)
+        .b_vault_lp_mint(reserves.vault_b.lp_mint)
+        .a_token_vault(reserves.vault_a.token_vault)
+        .b_token_vault(reserves.vault_b.token_vault)
+        .user_a_token(get_associated_token_address(&user, &pool.token_a_mint))
+        .user_b_token(get_associated_token_address(&user, &pool.token_b_mint))
+        .user(user)
+        .vault_program(meteora_vault_sdk::programs::VAULT_ID)
+        .pool_token_amount(pool_token_amount)
+        .maximum_token_a_amount(max_a)
+        .maximum_token_b_amount(max_b)
+        .instruction();
+    let ixs = [
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &user,
+            &user,
+            &pool.lp_mint,
+            &spl_token::ID,
+        ),
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &user,
+            &user,
+            &SOL_MINT,
+            &spl_token::ID,
+        ),
+        // 包装 SOL：转入 wSOL 账户后同步余额
+        solana_sdk::system_instruction::transfer(&user, &wsol_ata, max_sol),
+        spl_token::instruction::sync_native(&spl_token::ID, &wsol_ata)?,
+        add_ix,
+        // 关闭 wSOL 账户，未用完的 SOL 回到钱包
+        spl_token::instruction::close_account(&spl_token::ID, &wsol_ata, &user, &user, &[])?,
+    ];
+    submit_transaction(rpc, payer, &ixs).await?;
+    print_lp_position(rpc, user).await
+}
+
+async fn lp_remove(rpc: &RpcClient, payer: &dyn TxSigner) -> Result<(), anyhow::Error> {
+    let dry_run = env_flag("DRY_RUN");
+    let slippage_bps = lp_slippage_bps();
+    let user = payer.pubkey();
+    let reserves = get_meteora_reserves(rpc, known_address("METEORA_POOL", DEFAULT_METEORA_POOL)).await?;
+    let pool = &reserves.pool;
+    let user_lp = get_associated_token_address(&user, &pool.lp_mint);
+    let balance = rpc.get_token_account_balance(&user_lp).await.ok();
+    let held = balance.as_ref().and_then(|b| b.amount.parse::<u64>().ok()).unwrap_or(0);
+    let decimals = balance.map(|b| b.decimals).unwrap_or(0);
+    let lp_amount = match std::env::var("LP_AMOUNT") {
+        Ok(v) if v.trim() != "all" => ui_amount_to_amount(
+            v.trim().parse::<f64>().map_err(|_| CommandError::config("LP_AMOUNT 无效（数量或 all）"))?,
+            decimals,
+        ),
+        _ => held,
+    };
+    if lp_amount == 0 {
+        return Err(CommandError::nothing_to_do("没有可赎回的池子 LP").into());
+    }
+    if lp_amount > held {
+        return Err(CommandError::config(format!(
+            "LP 不足：需要 {}，持有 {}",
+            amount_to_ui_amount(lp_amount, decimals),
+            amount_to_ui_amount(held, decimals)
+        ))
+        .into());
+    }
+
+    let lp_supply = rpc.get_token_supply(&pool.lp_mint).await?.amount.parse::<u64>()?;
+    let (ore_out, sol_out) = lp_position_value(&reserves, lp_amount, lp_supply);
+    let min_ore = ore_out - ore_out * slippage_bps / DENOMINATOR_BPS;
+    let min_sol = sol_out - sol_out * slippage_bps / DENOMINATOR_BPS;
+    out!(
+        "[lp] 赎回 {} 池子 LP -> 约 {} + {}（滑点 {} bps，最少 {} / {}）",
+        amount_to_ui_amount(lp_amount, decimals),
+        fmt_ore(ore_out),
+        fmt_sol(sol_out),
+        slippage_bps,
+        fmt_ore(min_ore),
+        fmt_sol(min_sol)
+    );
+    if dry_run {
+        out!("[lp] 预览模式（DRY_RUN），不会发送交易");
+        return Ok(());
+    }
+
+    let (min_a, min_b) = if pool.token_a_mint == MINT_ADDRESS { (min_ore, min_sol) } else { (min_sol, min_ore) };
+    let remove_ix = meteora_pools_sdk::instructions::RemoveBalanceLiquidityBuilder::new()
+        .pool(reserves.address)
+        .lp_mint(pool.lp_mint)
+        .user_pool_lp(user_lp)
+        .a_vault_lp(pool.a_vault_lp)
+        .b_vault_lp(pool.b_vault_lp)
+        .a_vault(pool.a_vault)
+        .b_vault(pool.b_vault)
+        .a_vault_lp_mint(reserves.vault_a.lp_mint)
+        .b_vault_lp_mint(reserves.vault_b.lp_mint)
+        .a_token_vault(reserves.vault_a.token_vault)
+        .b_token_vault(reserves.vault_b.token_vault)
+        .user_a_token(get_associated_token_address(&user, &pool.token_a_mint))
+        .user_b_token(get_associated_token_address(&user, &pool.token_b_mint))
+        .user(user)
+        .vault_program(meteora_vault_sdk::programs::VAULT_ID)
+        .pool_token_amount(lp_amount)
+        .minimum_a_token_out(min_a)
+        .minimum_b_token_out(min_b)
+        .instruction();
+    let wsol_ata = get_associated_token_address(&user, &SOL_MINT);
+    let ixs = [
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &user,
+            &user,
+            &MINT_ADDRESS,
+            &spl_token::ID,
+        ),
+        spl_associated_token_account::instruction::create_associated_token_account_idempotent(
+            &user,
+            &user,
+            &SOL_MINT,
+            &spl_token::ID,
+        ),
+        remove_ix,
+        // 关闭 wSOL 账户，取回的 SOL 回到钱包
+        spl_token::instruction::close_account(&spl_token::ID, &wsol_ata, &user, &user, &[])?,
+    ];
+    submit_transaction(rpc, payer, &ixs).await?;
+    print_lp_position(rpc, user).await
+}
+
+async fn print_lp_position(rpc: &RpcClient, user: Pubkey) -> Result<(), anyhow::Error> {
+    let reserves = get_meteora_reserves(rpc, known_address("METEORA_POOL", DEFAULT_METEORA_POOL)).await?;
+    let lp_mint = reserves.pool.lp_mint;
+    let held = get_token_balance(rpc, &get_associated_token_address(&user, &lp_mint)).await;
+    let lp_supply = rpc.get_token_supply(&lp_mint).await?.amount.parse::<u64>()?;
+    let (ore, sol) = lp_position_value(&reserves, held, lp_supply);
+    out!(
+        "[lp] 当前持有 {} 池子 LP（占池子 {:.4}%），约 {} + {}",
+        held,
+        if lp_supply > 0 { held as f64 / lp_supply as f64 * 100.0 } else { 0.0 },
+        fmt_ore(ore),
+        fmt_sol(sol)
+    );
+    Ok(())
+}
+
+async fn log_automations(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let automations = get_automations(rpc).await?;
+    for (i, (address, automation)) in automations.iter().enumerate() {
+        out!("[{}/{}] {}", i + 1, automations.len(), address);
+        out!("  authority: {}", automation.authority);
+        out!("  balance: {}", automation.balance);
+        out!("  executor: {}", automation.executor);
+        out!("  fee: {}", automation.fee);
+        out!("  mask: {}", automation.mask);
+        out!("  strategy: {}", automation.strategy);
+        out!();
+    }
+    Ok(())
+}
+
+// ============ 新增：Automation 余额监控与补充 ============
+// Automation 账户预存 SOL，由 executor 每轮代为部署并收取 fee。
+// automation_monitor：按当前下注（amount × 格子数）+ executor fee 估算每轮消耗，
+// 余额可支撑的回合数低于 AUTOMATION_MIN_RUNWAY（默认 20）时通知（NOTIFY_EVENTS 中的 automation_low）；
+// FOLLOW=true 时每 AUTOMATION_CHECK_SECS（默认 300）秒检查一次，恢复之前不重复通知。
+// automation_topup：以相同参数调用 automate 追加存入 TOPUP_SOL，未设置时补足到 AUTOMATION_TOPUP_ROUNDS 轮
+// （默认 AUTOMATION_MIN_RUNWAY 的 2 倍）
+async fn get_automation(rpc: &RpcClient, authority: Pubkey) -> Result<Automation, anyhow::Error> {
+    let address = automation_pda(authority).0;
+    let account = rpc.get_account(&address).await.map_err(|_| {
+        CommandError::nothing_to_do(format!("{} 没有 Automation 账户", authority))
+    })?;
+    Ok(*Automation::try_from_bytes(&account.data)?)
+}
+
+// 每轮消耗：Preferred 按 mask 中的格子数；Random 的 mask 为格子数量
+fn automation_round_cost(automation: &Automation) -> u64 {
+    let squares = if automation.strategy == AutomationStrategy::Preferred as u64 {
+        (automation.mask & 0x1FF_FFFF).count_ones() as u64
+    } else {
+        automation.mask.clamp(1, 25)
+    };
+    automation.amount * squares + automation.fee
+}
+
+fn automation_runway(automation: &Automation) -> u64 {
+    match automation_round_cost(automation) {
+        0 => u64::MAX,
+        cost => automation.balance / cost,
+    }
+}
+
+fn print_automation_runway(automation: &Automation) {
+    let runway = automation_runway(automation);
+    out!(
+        "[automation] 余额 {}，每轮约 {}（下注 {} / 格 + fee {}），可支撑 {} 轮",
+        fmt_sol(automation.balance),
+        fmt_sol(automation_round_cost(automation)),
+        fmt_sol(automation.amount),
+        fmt_sol(automation.fee),
+        if runway == u64::MAX { "∞".to_string() } else { runway.to_string() }
+    );
+}
+
+async fn automation_monitor(rpc: &RpcClient, payer: &dyn TxSigner) -> Result<(), anyhow::Error> {
+    let authority = mining_authority(payer).pubkey();
+    let min_runway = std::env::var("AUTOMATION_MIN_RUNWAY")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(20);
+    let interval = Duration::from_secs(
+        std::env::var("AUTOMATION_CHECK_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(300),
+    );
+    let follow = env_flag("FOLLOW");
+    let mut alerted = false;
+    loop {
+        match get_automation(rpc, authority).await {
+            Ok(automation) => {
+                print_automation_runway(&automation);
+                let runway = automation_runway(&automation);
+                if runway < min_runway {
+                    if !alerted {
+                        notify_alert(
+                            "automation_low",
+                            &format!(
+                                "Automation {} 余额 {} 仅够 {} 轮（阈值 {} 轮），请运行 automation_topup 补充",
+                                authority,
+                                fmt_sol(automation.balance),
+                                runway,
+                                min_runway
+                            ),
+                        );
+                    }
+                    out!("[automation] ⚠️  可支撑回合数低于 AUTOMATION_MIN_RUNWAY={}", min_runway);
+                    alerted = true;
+                } else {
+                    alerted = false;
+                }
+            }
+            Err(e) if !follow => return Err(e),
+            Err(e) => out!("[automation] ⚠️  读取 Automation 失败: {}", e),
+        }
+        if !follow {
+            return Ok(());
+        }
+        sleep(interval).await;
+    }
+}
+
+async fn automation_topup(rpc: &RpcClient, payer: &dyn TxSigner) -> Result<(), anyhow::Error> {
+    let authority = mining_authority(payer).pubkey();
+    let automation = get_automation(rpc, authority).await?;
+    print_automation_runway(&automation);
+    let deposit = match std::env::var("TOPUP_SOL") {
+        Ok(_) => sol_to_lamports(env_f64("TOPUP_SOL", 0.0)),
+        Err(_) => {
+            let min_runway = std::env::var("AUTOMATION_MIN_RUNWAY")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(20);
+            let rounds = std::env::var("AUTOMATION_TOPUP_ROUNDS")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(min_runway * 2);
+            (automation_round_cost(&automation) * rounds).saturating_sub(automation.balance)
+        }
+    };
+    if deposit == 0 {
+        return Err(CommandError::nothing_to_do("[automation] 余额充足，无需补充").into());
+    }
+    let after = Automation {
+        balance: automation.balance + deposit,
+        ..automation
+    };
+    out!(
+        "[automation] 补充 {} -> 余额 {}，可支撑 {} 轮",
+        fmt_sol(deposit),
+        fmt_sol(after.balance),
+        automation_runway(&after)
+    );
+    if env_flag("DRY_RUN") {
+        out!("[automation] 预览模式（DRY_RUN），不发送交易");
+        return Ok(());
+    }
+    if !confirm("[automation] 确认补充？") {
+        return Err(CommandError::nothing_to_do("[automation] 已取消").into());
+    }
+    // automate 以原参数重新提交，只追加 deposit
+    let ix = ore_api::sdk::automate(
+        authority,
+        automation.amount,
+        deposit,
+        automation.executor,
+        automation.fee,
+        automation.mask,
+        automation.strategy as u8,
+    );
+    let sig = submit_transaction(rpc, payer, &[ix]).await?;
+    out!("[automation] ✅ 交易签名: {}", sig);
+    Ok(())
+}
+
+// SOL 以 lamports、ORE 以最小单位计；奖励因子为十进制字符串
+#[derive(Debug, Serialize)]
+struct TreasuryResult {
+    address: String,
+    balance: u64,
+    motherlode: u64,
+    miner_rewards_factor: String,
+    stake_rewards_factor: String,
+    total_staked: u64,
+    total_unclaimed: u64,
+    total_refined: u64,
+}
+
+async fn log_treasury(rpc: &RpcClient) -> Result<TreasuryResult, anyhow::Error> {
+    let treasury_address = ore_api::state::treasury_pda().0;
+    let treasury = get_treasury(rpc).await?;
+    out!("Treasury");
+    out!("  address: {}", treasury_address);
+    out!("  balance: {}", fmt_sol(treasury.balance));
+    out!(
+        "  motherlode: {}",
+        fmt_ore(treasury.motherlode)
+    );
+    out!(
+        "  miner_rewards_factor: {}",
+        treasury.miner_rewards_factor.to_i80f48().to_string()
+    );
+    out!(
+        "  stake_rewards_factor: {}",
+        treasury.stake_rewards_factor.to_i80f48().to_string()
+    );
+    out!(
+        "  total_staked: {}",
+        fmt_ore(treasury.total_staked)
+    );
+    out!(
+        "  total_unclaimed: {}",
+        fmt_ore(treasury.total_unclaimed)
+    );
+    out!(
+        "  total_refined: {}",
+        fmt_ore(treasury.total_refined)
+    );
+
+    // 顺便记录一次 Treasury 样本
+    if let Ok(sample) = treasury_sample(rpc).await {
+        append_treasury_sample(&sample);
+    }
+    Ok(TreasuryResult {
+        address: treasury_address.to_string(),
+        balance: treasury.balance,
+        motherlode: treasury.motherlode,
+        miner_rewards_factor: treasury.miner_rewards_factor.to_i80f48().to_string(),
+        stake_rewards_factor: treasury.stake_rewards_factor.to_i80f48().to_string(),
+        total_staked: treasury.total_staked,
+        total_unclaimed: treasury.total_unclaimed,
+        total_refined: treasury.total_refined,
+    })
+}
+
+// ============ 新增：Treasury 历史与代币经济报告 ============
+// treasury 命令顺便记录一次样本，treasury_record 每 TREASURY_RECORD_INTERVAL_SECS 秒（默认 1 小时）记录一次，
+// tokenomics 根据 ore.treasury_history.jsonl 推算 buy-bury 速度、供应量增速、流通 / 质押 / refined 占比，并做线性外推
+const TREASURY_HISTORY_FILE: &str = "ore.treasury_history.jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct TreasurySample {
+    timestamp: u64,
+    supply: u64,
+    balance: u64,
+    motherlode: u64,
+    total_staked: u64,
+    total_unclaimed: u64,
+    total_refined: u64,
+    stake_rewards_factor: f64,
+}
+
+impl TreasurySample {
+    // 流通量：总供应减去 Treasury 托管的质押、未领取奖励与 motherlode
+    fn circulating(&self) -> u64 {
+        self.supply
+            .saturating_sub(self.total_staked)
+            .saturating_sub(self.total_unclaimed)
+            .saturating_sub(self.motherlode)
+    }
+}
+
+async fn treasury_sample(rpc: &RpcClient) -> Result<TreasurySample, anyhow::Error> {
+    let treasury = get_treasury(rpc).await?;
+    let supply = rpc.get_token_supply(&MINT_ADDRESS).await?.amount.parse::<u64>()?;
+    Ok(TreasurySample {
+        timestamp: chain_now(),
+        supply,
+        balance: treasury.balance,
+        motherlode: treasury.motherlode,
+        total_staked: treasury.total_staked,
+        total_unclaimed: treasury.total_unclaimed,
+        total_refined: treasury.total_refined,
+        stake_rewards_factor: treasury.stake_rewards_factor.to_i80f48().to_num::<f64>(),
+    })
+}
+
+fn append_treasury_sample(sample: &TreasurySample) {
+    let Ok(line) = serde_json::to_string(sample) else {
+        return;
+    };
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(TREASURY_HISTORY_FILE))
+    {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+async fn treasury_record(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let interval_secs: u64 = std::env::var("TREASURY_RECORD_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(3600);
+    loop {
+        match treasury_sample(rpc).await {
+            Ok(sample) => {
+                out!(
+                    "[treasury] 已记录：供应 {}，Treasury 余额 {}",
+                    fmt_ore(sample.supply),
+                    fmt_sol(sample.balance)
+                );
+                append_treasury_sample(&sample);
+            }
+            Err(e) => {
+                out!("[treasury] ⚠️  读取 Treasury 失败: {:?}，下次再试", e);
+            }
+        }
+        sleep(Duration::from_secs(interval_secs)).await;
+    }
+}
+
+fn tokenomics() -> Result<(), anyhow::Error> {
+    let mut samples: Vec<TreasurySample> = fs::read_to_string(state_path(TREASURY_HISTORY_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    samples.sort_by_key(|s| s.timestamp);
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return Err(CommandError::nothing_to_do(format!(
+            "{} 中没有样本，请先运行 treasury 或 treasury_record",
+            state_path(TREASURY_HISTORY_FILE)
+        ))
+        .into());
+    };
+    let ore = |amount: u64| amount_to_ui_amount(amount, TOKEN_DECIMALS);
+    let pct = |part: u64, whole: u64| if whole == 0 { 0.0 } else { part as f64 / whole as f64 * 100.0 };
+
+    out!("Tokenomics ({} 个样本)", samples.len());
+    out!("  Supply: {:.2} ORE / max {:.0} ORE ({:.2}%)", ore(last.supply), ore(MAX_SUPPLY), pct(last.supply, MAX_SUPPLY));
+    out!("  Circulating: {:.2} ORE ({:.2}%)", ore(last.circulating()), pct(last.circulating(), last.supply));
+    out!("  Staked: {:.2} ORE ({:.2}%)", ore(last.total_staked), pct(last.total_staked, last.supply));
+    out!("  Unclaimed: {:.2} ORE ({:.2}%)", ore(last.total_unclaimed), pct(last.total_unclaimed, last.supply));
+    out!("  Refined: {:.2} ORE ({:.2}% of unclaimed)", ore(last.total_refined), pct(last.total_refined, last.total_unclaimed));
+    out!("  Motherlode: {:.2} ORE", ore(last.motherlode));
+    out!("  Treasury balance (buy-bury): {}", fmt_sol(last.balance));
+
+    let days = last.timestamp.saturating_sub(first.timestamp) as f64 / 86400.0;
+    if samples.len() < 2 || days <= 0.0 {
+        out!();
+        out!("  样本时间跨度不足，至少需要两个不同时间的样本才能计算速率与外推");
+        return Ok(());
+    }
+
+    // Treasury 余额上升为 SOL 流入，下降为 buy-bury 花掉的 SOL
+    let (mut sol_in, mut sol_buried) = (0u64, 0u64);
+    for pair in samples.windows(2) {
+        if pair[1].balance >= pair[0].balance {
+            sol_in += pair[1].balance - pair[0].balance;
+        } else {
+            sol_buried += pair[0].balance - pair[1].balance;
+        }
+    }
+    let supply_per_day = (last.supply as f64 - first.supply as f64) / days;
+    let staked_per_day = (last.total_staked as f64 - first.total_staked as f64) / days;
+    let stake_apr = (last.stake_rewards_factor - first.stake_rewards_factor).max(0.0) / days * 365.0 * 100.0;
+    out!();
+    out!("  Rates over {:.2} days:", days);
+    out!("    Net supply change: {:+.4} ORE/day", supply_per_day / 1e11);
+    out!("    Staked change: {:+.4} ORE/day", staked_per_day / 1e11);
+    out!("    SOL into treasury: {:.4} SOL/day", lamports_to_sol(sol_in) / days);
+    out!("    SOL spent on buy-bury: {:.4} SOL/day", lamports_to_sol(sol_buried) / days);
+    out!("    Staking APR (from stake_rewards_factor): {:.2}%", stake_apr);
+
+    out!();
+    out!("  Projections (linear):");
+    for horizon in [30.0, 90.0, 365.0] {
+        let supply = (last.supply as f64 + supply_per_day * horizon).clamp(0.0, MAX_SUPPLY as f64);
+        let staked = (last.total_staked as f64 + staked_per_day * horizon).max(0.0);
+        out!(
+            "    +{:>3} days: supply {:.2} ORE, staked {:.2} ORE ({:.2}%)",
+            horizon,
+            supply / 1e11,
+            staked / 1e11,
+            if supply > 0.0 { staked / supply * 100.0 } else { 0.0 }
+        );
+    }
+    if supply_per_day > 0.0 {
+        let days_to_max = MAX_SUPPLY.saturating_sub(last.supply) as f64 / supply_per_day;
+        out!("    按当前净增速约 {:.0} 天达到最大供应量", days_to_max);
+    } else {
+        out!("    当前净供应量不增反减（buy-bury 超过产出）");
+    }
+    Ok(())
+}
+
+// ============ 新增：挖矿成本与市价套利监控 ============
+// 全网视角的挖矿成本：每轮协议抽走的 SOL（部署 1% 管理费 + 未中奖格子 10% 金库费）/ 每轮发放的 ORE（top miner 奖励 + motherlode）
+// 取最近 ARB_WINDOW 个已结算回合（ore.round_results.jsonl，默认 60），没有记录时用当前回合部署量与 1 ORE + motherlode/625 估算
+// COMMAND=arb_monitor 每 ARB_INTERVAL_SECS 秒（默认 60）比较市价与挖矿成本，偏离超过 ARB_EDGE_PCT（默认 20%）时通知：
+//   市价 > 成本  挖矿明显 +EV；市价 < 成本  直接买 ORE 更便宜
+struct MiningCostEstimate {
+    rounds: usize,
+    sol_per_round: f64,
+    ore_per_round: f64,
+}
+
+impl MiningCostEstimate {
+    fn sol_per_ore(&self) -> f64 {
+        if self.ore_per_round > 0.0 {
+            self.sol_per_round / self.ore_per_round
+        } else {
+            f64::INFINITY
+        }
+    }
+}
+
+// 一轮中协议抽走的 SOL（lamports）
+fn round_protocol_take(deployed: &[u64; 25], winning_square: usize) -> f64 {
+    let total: u64 = deployed.iter().sum();
+    let losing = total.saturating_sub(deployed[winning_square]) as f64;
+    total as f64 * BACKTEST_ADMIN_FEE + losing * (1.0 - BACKTEST_ADMIN_FEE) * BACKTEST_VAULT_FEE
+}
+
+async fn mining_cost_estimate(rpc: &RpcClient) -> Result<MiningCostEstimate, anyhow::Error> {
+    let window: usize = std::env::var("ARB_WINDOW")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(60);
+    let results = read_round_results();
+    let recent = &results[results.len().saturating_sub(window)..];
+    if !recent.is_empty() {
+        let n = recent.len() as f64;
+        let sol: f64 = recent
+            .iter()
+            .map(|r| round_protocol_take(&r.deployed, r.winning_square as usize))
+            .sum();
+        let ore: u64 = recent.iter().map(|r| r.top_miner_reward + r.motherlode).sum();
+        return Ok(MiningCostEstimate {
+            rounds: recent.len(),
+            sol_per_round: lamports_to_sol(sol as u64) / n,
+            ore_per_round: amount_to_ui_amount(ore, TOKEN_DECIMALS) / n,
+        });
+    }
+
+    // 没有历史：按当前回合的部署量（假设开奖格子占 1/25）估算
+    let board = get_board(rpc).await?;
+    let round = get_round(rpc, board.round_id).await?;
+    let treasury = get_treasury(rpc).await?;
+    let total = round.deployed.iter().sum::<u64>() as f64;
+    let take = total * BACKTEST_ADMIN_FEE + total * 24.0 / 25.0 * (1.0 - BACKTEST_ADMIN_FEE) * BACKTEST_VAULT_FEE;
+    Ok(MiningCostEstimate {
+        rounds: 0,
+        sol_per_round: lamports_to_sol(take as u64),
+        ore_per_round: 1.0 + amount_to_ui_amount(treasury.motherlode, TOKEN_DECIMALS) / 625.0,
+    })
+}
+
+async fn arb_monitor(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let interval_secs: u64 = std::env::var("ARB_INTERVAL_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or(60);
+    let edge_pct = env_f64("ARB_EDGE_PCT", 20.0);
+    let pool = known_address("METEORA_POOL", DEFAULT_METEORA_POOL);
+    install_stop_handler();
+    // 只在信号变化时通知：1 = 挖矿 +EV，-1 = 买入更便宜，0 = 无明显差异
+    let mut last_signal = 0;
+    while !stop_requested() {
+        let (reserves, estimate) = match (get_meteora_reserves(rpc, pool).await, mining_cost_estimate(rpc).await) {
+            (Ok(reserves), Ok(estimate)) => (reserves, estimate),
+            (Err(e), _) | (_, Err(e)) => {
+                out!("[arb] ⚠️  读取市价或挖矿成本失败: {:?}，下次再试", e);
+                sleep(Duration::from_secs(interval_secs)).await;
+                continue;
+            }
+        };
+        let price = reserves.mid_price();
+        let cost = estimate.sol_per_ore();
+        let edge = (price / cost - 1.0) * 100.0;
+        out!(
+            "[arb] 市价 {:.6} SOL/ORE，挖矿成本 {:.6} SOL/ORE（{}，每轮 {:.4} SOL / {:.4} ORE），偏离 {:+.1}%",
+            price,
+            cost,
+            if estimate.rounds > 0 { format!("最近 {} 轮", estimate.rounds) } else { "当前回合估算".to_string() },
+            estimate.sol_per_round,
+            estimate.ore_per_round,
+            edge
+        );
+        let signal = if edge >= edge_pct {
+            1
+        } else if edge <= -edge_pct {
+            -1
+        } else {
+            0
+        };
+        if signal != last_signal {
+            let message = match signal {
+                1 => format!("挖矿明显 +EV：市价 {:.6} 高于挖矿成本 {:.6} SOL/ORE（{:+.1}%）", price, cost, edge),
+                -1 => format!("直接买 ORE 更便宜：市价 {:.6} 低于挖矿成本 {:.6} SOL/ORE（{:+.1}%）", price, cost, edge),
+                _ => format!("市价与挖矿成本回到 ±{}% 以内（{:+.1}%）", edge_pct, edge),
+            };
+            out!("[arb] 🔔 {}", message);
+            notify_reward_event(&RewardEvent {
+                kind: Some(message),
+                ..RewardEvent::new("arb_signal")
+            });
+            last_signal = signal;
+        }
+        sleep(Duration::from_secs(interval_secs)).await;
+    }
+    Ok(())
+}
+
+// SOL 以 lamports、ORE 以最小单位计
+#[derive(Debug, Serialize)]
+struct RoundInfoResult {
+    address: String,
+    id: u64,
+    count: [u64; 25],
+    deployed: [u64; 25],
+    expires_at: u64,
+    motherlode: u64,
+    rent_payer: String,
+    top_miner: String,
+    top_miner_reward: u64,
+    total_deployed: u64,
+    total_vaulted: u64,
+    total_winnings: u64,
+    winning_square: Option<usize>,
+}
+
+async fn log_round(rpc: &RpcClient) -> Result<RoundInfoResult, anyhow::Error> {
+    let id: u64 = parse_env("ID")?;
+    let round_address = round_pda(id).0;
+    let round = get_round(rpc, id).await?;
+    let rng = round.rng();
+    out!("Round");
+    out!("  Address: {}", round_address);
+    out!("  Count: {:?}", round.count);
+    out!("  Deployed: {:?}", round.deployed);
+    out!("  Expires at: {}", round.expires_at);
+    out!("  Id: {:?}", round.id);
+    out!("  Motherlode: {}", round.motherlode);
+    out!("  Rent payer: {}", round.rent_payer);
+    out!("  Slot hash: {:?}", round.slot_hash);
+    out!("  Top miner: {:?}", round.top_miner);
+    out!("  Top miner reward: {}", round.top_miner_reward);
+    out!("  Total deployed: {}", round.total_deployed);
+    out!("  Total vaulted: {}", round.total_vaulted);
+    out!("  Total winnings: {}", round.total_winnings);
+    if let Some(rng) = rng {
+        out!("  Winning square: {}", round.winning_square(rng));
+    }
+    // if round.slot_hash != [0; 32] {
+    //     println!("  Winning square: {}", get_winning_square(&round.slot_hash));
+    // }
+    Ok(RoundInfoResult {
+        address: round_address.to_string(),
+        id: round.id,
+        count: round.count,
+        deployed: round.deployed,
+        expires_at: round.expires_at,
+        motherlode: round.motherlode,
+        rent_payer: round.rent_payer.to_string(),
+        top_miner: round.top_miner.to_string(),
+        top_miner_reward: round.top_miner_reward,
+        total_deployed: round.total_deployed,
+        total_vaulted: round.total_vaulted,
+        total_winnings: round.total_winnings,
+        winning_square: rng.map(|rng| round.winning_square(rng)),
+    })
+}
+
+// 所有未关闭的 Round 账户概览（按紧急程度排序），供 close_all / checkpoint_all 操作者参考
+async fn log_rounds(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let clock = get_clock(rpc).await?;
+    let mut rounds = get_rounds(rpc).await?;
+    rounds.sort_by_key(|(_, round)| round.expires_at);
+    out!("Rounds ({} open, slot {})", rounds.len(), clock.slot);
+    out!(
+        "  {:>8}  {:>14}  {:>12}  {:>8}  fee window",
+        "id", "deployed (SOL)", "expires_at", "closable"
+    );
+    for (_address, round) in rounds.iter() {
+        let view = RoundView::new(*round, clock.slot);
+        out!(
+            "  {:>8}  {:>14.6}  {:>12}  {:>8}  {}",
+            round.id,
+            lamports_to_sol(round.total_deployed),
+            round.expires_at,
+            if view.is_closable() { "yes" } else { "no" },
+            describe_fee_window(round.expires_at, clock.slot)
+        );
+    }
+    Ok(())
+}
+
+// SOL 以 lamports、ORE 以最小单位计
+#[derive(Debug, Serialize)]
+struct MinerResult {
+    address: String,
+    authority: String,
+    deployed: [u64; 25],
+    cumulative: [u64; 25],
+    rewards_sol: u64,
+    rewards_ore: u64,
+    refined_ore: u64,
+    round_id: u64,
+    checkpoint_id: u64,
+    fee_window: Option<String>,
+    lifetime_rewards_sol: u64,
+    lifetime_rewards_ore: u64,
+}
+
+async fn log_miner(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<MinerResult, anyhow::Error> {
+    let authority = parse_env_opt::<Pubkey>("AUTHORITY")?.unwrap_or(mining_authority(payer).pubkey());
+    let miner_address = ore_api::state::miner_pda(authority).0;
+    let miner = get_miner(&rpc, authority).await?;
+    out!("Miner");
+    out!("  address: {}", miner_address);
+    out!("  authority: {}", authority);
+    out!("  deployed: {:?}", miner.deployed);
+    out!("  cumulative: {:?}", miner.cumulative);
+    out!("  rewards_sol: {}", fmt_sol(miner.rewards_sol));
+    out!(
+        "  rewards_ore: {}",
+        fmt_ore(miner.rewards_ore)
+    );
+    out!(
+        "  refined_ore: {}",
+        fmt_ore(miner.refined_ore)
+    );
+    out!("  round_id: {}", miner.round_id);
+    out!("  checkpoint_id: {}", miner.checkpoint_id);
+    let fee_window = pending_checkpoint_warning(rpc, &miner).await;
+    if let Some(warning) = &fee_window {
+        out!("  fee_window: {}", warning);
+    }
+    out!(
+        "  lifetime_rewards_sol: {}",
+        fmt_sol(miner.lifetime_rewards_sol)
+    );
+    out!(
+        "  lifetime_rewards_ore: {}",
+        fmt_ore(miner.lifetime_rewards_ore)
+    );
+    Ok(MinerResult {
+        address: miner_address.to_string(),
+        authority: authority.to_string(),
+        deployed: miner.deployed,
+        cumulative: miner.cumulative,
+        rewards_sol: miner.rewards_sol,
+        rewards_ore: miner.rewards_ore,
+        refined_ore: miner.refined_ore,
+        round_id: miner.round_id,
+        checkpoint_id: miner.checkpoint_id,
+        fee_window,
+        lifetime_rewards_sol: miner.lifetime_rewards_sol,
+        lifetime_rewards_ore: miner.lifetime_rewards_ore,
+    })
+}
+
+async fn log_seeker(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let mint: Pubkey = parse_env("MINT")?;
+    let seeker = get_seeker(&rpc, mint).await?;
+    let seeker_address = ore_api::state::seeker_pda(mint).0;
+    out!("Seeker");
+    out!("  address: {}", seeker_address);
+    out!("  mint: {}", seeker.mint);
+    Ok(())
+}
+
+// Seeker 激活状态：列出钱包持有的 Token-2022 NFT（数量 1、精度 0），检查对应 Seeker PDA 是否已存在，
+// 对尚未激活的逐个模拟 claim_seeker（不签名、不提交），在真正提交前看清结果
+// WALLET 默认为付款账户（claim_seeker 的签名者）
+const TOKEN_2022_PROGRAM_ID: Pubkey = pubkey!("TokenzQdBNbLqP5VEhdkAS6EPFLC1PHnBqCXEpPxuEb");
+
+async fn seeker_status(rpc: &RpcClient, payer: &dyn TxSigner) -> Result<(), anyhow::Error> {
+    let wallet = std::env::var("WALLET").unwrap_or(payer.pubkey().to_string());
+    let wallet = Pubkey::from_str(&wallet).map_err(|_| CommandError::config("WALLET 不是合法的公钥"))?;
+    let config = get_config(rpc).await?;
+
+    let token_accounts = rpc
+        .get_token_accounts_by_owner(
+            &wallet,
+            solana_client::rpc_request::TokenAccountsFilter::ProgramId(TOKEN_2022_PROGRAM_ID),
+        )
+        .await?;
+    let mut candidates = vec![];
+    for keyed in token_accounts {
+        let solana_account_decoder::UiAccountData::Json(parsed) = keyed.account.data else {
+            continue;
+        };
+        let info = &parsed.parsed["info"];
+        let amount = info["tokenAmount"]["amount"].as_str().unwrap_or("0");
+        let decimals = info["tokenAmount"]["decimals"].as_u64().unwrap_or(u64::MAX);
+        let Some(mint) = info["mint"].as_str().and_then(|m| Pubkey::from_str(m).ok()) else {
+            continue;
+        };
+        if amount == "1" && decimals == 0 {
+            candidates.push((Pubkey::from_str(&keyed.pubkey)?, mint));
+        }
+    }
+
+    out!("Seeker status ({})", wallet);
+    out!(
+        "  is_seeker_activation_enabled: {}",
+        config.is_seeker_activation_enabled
+    );
+    if candidates.is_empty() {
+        return Err(CommandError::nothing_to_do("钱包中没有可能符合条件的 Token-2022 NFT").into());
+    }
+
+    let seeker_addresses: Vec<Pubkey> = candidates
+        .iter()
+        .map(|(_, mint)| ore_api::state::seeker_pda(*mint).0)
+        .collect();
+    let mut activated = vec![];
+    for chunk in seeker_addresses.chunks(100) {
+        activated.extend(
+            rpc.get_multiple_accounts(chunk)
+                .await?
+                .into_iter()
+                .map(|a| a.is_some()),
+        );
+    }
+
+    let mut claimable = 0;
+    for ((token_account, mint), activated) in candidates.iter().zip(activated) {
+        out!();
+        out!("  mint: {}", mint);
+        out!("    seeker: {}", ore_api::state::seeker_pda(*mint).0);
+        if activated {
+            out!("    status: 已激活（Seeker PDA 已存在）");
+            continue;
+        }
+        let ata = spl_associated_token_account::get_associated_token_address_with_program_id(
+            &wallet,
+            mint,
+            &TOKEN_2022_PROGRAM_ID,
+        );
+        if *token_account != ata {
+            out!("    status: 未激活，但代币不在 ATA（{}）中，claim_seeker 会失败", ata);
+            continue;
+        }
+
+        // 以钱包为付款人构造未签名交易并模拟
+        let ix = ore_api::sdk::claim_seeker(wallet, *mint);
+        let message = solana_sdk::message::Message::new(&[ix], Some(&wallet));
+        let transaction = Transaction::new_unsigned(message);
+        let simulation = rpc
+            .simulate_transaction_with_config(
+                &transaction,
+                solana_client::rpc_config::RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    ..Default::default()
+                },
+            )
+            .await?
+            .value;
+        match simulation.err {
+            None => {
+                claimable += 1;
+                out!(
+                    "    status: 未激活，claim_seeker 模拟成功（{} CU）",
+                    simulation.units_consumed.unwrap_or(0)
+                );
+            }
+            Some(err) => out!("    status: 未激活，claim_seeker 模拟失败: {:?}", err),
+        }
+        for log in simulation.logs.unwrap_or_default().iter().filter(|l| l.contains("Program log:")) {
+            out!("      {}", log);
+        }
+    }
+    out!();
+    out!("  合计 {} 个候选 NFT，{} 个可以 claim_seeker", candidates.len(), claimable);
+    Ok(())
+}
+
+#[derive(Debug, Serialize)]
+struct ClockResult {
+    slot: u64,
+    epoch_start_timestamp: i64,
+    epoch: u64,
+    leader_schedule_epoch: u64,
+    unix_timestamp: i64,
+}
+
+async fn log_clock(rpc: &RpcClient) -> Result<ClockResult, anyhow::Error> {
+    let clock = get_clock(&rpc).await?;
+    out!("Clock");
+    out!("  slot: {}", clock.slot);
+    out!("  epoch_start_timestamp: {}", clock.epoch_start_timestamp);
+    out!("  epoch: {}", clock.epoch);
+    out!("  leader_schedule_epoch: {}", clock.leader_schedule_epoch);
+    out!("  unix_timestamp: {}", clock.unix_timestamp);
+    Ok(ClockResult {
+        slot: clock.slot,
+        epoch_start_timestamp: clock.epoch_start_timestamp,
+        epoch: clock.epoch,
+        leader_schedule_epoch: clock.leader_schedule_epoch,
+        unix_timestamp: clock.unix_timestamp,
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct ConfigResult {
+    admin: String,
+    bury_authority: String,
+    fee_collector: String,
+    last_boost: i64,
+    is_seeker_activation_enabled: String,
+}
+
+async fn log_config(rpc: &RpcClient) -> Result<ConfigResult, anyhow::Error> {
+    let config = get_config(&rpc).await?;
+    out!("Config");
+    out!("  admin: {}", config.admin);
+    out!("  bury_authority: {}", config.bury_authority);
+    out!("  fee_collector: {}", config.fee_collector);
+    out!("  last_boost: {}", config.last_boost);
+    out!(
+        "  is_seeker_activation_enabled: {}",
+        config.is_seeker_activation_enabled
+    );
+
+    Ok(ConfigResult {
+        admin: config.admin.to_string(),
+        bury_authority: config.bury_authority.to_string(),
+        fee_collector: config.fee_collector.to_string(),
+        last_boost: config.last_boost,
+        is_seeker_activation_enabled: config.is_seeker_activation_enabled.to_string(),
+    })
+}
+
+// deployed 只在 DIFF / SNAPSHOT_RECORD 读取了 Round 时提供
+#[derive(Debug, Serialize)]
+struct BoardResult {
+    round_id: u64,
+    start_slot: u64,
+    end_slot: u64,
+    slot: u64,
+    slots_remaining: u64,
+    deployed: Option<[u64; 25]>,
+}
+
+// FOLLOW 持续输出事件流，没有结果对象
+async fn log_board(rpc: &RpcClient) -> Result<Option<BoardResult>, anyhow::Error> {
+    if env_flag("FOLLOW") {
+        follow_board(rpc).await?;
+        return Ok(None);
+    }
+    let board = get_board(&rpc).await?;
+    let clock = get_clock(&rpc).await?;
+    print_board(board, &clock);
+    let mut result = BoardResult {
+        round_id: board.round_id,
+        start_slot: board.start_slot,
+        end_slot: board.end_slot,
+        slot: clock.slot,
+        slots_remaining: board.end_slot.saturating_sub(clock.slot),
+        deployed: None,
+    };
+
+    // 只有 DIFF 或 SNAPSHOT_RECORD 开启时才读取 Round 并记录快照，供 DIFF 对比使用
+    if !env_flag("DIFF") && !env_flag("SNAPSHOT_RECORD") {
+        return Ok(Some(result));
+    }
+    let round = get_round(rpc, board.round_id).await?;
+    result.deployed = Some(round.deployed);
+    let snapshot = BoardSnapshot {
+        timestamp: chain_now(),
+        slot: clock.slot,
+        round_id: round.id,
+        deployed: round.deployed,
+        end_slot: Some(board.end_slot),
+    };
+    if env_flag("DIFF") {
+        let history: Vec<BoardSnapshot> = read_board_snapshots()
+            .into_iter()
+            .filter(|s| s.round_id == snapshot.round_id && s.slot < snapshot.slot)
+            .collect();
+        // SNAPSHOT 指定本回合第 N 个快照（从 0 开始），默认取最近一个
+        let base = match std::env::var("SNAPSHOT").ok().and_then(|s| s.parse::<usize>().ok()) {
+            Some(idx) => history.get(idx).cloned(),
+            None => history.last().cloned(),
+        };
+        match base {
+            Some(base) => print_board_diff(&base, &snapshot),
+            None => out!(
+                "[warn] 本回合 (round={}) 没有可对比的历史快照，本次快照已保存，下次运行 DIFF 时生效。",
+                snapshot.round_id
+            ),
+        }
+    }
+    append_board_snapshot(&snapshot);
+    Ok(Some(result))
+}
+
+// 持续监听 board/round 变化（FOLLOW=true）：新回合、各格子入金、回合重置（开奖格子）。
+// OUTPUT=json 时每个事件输出一行 JSON（配合 QUIET=true 可得到纯净的数据流）
+async fn follow_board(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let json = std::env::var("OUTPUT").map(|o| o == "json").unwrap_or(false);
+    let interval_ms: u64 = std::env::var("FOLLOW_INTERVAL_MS")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(1000);
+    let emit = |event: serde_json::Value, text: String| {
+        if json {
+            out!("{}", event);
+        } else {
+            out!("[follow] {}", text);
+        }
+        let _ = io::stdout().flush();
+    };
+
+    let mut last: Option<(u64, [u64; 25])> = None;
+    loop {
+        let (board, clock) = tokio::join!(get_board(rpc), get_clock(rpc));
+        let (board, slot) = match (board, clock) {
+            (Ok(board), Ok(clock)) => (board, clock.slot),
+            (Err(e), _) | (_, Err(e)) => {
+                out!("[follow] ⚠️  读取 Board/Clock 失败: {:?}，稍后重试", e);
+                sleep(Duration::from_millis(interval_ms)).await;
+                continue;
+            }
+        };
+        let Ok(round) = get_round(rpc, board.round_id).await else {
+            sleep(Duration::from_millis(interval_ms)).await;
+            continue;
+        };
+
+        match last {
+            Some((last_round_id, _)) if last_round_id != board.round_id => {
+                // 上一回合已重置：读取开奖结果
+                if let Ok(prev) = get_round(rpc, last_round_id).await {
+                    let result = RoundResult::from_round(&prev);
+                    if let Some(result) = &result {
+                        append_round_result(result);
+                    }
+                    let winning_square = result.map(|r| r.winning_square);
+                    emit(
+                        serde_json::json!({
+                            "event": "round_reset",
+                            "round": last_round_id,
+                            "slot": slot,
+                            "winning_square": winning_square,
+                            "total_deployed": prev.total_deployed,
+                            "total_winnings": prev.total_winnings,
+                            "motherlode": prev.motherlode,
+                        }),
+                        format!(
+                            "round {} 结束：开奖格子 {}，总部署 {}",
+                            last_round_id,
+                            winning_square.map(|s| format!("#{}", s)).unwrap_or("未知".to_string()),
+                            fmt_sol(prev.total_deployed)
+                        ),
+                    );
+                }
+                emit(
+                    serde_json::json!({
+                        "event": "round_started",
+                        "round": board.round_id,
+                        "slot": slot,
+                        "start_slot": board.start_slot,
+                        "end_slot": board.end_slot,
+                        "deployed": round.deployed,
+                    }),
+                    format!("round {} 开始（end_slot {}）", board.round_id, board.end_slot),
+                );
+            }
+            Some((_, last_deployed)) => {
+                for (i, (total, before)) in round.deployed.iter().zip(last_deployed.iter()).enumerate() {
+                    if total > before {
+                        let amount = total - before;
+                        emit(
+                            serde_json::json!({
+                                "event": "deposit",
+                                "round": board.round_id,
+                                "slot": slot,
+                                "square": i,
+                                "amount": amount,
+                                "total": total,
+                            }),
+                            format!(
+                                "round {} #{} +{}（合计 {}）",
+                                board.round_id,
+                                i,
+                                fmt_sol(amount),
+                                fmt_sol(*total)
+                            ),
+                        );
+                    }
+                }
+            }
+            None => emit(
+                serde_json::json!({
+                    "event": "snapshot",
+                    "round": board.round_id,
+                    "slot": slot,
+                    "start_slot": board.start_slot,
+                    "end_slot": board.end_slot,
+                    "deployed": round.deployed,
+                }),
+                format!(
+                    "round {} 当前总部署 {}，开始监听...",
+                    board.round_id,
+                    fmt_sol(round.deployed.iter().sum())
+                ),
+            ),
+        }
+        // 有变化时记录快照，供归档导出
+        if last.map(|(id, deployed)| id != board.round_id || deployed != round.deployed).unwrap_or(true) {
+            append_board_snapshot(&BoardSnapshot {
+                timestamp: chain_now(),
+                slot,
+                round_id: round.id,
+                deployed: round.deployed,
+                end_slot: Some(board.end_slot),
+            });
+        }
+        last = Some((board.round_id, round.deployed));
+        sleep(Duration::from_millis(interval_ms)).await;
+    }
+}
+
+// ============ 新增：回合数据归档（导出 / 导入） ============
+
+const ROUND_RESULTS_FILE: &str = "ore.round_results.jsonl";
+
+// 已结束回合的开奖结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RoundResult {
+    round_id: u64,
+    winning_square: u64,
+    deployed: [u64; 25],
+    total_deployed: u64,
+    total_winnings: u64,
+    motherlode: u64,
+    top_miner: String,
+    top_miner_reward: u64,
+}
+
+impl RoundResult {
+    // slot_hash 尚未写入（回合未重置）时返回 None
+    fn from_round(round: &Round) -> Option<Self> {
+        if round.slot_hash == [0; 32] || round.slot_hash == [u8::MAX; 32] {
+            return None;
+        }
+        Some(Self {
+            round_id: round.id,
+            winning_square: get_winning_square(&round.slot_hash),
+            deployed: round.deployed,
+            total_deployed: round.total_deployed,
+            total_winnings: round.total_winnings,
+            motherlode: round.motherlode,
+            top_miner: round.top_miner.to_string(),
+            top_miner_reward: round.top_miner_reward,
+        })
+    }
+}
+
+fn append_round_result(result: &RoundResult) {
+    let Ok(line) = serde_json::to_string(result) else {
+        return;
+    };
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(ROUND_RESULTS_FILE))
+    {
+        let _ = writeln!(file, "{}", line);
+    }
+}
+
+// 按 round_id 去重（保留最后一条）并排序
+fn read_round_results() -> Vec<RoundResult> {
+    let mut by_round: std::collections::BTreeMap<u64, RoundResult> = std::collections::BTreeMap::new();
+    for result in fs::read_to_string(state_path(ROUND_RESULTS_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RoundResult>(line).ok())
+    {
+        by_round.insert(result.round_id, result);
+    }
+    by_round.into_values().collect()
+}
+
+// 归档格式（JSON Lines，ARCHIVE_FILE 以 .gz 结尾时经 gzip 压缩）：
+//   第一行 {"format":"ore-archive","version":1,"exported_at":<unix 秒>,"snapshots":N,"results":M}
+//   其后每行 {"type":"snapshot", ...BoardSnapshot}：timestamp / slot / round_id / deployed[25]（lamports）
+//        或 {"type":"result", ...RoundResult}：round_id / winning_square / deployed[25] / total_deployed /
+//           total_winnings / motherlode / top_miner / top_miner_reward（金额均为最小单位）
+const ARCHIVE_FORMAT: &str = "ore-archive";
+const ARCHIVE_VERSION: u32 = 1;
+
+fn archive_path() -> String {
+    std::env::var("ARCHIVE_FILE").unwrap_or(state_path("ore.archive.jsonl.gz"))
+}
+
+// 通过 gzip 命令压缩 / 解压（需要系统安装 gzip）
+fn run_gzip(args: &[&str], input: &[u8]) -> Result<Vec<u8>, anyhow::Error> {
+    let mut child = std::process::Command::new("gzip")
+        .args(args)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("无法运行 gzip（需要安装 gzip 命令）: {}", e))?;
+    let mut stdin = child.stdin.take().ok_or_else(|| anyhow::anyhow!("gzip stdin 不可用"))?;
+    let input = input.to_vec();
+    // 单独线程写入，避免输出缓冲区写满时互相等待
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+    if !output.status.success() {
+        return Err(anyhow::anyhow!("gzip 退出码 {:?}", output.status.code()));
+    }
+    Ok(output.stdout)
+}
+
+fn archive_export() -> Result<(), anyhow::Error> {
+    let snapshots = read_board_snapshots();
+    let results = read_round_results();
+    if snapshots.is_empty() && results.is_empty() {
+        return Err(CommandError::nothing_to_do("没有可导出的回合数据（board 快照 / 开奖结果均为空）").into());
+    }
+    let mut out = serde_json::json!({
+        "format": ARCHIVE_FORMAT,
+        "version": ARCHIVE_VERSION,
+        "exported_at": chain_now(),
+        "snapshots": snapshots.len(),
+        "results": results.len(),
+    })
+    .to_string();
+    out.push('\n');
+    for snapshot in snapshots.iter() {
+        let mut value = serde_json::to_value(snapshot)?;
+        value["type"] = serde_json::json!("snapshot");
+        out.push_str(&value.to_string());
+        out.push('\n');
+    }
+    for result in results.iter() {
+        let mut value = serde_json::to_value(result)?;
+        value["type"] = serde_json::json!("result");
+        out.push_str(&value.to_string());
+        out.push('\n');
+    }
+    let path = archive_path();
+    let bytes = if path.ends_with(".gz") {
+        run_gzip(&["-c"], out.as_bytes())?
+    } else {
+        out.into_bytes()
+    };
+    fs::write(&path, &bytes)?;
+    out!(
+        "[archive] ✅ 已导出 {}：{} 个快照，{} 个回合结果（{} 字节）",
+        path,
+        snapshots.len(),
+        results.len(),
+        bytes.len()
+    );
+    Ok(())
+}
+
+// 合并其他运营者导出的归档：快照按 (round_id, slot) 去重，结果按 round_id 去重；DRY_RUN 时只统计
+fn archive_import() -> Result<(), anyhow::Error> {
+    let path = archive_path();
+    let bytes = fs::read(&path).map_err(|e| CommandError::config(format!("无法读取 {}: {}", path, e)))?;
+    let bytes = if path.ends_with(".gz") {
+        run_gzip(&["-dc"], &bytes)?
+    } else {
+        bytes
+    };
+    let content = String::from_utf8_lossy(&bytes);
+    let mut lines = content.lines();
+    let header: serde_json::Value = lines
+        .next()
+        .and_then(|line| serde_json::from_str(line).ok())
+        .unwrap_or_default();
+    if header["format"] != ARCHIVE_FORMAT {
+        return Err(CommandError::config(format!("{} 不是 ore 归档文件", path)).into());
+    }
+    if header["version"].as_u64().unwrap_or(0) > ARCHIVE_VERSION as u64 {
+        return Err(CommandError::config(format!(
+            "归档版本 {} 高于当前支持的版本 {}",
+            header["version"], ARCHIVE_VERSION
+        ))
+        .into());
+    }
+
+    let mut snapshots = read_board_snapshots();
+    let mut snapshot_keys: std::collections::HashSet<(u64, u64)> =
+        snapshots.iter().map(|s| (s.round_id, s.slot)).collect();
+    let existing_results = read_round_results();
+    let mut result_rounds: std::collections::HashSet<u64> =
+        existing_results.iter().map(|r| r.round_id).collect();
+    let mut new_results = vec![];
+    let (mut invalid, mut duplicate) = (0usize, 0usize);
+    let snapshots_before = snapshots.len();
+    for line in lines.filter(|l| !l.trim().is_empty()) {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+            invalid += 1;
+            continue;
+        };
+        match value["type"].as_str() {
+            Some("snapshot") => match serde_json::from_value::<BoardSnapshot>(value) {
+                Ok(snapshot) if snapshot_keys.insert((snapshot.round_id, snapshot.slot)) => {
+                    snapshots.push(snapshot)
+                }
+                Ok(_) => duplicate += 1,
+                Err(_) => invalid += 1,
+            },
+            Some("result") => match serde_json::from_value::<RoundResult>(value) {
+                Ok(result) if result_rounds.insert(result.round_id) => new_results.push(result),
+                Ok(_) => duplicate += 1,
+                Err(_) => invalid += 1,
+            },
+            _ => invalid += 1,
+        }
+    }
+    let new_snapshots = snapshots.len() - snapshots_before;
+    out!(
+        "[archive] {}：新增 {} 个快照，{} 个回合结果（重复 {}，无法解析 {}）",
+        path,
+        new_snapshots,
+        new_results.len(),
+        duplicate,
+        invalid
+    );
+    if new_snapshots == 0 && new_results.is_empty() {
+        return Err(CommandError::nothing_to_do("归档中没有新的数据").into());
+    }
+    if env_flag("DRY_RUN") {
+        out!("[archive] DRY_RUN：未写入本地数据");
+        return Ok(());
+    }
+
+    if new_snapshots > 0 {
+        snapshots.sort_by_key(|s| (s.round_id, s.slot));
+        let mut out = String::new();
+        for snapshot in snapshots.iter() {
+            out.push_str(&serde_json::to_string(snapshot)?);
+            out.push('\n');
+        }
+        let tmp_path = format!("{}.tmp", state_path(BOARD_SNAPSHOT_FILE));
+        fs::write(&tmp_path, out)?;
+        fs::rename(&tmp_path, state_path(BOARD_SNAPSHOT_FILE))?;
+    }
+    for result in new_results.iter() {
+        append_round_result(result);
+    }
+    out!("[archive] ✅ 已合并到 {} / {}", state_path(BOARD_SNAPSHOT_FILE), state_path(ROUND_RESULTS_FILE));
+    Ok(())
+}
+
+// ============ 新增：指标（Prometheus 抓取 / Pushgateway / OTLP） ============
+
+#[derive(Debug, Clone, Copy)]
+enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+struct Metric {
+    name: &'static str,
+    help: &'static str,
+    kind: MetricKind,
+    value: std::sync::atomic::AtomicU64,
+}
+
+impl Metric {
+    const fn new(name: &'static str, help: &'static str, kind: MetricKind) -> Self {
+        Self {
+            name,
+            help,
+            kind,
+            value: std::sync::atomic::AtomicU64::new(0),
+        }
+    }
+
+    fn inc_by(&self, v: u64) {
+        self.value.fetch_add(v, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn set(&self, v: u64) {
+        self.value.store(v, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    fn get(&self) -> u64 {
+        self.value.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+static METRIC_DEPLOYS: Metric = Metric::new("ore_deploys_total", "Successful deploy transactions", MetricKind::Counter);
+static METRIC_DEPLOY_FAILURES: Metric = Metric::new("ore_deploy_failures_total", "Failed deploy attempts", MetricKind::Counter);
+static METRIC_DEPLOYED_LAMPORTS: Metric = Metric::new("ore_deployed_lamports_total", "Lamports spent on deploys", MetricKind::Counter);
+static METRIC_CHECKPOINTS: Metric = Metric::new("ore_checkpoints_total", "Checkpoints recorded", MetricKind::Counter);
+static METRIC_REWARD_LAMPORTS: Metric = Metric::new("ore_reward_lamports_total", "SOL rewards settled by checkpoints (lamports)", MetricKind::Counter);
+static METRIC_REWARD_ORE: Metric = Metric::new("ore_reward_ore_grams_total", "ORE rewards settled by checkpoints (smallest unit)", MetricKind::Counter);
+static METRIC_CLAIMS: Metric = Metric::new("ore_claims_total", "Claim transactions", MetricKind::Counter);
+static METRIC_ROUND_ID: Metric = Metric::new("ore_round_id", "Current round id seen by the bot", MetricKind::Gauge);
+static METRIC_DECISION_LATENCY_MS: Metric = Metric::new("ore_decision_latency_ms", "Latency of the last deploy decision (ms)", MetricKind::Gauge);
+static METRIC_PHASE_FETCH_MS: Metric = Metric::new("ore_phase_fetch_ms", "Last fetch phase duration (ms)", MetricKind::Gauge);
+static METRIC_PHASE_DECIDE_MS: Metric = Metric::new("ore_phase_decide_ms", "Last decide phase duration (ms)", MetricKind::Gauge);
+static METRIC_PHASE_BUILD_MS: Metric = Metric::new("ore_phase_build_ms", "Last build phase duration (ms)", MetricKind::Gauge);
+static METRIC_PHASE_SIGN_MS: Metric = Metric::new("ore_phase_sign_ms", "Last sign phase duration (ms)", MetricKind::Gauge);
+static METRIC_PHASE_SEND_MS: Metric = Metric::new("ore_phase_send_ms", "Last send phase duration (ms)", MetricKind::Gauge);
+
+static METRICS: [&Metric; 14] = [
+    &METRIC_DEPLOYS,
+    &METRIC_DEPLOY_FAILURES,
+    &METRIC_DEPLOYED_LAMPORTS,
+    &METRIC_CHECKPOINTS,
+    &METRIC_REWARD_LAMPORTS,
+    &METRIC_REWARD_ORE,
+    &METRIC_CLAIMS,
+    &METRIC_ROUND_ID,
+    &METRIC_DECISION_LATENCY_MS,
+    &METRIC_PHASE_FETCH_MS,
+    &METRIC_PHASE_DECIDE_MS,
+    &METRIC_PHASE_BUILD_MS,
+    &METRIC_PHASE_SIGN_MS,
+    &METRIC_PHASE_SEND_MS,
+];
+
+// 奖励事件同步计入指标
+fn record_reward_event_metrics(event: &RewardEvent) {
+    match event.event.as_str() {
+        "deploy" => {
+            METRIC_DEPLOYS.inc_by(1);
+            METRIC_DEPLOYED_LAMPORTS.inc_by(event.cost_lamports.unwrap_or(0));
+        }
+        "checkpoint" => {
+            METRIC_CHECKPOINTS.inc_by(1);
+            METRIC_REWARD_LAMPORTS.inc_by(event.delta_sol_lamports.unwrap_or(0));
+            METRIC_REWARD_ORE.inc_by(
+                event.delta_rewards_ore.unwrap_or(0) + event.delta_refined_ore.unwrap_or(0),
+            );
+        }
+        "claim" => METRIC_CLAIMS.inc_by(1),
+        _ => {}
+    }
+}
+
+// Prometheus 文本格式
+fn render_prometheus_metrics() -> String {
+    let mut out = String::new();
+    for metric in METRICS.iter() {
+        let kind = match metric.kind {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        };
+        out.push_str(&format!(
+            "# HELP {} {}\n# TYPE {} {}\n{} {}\n",
+            metric.name,
+            metric.help,
+            metric.name,
+            kind,
+            metric.name,
+            metric.get()
+        ));
+    }
+    out
+}
+
+// OTLP/HTTP JSON（ExportMetricsServiceRequest）
+fn render_otlp_metrics(instance: &str) -> serde_json::Value {
+    let now_nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos()
+        .to_string();
+    let metrics: Vec<serde_json::Value> = METRICS
+        .iter()
+        .map(|metric| {
+            let data_points = serde_json::json!([{
+                "asInt": metric.get().to_string(),
+                "timeUnixNano": now_nanos,
+            }]);
+            match metric.kind {
+                MetricKind::Counter => serde_json::json!({
+                    "name": metric.name,
+                    "description": metric.help,
+                    "sum": {
+                        "aggregationTemporality": 2,
+                        "isMonotonic": true,
+                        "dataPoints": data_points,
+                    },
+                }),
+                MetricKind::Gauge => serde_json::json!({
+                    "name": metric.name,
+                    "description": metric.help,
+                    "gauge": { "dataPoints": data_points },
+                }),
+            }
+        })
+        .collect();
+    serde_json::json!({
+        "resourceMetrics": [{
+            "resource": {
+                "attributes": [
+                    { "key": "service.name", "value": { "stringValue": "ore-bot" } },
+                    { "key": "service.instance.id", "value": { "stringValue": instance } },
+                ],
+            },
+            "scopeMetrics": [{
+                "scope": { "name": "ore" },
+                "metrics": metrics,
+            }],
+        }],
+    })
+}
+
+// 推送到 METRICS_PUSHGATEWAY_URL 和/或 METRICS_OTLP_ENDPOINT（均未设置时不做任何事）
+async fn push_metrics(instance: &str) {
+    let client = solana_client::client_error::reqwest::Client::new();
+    if let Ok(url) = std::env::var("METRICS_PUSHGATEWAY_URL") {
+        let url = format!("{}/metrics/job/ore_bot/instance/{}", url.trim_end_matches('/'), instance);
+        let result = client
+            .post(&url)
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .timeout(Duration::from_secs(10))
+            .body(render_prometheus_metrics())
+            .send()
+            .await;
+        match result {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => out!("[metrics] ⚠️  Pushgateway 返回 HTTP {}", response.status()),
+            Err(e) => out!("[metrics] ⚠️  推送 Pushgateway 失败: {}", e),
+        }
+    }
+    if let Ok(endpoint) = std::env::var("METRICS_OTLP_ENDPOINT") {
+        let url = format!("{}/v1/metrics", endpoint.trim_end_matches('/'));
+        let result = client
+            .post(&url)
+            .header("Content-Type", "application/json")
+            .timeout(Duration::from_secs(10))
+            .body(render_otlp_metrics(instance).to_string())
+            .send()
+            .await;
+        match result {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => out!("[metrics] ⚠️  OTLP 端点返回 HTTP {}", response.status()),
+            Err(e) => out!("[metrics] ⚠️  OTLP 导出失败: {}", e),
+        }
+    }
+}
+
+// 仅在本进程启动过导出器（长时间运行的命令）时才在退出前推送，避免一次性命令用 0 覆盖同名指标
+static METRICS_EXPORTERS_STARTED: std::sync::atomic::AtomicBool =
+    std::sync::atomic::AtomicBool::new(false);
+
+fn metrics_push_configured() -> bool {
+    std::env::var("METRICS_PUSHGATEWAY_URL").is_ok() || std::env::var("METRICS_OTLP_ENDPOINT").is_ok()
+}
+
+// 长时间运行的命令启动：METRICS_BIND 提供 /metrics 抓取端点；配置了推送时每 METRICS_PUSH_INTERVAL_SECS 秒推送一次
+fn start_metrics_exporters(instance: String) {
+    METRICS_EXPORTERS_STARTED.store(true, std::sync::atomic::Ordering::Relaxed);
+    if let Ok(bind) = std::env::var("METRICS_BIND") {
+        tokio::spawn(async move {
+            if let Err(e) = serve_metrics(&bind).await {
+                out!("[metrics] ⚠️  /metrics 端点启动失败 ({}): {}", bind, e);
+            }
+        });
+    }
+    if metrics_push_configured() {
+        let interval_secs = std::env::var("METRICS_PUSH_INTERVAL_SECS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(15)
+            .max(1);
+        tokio::spawn(async move {
+            loop {
+                sleep(Duration::from_secs(interval_secs)).await;
+                push_metrics(&instance).await;
+            }
+        });
+    }
+}
+
+async fn serve_metrics(bind: &str) -> Result<(), anyhow::Error> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind(bind).await?;
+    out!("[metrics] Prometheus 抓取端点：http://{}/metrics", bind);
+    loop {
+        let Ok((mut stream, _)) = listener.accept().await else {
+            continue;
+        };
+        // 每个连接单独处理，慢连接（最多等待 5 秒读取请求）不会阻塞其他抓取
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 4096];
+            let Ok(Ok(n)) = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await else {
+                return;
+            };
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let (status, body) = if request.starts_with("GET /metrics") {
+                ("200 OK", render_prometheus_metrics())
+            } else {
+                ("404 Not Found", "not found\n".to_string())
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        });
+    }
+}
+
+// ============ 新增：回测与参数优化 ============
+
+// 回测收益模型（简化，仅计 SOL，不计 ORE 奖励）：
+// - 部署时扣除 1% 管理费；
+// - 未中奖格子的 SOL 扣除 10% 进入金库后，按中奖格子内的份额分配给中奖者，中奖者同时取回本金
+const BACKTEST_ADMIN_FEE: f64 = 0.01;
+const BACKTEST_VAULT_FEE: f64 = 0.10;
+
+// 一个可回测的回合：决策时看到的分布（最后一个快照，缺失时用最终分布）、最终分布与开奖格子
+#[derive(Debug, Clone)]
+struct BacktestRound {
+    round_id: u64,
+    decision: [u64; 25],
+    settled: [u64; 25],
+    winning_square: usize,
+}
+
+fn load_backtest_rounds() -> Vec<BacktestRound> {
+    let mut last_snapshot: HashMap<u64, BoardSnapshot> = HashMap::new();
+    for snapshot in read_board_snapshots() {
+        let keep = last_snapshot
+            .get(&snapshot.round_id)
+            .map(|s| s.slot < snapshot.slot)
+            .unwrap_or(true);
+        if keep {
+            last_snapshot.insert(snapshot.round_id, snapshot);
+        }
+    }
+    read_round_results()
+        .into_iter()
+        .filter(|r| (r.winning_square as usize) < 25)
+        .map(|r| BacktestRound {
+            round_id: r.round_id,
+            decision: last_snapshot
+                .get(&r.round_id)
+                .map(|s| s.deployed)
+                .unwrap_or(r.deployed),
+            settled: r.deployed,
+            winning_square: r.winning_square as usize,
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct StrategyParams {
+    algorithm: SquareSelectionAlgorithm,
+    // 随机类算法的种子（与回合号组合）
+    seed: u64,
+    threshold_sol: f64,
+    min_squares: usize,
+    pick_squares: usize,
+    amount_lamports: u64,
+    optimized: OptimizedThreshold,
+    dynamic_pick: Option<DynamicPick>,
+}
+
+impl StrategyParams {
+    fn label(&self) -> String {
+        let coefficients = if self.algorithm == SquareSelectionAlgorithm::Optimized {
+            format!(" slope={} offset={}", self.optimized.slope, self.optimized.offset_sol)
+        } else {
+            String::new()
+        };
+        let pick = match &self.dynamic_pick {
+            Some(dynamic) if !self.algorithm.is_random() => dynamic.describe(),
+            _ => self.pick_squares.to_string(),
+        };
+        format!(
+            "{} threshold={} min={} pick={} amount={}{}",
+            self.algorithm.name(),
+            self.threshold_sol,
+            self.min_squares,
+            pick,
+            lamports_to_sol(self.amount_lamports),
+            coefficients
+        )
+    }
+}
+
+// 最优化算法的动态阈值：slope * 本轮总部署 - offset（SOL），默认 0.036 / 0.005，
+// 可由 OPTIMIZED_SLOPE / OPTIMIZED_OFFSET_SOL 覆盖，calibrate_optimized 用历史回合拟合并给出置信区间
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OptimizedThreshold {
+    slope: f64,
+    offset_sol: f64,
+}
+
+impl OptimizedThreshold {
+    const DEFAULT: OptimizedThreshold = OptimizedThreshold {
+        slope: 0.036,
+        offset_sol: 0.005,
+    };
+
+    fn from_env() -> Self {
+        Self {
+            slope: env_f64("OPTIMIZED_SLOPE", Self::DEFAULT.slope),
+            offset_sol: env_f64("OPTIMIZED_OFFSET_SOL", Self::DEFAULT.offset_sol),
+        }
+    }
+
+    fn threshold(&self, total_sol: f64) -> f64 {
+        total_sol * self.slope - self.offset_sol
+    }
+}
+
+// 动态选格数量（PICK_DYNAMIC=true，仅阈值 / 最优化算法）：按排序依次加入候选格子，
+// 只要新增格子的边际 EV 为正就继续，数量限制在 PICK_MIN ~ PICK_MAX（默认 1 ~ PICK_SQUARES）之间，
+// 且总花费不超过 ROUND_BUDGET_SOL。边际 EV 按回测同样的费用模型计算（1/25 中奖概率），不计 ORE 奖励，
+// 包含新格子的本金在其他已选格子中奖时作为失败池带来的收益
+#[derive(Debug, Clone, Copy)]
+struct DynamicPick {
+    min: usize,
+    max: usize,
+    budget_lamports: Option<u64>,
+}
+
+impl DynamicPick {
+    fn from_env(pick_squares: usize) -> Option<Self> {
+        if !env_flag("PICK_DYNAMIC") {
+            return None;
+        }
+        let read = |key: &str, default: usize| {
+            std::env::var(key)
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(default)
+        };
+        let min = read("PICK_MIN", 1);
+        Some(Self {
+            min,
+            max: read("PICK_MAX", pick_squares).max(min),
+            budget_lamports: std::env::var("ROUND_BUDGET_SOL")
+                .ok()
+                .and_then(|s| s.parse::<f64>().ok())
+                .map(sol_to_lamports),
+        })
+    }
+
+    fn describe(&self) -> String {
+        match self.budget_lamports {
+            Some(budget) => format!("dynamic({}-{}, budget {})", self.min, self.max, lamports_to_sol(budget)),
+            None => format!("dynamic({}-{})", self.min, self.max),
+        }
+    }
 
-                        let current_slot_for_check = match get_clock(rpc).await {
-                            Ok(c) => c.slot,
-                            Err(e) => {
-                                println!("[auto] 警告：读取 Clock 失败（检查回合结束）: {:?}，跳过本次部署", e);
-                                continue;
-                            }
-                        };
+    // ranked 为按优先级排序的候选格子，返回应选取的前几个
+    fn count(&self, ranked: &[(usize, f64)], deployed: &[u64; 25], amount_lamports: u64) -> usize {
+        let affordable = match self.budget_lamports {
+            Some(budget) if amount_lamports > 0 => (budget / amount_lamports) as usize,
+            _ => usize::MAX,
+        };
+        let limit = self.max.min(affordable).min(ranked.len());
+        let stake = amount_lamports as f64 * (1.0 - BACKTEST_ADMIN_FEE);
+        let total: f64 = deployed.iter().sum::<u64>() as f64;
+        let mut count = 0;
+        while count < limit {
+            let square = ranked[count].0;
+            let mine = deployed[square] as f64 + stake;
+            // 新格子中奖：取回本金 + 按份额分失败池（其他格子 + 我在已选格子的本金）
+            let losing_pool = total - deployed[square] as f64 + stake * count as f64;
+            let own_win = stake + losing_pool * (1.0 - BACKTEST_VAULT_FEE) * stake / mine;
+            // 已选格子中奖时，新格子的本金进入失败池
+            let spillover: f64 = ranked[..count]
+                .iter()
+                .map(|(i, _)| stake * (1.0 - BACKTEST_VAULT_FEE) * stake / (deployed[*i] as f64 + stake))
+                .sum();
+            let marginal_ev = (own_win + spillover) / 25.0 - amount_lamports as f64;
+            if count >= self.min && marginal_ev <= 0.0 {
+                break;
+            }
+            count += 1;
+        }
+        count
+    }
+}
 
-                        // 检查轮次是否即将结束
-                        let slots_remaining = if latest_board.end_slot > current_slot_for_check {
-                            latest_board.end_slot - current_slot_for_check
-                        } else {
-                            0
-                        };
+// 选取前几个候选格子：PICK_DYNAMIC 开启时按边际 EV 决定，否则固定 PICK_SQUARES
+fn pick_count(
+    dynamic_pick: &Option<DynamicPick>,
+    pick_squares: usize,
+    ranked: &[(usize, f64)],
+    deployed: &[u64; 25],
+    amount_lamports: u64,
+) -> usize {
+    match dynamic_pick {
+        Some(dynamic) => dynamic.count(ranked, deployed, amount_lamports),
+        None => pick_squares,
+    }
+}
 
-                        // 定义两个阈值：
-                        // - danger_zone_slots (约6秒): 在这个时间内，只进行单次快速提交，不重试
-                        // - buffer_slots (约2秒): 这个时间内不再尝试提交
-                        let danger_zone_slots = 15u64;  // ~6秒 (15 * 0.4秒)
-                        let buffer_slots = 5u64;        // ~2秒 (5 * 0.4秒)
+// 随机选格使用的伪随机数生成器（SplitMix64），同一种子 + 回合号得到相同结果，便于回测复现
+struct SplitMix64(u64);
 
-                        if slots_remaining <= buffer_slots {
-                            println!("[auto] ⚠️  轮次即将结束：剩余 {} slots (~{:.1}s，< {:.1}s 缓冲)，跳过本次部署以避免交易过期",
-                                slots_remaining, slots_remaining as f64 * 0.4, buffer_slots as f64 * 0.4);
-                            continue;
-                        }
+impl SplitMix64 {
+    fn for_round(seed: u64, round_id: u64) -> Self {
+        SplitMix64(seed ^ round_id.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+    }
 
-                        if latest_board.end_slot <= current_slot_for_check {
-                            println!("[auto] ⚠️  当前回合已结束，跳过本次部署");
-                            continue;
-                        }
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
 
-                        // 判断是否处于危险区间（轮次剩余时间很短）
-                        let is_danger_zone = slots_remaining <= danger_zone_slots;
-                        if is_danger_zone {
-                            println!("[auto] ⚠️  进入危险区间：轮次剩余 {:.1}s (~{} slots)，将进行单次快速提交（不重试）",
-                                slots_remaining as f64 * 0.4, slots_remaining);
-                        }
-                        
-                        let mut squares = [false; 25];
-                        for &i in &picked {
-                            if i < 25 {
-                                squares[i] = true;
-                            }
-                        }
+    // [0, 1) 均匀分布
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
 
-                        // 部署前记录关键信息
-                        println!("[auto] 准备部署到轮次 {}，剩余时间约 {:.2}s，格子: {:?}",
-                            latest_board.round_id,
-                            (latest_board.end_slot as f64 - current_slot_for_check as f64) * 0.4,
-                            picked);
+// RNG_SEED 未设置时使用进程启动时间作为种子（整个进程内保持不变）
+fn rng_seed() -> u64 {
+    static SEED: std::sync::OnceLock<u64> = std::sync::OnceLock::new();
+    *SEED.get_or_init(|| {
+        std::env::var("RNG_SEED")
+            .ok()
+            .and_then(|s| s.trim().parse::<u64>().ok())
+            .unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos() as u64
+            })
+    })
+}
 
-                        let ix = ore_api::sdk::deploy(
-                            payer.pubkey(),
-                            payer.pubkey(),
-                            amount_lamports,
-                            latest_board.round_id,
-                            squares,
-                        );
+// 按权重不放回抽取 n 个格子
+fn weighted_sample(rng: &mut SplitMix64, mut candidates: Vec<(usize, f64)>, n: usize) -> Vec<usize> {
+    let mut picked = vec![];
+    while picked.len() < n && !candidates.is_empty() {
+        let total: f64 = candidates.iter().map(|(_, w)| w).sum();
+        let mut target = rng.next_f64() * total;
+        let mut chosen = candidates.len() - 1;
+        for (pos, (_, w)) in candidates.iter().enumerate() {
+            if target < *w {
+                chosen = pos;
+                break;
+            }
+            target -= w;
+        }
+        picked.push(candidates.swap_remove(chosen).0);
+    }
+    picked
+}
 
-                        // 改进错误处理：不 panic，记录错误并继续
-                        let this_round_cost = (amount_lamports as u128) * (picked.len() as u128);
-                        let this_round_cost_u64 =
-                            this_round_cost.min(u64::MAX as u128) as u64;
+// 与 auto_mine 相同的选格规则：阈值算法用固定阈值，最优化算法用 slope * 总和 - offset（默认 0.036 / 0.005），
+// 随机类算法按种子 + 回合号抽取 PICK_SQUARES 个格子
+fn select_squares(params: &StrategyParams, round_id: u64, deployed: &[u64; 25]) -> Option<Vec<usize>> {
+    select_squares_where(params, round_id, deployed, |_| true)
+}
 
-                        // 根据轮次剩余时间选择提交策略
-                        // 危险区间（剩余时间少于6秒）：单次快速提交，不重试
-                        // 安全区间：有重试的提交
-                        let submit_result = if is_danger_zone {
-                            println!("[auto] 💨 危险区间：采用快速单次提交！");
-                            submit_transaction_danger_zone_no_retry(rpc, payer, &[ix]).await
-                        } else {
-                            submit_transaction(rpc, payer, &[ix]).await
-                        };
+// allow 额外过滤候选格子（如流入速度过快的格子）
+fn select_squares_where(
+    params: &StrategyParams,
+    round_id: u64,
+    deployed: &[u64; 25],
+    allow: impl Fn(usize) -> bool,
+) -> Option<Vec<usize>> {
+    let squares: Vec<(usize, f64)> = deployed
+        .iter()
+        .enumerate()
+        .map(|(i, &lamports)| (i, lamports_to_sol(lamports)))
+        .collect();
+    if params.algorithm.is_random() {
+        let weighted = params.algorithm == SquareSelectionAlgorithm::WeightedRandom;
+        let candidates: Vec<(usize, f64)> = squares
+            .into_iter()
+            .filter(|(i, _)| allow(*i))
+            .map(|(i, v)| (i, if weighted { 1.0 / (v + 0.001) } else { 1.0 }))
+            .collect();
+        let mut rng = SplitMix64::for_round(params.seed, round_id);
+        let picked = weighted_sample(&mut rng, candidates, params.pick_squares);
+        return (!picked.is_empty()).then_some(picked);
+    }
+    let threshold = if params.algorithm == SquareSelectionAlgorithm::Optimized {
+        let total: f64 = squares.iter().map(|(_, v)| v).sum();
+        params.optimized.threshold(total)
+    } else {
+        params.threshold_sol
+    };
+    let mut candidates: Vec<(usize, f64)> = squares
+        .into_iter()
+        .filter(|(_, v)| *v < threshold)
+        .filter(|(i, _)| allow(*i))
+        .collect();
+    if candidates.len() < params.min_squares {
+        return None;
+    }
+    candidates.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+    let take = pick_count(&params.dynamic_pick, params.pick_squares, &candidates, deployed, params.amount_lamports);
+    let picked: Vec<usize> = candidates.into_iter().take(take).map(|(i, _)| i).collect();
+    (!picked.is_empty()).then_some(picked)
+}
 
-                        match submit_result {
-                            Ok(sig) => {
-                                println!("[auto] ✅ 部署成功！交易签名: {}", sig);
-                                println!("[auto] 本次部署花费: {:.6} SOL ({} 个格子 × {:.6} SOL/格子)",
-                                    lamports_to_sol(this_round_cost_u64),
-                                    picked.len(),
-                                    lamports_to_sol(amount_lamports));
-                                total_spent += this_round_cost;
-                                // 只有成功部署后，才标记为已处理，后续等待下一轮
-                                processed_round = Some(latest_board.round_id);
-                                // 保存本轮部署信息，用于后续循环显示
-                                round_deployment_info =
-                                    Some((latest_board.round_id, picked.len(), this_round_cost_u64));
+#[derive(Debug, Clone, Copy, Default)]
+struct BacktestStats {
+    rounds: usize,
+    played: usize,
+    wins: usize,
+    cost: u64,
+    payout: u64,
+}
 
-                                let algo_label = match algorithm {
-                                    SquareSelectionAlgorithm::Threshold => "threshold",
-                                    SquareSelectionAlgorithm::Optimized => "optimized",
-                                };
-                                append_reward_log(&format!(
-                                    "round={} event=deploy algorithm={} squares={} cost_sol={:.6} cost_lamports={} tx={}",
-                                    latest_board.round_id,
-                                    algo_label,
-                                    picked.len(),
-                                    lamports_to_sol(this_round_cost_u64),
-                                    this_round_cost_u64,
-                                    sig
-                                ));
+impl BacktestStats {
+    fn pnl_sol(&self) -> f64 {
+        lamports_to_sol(self.payout) - lamports_to_sol(self.cost)
+    }
 
-                                // 写入持久化记录（避免同轮次重复部署）
-                                write_last_deployed_round(latest_board.round_id);
+    fn roi(&self) -> f64 {
+        if self.cost == 0 {
+            0.0
+        } else {
+            self.pnl_sol() / lamports_to_sol(self.cost)
+        }
+    }
+}
 
-                                // 输出收益信息
-                                if let Ok(miner) = get_miner(rpc, payer.pubkey()).await {
-                                    println!(
-                                        "[auto] 累计花费 {:.6} SOL，当前可领 ORE: {} ORE，SOL: {:.6}",
-                                        lamports_to_sol(total_spent as u64),
-                                        amount_to_ui_amount(miner.rewards_ore + miner.refined_ore, TOKEN_DECIMALS),
-                                        lamports_to_sol(miner.rewards_sol),
-                                    );
-                                }
-                                println!("[auto] 本轮已部署完成，等待下一轮...");
-                            }
-                            Err(e) => {
-                                println!("[auto] ⚠️  部署失败: {:?}", e);
-                                println!("[auto] 可能原因：Round 账户数据无效、账户未初始化、或网络问题。将重试。");
-                                // 不设置 processed_round，下次循环继续尝试
-                                // 重要：使用 latest_board.round_id 而非 board.round_id，确保轮次一致
-                            }
-                        }
-                } else {
-                    // 未选中任何格子，继续尝试
-                    // 注意：不设置 processed_round，下次循环继续尝试读取和判定
+fn backtest(params: &StrategyParams, rounds: &[BacktestRound]) -> BacktestStats {
+    let mut stats = BacktestStats {
+        rounds: rounds.len(),
+        ..Default::default()
+    };
+    // 与 auto_mine 一致地应用 SQUARE_PIN / SQUARE_EXCLUDE
+    let constraints = SquareConstraints::from_env();
+    for round in rounds {
+        let Some(picked) = select_squares(params, round.round_id, &round.decision).and_then(|p| constraints.apply_picks(p)) else {
+            continue;
+        };
+        let stake = (params.amount_lamports as f64 * (1.0 - BACKTEST_ADMIN_FEE)) as u64;
+        let mut settled = round.settled;
+        for &i in picked.iter() {
+            settled[i] += stake;
+        }
+        stats.played += 1;
+        stats.cost += params.amount_lamports * picked.len() as u64;
+        if picked.contains(&round.winning_square) {
+            stats.wins += 1;
+            let losing_pool: u64 = settled
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| *i != round.winning_square)
+                .map(|(_, v)| *v)
+                .sum();
+            let share = stake as f64 / settled[round.winning_square] as f64;
+            let winnings = losing_pool as f64 * (1.0 - BACKTEST_VAULT_FEE) * share;
+            stats.payout += stake + winnings as u64;
+        }
+    }
+    stats
+}
+
+fn print_backtest_stats(label: &str, stats: &BacktestStats) {
+    out!(
+        "  {}: rounds={} played={} wins={} cost={} payout={} pnl={} roi={:+.2}%",
+        label,
+        stats.rounds,
+        stats.played,
+        stats.wins,
+        fmt_sol(stats.cost),
+        fmt_sol(stats.payout),
+        fmt_sol_signed(stats.payout as i64 - stats.cost as i64),
+        stats.roi() * 100.0
+    );
+}
+
+// 当前配置参数（THRESHOLD_SOL / MIN_SQUARES_REQUIRED / PICK_SQUARES / AMOUNT，算法由 ALGORITHM 决定，随机种子 RNG_SEED）
+fn strategy_params_from_env() -> StrategyParams {
+    let (amount_lamports, threshold_sol, min_squares, pick_squares, _) = read_auto_params_from_env();
+    StrategyParams {
+        algorithm: SquareSelectionAlgorithm::from_env(),
+        seed: rng_seed(),
+        threshold_sol,
+        min_squares,
+        pick_squares,
+        amount_lamports,
+        optimized: OptimizedThreshold::from_env(),
+        dynamic_pick: DynamicPick::from_env(pick_squares),
+    }
+}
+
+fn load_backtest_rounds_or_err() -> Result<Vec<BacktestRound>, anyhow::Error> {
+    let rounds = load_backtest_rounds();
+    if rounds.is_empty() {
+        return Err(CommandError::nothing_to_do(format!(
+            "没有历史回合数据（{}），请先运行 board FOLLOW=true / auto_mine 或 archive_import",
+            state_path(ROUND_RESULTS_FILE)
+        ))
+        .into());
+    }
+    Ok(rounds)
+}
+
+fn backtest_command() -> Result<(), anyhow::Error> {
+    let rounds = load_backtest_rounds_or_err()?;
+    let params = strategy_params_from_env();
+    if params.amount_lamports == 0 {
+        return Err(CommandError::config("AMOUNT/AMOUNT_SOL 未设置或为 0").into());
+    }
+    out!("Backtest ({} rounds)", rounds.len());
+    out!("  params: {}", params.label());
+    print_backtest_stats("result", &backtest(&params, &rounds));
+    // 同样格子数 / 金额的随机选格作为基准（种子 RNG_SEED）
+    if !params.algorithm.is_random() {
+        out!("  baseline (seed={}):", params.seed);
+        for algorithm in [SquareSelectionAlgorithm::Random, SquareSelectionAlgorithm::WeightedRandom] {
+            let baseline = StrategyParams { algorithm, ..params };
+            print_backtest_stats(algorithm.name(), &backtest(&baseline, &rounds));
+        }
+    }
+    Ok(())
+}
+
+// 用历史回合拟合最优化算法的系数：决策时刻获胜格子的部署量 ≈ slope * 总部署 + intercept（最小二乘），
+// 阈值公式为 slope * 总和 - offset，因此 offset = -intercept。打印 95% 置信区间，
+// 并检查当前配置（OPTIMIZED_SLOPE / OPTIMIZED_OFFSET_SOL）是否落在区间内；设置了 AMOUNT 时对比两组系数的回测结果
+fn calibrate_optimized() -> Result<(), anyhow::Error> {
+    let rounds = load_backtest_rounds_or_err()?;
+    let points: Vec<(f64, f64)> = rounds
+        .iter()
+        .map(|r| {
+            let total = lamports_to_sol(r.decision.iter().sum());
+            (total, lamports_to_sol(r.decision[r.winning_square]))
+        })
+        .filter(|(total, _)| *total > 0.0)
+        .collect();
+    let n = points.len() as f64;
+    if points.len() < 3 {
+        return Err(CommandError::nothing_to_do(format!(
+            "有效历史回合不足（{}），至少需要 3 个",
+            points.len()
+        ))
+        .into());
+    }
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let sxx: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let sxy: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let syy: f64 = points.iter().map(|(_, y)| (y - mean_y).powi(2)).sum();
+    if sxx <= 0.0 {
+        return Err(CommandError::nothing_to_do("历史回合的总部署量完全相同，无法拟合").into());
+    }
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+    let rss: f64 = points.iter().map(|(x, y)| (y - (slope * x + intercept)).powi(2)).sum();
+    let sigma2 = rss / (n - 2.0);
+    let se_slope = (sigma2 / sxx).sqrt();
+    let se_intercept = (sigma2 * (1.0 / n + mean_x * mean_x / sxx)).sqrt();
+    let r2 = if syy > 0.0 { 1.0 - rss / syy } else { 0.0 };
+    // 获胜格子占总部署的比例（与 slope 对照，完全随机时约为 1/25 = 0.04）
+    let shares: Vec<f64> = points.iter().map(|(x, y)| y / x).collect();
+    let mean_share = shares.iter().sum::<f64>() / n;
+    let se_share = (shares.iter().map(|s| (s - mean_share).powi(2)).sum::<f64>() / (n - 1.0) / n).sqrt();
+
+    let z = 1.96;
+    let fitted = OptimizedThreshold {
+        slope,
+        offset_sol: -intercept,
+    };
+    let current = OptimizedThreshold::from_env();
+    out!("Optimized calibration ({} rounds)", points.len());
+    out!(
+        "  slope:  {:.6}  95% CI [{:.6}, {:.6}]  (current {})",
+        slope,
+        slope - z * se_slope,
+        slope + z * se_slope,
+        current.slope
+    );
+    out!(
+        "  offset: {:.6} SOL  95% CI [{:.6}, {:.6}]  (current {})",
+        fitted.offset_sol,
+        fitted.offset_sol - z * se_intercept,
+        fitted.offset_sol + z * se_intercept,
+        current.offset_sol
+    );
+    out!("  R²: {:.4}  residual σ: {:.6} SOL", r2, sigma2.sqrt());
+    out!(
+        "  winning square share: {:.4}  95% CI [{:.4}, {:.4}]",
+        mean_share,
+        mean_share - z * se_share,
+        mean_share + z * se_share
+    );
+    let slope_ok = (current.slope - slope).abs() <= z * se_slope;
+    let offset_ok = (current.offset_sol - fitted.offset_sol).abs() <= z * se_intercept;
+    out!(
+        "  current slope {}, current offset {}",
+        if slope_ok { "within CI" } else { "OUTSIDE CI" },
+        if offset_ok { "within CI" } else { "OUTSIDE CI" }
+    );
+
+    let params = StrategyParams {
+        algorithm: SquareSelectionAlgorithm::Optimized,
+        ..strategy_params_from_env()
+    };
+    if params.amount_lamports > 0 {
+        out!("  backtest (min={} pick={}):", params.min_squares, params.pick_squares);
+        print_backtest_stats("current", &backtest(&params, &rounds));
+        print_backtest_stats(
+            "fitted",
+            &backtest(&StrategyParams { optimized: fitted, ..params }, &rounds),
+        );
+    }
+    Ok(())
+}
+
+// 逗号分隔的数值列表，未设置时使用默认值
+fn env_list<T: FromStr + Clone>(key: &str, default: &[T]) -> Vec<T> {
+    std::env::var(key)
+        .ok()
+        .map(|s| s.split(',').filter_map(|v| v.trim().parse::<T>().ok()).collect::<Vec<_>>())
+        .filter(|v| !v.is_empty())
+        .unwrap_or(default.to_vec())
+}
+
+// 参数网格：OPT_THRESHOLDS / OPT_MIN_SQUARES / OPT_PICKS / OPT_AMOUNTS_SOL，两种算法都参与
+fn strategy_param_grid() -> Vec<StrategyParams> {
+    let thresholds = env_list("OPT_THRESHOLDS", &[0.005, 0.01, 0.02, 0.05]);
+    let mins = env_list("OPT_MIN_SQUARES", &[1usize, 5, 10, 15]);
+    let picks = env_list("OPT_PICKS", &[1usize, 3, 5, 8]);
+    let amounts = env_list("OPT_AMOUNTS_SOL", &[0.001, 0.01]);
+    let seed = rng_seed();
+    let optimized = OptimizedThreshold::from_env();
+    let mut grid = vec![];
+    for &amount in amounts.iter() {
+        for &min_squares in mins.iter() {
+            for &pick_squares in picks.iter() {
+                // 最优化算法不使用固定阈值，只需一组
+                grid.push(StrategyParams {
+                    algorithm: SquareSelectionAlgorithm::Optimized,
+                    seed,
+                    threshold_sol: 0.0,
+                    min_squares,
+                    pick_squares,
+                    amount_lamports: sol_to_lamports(amount),
+                    optimized,
+                    dynamic_pick: None,
+                });
+                for &threshold_sol in thresholds.iter() {
+                    grid.push(StrategyParams {
+                        algorithm: SquareSelectionAlgorithm::Threshold,
+                        seed,
+                        threshold_sol,
+                        min_squares,
+                        pick_squares,
+                        amount_lamports: sol_to_lamports(amount),
+                        optimized,
+                        dynamic_pick: None,
+                    });
+                }
+            }
+        }
+    }
+    grid
+}
+
+// 网格搜索：按时间顺序前 OPT_TRAIN_FRACTION（默认 0.7）的回合训练，其余回合做样本外验证
+fn optimize() -> Result<(), anyhow::Error> {
+    let mut rounds = load_backtest_rounds_or_err()?;
+    rounds.sort_by_key(|r| r.round_id);
+    let train_fraction = env_f64("OPT_TRAIN_FRACTION", 0.7).clamp(0.1, 1.0);
+    let split = ((rounds.len() as f64 * train_fraction) as usize).clamp(1, rounds.len());
+    let (train, test) = rounds.split_at(split);
+    let top: usize = std::env::var("OPT_TOP")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(5);
+
+    let grid = strategy_param_grid();
+    out!(
+        "Optimize: {} 组参数，训练 {} 回合（{} - {}），验证 {} 回合",
+        grid.len(),
+        train.len(),
+        train.first().map(|r| r.round_id).unwrap_or(0),
+        train.last().map(|r| r.round_id).unwrap_or(0),
+        test.len()
+    );
+    let mut results: Vec<(StrategyParams, BacktestStats)> = grid
+        .into_iter()
+        .map(|params| {
+            let stats = backtest(&params, train);
+            (params, stats)
+        })
+        .filter(|(_, stats)| stats.played > 0)
+        .collect();
+    if results.is_empty() {
+        return Err(CommandError::nothing_to_do("所有参数组合在训练集上都没有下注").into());
+    }
+    results.sort_by(|a, b| b.1.pnl_sol().partial_cmp(&a.1.pnl_sol()).unwrap());
+    for (rank, (params, train_stats)) in results.iter().take(top).enumerate() {
+        out!("#{} {}", rank + 1, params.label());
+        print_backtest_stats("train", train_stats);
+        if test.is_empty() {
+            out!("  test: （无验证集，OPT_TRAIN_FRACTION < 1 时可用）");
+        } else {
+            print_backtest_stats("test ", &backtest(params, test));
+        }
+    }
+    Ok(())
+}
+
+// 累加另一段回测结果
+impl std::ops::AddAssign for BacktestStats {
+    fn add_assign(&mut self, other: Self) {
+        self.rounds += other.rounds;
+        self.played += other.played;
+        self.wins += other.wins;
+        self.cost += other.cost;
+        self.payout += other.payout;
+    }
+}
+
+// 在参数网格中选出训练集 PnL 最高的一组
+fn best_params(grid: &[StrategyParams], rounds: &[BacktestRound]) -> Option<(StrategyParams, BacktestStats)> {
+    grid.iter()
+        .map(|params| (*params, backtest(params, rounds)))
+        .filter(|(_, stats)| stats.played > 0)
+        .max_by(|a, b| a.1.pnl_sol().partial_cmp(&b.1.pnl_sol()).unwrap())
+}
+
+// 滚动窗口前向验证：每 WF_TRAIN_ROUNDS（默认 200）个回合选参，在随后 WF_TEST_ROUNDS（默认 50）个回合上评估，
+// 窗口按 WF_TEST_ROUNDS 向前滚动；样本外 PnL 远低于样本内时说明参数过拟合
+fn walk_forward() -> Result<(), anyhow::Error> {
+    let mut rounds = load_backtest_rounds_or_err()?;
+    rounds.sort_by_key(|r| r.round_id);
+    let train_len: usize = std::env::var("WF_TRAIN_ROUNDS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(200)
+        .max(1);
+    let test_len: usize = std::env::var("WF_TEST_ROUNDS")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(50)
+        .max(1);
+    if rounds.len() < train_len + test_len {
+        return Err(CommandError::nothing_to_do(format!(
+            "历史回合不足：需要至少 {} 个（WF_TRAIN_ROUNDS + WF_TEST_ROUNDS），当前 {} 个",
+            train_len + test_len,
+            rounds.len()
+        ))
+        .into());
+    }
+
+    let grid = strategy_param_grid();
+    out!(
+        "Walk-forward: {} 回合，训练窗口 {}，验证窗口 {}，{} 组参数",
+        rounds.len(),
+        train_len,
+        test_len,
+        grid.len()
+    );
+    let mut in_sample = BacktestStats::default();
+    let mut out_of_sample = BacktestStats::default();
+    let mut chosen: HashMap<String, usize> = HashMap::new();
+    let mut windows = 0usize;
+    let mut start = 0usize;
+    while start + train_len + test_len <= rounds.len() {
+        let train = &rounds[start..start + train_len];
+        let test = &rounds[start + train_len..start + train_len + test_len];
+        windows += 1;
+        match best_params(&grid, train) {
+            Some((params, train_stats)) => {
+                let test_stats = backtest(&params, test);
+                out!(
+                    "  window {} (train {}-{}, test {}-{}): {}",
+                    windows,
+                    train[0].round_id,
+                    train[train.len() - 1].round_id,
+                    test[0].round_id,
+                    test[test.len() - 1].round_id,
+                    params.label()
+                );
+                out!(
+                    "    in-sample pnl={:+.6} SOL, out-of-sample pnl={:+.6} SOL (played {}/{})",
+                    train_stats.pnl_sol(),
+                    test_stats.pnl_sol(),
+                    test_stats.played,
+                    test_stats.rounds
+                );
+                in_sample += train_stats;
+                out_of_sample += test_stats;
+                *chosen.entry(params.label()).or_default() += 1;
+            }
+            None => out!("  window {}: 训练窗口内所有参数都没有下注，跳过", windows),
+        }
+        start += test_len;
+    }
+
+    out!("Summary ({} windows)", windows);
+    print_backtest_stats("in-sample    ", &in_sample);
+    print_backtest_stats("out-of-sample", &out_of_sample);
+    // 样本外 ROI / 样本内 ROI（walk-forward efficiency），明显低于 0.5 时通常说明过拟合
+    if in_sample.roi() > 0.0 {
+        out!(
+            "  walk-forward efficiency: {:.2}",
+            out_of_sample.roi() / in_sample.roi()
+        );
+    }
+    let mut chosen: Vec<(String, usize)> = chosen.into_iter().collect();
+    chosen.sort_by_key(|c| std::cmp::Reverse(c.1));
+    out!("  参数稳定性（被选中次数）:");
+    for (label, count) in chosen.iter().take(5) {
+        out!("    {}x {}", count, label);
+    }
+    Ok(())
+}
+
+// ============ 新增：多策略组合 ============
+// STRATEGIES="threshold:60,optimized:40"（另有 random / weighted_random）：同一钱包同时运行多个策略，
+// 每个策略按百分比分得每格金额（AMOUNT × 比例），threshold=0.02:50 可为该策略单独指定阈值；
+// 各策略选中的格子金额相加后，按金额相同的格子合并为最少的 deploy 指令
+#[derive(Debug, Clone, Copy)]
+struct EnsembleMember {
+    params: StrategyParams,
+    allocation_pct: f64,
+}
+
+fn ensemble_from_env() -> Result<Option<Vec<EnsembleMember>>, anyhow::Error> {
+    let Ok(spec) = std::env::var("STRATEGIES") else {
+        return Ok(None);
+    };
+    let base = strategy_params_from_env();
+    let mut members = vec![];
+    for item in spec.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+        let (name, pct) = item
+            .rsplit_once(':')
+            .ok_or_else(|| CommandError::config(format!("STRATEGIES 格式错误: {}（应为 name:百分比）", item)))?;
+        let allocation_pct: f64 = pct
+            .trim()
+            .parse()
+            .map_err(|_| CommandError::config(format!("STRATEGIES 百分比无效: {}", item)))?;
+        let (kind, threshold) = match name.split_once('=') {
+            Some((kind, t)) => (
+                kind.trim(),
+                t.trim()
+                    .parse::<f64>()
+                    .map_err(|_| CommandError::config(format!("STRATEGIES 阈值无效: {}", item)))?,
+            ),
+            None => (name.trim(), base.threshold_sol),
+        };
+        let algorithm = SquareSelectionAlgorithm::parse(kind)
+            .ok_or_else(|| CommandError::config(format!("STRATEGIES 未知策略: {}", kind)))?;
+        members.push(EnsembleMember {
+            params: StrategyParams {
+                algorithm,
+                threshold_sol: threshold,
+                amount_lamports: (base.amount_lamports as f64 * allocation_pct / 100.0) as u64,
+                ..base
+            },
+            allocation_pct,
+        });
+    }
+    let total_pct: f64 = members.iter().map(|m| m.allocation_pct).sum();
+    if members.is_empty() || total_pct > 100.0 + f64::EPSILON {
+        return Err(CommandError::config(format!(
+            "STRATEGIES 分配比例之和必须在 (0, 100] 之间，当前 {:.1}",
+            total_pct
+        ))
+        .into());
+    }
+    Ok(Some(members))
+}
+
+// 合并各策略的选格结果：返回每个格子的部署金额，没有任何策略选中时返回 None
+fn plan_ensemble(
+    members: &[EnsembleMember],
+    round_id: u64,
+    deployed: &[u64; 25],
+    allow: impl Fn(usize) -> bool,
+) -> Option<[u64; 25]> {
+    let mut plan = [0u64; 25];
+    for member in members {
+        match select_squares_where(&member.params, round_id, deployed, &allow) {
+            Some(picked) => {
+                out!(
+                    "[auto] [组合 {:.0}%] {} -> {:?}",
+                    member.allocation_pct,
+                    member.params.label(),
+                    picked
+                );
+                for i in picked {
+                    plan[i] += member.params.amount_lamports;
+                }
+            }
+            None => out!(
+                "[auto] [组合 {:.0}%] {} -> 本轮不参与",
+                member.allocation_pct,
+                member.params.label()
+            ),
+        }
+    }
+    plan.iter().any(|&v| v > 0).then_some(plan)
+}
+
+// 所有选中格子使用同一金额
+fn uniform_plan(picked: &[usize], amount_lamports: u64) -> [u64; 25] {
+    let mut plan = [0u64; 25];
+    for &i in picked {
+        if i < 25 {
+            plan[i] = amount_lamports;
+        }
+    }
+    plan
+}
+
+// deploy 指令只能为一组格子指定同一金额：按金额分组，每组一条指令
+fn group_deploy_plan(plan: &[u64; 25]) -> Vec<(u64, [bool; 25])> {
+    let mut groups: Vec<(u64, [bool; 25])> = vec![];
+    for (i, &amount) in plan.iter().enumerate() {
+        if amount == 0 {
+            continue;
+        }
+        match groups.iter_mut().find(|(a, _)| *a == amount) {
+            Some((_, squares)) => squares[i] = true,
+            None => {
+                let mut squares = [false; 25];
+                squares[i] = true;
+                groups.push((amount, squares));
+            }
+        }
+    }
+    groups
+}
+
+// ============ 新增：格子模板 ============
+// 给常用的格子组合起名字，可在 SQUARE_PIN / SQUARE_EXCLUDE / SQUARE_BOOST 和 deploy 的 TEMPLATE 中直接引用。
+// 内置：corners、center、center-cross、diagonal、anti-diagonal、edges；
+// 自定义：配置文件中 "SQUARE_TEMPLATES": {"my-set": [1, 3, 5]}（环境变量同为 JSON 对象），同名时覆盖内置模板
+fn builtin_square_template(name: &str) -> Option<Vec<usize>> {
+    let squares = match name {
+        "corners" => vec![0, 4, 20, 24],
+        "center" => vec![12],
+        "center-cross" => vec![2, 7, 10, 11, 12, 13, 14, 17, 22],
+        "diagonal" => vec![0, 6, 12, 18, 24],
+        "anti-diagonal" => vec![4, 8, 12, 16, 20],
+        "edges" => (0..25).filter(|i| i / 5 == 0 || i / 5 == 4 || i % 5 == 0 || i % 5 == 4).collect(),
+        _ => return None,
+    };
+    Some(squares)
+}
+
+fn square_template(name: &str) -> Option<Vec<usize>> {
+    let custom = std::env::var("SQUARE_TEMPLATES")
+        .ok()
+        .and_then(|s| serde_json::from_str::<HashMap<String, Vec<usize>>>(&s).ok())
+        .and_then(|templates| templates.get(name).cloned());
+    custom.or_else(|| builtin_square_template(name))
+}
+
+// 逗号分隔的格子编号或模板名，展开为去重后的格子列表；超出范围或未知的名字打印警告后忽略
+fn parse_square_list(key: &str, value: &str) -> Vec<usize> {
+    let mut squares = vec![];
+    for item in value.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let expanded = match item.parse::<usize>() {
+            Ok(i) => vec![i],
+            Err(_) => match square_template(item) {
+                Some(template) => template,
+                None => {
+                    out!("[squares] ⚠️  {} 中的模板 {} 不存在，忽略", key, item);
+                    continue;
                 }
+            },
+        };
+        for i in expanded {
+            if i >= 25 {
+                out!("[squares] ⚠️  {} 中的格子 #{} 超出范围（0-24），忽略", key, i);
+            } else if !squares.contains(&i) {
+                squares.push(i);
+            }
+        }
+    }
+    squares
+}
+
+// ============ 新增：格子白名单 / 黑名单 ============
+// SQUARE_PIN="0,4,20,24"：只要本轮决定部署，这些格子总会加入（按 AMOUNT）；
+// SQUARE_EXCLUDE="12"：永不部署这些格子。两者都在策略排序选格之后应用，被排除的格子不会由后续排名补位。
+// SQUARE_BOOST="corners:1.5;12:0.5"：选中这些格子时金额乘以对应系数（分号分隔，格子可写编号或模板名）
+#[derive(Debug, Clone, Default)]
+struct SquareConstraints {
+    pinned: Vec<usize>,
+    excluded: Vec<usize>,
+    boosts: Vec<(usize, f64)>,
+}
+
+impl SquareConstraints {
+    fn from_env() -> Self {
+        let parse = |key: &str| -> Vec<usize> {
+            std::env::var(key)
+                .map(|value| parse_square_list(key, &value))
+                .unwrap_or_default()
+        };
+        let mut boosts = vec![];
+        for entry in std::env::var("SQUARE_BOOST").unwrap_or_default().split(';') {
+            let Some((squares, factor)) = entry.rsplit_once(':') else {
+                continue;
+            };
+            let Ok(factor) = factor.trim().parse::<f64>() else {
+                out!("[squares] ⚠️  SQUARE_BOOST 中的系数 {} 无效，忽略", factor);
+                continue;
+            };
+            for i in parse_square_list("SQUARE_BOOST", squares) {
+                boosts.push((i, factor.max(0.0)));
+            }
+        }
+        Self {
+            pinned: parse("SQUARE_PIN"),
+            excluded: parse("SQUARE_EXCLUDE"),
+            boosts,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pinned.is_empty() && self.excluded.is_empty() && self.boosts.is_empty()
+    }
+
+    // 作用于每格金额计划；全部被排除时返回 None（本轮不部署）
+    fn apply(&self, mut plan: [u64; 25], amount_lamports: u64) -> Option<[u64; 25]> {
+        for &i in self.pinned.iter() {
+            if plan[i] == 0 {
+                plan[i] = amount_lamports;
+            }
+        }
+        for &(i, factor) in self.boosts.iter() {
+            plan[i] = (plan[i] as f64 * factor) as u64;
+        }
+        for &i in self.excluded.iter() {
+            plan[i] = 0;
+        }
+        plan.iter().any(|&v| v > 0).then_some(plan)
+    }
+
+    fn apply_picks(&self, picked: Vec<usize>) -> Option<Vec<usize>> {
+        let mut picked: Vec<usize> = picked.into_iter().filter(|i| !self.excluded.contains(i)).collect();
+        for &i in self.pinned.iter() {
+            if !picked.contains(&i) && !self.excluded.contains(&i) {
+                picked.push(i);
             }
         }
+        (!picked.is_empty()).then_some(picked)
+    }
+}
 
-        sleep(Duration::from_millis(500)).await;
+// ============ 新增：下注总额与奖池比例上限 ============
+// MAX_POT_FRACTION=0.5：本轮下注总额（格子数 × 金额）不得超过当前奖池（本轮已部署总额）的该比例，
+// 投入超过奖池本身在数学上是 -EV。POT_GUARD=clamp（默认）按比例缩小每格金额，POT_GUARD=warn 只告警；
+// 未设置 MAX_POT_FRACTION 时不检查。检查的是金额随机化之后、实际提交的下注计划（见 DeployDecider::guard_pot）
+#[derive(Debug, Clone, Copy)]
+struct PotGuard {
+    max_fraction: f64,
+    clamp: bool,
+}
 
-        // 重新获取最新的 board 和 clock，检查是否进入新轮次（使用重试机制）
-        let new_board = match get_board(rpc).await {
-            Ok(b) => b,
-            Err(e) => {
-                println!("[auto] ⚠️  读取 Board 失败（检查新轮次）: {:?}，等待 2 秒后重试...", e);
-                sleep(Duration::from_secs(2)).await;
-                continue;
-            }
+impl PotGuard {
+    fn from_env() -> Result<Option<Self>, CommandError> {
+        let Some(max_fraction) = parse_env_opt::<f64>("MAX_POT_FRACTION")? else {
+            return Ok(None);
+        };
+        let clamp = match std::env::var("POT_GUARD").as_deref() {
+            Ok("warn") => false,
+            Ok("clamp") | Err(_) => true,
+            Ok(other) => return Err(CommandError::config(format!("Invalid POT_GUARD: {}（可选 clamp / warn）", other))),
         };
+        Ok(Some(Self { max_fraction, clamp }))
+    }
 
-        let new_clock = match get_clock(rpc).await {
-            Ok(c) => c,
-            Err(e) => {
-                println!("[auto] ⚠️  读取 Clock 失败（检查新轮次）: {:?}，等待 2 秒后重试...", e);
-                sleep(Duration::from_secs(2)).await;
+    // 超限时缩小或告警；缩小后所有格子都低于 1 lamport 时返回 None（本轮不部署）
+    fn apply(&self, plan: [u64; 25], deployed: &[u64; 25]) -> Option<[u64; 25]> {
+        let total: u64 = plan.iter().sum();
+        let pot: u64 = deployed.iter().sum();
+        let limit = (pot as f64 * self.max_fraction) as u64;
+        if total <= limit {
+            return Some(plan);
+        }
+        out!(
+            "[pot] ⚠️  下注总额 {} 超过奖池 {} 的 {:.0}%（上限 {}）",
+            fmt_sol(total),
+            fmt_sol(pot),
+            self.max_fraction * 100.0,
+            fmt_sol(limit)
+        );
+        if !self.clamp {
+            return Some(plan);
+        }
+        let scale = limit as f64 / total as f64;
+        let clamped = plan.map(|v| (v as f64 * scale) as u64);
+        if clamped.iter().all(|&v| v == 0) {
+            out!("[pot] 奖池过小，本轮不部署");
+            return None;
+        }
+        out!("[pot] 每格金额按 ×{:.3} 缩小，合计 {}", scale, fmt_sol(clamped.iter().sum()));
+        Some(clamped)
+    }
+}
+
+// ============ 新增：尾盘流入预估 ============
+// 当前快照并不是回合的最终分布：最后几秒往往还有资金涌入金额最少的格子。LATE_INFLOW=true 时，
+// 用历史快照（RECORD_SNAPSHOTS 记录，需带 end_slot）与回合最终分布（ore.round_results.jsonl）估计
+// 回合最后 LATE_INFLOW_WINDOW_SECS（默认 10）秒内，按金额从少到多排第 k 位的格子平均每秒还会流入多少，
+// 决策时把"剩余秒数 × 该速度"加到对应格子上，阈值 / EV / 奖池比例都按预估的最终金额计算。
+// 样本少于 LATE_INFLOW_MIN_SAMPLES（默认 20）时不启用
+struct LateInflowModel {
+    // 名次（0 = 当前金额最少）→ 尾盘平均流入速度（lamports/秒）
+    rate_by_rank: [f64; 25],
+    samples: usize,
+}
+
+impl LateInflowModel {
+    fn from_env() -> Option<Self> {
+        if !env_flag("LATE_INFLOW") {
+            return None;
+        }
+        let window_secs = env_f64("LATE_INFLOW_WINDOW_SECS", 10.0);
+        let min_samples = std::env::var("LATE_INFLOW_MIN_SAMPLES")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(20);
+        let finals: HashMap<u64, [u64; 25]> = read_round_results()
+            .into_iter()
+            .map(|r| (r.round_id, r.deployed))
+            .collect();
+        let mut totals = [0.0; 25];
+        let mut samples = 0usize;
+        for snapshot in read_board_snapshots() {
+            let (Some(end_slot), Some(last)) = (snapshot.end_slot, finals.get(&snapshot.round_id)) else {
+                continue;
+            };
+            let remaining = slots_to_secs(end_slot.saturating_sub(snapshot.slot));
+            if remaining <= 0.0 || remaining > window_secs {
                 continue;
             }
+            for (rank, square) in rank_ascending(&snapshot.deployed).into_iter().enumerate() {
+                totals[rank] += last[square].saturating_sub(snapshot.deployed[square]) as f64 / remaining;
+            }
+            samples += 1;
+        }
+        if samples < min_samples {
+            out!(
+                "[late] ⚠️  最后 {}s 的历史快照只有 {} 个（需要 {}），尾盘流入预估未启用",
+                window_secs, samples, min_samples
+            );
+            return None;
+        }
+        let model = Self {
+            rate_by_rank: totals.map(|t| t / samples as f64),
+            samples,
         };
+        out!(
+            "[late] 尾盘流入预估：{} 个样本，金额最少的 5 个格子平均 {}/s",
+            model.samples,
+            fmt_sol((model.rate_by_rank[..5].iter().sum::<f64>() / 5.0) as u64)
+        );
+        Some(model)
+    }
 
-        // 检查轮次是否变化
-        if new_board.round_id != board.round_id {
-            // 轮次已经变化，这是正常的轮次切换
-            println!("[auto] ✅ 检测到新轮次：{} -> {}", board.round_id, new_board.round_id);
-            loops_done += 1;
-            processed_round = None;
-            round_deployment_info = None; // 清除上一轮的部署信息
-            // 清除持久化记录，允许新轮次重新部署
-            clear_last_deployed_round();
-        } else if new_clock.slot >= board.end_slot {
-            // slot 已经超过或等于 end_slot，但 round_id 还没变化
-            // 这可能表示：
-            // 1. 轮次正在重置过程中
-            // 2. Board 账户还未更新
-            // 3. 出现了网络延迟
-            // 最安全的做法是再等一会，然后重新检查
-            println!("[auto] ⚠️  当前 slot {} >= end_slot {}，轮次可能正在切换，等待状态更新...", new_clock.slot, board.end_slot);
-            // 如果 processed_round 已设置，则等待下一个轮次；否则继续尝试
-            if processed_round.is_some() {
-                // 已经部署过，等待轮次变化
-                println!("[auto] 已在本轮部署，等待新轮次到来...");
-                sleep(Duration::from_secs(3)).await;
-            }
+    // 剩余 secs_remaining 秒内各格子的预计流入（lamports）
+    fn expected(&self, deployed: &[u64; 25], secs_remaining: f64) -> [u64; 25] {
+        let mut late = [0u64; 25];
+        for (rank, square) in rank_ascending(deployed).into_iter().enumerate() {
+            late[square] = (self.rate_by_rank[rank] * secs_remaining.max(0.0)) as u64;
         }
+        late
     }
+}
 
-    println!(
-        "[auto] 结束。总花费约 {:.6} SOL",
-        lamports_to_sol(total_spent as u64)
-    );
-    Ok(())
+// 按金额从少到多排列的格子编号（金额相同时编号小的在前）
+fn rank_ascending(deployed: &[u64; 25]) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..25).collect();
+    order.sort_by_key(|&i| (deployed[i], i));
+    order
 }
 
-// ============ 新增：交互式菜单 ============
+// ============ 新增：竞争激烈时自动降额 ============
+// 决策时本轮总流入速度（各格子流入速度之和）或参与人数（Round.count 之和）明显高于最近回合的平均水平时，
+// 视为竞争激烈：COMPETITION_ACTION=reduce（默认）按 COMPETITION_REDUCE_FACTOR（默认 0.5）缩小下注，skip 直接跳过本轮。
+//   COMPETITION_INFLOW_SPIKE   总流入速度达到近期平均的 N 倍（如 2.0）
+//   COMPETITION_MINERS_SPIKE   参与人数达到近期平均的 N 倍（如 1.5）
+//   COMPETITION_HISTORY        参与平均的最近回合数（默认 20，至少积累 3 个回合后才判断）
+// 两个阈值都未设置时不检查。结果以下注系数的形式与亏损冷却相乘（0 表示跳过），回放文件中一并记录
+struct CompetitionGuard {
+    inflow_spike: Option<f64>,
+    miners_spike: Option<f64>,
+    skip: bool,
+    reduce_factor: f64,
+    capacity: usize,
+    // (回合, 总流入 lamports/秒, 参与人数)，同一回合只保留最近一次观察
+    history: VecDeque<(u64, f64, u64)>,
+}
 
-async fn interactive_menu(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    // 显示当前奖励
-    let miner = get_miner(rpc, payer.pubkey()).await.ok();
-    if let Some(m) = &miner {
-        println!(
-            "当前可领：SOL {:.6}，ORE {}",
-            lamports_to_sol(m.rewards_sol),
-            amount_to_ui_amount(m.rewards_ore + m.refined_ore, TOKEN_DECIMALS)
-        );
+impl CompetitionGuard {
+    fn from_env() -> Result<Option<Self>, CommandError> {
+        let inflow_spike = parse_env_opt::<f64>("COMPETITION_INFLOW_SPIKE")?;
+        let miners_spike = parse_env_opt::<f64>("COMPETITION_MINERS_SPIKE")?;
+        if inflow_spike.is_none() && miners_spike.is_none() {
+            return Ok(None);
+        }
+        let skip = match std::env::var("COMPETITION_ACTION").as_deref() {
+            Ok("skip") => true,
+            Ok("reduce") | Err(_) => false,
+            Ok(other) => {
+                return Err(CommandError::config(format!("Invalid COMPETITION_ACTION: {}（可选 reduce / skip）", other)))
+            }
+        };
+        Ok(Some(Self {
+            inflow_spike,
+            miners_spike,
+            skip,
+            reduce_factor: env_f64("COMPETITION_REDUCE_FACTOR", 0.5).clamp(0.0, 1.0),
+            capacity: std::env::var("COMPETITION_HISTORY")
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+                .unwrap_or(20)
+                .max(3),
+            history: VecDeque::new(),
+        }))
     }
-    println!("请选择：");
-    println!("1) 按预设自动挖矿（阈值算法）");
-    println!("2) 按预设自动挖矿（最优化算法）");
-    println!("3) claim 所有 SOL");
-    println!("4) claim 所有 ORE");
-    println!("5) 查询账户状态（余额/是否为矿工/可领取）");
-    print!("输入选项序号并回车: ");
-    let _ = io::stdout().flush();
-    let mut line = String::new();
-    let _ = io::stdin().read_line(&mut line);
-    let choice = line.trim();
 
-    match choice {
-        "1" => {
-            auto_mine(rpc, payer, SquareSelectionAlgorithm::Threshold).await?;
+    // 返回本轮下注系数：1.0 正常，reduce_factor 降额，0.0 跳过
+    fn assess(&mut self, round_id: u64, inflow: f64, miners: u64) -> f64 {
+        let previous: Vec<(f64, u64)> = self
+            .history
+            .iter()
+            .filter(|(r, _, _)| *r != round_id)
+            .map(|(_, i, m)| (*i, *m))
+            .collect();
+        if self.history.back().is_some_and(|(r, _, _)| *r == round_id) {
+            self.history.pop_back();
         }
-        "2" => {
-            auto_mine(rpc, payer, SquareSelectionAlgorithm::Optimized).await?;
+        self.history.push_back((round_id, inflow, miners));
+        while self.history.len() > self.capacity {
+            self.history.pop_front();
         }
-        "3" => {
-            if let Some(m) = &miner {
-                let sol_amt = lamports_to_sol(m.rewards_sol);
-                if sol_amt <= 0.0 {
-                    println!("当前可领 SOL 为 0，已取消。");
-                    return Ok(());
-                }
-                println!("当前可领 SOL {:.6}。输入 y 确认领取，其他任意键取消：", sol_amt);
-                let mut c = String::new();
-                let _ = io::stdin().read_line(&mut c);
-                if c.trim().to_lowercase() != "y" { println!("已取消。"); return Ok(()); }
+        if previous.len() < 3 {
+            return 1.0;
+        }
+        let n = previous.len() as f64;
+        let avg_inflow = previous.iter().map(|(i, _)| i).sum::<f64>() / n;
+        let avg_miners = previous.iter().map(|(_, m)| *m as f64).sum::<f64>() / n;
+        let mut reasons = vec![];
+        if let Some(spike) = self.inflow_spike {
+            if avg_inflow > 0.0 && inflow >= avg_inflow * spike {
+                reasons.push(format!(
+                    "总流入 {:.4} SOL/s，为近期平均的 {:.1} 倍",
+                    lamports_to_sol(inflow as u64),
+                    inflow / avg_inflow
+                ));
             }
-            let ix_sol = ore_api::sdk::claim_sol(payer.pubkey());
-            submit_transaction(rpc, payer, &[ix_sol]).await?;
         }
-        "4" => {
-            if let Some(m) = &miner {
-                let ore_amount = amount_to_ui_amount(m.rewards_ore + m.refined_ore, TOKEN_DECIMALS);
-                if ore_amount <= 0.0 {
-                    println!("当前可领 ORE 为 0，已取消。");
-                    return Ok(());
-                }
-                println!("当前可领 ORE {}。输入 y 确认领取，其他任意键取消：", ore_amount);
-                let mut c = String::new();
-                let _ = io::stdin().read_line(&mut c);
-                if c.trim().to_lowercase() != "y" { println!("已取消。"); return Ok(()); }
+        if let Some(spike) = self.miners_spike {
+            if avg_miners > 0.0 && miners as f64 >= avg_miners * spike {
+                reasons.push(format!("参与人数 {}，为近期平均的 {:.1} 倍", miners, miners as f64 / avg_miners));
             }
-            let ix_ore = ore_api::sdk::claim_ore(payer.pubkey());
-            submit_transaction(rpc, payer, &[ix_ore]).await?;
         }
-        "5" => {
-            query_account_status(rpc, payer).await?;
+        if reasons.is_empty() {
+            return 1.0;
         }
-        _ => println!("已取消。"),
+        let factor = if self.skip { 0.0 } else { self.reduce_factor };
+        out!(
+            "[competition] ⚠️  round={} 竞争激烈（{}），{}",
+            round_id,
+            reasons.join("；"),
+            if self.skip { "跳过本轮".to_string() } else { format!("下注 ×{}", factor) }
+        );
+        factor
     }
+}
 
-    Ok(())
+// ============ 新增：矿工群体分析（新 / 老矿工、流失、平均下注） ============
+
+const COHORT_FILE: &str = "ore.cohorts.jsonl";
+
+// 某一回合的参与者快照：(authority, 本轮部署 lamports)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CohortSample {
+    round_id: u64,
+    timestamp: u64,
+    miners: Vec<(String, u64)>,
 }
 
-async fn query_account_status(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    println!("[status] 开始查询账户状态...");
-    let address = payer.pubkey();
-    // 基本网络连通与钱包 SOL 余额
-    match rpc.get_balance(&address).await {
-        Ok(lamports) => {
-            println!("钱包地址: {}", address);
-            println!("钱包余额: {:.6} SOL", lamports_to_sol(lamports));
-        }
-        Err(e) => {
-            println!("[error] 无法读取钱包余额: {}", e);
-            println!("可能原因：RPC 不可用/网络不匹配。");
-            return Ok(());
-        }
+fn append_cohort_sample(sample: &CohortSample) {
+    let Ok(line) = serde_json::to_string(sample) else {
+        return;
+    };
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(COHORT_FILE))
+    {
+        let _ = writeln!(file, "{}", line);
     }
+}
 
-    // 读取 ORE 配置与当前回合，验证网络是否存在程序状态
-    match get_board(rpc).await {
-        Ok(board) => {
-            println!("当前回合: {}，距结束约 {:.2}s", board.round_id, (board.end_slot as f64) * 0.4);
-        }
-        Err(_) => {
-            println!("[warn] 读取 ORE Board 失败，可能连接了错误网络（例如 devnet）。");
-        }
+fn read_cohort_samples() -> Vec<CohortSample> {
+    let mut by_round: std::collections::BTreeMap<u64, CohortSample> = std::collections::BTreeMap::new();
+    for sample in fs::read_to_string(state_path(COHORT_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<CohortSample>(line).ok())
+    {
+        by_round.insert(sample.round_id, sample);
     }
+    by_round.into_values().collect()
+}
 
-    // Miner 账户与可领取
-    match get_miner(rpc, address).await {
-        Ok(miner) => {
-            let claimable_ore = amount_to_ui_amount(miner.rewards_ore + miner.refined_ore, TOKEN_DECIMALS);
-            let claimable_sol = lamports_to_sol(miner.rewards_sol);
-            println!("矿工账户: 存在");
-            println!("可领取 ORE: {}", claimable_ore);
-            println!("可领取 SOL: {:.6}", claimable_sol);
-            println!("当前回合ID: {}，checkpoint到: {}", miner.round_id, miner.checkpoint_id);
-            if claimable_ore == 0.0 && claimable_sol == 0.0 {
-                println!("提示：当前无可领取奖励。如刚部署，请在回合结束后执行 checkpoint 再领取。");
+// Miner 账户只保存最近一次参与的 round_id，回合结束后很快会被下一轮覆盖，
+// 因此在每轮结束前 COHORT_SAMPLE_BEFORE_SECONDS 秒（默认 5）按回合过滤采样一次
+async fn cohort_record(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let sample_before_secs = env_f64("COHORT_SAMPLE_BEFORE_SECONDS", 5.0);
+    let mut sampled_round: Option<u64> = None;
+    loop {
+        let (board, clock) = match tokio::join!(get_board(rpc), get_clock(rpc)) {
+            (Ok(board), Ok(clock)) => (board, clock),
+            (Err(e), _) | (_, Err(e)) => {
+                out!("[cohort] ⚠️  读取 Board/Clock 失败: {:?}，稍后重试", e);
+                sleep(Duration::from_secs(2)).await;
+                continue;
+            }
+        };
+        let view = BoardView::new(board, clock.slot);
+        if sampled_round != Some(board.round_id) && view.secs_remaining() <= sample_before_secs {
+            match get_miners_participating(rpc, board.round_id).await {
+                Ok(miners) => {
+                    let sample = CohortSample {
+                        round_id: board.round_id,
+                        timestamp: chain_now(),
+                        miners: miners
+                            .iter()
+                            .map(|(_, m)| (m.authority.to_string(), m.deployed.iter().sum()))
+                            .collect(),
+                    };
+                    out!("[cohort] round {}：{} 个矿工", sample.round_id, sample.miners.len());
+                    append_cohort_sample(&sample);
+                    sampled_round = Some(board.round_id);
+                }
+                Err(e) => out!("[cohort] ⚠️  读取参与矿工失败: {:?}", e),
             }
         }
-        Err(_) => {
-            println!("矿工账户: 不存在 (未注册/未初始化)。你需要先成功部署一次来创建 Miner 账户。");
-        }
+        let wait = if view.secs_remaining() > sample_before_secs + 2.0 {
+            (view.secs_remaining() - sample_before_secs - 1.0).min(30.0)
+        } else {
+            1.0
+        };
+        sleep(Duration::from_secs_f64(wait)).await;
     }
-
-    Ok(())
 }
 
-async fn claim_seeker(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let seeker_mint = pubkey!("5mXbkqKz883aufhAsx3p5Z1NcvD2ppZbdTTznM6oUKLj");
-    let ix = ore_api::sdk::claim_seeker(payer.pubkey(), seeker_mint);
-    simulate_transaction(rpc, payer, &[ix]).await;
+fn cohort_report() -> Result<(), anyhow::Error> {
+    let samples = read_cohort_samples();
+    if samples.is_empty() {
+        return Err(CommandError::nothing_to_do("没有群体采样数据，请先运行 cohort_record").into());
+    }
+    let mut seen: std::collections::HashSet<&str> = std::collections::HashSet::new();
+    let mut prev: Option<std::collections::HashSet<&str>> = None;
+    let mut avg_bets = vec![];
+    out!("Miner cohorts ({} rounds)", samples.len());
+    out!(
+        "  {:>8}  {:>7}  {:>5}  {:>9}  {:>7}  {:>14}",
+        "round", "miners", "new", "returning", "churned", "avg bet (SOL)"
+    );
+    for sample in samples.iter() {
+        let current: std::collections::HashSet<&str> =
+            sample.miners.iter().map(|(a, _)| a.as_str()).collect();
+        let new = current.iter().filter(|a| !seen.contains(*a)).count();
+        let returning = current.len() - new;
+        // 流失：上一采样回合参与、本回合未参与（仅在采样回合连续时有意义）
+        let churned = prev
+            .as_ref()
+            .map(|p| p.iter().filter(|a| !current.contains(*a)).count());
+        let total: u64 = sample.miners.iter().map(|(_, v)| *v).sum();
+        let avg_bet = if sample.miners.is_empty() {
+            0.0
+        } else {
+            lamports_to_sol(total) / sample.miners.len() as f64
+        };
+        avg_bets.push(avg_bet);
+        out!(
+            "  {:>8}  {:>7}  {:>5}  {:>9}  {:>7}  {:>14.6}",
+            sample.round_id,
+            current.len(),
+            new,
+            returning,
+            churned.map(|c| c.to_string()).unwrap_or("-".to_string()),
+            avg_bet
+        );
+        seen.extend(current.iter().copied());
+        prev = Some(current);
+    }
+    out!("  unique authorities: {}", seen.len());
+    // 平均下注趋势：前半段与后半段对比
+    if avg_bets.len() >= 2 {
+        let half = avg_bets.len() / 2;
+        let first = avg_bets[..half].iter().sum::<f64>() / half as f64;
+        let second = avg_bets[half..].iter().sum::<f64>() / (avg_bets.len() - half) as f64;
+        let change = if first > 0.0 { (second - first) / first * 100.0 } else { 0.0 };
+        out!(
+            "  avg bet trend: {:.6} -> {:.6} SOL ({:+.1}%)",
+            first, second, change
+        );
+    }
     Ok(())
 }
 
-async fn set_admin(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let ix = ore_api::sdk::set_admin(payer.pubkey(), payer.pubkey());
-    submit_transaction(rpc, payer, &[ix]).await?;
-    Ok(())
-}
+// ============ 新增：手机状态页（带 token 的 HTTP 服务） ============
 
-async fn set_fee_collector(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let fee_collector = std::env::var("FEE_COLLECTOR").expect("Missing FEE_COLLECTOR env var");
-    let fee_collector = Pubkey::from_str(&fee_collector).expect("Invalid FEE_COLLECTOR");
-    let ix = ore_api::sdk::set_fee_collector(payer.pubkey(), fee_collector);
-    submit_transaction(rpc, payer, &[ix]).await?;
-    Ok(())
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
 }
 
-async fn checkpoint(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let authority = std::env::var("AUTHORITY").unwrap_or(payer.pubkey().to_string());
-    let authority = Pubkey::from_str(&authority).expect("Invalid AUTHORITY");
-    let miner = get_miner(rpc, authority).await?;
-    let ix = ore_api::sdk::checkpoint(payer.pubkey(), authority, miner.round_id);
-    submit_transaction(rpc, payer, &[ix]).await?;
-    Ok(())
+// 读取最近 n 条奖励事件（新的在前）
+fn recent_reward_events(n: usize) -> Vec<RewardEvent> {
+    let mut events: Vec<RewardEvent> = read_reward_events();
+    events.reverse();
+    events.truncate(n);
+    events
 }
 
-async fn checkpoint_all(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let clock = get_clock(rpc).await?;
-    let miners = get_miners(rpc).await?;
-    let mut expiry_slots = HashMap::new();
-    let mut ixs = vec![];
-    for (i, (_address, miner)) in miners.iter().enumerate() {
-        if miner.checkpoint_id < miner.round_id {
-            // Log the expiry slot for the round.
-            if !expiry_slots.contains_key(&miner.round_id) {
-                if let Ok(round) = get_round(rpc, miner.round_id).await {
-                    expiry_slots.insert(miner.round_id, round.expires_at);
+// 状态页 HTML：当前回合、我的格子、最近结果、余额
+async fn render_status_page(rpc: &RpcClient, authority: Pubkey) -> String {
+    let mut sections = vec![];
+
+    let board = get_board(rpc).await.ok();
+    let slot = get_clock(rpc).await.map(|c| c.slot).unwrap_or(0);
+    let miner = get_miner(rpc, authority).await.ok();
+    match board {
+        Some(board) => {
+            let view = BoardView::new(board, slot);
+            let mut round_html = format!(
+                "<h2>当前回合 {}</h2><p>剩余约 {:.1}s（{} slots）</p>",
+                view.round_id(),
+                view.secs_remaining(),
+                view.slots_remaining()
+            );
+            if let Ok(round) = get_round(rpc, board.round_id).await {
+                let mut round_view = RoundView::new(round, slot);
+                if let Some(miner) = &miner {
+                    round_view = round_view.with_miner(miner);
+                }
+                round_html.push_str(&format!(
+                    "<p>总部署 {}，我的部署 {}</p><table class=\"grid\">",
+                    fmt_sol(round_view.deployed_lamports()),
+                    fmt_sol(round_view.my_deployed_lamports())
+                ));
+                for row in 0..5 {
+                    round_html.push_str("<tr>");
+                    for col in 0..5 {
+                        let i = row * 5 + col;
+                        let mine = round_view.my_share(i) > 0.0;
+                        round_html.push_str(&format!(
+                            "<td class=\"{}\">#{}<br>{:.3}{}</td>",
+                            if mine { "mine" } else { "" },
+                            i,
+                            round_view.square_sol(i),
+                            if mine {
+                                format!("<br>{:.1}%", round_view.my_share(i) * 100.0)
+                            } else {
+                                String::new()
+                            }
+                        ));
+                    }
+                    round_html.push_str("</tr>");
                 }
+                round_html.push_str("</table>");
             }
+            sections.push(round_html);
+        }
+        None => sections.push("<h2>当前回合</h2><p>读取 Board 失败</p>".to_string()),
+    }
 
-            // Get the expiry slot for the round.
-            let Some(expires_at) = expiry_slots.get(&miner.round_id) else {
-                continue;
-            };
+    let wallet = rpc.get_balance(&authority).await.map(lamports_to_sol).unwrap_or(0.0);
+    let mut balance_html = format!("<h2>余额</h2><p>钱包：{:.6} SOL</p>", wallet);
+    match &miner {
+        Some(miner) => {
+            let view = MinerView::new(*miner);
+            balance_html.push_str(&format!(
+                "<p>可领取：{:.6} SOL，{} ORE</p>",
+                view.claimable_sol(),
+                view.claimable_ore()
+            ));
+            if let Some(warning) = pending_checkpoint_warning(rpc, miner).await {
+                balance_html.push_str(&format!("<p class=\"warn\">{}</p>", html_escape(&warning)));
+            }
+        }
+        None => balance_html.push_str("<p>矿工账户不存在</p>"),
+    }
+    sections.push(balance_html);
 
-            // If we are in fee collection period, checkpoint the miner.
-            if clock.slot >= expires_at - TWELVE_HOURS_SLOTS {
-                println!(
-                    "[{}/{}] Checkpoint miner: {} ({} s)",
-                    i + 1,
-                    miners.len(),
-                    miner.authority,
-                    (expires_at - clock.slot) as f64 * 0.4
-                );
-                ixs.push(ore_api::sdk::checkpoint(
-                    payer.pubkey(),
-                    miner.authority,
-                    miner.round_id,
-                ));
+    let mut results_html = String::from("<h2>最近结果</h2><ul>");
+    let events = recent_reward_events(10);
+    if events.is_empty() {
+        results_html.push_str("<li>暂无记录</li>");
+    }
+    for event in events.iter() {
+        results_html.push_str(&format!(
+            "<li><small>{}</small> {}</li>",
+            event.ts,
+            html_escape(&describe_reward_event(event))
+        ));
+    }
+    results_html.push_str("</ul>");
+    sections.push(results_html);
+
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\">\
+<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\
+<meta http-equiv=\"refresh\" content=\"10\"><title>ore status</title>\
+<style>body{{font-family:sans-serif;margin:12px;max-width:640px}}\
+table.grid{{border-collapse:collapse;width:100%}}\
+table.grid td{{border:1px solid #ccc;text-align:center;font-size:12px;padding:4px}}\
+td.mine{{background:#ffe08a}}.warn{{color:#b00}}</style></head>\
+<body><h1>ore status</h1><p><small>{}</small></p>{}</body></html>",
+        authority,
+        sections.join("")
+    )
+}
+
+// 比较耗时与内容无关（长度不同时直接返回，长度本身不是秘密）
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b.iter()).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+// token 只接受请求头 Authorization: Bearer ... 或 Cookie: ore_status_token=...；
+// 不接受查询参数，避免 token 出现在访问日志、浏览器历史与 Referer 中
+fn status_request_authorized(request: &str, token: &str) -> bool {
+    let mut authorized = false;
+    for line in request.lines().skip(1) {
+        let Some((name, value)) = line.split_once(':') else {
+            continue;
+        };
+        let name = name.trim();
+        if name.eq_ignore_ascii_case("authorization") {
+            if let Some(bearer) = value.trim().strip_prefix("Bearer ") {
+                authorized |= constant_time_eq(bearer.trim().as_bytes(), token.as_bytes());
+            }
+        } else if name.eq_ignore_ascii_case("cookie") {
+            for cookie in value.split(';') {
+                if let Some(("ore_status_token", v)) = cookie.trim().split_once('=') {
+                    authorized |= constant_time_eq(v.trim().as_bytes(), token.as_bytes());
+                }
             }
         }
     }
+    authorized
+}
 
-    // Batch and submit the instructions.
-    while !ixs.is_empty() {
-        let batch = ixs
-            .drain(..std::cmp::min(10, ixs.len()))
-            .collect::<Vec<Instruction>>();
-        submit_transaction(rpc, payer, &batch).await?;
+// STATUS_BIND（默认 127.0.0.1:8080）上提供状态页，必须设置 STATUS_TOKEN；每个连接单独处理
+async fn status_server(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+) -> Result<(), anyhow::Error> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let token = std::env::var("STATUS_TOKEN")
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+        .ok_or_else(|| CommandError::config("Missing STATUS_TOKEN env var（状态页必须设置访问 token）"))?;
+    let token = std::sync::Arc::new(token);
+    let bind = std::env::var("STATUS_BIND").unwrap_or("127.0.0.1:8080".to_string());
+    let authority = payer.pubkey();
+    // 连接处理任务共享的 RPC 客户端（与命令使用同一节点与确认级别）
+    let shared_rpc = std::sync::Arc::new(RpcClient::new_with_timeout_and_commitment(
+        rpc.url(),
+        rpc_request_timeout(),
+        rpc.commitment(),
+    ));
+    let listener = tokio::net::TcpListener::bind(&bind).await?;
+    out!(
+        "[status] 状态页已启动：http://{}/（Authorization: Bearer <STATUS_TOKEN> 或 Cookie ore_status_token=<STATUS_TOKEN>）",
+        bind
+    );
+
+    loop {
+        let (mut stream, peer) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(e) => {
+                out!("[status] ⚠️  accept 失败: {}", e);
+                continue;
+            }
+        };
+        let rpc = shared_rpc.clone();
+        let token = token.clone();
+        tokio::spawn(async move {
+            let mut buf = vec![0u8; 8192];
+            let n = match tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await {
+                Ok(Ok(n)) => n,
+                _ => return,
+            };
+            let request = String::from_utf8_lossy(&buf[..n]).to_string();
+            let (status, body) = if !request.starts_with("GET ") {
+                ("405 Method Not Allowed", "method not allowed".to_string())
+            } else if !status_request_authorized(&request, &token) {
+                out!("[status] 拒绝未授权请求: {}", peer);
+                ("401 Unauthorized", "unauthorized".to_string())
+            } else {
+                ("200 OK", render_status_page(&rpc, authority).await)
+            };
+            let content_type = if status.starts_with("200") {
+                "text/html; charset=utf-8"
+            } else {
+                "text/plain; charset=utf-8"
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+                status,
+                content_type,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        });
     }
+}
 
-    Ok(())
+// ============ 新增：只读公共 API ============
+// COMMAND=api_server：供社区看板读取 ORE 状态的 JSON HTTP API（无需鉴权，只读）
+//   GET /board                当前回合、剩余时间、各格部署
+//   GET /round/:id            回合详情（已结束回合含开奖格子）
+//   GET /miner/:authority     矿工账户与可领取奖励
+// 监听地址 API_BIND（默认 127.0.0.1:8081），每个连接单独处理。响应按路径缓存在共享缓存中 API_CACHE_MS 毫秒（默认 1000），
+// 已结束的回合不再变化，缓存 1 小时；/board 由后台任务按缓存周期刷新，请求不直接打到 RPC。
+// 每个 IP 每分钟最多 API_RATE_LIMIT 个请求（默认 120，0 不限制），超出返回 429
+fn api_board_json(board: &Board, round: &Round, slot: u64) -> serde_json::Value {
+    let view = BoardView::new(*board, slot);
+    let round_view = RoundView::new(*round, slot);
+    serde_json::json!({
+        "round_id": board.round_id,
+        "start_slot": board.start_slot,
+        "end_slot": board.end_slot,
+        "slot": slot,
+        "slots_remaining": view.slots_remaining(),
+        "secs_remaining": view.secs_remaining(),
+        "deployed": round.deployed,
+        "total_deployed": round_view.deployed_lamports(),
+    })
 }
 
-async fn close_all(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let rounds = get_rounds(rpc).await?;
-    let mut ixs = vec![];
-    let clock = get_clock(rpc).await?;
-    for (_i, (_address, round)) in rounds.iter().enumerate() {
-        if clock.slot >= round.expires_at {
-            ixs.push(ore_api::sdk::close(
-                payer.pubkey(),
-                round.id,
-                round.rent_payer,
-            ));
+fn api_round_json(round: &Round) -> serde_json::Value {
+    serde_json::json!({
+        "round_id": round.id,
+        "deployed": round.deployed,
+        "total_deployed": round.total_deployed,
+        "total_vaulted": round.total_vaulted,
+        "total_winnings": round.total_winnings,
+        "motherlode": round.motherlode,
+        "top_miner": round.top_miner.to_string(),
+        "top_miner_reward": round.top_miner_reward,
+        "expires_at": round.expires_at,
+        "winning_square": RoundResult::from_round(round).map(|r| r.winning_square),
+    })
+}
+
+fn api_miner_json(miner: &Miner) -> serde_json::Value {
+    let view = MinerView::new(*miner);
+    serde_json::json!({
+        "authority": miner.authority.to_string(),
+        "address": view.address.to_string(),
+        "round_id": miner.round_id,
+        "checkpoint_id": miner.checkpoint_id,
+        "deployed": miner.deployed,
+        "rewards_sol": miner.rewards_sol,
+        "rewards_ore": miner.rewards_ore,
+        "refined_ore": miner.refined_ore,
+        "claimable_sol": view.claimable_sol(),
+        "claimable_ore": view.claimable_ore(),
+    })
+}
+
+// 返回 (状态, JSON, 缓存时长)
+async fn api_route(rpc: &RpcClient, path: &str, cache: Duration) -> (&'static str, serde_json::Value, Duration) {
+    let error = |status: &'static str, message: String| (status, serde_json::json!({ "error": message }), Duration::ZERO);
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["board"] => {
+            let (board, clock) = tokio::join!(get_board(rpc), get_clock(rpc));
+            let (board, clock) = match (board, clock) {
+                (Ok(b), Ok(c)) => (b, c),
+                (Err(e), _) | (_, Err(e)) => return error("502 Bad Gateway", e.to_string()),
+            };
+            match get_round(rpc, board.round_id).await {
+                Ok(round) => ("200 OK", api_board_json(&board, &round, clock.slot), cache),
+                Err(e) => error("502 Bad Gateway", e.to_string()),
+            }
+        }
+        ["round", id] => {
+            let Ok(id) = id.parse::<u64>() else {
+                return error("400 Bad Request", format!("invalid round id: {}", id));
+            };
+            match get_round(rpc, id).await {
+                Ok(round) => {
+                    let ttl = if RoundResult::from_round(&round).is_some() {
+                        Duration::from_secs(3600)
+                    } else {
+                        cache
+                    };
+                    ("200 OK", api_round_json(&round), ttl)
+                }
+                Err(e) => error("404 Not Found", e.to_string()),
+            }
+        }
+        ["miner", authority] => {
+            let Ok(authority) = Pubkey::from_str(authority) else {
+                return error("400 Bad Request", format!("invalid authority: {}", authority));
+            };
+            match get_miner(rpc, authority).await {
+                Ok(miner) => ("200 OK", api_miner_json(&miner), cache),
+                Err(e) => error("404 Not Found", e.to_string()),
+            }
         }
+        _ => error("404 Not Found", "not found".to_string()),
     }
+}
 
-    // Batch and submit the instructions.
-    while !ixs.is_empty() {
-        let batch = ixs
-            .drain(..std::cmp::min(12, ixs.len()))
-            .collect::<Vec<Instruction>>();
-        submit_transaction(rpc, payer, &batch).await?;
+// path -> (过期时间, 状态, 响应体)
+type ApiCache = std::sync::Arc<std::sync::Mutex<HashMap<String, (Instant, &'static str, String)>>>;
+// IP -> (当前分钟窗口起点, 窗口内请求数)
+type ApiRateLimits = std::sync::Arc<std::sync::Mutex<HashMap<std::net::IpAddr, (Instant, u32)>>>;
+
+async fn api_cached_route(rpc: &RpcClient, cache: &ApiCache, path: &str, cache_ttl: Duration) -> (&'static str, String) {
+    let cached = cache.lock().ok().and_then(|cache| {
+        cache
+            .get(path)
+            .filter(|(expires, _, _)| Instant::now() < *expires)
+            .map(|(_, status, body)| (*status, body.clone()))
+    });
+    if let Some(hit) = cached {
+        return hit;
     }
-
-    Ok(())
+    let (status, value, ttl) = api_route(rpc, path, cache_ttl).await;
+    let body = value.to_string();
+    if !ttl.is_zero() {
+        if let Ok(mut cache) = cache.lock() {
+            cache.insert(path.to_string(), (Instant::now() + ttl, status, body.clone()));
+        }
+    }
+    (status, body)
 }
 
-async fn log_meteora_pool(rpc: &RpcClient) -> Result<(), anyhow::Error> {
-    let address = pubkey!("GgaDTFbqdgjoZz3FP7zrtofGwnRS4E6MCzmmD5Ni1Mxj");
-    let pool = get_meteora_pool(rpc, address).await?;
-    let vault_a = get_meteora_vault(rpc, pool.a_vault).await?;
-    let vault_b = get_meteora_vault(rpc, pool.b_vault).await?;
+// 固定一分钟窗口计数；limit 为 0 时不限制
+fn api_rate_allowed(limits: &ApiRateLimits, ip: std::net::IpAddr, limit: u32) -> bool {
+    if limit == 0 {
+        return true;
+    }
+    let Ok(mut limits) = limits.lock() else {
+        return true;
+    };
+    let now = Instant::now();
+    let entry = limits.entry(ip).or_insert((now, 0));
+    if now.duration_since(entry.0) >= Duration::from_secs(60) {
+        *entry = (now, 0);
+    }
+    entry.1 += 1;
+    entry.1 <= limit
+}
 
-    println!("Pool");
-    println!("  address: {}", address);
-    println!("  lp_mint: {}", pool.lp_mint);
-    println!("  token_a_mint: {}", pool.token_a_mint);
-    println!("  token_b_mint: {}", pool.token_b_mint);
-    println!("  a_vault: {}", pool.a_vault);
-    println!("  b_vault: {}", pool.b_vault);
-    println!("  a_token_vault: {}", vault_a.token_vault);
-    println!("  b_token_vault: {}", vault_b.token_vault);
-    println!("  a_vault_lp_mint: {}", vault_a.lp_mint);
-    println!("  b_vault_lp_mint: {}", vault_b.lp_mint);
-    println!("  a_vault_lp: {}", pool.a_vault_lp);
-    println!("  b_vault_lp: {}", pool.b_vault_lp);
-    println!("  protocol_token_fee: {}", pool.protocol_token_b_fee);
+async fn api_server(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-    // pool: *pool.key,
-    // user_source_token: *user_source_token.key,
-    // user_destination_token: *user_destination_token.key,
-    // a_vault: *a_vault.key,
-    // b_vault: *b_vault.key,
-    // a_token_vault: *a_token_vault.key,
-    // b_token_vault: *b_token_vault.key,
-    // a_vault_lp_mint: *a_vault_lp_mint.key,
-    // b_vault_lp_mint: *b_vault_lp_mint.key,
-    // a_vault_lp: *a_vault_lp.key,
-    // b_vault_lp: *b_vault_lp.key,
-    // protocol_token_fee: *protocol_token_fee.key,
-    // user: *user.key,
-    // vault_program: *vault_program.key,
-    // token_program: *token_program.key,
+    let bind = std::env::var("API_BIND").unwrap_or("127.0.0.1:8081".to_string());
+    let cache_ttl = Duration::from_millis(
+        std::env::var("API_CACHE_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(1000),
+    );
+    let rate_limit: u32 = parse_env_opt("API_RATE_LIMIT")?.unwrap_or(120);
+    // 连接处理任务与刷新任务共享的 RPC 客户端（与命令使用同一节点与确认级别）
+    let shared_rpc = std::sync::Arc::new(RpcClient::new_with_timeout_and_commitment(
+        rpc.url(),
+        rpc_request_timeout(),
+        rpc.commitment(),
+    ));
+    let cache: ApiCache = Default::default();
+    let limits: ApiRateLimits = Default::default();
+    let listener = tokio::net::TcpListener::bind(&bind).await?;
+    out!("[api] 只读 API 已启动：http://{}/board /round/:id /miner/:authority", bind);
 
-    Ok(())
-}
+    // 后台刷新 /board，并清理过期的缓存与限流记录
+    {
+        let rpc = shared_rpc.clone();
+        let cache = cache.clone();
+        let limits = limits.clone();
+        let refresh = cache_ttl.max(Duration::from_millis(200));
+        tokio::spawn(async move {
+            loop {
+                let (status, value, ttl) = api_route(&rpc, "/board", cache_ttl).await;
+                if let Ok(mut cache) = cache.lock() {
+                    let now = Instant::now();
+                    cache.retain(|_, (expires, _, _)| now < *expires);
+                    if !ttl.is_zero() {
+                        // 多留一个刷新周期，刷新稍慢时请求仍命中缓存
+                        cache.insert("/board".to_string(), (now + ttl + refresh, status, value.to_string()));
+                    }
+                }
+                if let Ok(mut limits) = limits.lock() {
+                    limits.retain(|_, (window, _)| window.elapsed() < Duration::from_secs(60));
+                }
+                sleep(refresh).await;
+            }
+        });
+    }
 
-async fn log_automations(rpc: &RpcClient) -> Result<(), anyhow::Error> {
-    let automations = get_automations(rpc).await?;
-    for (i, (address, automation)) in automations.iter().enumerate() {
-        println!("[{}/{}] {}", i + 1, automations.len(), address);
-        println!("  authority: {}", automation.authority);
-        println!("  balance: {}", automation.balance);
-        println!("  executor: {}", automation.executor);
-        println!("  fee: {}", automation.fee);
-        println!("  mask: {}", automation.mask);
-        println!("  strategy: {}", automation.strategy);
-        println!();
+    loop {
+        let Ok((mut stream, peer)) = listener.accept().await else {
+            continue;
+        };
+        let rpc = shared_rpc.clone();
+        let cache = cache.clone();
+        let limits = limits.clone();
+        tokio::spawn(async move {
+            let (status, body) = if !api_rate_allowed(&limits, peer.ip(), rate_limit) {
+                ("429 Too Many Requests", r#"{"error":"rate limited"}"#.to_string())
+            } else {
+                let mut buf = vec![0u8; 4096];
+                let Ok(Ok(n)) = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await else {
+                    return;
+                };
+                let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                match request.strip_prefix("GET ") {
+                    None => ("405 Method Not Allowed", r#"{"error":"method not allowed"}"#.to_string()),
+                    Some(rest) => {
+                        let path = rest
+                            .split_whitespace()
+                            .next()
+                            .unwrap_or("/")
+                            .split('?')
+                            .next()
+                            .unwrap_or("/")
+                            .to_string();
+                        api_cached_route(&rpc, &cache, &path, cache_ttl).await
+                    }
+                }
+            };
+            let response = format!(
+                "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        });
     }
-    Ok(())
 }
 
-async fn log_treasury(rpc: &RpcClient) -> Result<(), anyhow::Error> {
-    let treasury_address = ore_api::state::treasury_pda().0;
-    let treasury = get_treasury(rpc).await?;
-    println!("Treasury");
-    println!("  address: {}", treasury_address);
-    println!("  balance: {} SOL", lamports_to_sol(treasury.balance));
-    println!(
-        "  motherlode: {} ORE",
-        amount_to_ui_amount(treasury.motherlode, TOKEN_DECIMALS)
-    );
-    println!(
-        "  miner_rewards_factor: {}",
-        treasury.miner_rewards_factor.to_i80f48().to_string()
-    );
-    println!(
-        "  stake_rewards_factor: {}",
-        treasury.stake_rewards_factor.to_i80f48().to_string()
-    );
-    println!(
-        "  total_staked: {} ORE",
-        amount_to_ui_amount(treasury.total_staked, TOKEN_DECIMALS)
-    );
-    println!(
-        "  total_unclaimed: {} ORE",
-        amount_to_ui_amount(treasury.total_unclaimed, TOKEN_DECIMALS)
-    );
-    println!(
-        "  total_refined: {} ORE",
-        amount_to_ui_amount(treasury.total_refined, TOKEN_DECIMALS)
-    );
-    Ok(())
-}
 
-async fn log_round(rpc: &RpcClient) -> Result<(), anyhow::Error> {
-    let id = std::env::var("ID").expect("Missing ID env var");
-    let id = u64::from_str(&id).expect("Invalid ID");
-    let round_address = round_pda(id).0;
-    let round = get_round(rpc, id).await?;
-    let rng = round.rng();
-    println!("Round");
-    println!("  Address: {}", round_address);
-    println!("  Count: {:?}", round.count);
-    println!("  Deployed: {:?}", round.deployed);
-    println!("  Expires at: {}", round.expires_at);
-    println!("  Id: {:?}", round.id);
-    println!("  Motherlode: {}", round.motherlode);
-    println!("  Rent payer: {}", round.rent_payer);
-    println!("  Slot hash: {:?}", round.slot_hash);
-    println!("  Top miner: {:?}", round.top_miner);
-    println!("  Top miner reward: {}", round.top_miner_reward);
-    println!("  Total deployed: {}", round.total_deployed);
-    println!("  Total vaulted: {}", round.total_vaulted);
-    println!("  Total winnings: {}", round.total_winnings);
-    if let Some(rng) = rng {
-        println!("  Winning square: {}", round.winning_square(rng));
-    }
-    // if round.slot_hash != [0; 32] {
-    //     println!("  Winning square: {}", get_winning_square(&round.slot_hash));
-    // }
-    Ok(())
-}
+// ============ 新增：Board 快照与对比 ============
 
-async fn log_miner(
-    rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
-) -> Result<(), anyhow::Error> {
-    let authority = std::env::var("AUTHORITY").unwrap_or(payer.pubkey().to_string());
-    let authority = Pubkey::from_str(&authority).expect("Invalid AUTHORITY");
-    let miner_address = ore_api::state::miner_pda(authority).0;
-    let miner = get_miner(&rpc, authority).await?;
-    println!("Miner");
-    println!("  address: {}", miner_address);
-    println!("  authority: {}", authority);
-    println!("  deployed: {:?}", miner.deployed);
-    println!("  cumulative: {:?}", miner.cumulative);
-    println!("  rewards_sol: {} SOL", lamports_to_sol(miner.rewards_sol));
-    println!(
-        "  rewards_ore: {} ORE",
-        amount_to_ui_amount(miner.rewards_ore, TOKEN_DECIMALS)
-    );
-    println!(
-        "  refined_ore: {} ORE",
-        amount_to_ui_amount(miner.refined_ore, TOKEN_DECIMALS)
-    );
-    println!("  round_id: {}", miner.round_id);
-    println!("  checkpoint_id: {}", miner.checkpoint_id);
-    println!(
-        "  lifetime_rewards_sol: {} SOL",
-        lamports_to_sol(miner.lifetime_rewards_sol)
-    );
-    println!(
-        "  lifetime_rewards_ore: {} ORE",
-        amount_to_ui_amount(miner.lifetime_rewards_ore, TOKEN_DECIMALS)
-    );
-    Ok(())
-}
+const BOARD_SNAPSHOT_FILE: &str = "ore.board_snapshots.jsonl";
 
-async fn log_seeker(rpc: &RpcClient) -> Result<(), anyhow::Error> {
-    let mint = std::env::var("MINT").unwrap();
-    let mint = Pubkey::from_str(&mint).expect("Invalid MINT");
-    let seeker = get_seeker(&rpc, mint).await?;
-    let seeker_address = ore_api::state::seeker_pda(mint).0;
-    println!("Seeker");
-    println!("  address: {}", seeker_address);
-    println!("  mint: {}", seeker.mint);
-    Ok(())
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BoardSnapshot {
+    timestamp: u64,
+    slot: u64,
+    round_id: u64,
+    deployed: [u64; 25],
+    // 回合结束 slot（较早的快照没有）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    end_slot: Option<u64>,
 }
 
-async fn log_clock(rpc: &RpcClient) -> Result<(), anyhow::Error> {
-    let clock = get_clock(&rpc).await?;
-    println!("Clock");
-    println!("  slot: {}", clock.slot);
-    println!("  epoch_start_timestamp: {}", clock.epoch_start_timestamp);
-    println!("  epoch: {}", clock.epoch);
-    println!("  leader_schedule_epoch: {}", clock.leader_schedule_epoch);
-    println!("  unix_timestamp: {}", clock.unix_timestamp);
-    Ok(())
+fn append_board_snapshot(snapshot: &BoardSnapshot) {
+    let Ok(line) = serde_json::to_string(snapshot) else {
+        return;
+    };
+    if let Ok(mut file) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(state_path(BOARD_SNAPSHOT_FILE))
+    {
+        let _ = writeln!(file, "{}", line);
+    }
+    trim_board_snapshots();
 }
 
-async fn log_config(rpc: &RpcClient) -> Result<(), anyhow::Error> {
-    let config = get_config(&rpc).await?;
-    println!("Config");
-    println!("  admin: {}", config.admin);
-    println!("  bury_authority: {}", config.bury_authority);
-    println!("  fee_collector: {}", config.fee_collector);
-    println!("  last_boost: {}", config.last_boost);
-    println!(
-        "  is_seeker_activation_enabled: {}",
-        config.is_seeker_activation_enabled
-    );
+// 快照文件超过 BOARD_SNAPSHOT_MAX_BYTES（默认 50MB）时丢弃较旧的一半
+fn trim_board_snapshots() {
+    let path = state_path(BOARD_SNAPSHOT_FILE);
+    let max_bytes = std::env::var("BOARD_SNAPSHOT_MAX_BYTES")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(50 * 1024 * 1024);
+    if fs::metadata(&path).map(|m| m.len() <= max_bytes).unwrap_or(true) {
+        return;
+    }
+    let Ok(content) = fs::read_to_string(&path) else {
+        return;
+    };
+    let lines: Vec<&str> = content.lines().collect();
+    let mut out = lines[lines.len() / 2..].join("\n");
+    out.push('\n');
+    let tmp_path = format!("{}.tmp", path);
+    if fs::write(&tmp_path, out).is_ok() {
+        let _ = fs::rename(&tmp_path, &path);
+    }
+}
 
-    Ok(())
+fn read_board_snapshots() -> Vec<BoardSnapshot> {
+    fs::read_to_string(state_path(BOARD_SNAPSHOT_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str::<BoardSnapshot>(line).ok())
+        .collect()
 }
 
-async fn log_board(rpc: &RpcClient) -> Result<(), anyhow::Error> {
-    let board = get_board(&rpc).await?;
-    let clock = get_clock(&rpc).await?;
-    print_board(board, &clock);
-    Ok(())
+fn print_board_diff(base: &BoardSnapshot, current: &BoardSnapshot) {
+    // 时间窗口按 slot 估算（与 print_board 一致，每 slot 约 0.4 秒）
+    let window_secs = (current.slot.saturating_sub(base.slot) as f64) * 0.4;
+    out!(
+        "Board diff (round {}, slot {} -> {}, {:.2}s)",
+        current.round_id, base.slot, current.slot, window_secs
+    );
+    let mut changed = 0;
+    for i in 0..25 {
+        let delta = current.deployed[i].saturating_sub(base.deployed[i]);
+        if delta == 0 {
+            continue;
+        }
+        changed += 1;
+        let rate = if window_secs > 0.0 {
+            lamports_to_sol(delta) / window_secs
+        } else {
+            0.0
+        };
+        out!(
+            "  #{}: {} -> {} (+{}, {:.6} SOL/s)",
+            i,
+            fmt_sol(base.deployed[i]),
+            fmt_sol(current.deployed[i]),
+            fmt_sol(delta),
+            rate
+        );
+    }
+    if changed == 0 {
+        out!("  (no changes)");
+    }
 }
 
 fn print_board(board: Board, clock: &Clock) {
     let current_slot = clock.slot;
-    println!("Board");
-    println!("  Id: {:?}", board.round_id);
-    println!("  Start slot: {}", board.start_slot);
-    println!("  End slot: {}", board.end_slot);
-    // 使用理论值计算（在 log_board 中我们已经获取了 clock，这里简单显示）
-    let secs_left = if board.end_slot > current_slot {
-        (board.end_slot.saturating_sub(current_slot) as f64) * 0.4
-    } else {
-        0.0
-    };
-    println!("  Time remaining: {:.2} sec", secs_left);
+    out!("Board");
+    out!("  Id: {:?}", board.round_id);
+    out!("  Start slot: {}", board.start_slot);
+    out!("  End slot: {}", board.end_slot);
+    out!(
+        "  Time remaining: {:.2} sec",
+        BoardView::new(board, current_slot).secs_remaining()
+    );
 }
 
 async fn get_automations(rpc: &RpcClient) -> Result<Vec<(Pubkey, Automation)>, anyhow::Error> {
-    const REGOLITH_EXECUTOR: Pubkey = pubkey!("HNWhK5f8RMWBqcA7mXJPaxdTPGrha3rrqUrri7HSKb3T");
+    let executor = known_address("REGOLITH_EXECUTOR", DEFAULT_REGOLITH_EXECUTOR);
     let filter = RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
         56,
-        &REGOLITH_EXECUTOR.to_bytes(),
+        &executor.to_bytes(),
     ));
     let automations = get_program_accounts::<Automation>(rpc, ore_api::ID, vec![filter]).await?;
     Ok(automations)
@@ -1464,30 +13881,309 @@ async fn get_meteora_vault(rpc: &RpcClient, address: Pubkey) -> Result<Vault, an
     Ok(vault)
 }
 
+// Meteora 池子的实际储备：池子持有的 vault LP 份额按比例换算成代币数量
+struct MeteoraReserves {
+    address: Pubkey,
+    pool: Pool,
+    vault_a: Vault,
+    vault_b: Vault,
+    reserve_a: u64,
+    reserve_b: u64,
+}
+
+impl MeteoraReserves {
+    // (ORE 储备, SOL 储备)，与池子里 a / b 的顺序无关
+    fn ore_sol_reserves(&self) -> (u64, u64) {
+        if self.pool.token_a_mint == MINT_ADDRESS {
+            (self.reserve_a, self.reserve_b)
+        } else {
+            (self.reserve_b, self.reserve_a)
+        }
+    }
+
+    // 中间价（SOL/ORE），不含手续费
+    fn mid_price(&self) -> f64 {
+        let (reserve_ore, reserve_sol) = self.ore_sol_reserves();
+        if reserve_ore == 0 {
+            return 0.0;
+        }
+        lamports_to_sol(reserve_sol) / amount_to_ui_amount(reserve_ore, TOKEN_DECIMALS)
+    }
+
+    // 常数乘积报价：扣除交易手续费后，in_amount 个输入代币可换得的输出数量
+    fn quote(&self, input_mint: &Pubkey, in_amount: u64) -> u64 {
+        let (reserve_in, reserve_out) = if *input_mint == self.pool.token_a_mint {
+            (self.reserve_a, self.reserve_b)
+        } else {
+            (self.reserve_b, self.reserve_a)
+        };
+        let fees = &self.pool.fees;
+        let fee = if fees.trade_fee_denominator > 0 {
+            (in_amount as u128) * (fees.trade_fee_numerator as u128)
+                / (fees.trade_fee_denominator as u128)
+        } else {
+            0
+        };
+        let in_after_fee = (in_amount as u128).saturating_sub(fee);
+        let denominator = reserve_in as u128 + in_after_fee;
+        if denominator == 0 {
+            return 0;
+        }
+        ((reserve_out as u128) * in_after_fee / denominator) as u64
+    }
+}
+
+async fn get_meteora_vault_share(
+    rpc: &RpcClient,
+    vault: &Vault,
+    pool_vault_lp: &Pubkey,
+) -> Result<u64, anyhow::Error> {
+    let lp_balance = rpc
+        .get_token_account_balance(pool_vault_lp)
+        .await?
+        .amount
+        .parse::<u128>()?;
+    let lp_supply = rpc.get_token_supply(&vault.lp_mint).await?.amount.parse::<u128>()?;
+    if lp_supply == 0 {
+        return Ok(0);
+    }
+    Ok(((vault.total_amount as u128) * lp_balance / lp_supply) as u64)
+}
+
+async fn get_meteora_reserves(
+    rpc: &RpcClient,
+    address: Pubkey,
+) -> Result<MeteoraReserves, anyhow::Error> {
+    let pool = get_meteora_pool(rpc, address).await?;
+    let vault_a = get_meteora_vault(rpc, pool.a_vault).await?;
+    let vault_b = get_meteora_vault(rpc, pool.b_vault).await?;
+    let reserve_a = get_meteora_vault_share(rpc, &vault_a, &pool.a_vault_lp).await?;
+    let reserve_b = get_meteora_vault_share(rpc, &vault_b, &pool.b_vault_lp).await?;
+    Ok(MeteoraReserves {
+        address,
+        pool,
+        vault_a,
+        vault_b,
+        reserve_a,
+        reserve_b,
+    })
+}
+
+fn build_meteora_swap_ix(
+    reserves: &MeteoraReserves,
+    user: Pubkey,
+    input_mint: &Pubkey,
+    in_amount: u64,
+    minimum_out_amount: u64,
+) -> Instruction {
+    let pool = &reserves.pool;
+    let (output_mint, protocol_token_fee) = if *input_mint == pool.token_a_mint {
+        (pool.token_b_mint, pool.protocol_token_a_fee)
+    } else {
+        (pool.token_a_mint, pool.protocol_token_b_fee)
+    };
+    meteora_pools_sdk::instructions::SwapBuilder::new()
+        .pool(reserves.address)
+        .user_source_token(get_associated_token_address(&user, input_mint))
+        .user_destination_token(get_associated_token_address(&user, &output_mint))
+        .a_vault(pool.a_vault)
+        .b_vault(pool.b_vault)
+        .a_token_vault(reserves.vault_a.token_vault)
+        .b_token_vault(reserves.vault_b.token_vault)
+        .a_vault_lp_mint(reserves.vault_a.lp_mint)
+        .b_vault_lp_mint(reserves.vault_b.lp_mint)
+        .a_vault_lp(pool.a_vault_lp)
+        .b_vault_lp(pool.b_vault_lp)
+        .protocol_token_fee(protocol_token_fee)
+        .user(user)
+        .vault_program(meteora_vault_sdk::programs::VAULT_ID)
+        .in_amount(in_amount)
+        .minimum_out_amount(minimum_out_amount)
+        .instruction()
+}
+
+// 操作类别：不同类别使用不同确认级别
+#[derive(Debug, Clone, Copy)]
+enum OpClass {
+    // 策略实时读取（board/round/clock），默认 processed
+    Strategy,
+    // 交易确认与部署后校验，默认 confirmed
+    Confirmation,
+    // 统计/查询类读取（miner、getProgramAccounts 等），默认 confirmed
+    Analytics,
+}
+
+// 可通过 COMMITMENT_STRATEGY / COMMITMENT_CONFIRMATION / COMMITMENT_ANALYTICS 覆盖
+fn commitment_for(op: OpClass) -> CommitmentConfig {
+    let (key, default) = match op {
+        OpClass::Strategy => ("COMMITMENT_STRATEGY", CommitmentConfig::processed()),
+        OpClass::Confirmation => ("COMMITMENT_CONFIRMATION", CommitmentConfig::confirmed()),
+        OpClass::Analytics => ("COMMITMENT_ANALYTICS", CommitmentConfig::confirmed()),
+    };
+    match std::env::var(key) {
+        Ok(v) => CommitmentConfig::from_str(v.trim()).unwrap_or_else(|_| {
+            out!("[warn] {}={} 无效（可选 processed/confirmed/finalized），使用默认值", key, v);
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
 async fn get_board(rpc: &RpcClient) -> Result<Board, anyhow::Error> {
+    Ok(get_board_at(rpc, None).await?.0)
+}
+
+// 读取策略类账户，同时返回 RPC 响应的 context slot；min_context_slot 保证不读到更旧的状态
+async fn get_strategy_account(
+    rpc: &RpcClient,
+    address: &Pubkey,
+    min_context_slot: Option<u64>,
+) -> Result<(Vec<u8>, u64), anyhow::Error> {
+    let response = rpc
+        .get_account_with_config(
+            address,
+            RpcAccountInfoConfig {
+                encoding: Some(UiAccountEncoding::Base64),
+                commitment: Some(commitment_for(OpClass::Strategy)),
+                min_context_slot,
+                ..Default::default()
+            },
+        )
+        .await?;
+    let account = response
+        .value
+        .ok_or_else(|| anyhow::anyhow!("Account {} not found", address))?;
+    Ok((account.data, response.context.slot))
+}
+
+async fn get_board_at(
+    rpc: &RpcClient,
+    min_context_slot: Option<u64>,
+) -> Result<(Board, u64), anyhow::Error> {
     let board_pda = ore_api::state::board_pda();
-    // 使用 processed 确认级别以获得最快响应
-    let account = rpc.get_account_with_commitment(&board_pda.0, CommitmentConfig::processed()).await?;
-    let account = account.value.ok_or_else(|| anyhow::anyhow!("Board account not found"))?;
-    let board = Board::try_from_bytes(&account.data)?;
-    Ok(*board)
+    let (data, context_slot) = get_strategy_account(rpc, &board_pda.0, min_context_slot).await?;
+    let board = Board::try_from_bytes(&data)?;
+    Ok((*board, context_slot))
+}
+
+async fn get_round_at(
+    rpc: &RpcClient,
+    id: u64,
+    min_context_slot: Option<u64>,
+) -> Result<(Round, u64), anyhow::Error> {
+    let round_pda = ore_api::state::round_pda(id);
+    let (data, context_slot) = get_strategy_account(rpc, &round_pda.0, min_context_slot).await?;
+    let round = Round::try_from_bytes(&data)?;
+    Ok((*round, context_slot))
+}
+
+// Board/Round 回合 ID 不一致时，按 context slot 判断哪一方过期，只重新读取过期的一方
+// （round 总是按 board.round_id 读取），最多尝试 3 次；成功时返回一致的 (Board, Round)
+async fn resolve_round_mismatch(
+    rpc: &RpcClient,
+    board: (Board, u64),
+    round: (Round, u64),
+) -> Option<(Board, Round)> {
+    let (mut board, mut board_slot) = board;
+    let (mut round, mut round_slot) = round;
+    for _ in 0..3 {
+        if board.round_id == round.id {
+            return Some((board, round));
+        }
+        if round_slot < board_slot {
+            out!("[auto] Round 数据较旧 (slot {} < {})，重新读取 Round", round_slot, board_slot);
+            (round, round_slot) = get_round_at(rpc, board.round_id, Some(board_slot)).await.ok()?;
+        } else {
+            out!("[auto] Board 数据较旧 (slot {} <= {})，重新读取 Board", board_slot, round_slot);
+            let previous_round_id = board.round_id;
+            (board, board_slot) = get_board_at(rpc, Some(round_slot)).await.ok()?;
+            if board.round_id != previous_round_id {
+                (round, round_slot) = get_round_at(rpc, board.round_id, Some(board_slot)).await.ok()?;
+            }
+        }
+    }
+    (board.round_id == round.id).then_some((board, round))
+}
+
+// ============ 新增：sysvar 多源读取 ============
+// Clock / SlotHashes 决定 reset 与危险区间的时机，单个节点失败不应中断整个操作：
+// 先在主节点 RPC 重试一次，仍失败时依次尝试 RPC_SYSVAR_FALLBACKS（逗号分隔，未设置时使用 RPC_REFERENCE）。
+// 成功结果缓存 SYSVAR_CACHE_MS 毫秒（默认 200，约半个 slot），同一轮循环内的多次读取不重复请求
+struct SysvarCache {
+    clock: Option<(Instant, Clock)>,
+    slot_hashes: Option<(Instant, Vec<(u64, solana_sdk::hash::Hash)>)>,
+}
+
+static SYSVAR_CACHE: std::sync::Mutex<SysvarCache> = std::sync::Mutex::new(SysvarCache {
+    clock: None,
+    slot_hashes: None,
+});
+
+static SYSVAR_FALLBACKS: std::sync::OnceLock<Vec<RpcClient>> = std::sync::OnceLock::new();
+
+fn sysvar_fallbacks() -> &'static [RpcClient] {
+    SYSVAR_FALLBACKS.get_or_init(|| {
+        let mut urls = env_list::<String>("RPC_SYSVAR_FALLBACKS", &[]);
+        if urls.is_empty() {
+            urls.extend(std::env::var("RPC_REFERENCE").ok().filter(|url| !url.trim().is_empty()));
+        }
+        urls.into_iter()
+            .map(|url| RpcClient::new_with_timeout(url, rpc_request_timeout()))
+            .collect()
+    })
+}
+
+fn sysvar_cache_ttl() -> Duration {
+    Duration::from_millis(
+        std::env::var("SYSVAR_CACHE_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(200),
+    )
+}
+
+async fn read_sysvar_from(rpc: &RpcClient, address: &Pubkey, commitment: CommitmentConfig) -> Result<Vec<u8>, anyhow::Error> {
+    let account = rpc.get_account_with_commitment(address, commitment).await?;
+    Ok(account.value.ok_or_else(|| anyhow::anyhow!("Sysvar account {} not found", address))?.data)
+}
+
+async fn read_sysvar(rpc: &RpcClient, address: &Pubkey, commitment: CommitmentConfig) -> Result<Vec<u8>, anyhow::Error> {
+    if let Ok(data) = read_sysvar_from(rpc, address, commitment).await {
+        return Ok(data);
+    }
+    sleep(Duration::from_millis(100)).await;
+    let mut last_err = match read_sysvar_from(rpc, address, commitment).await {
+        Ok(data) => return Ok(data),
+        Err(e) => e,
+    };
+    for fallback in sysvar_fallbacks() {
+        match read_sysvar_from(fallback, address, commitment).await {
+            Ok(data) => {
+                out!("[rpc] ⚠️  主节点读取 sysvar 失败（{}），已改用 {}", last_err, fallback.url());
+                return Ok(data);
+            }
+            Err(e) => last_err = e,
+        }
+    }
+    Err(last_err)
 }
 
 async fn get_slot_hashes(rpc: &RpcClient) -> Result<SlotHashes, anyhow::Error> {
-    let data = rpc
-        .get_account_data(&solana_sdk::sysvar::slot_hashes::ID)
-        .await?;
+    if let Some((fetched, slot_hashes)) = SYSVAR_CACHE.lock().ok().and_then(|c| c.slot_hashes.clone()) {
+        if fetched.elapsed() < sysvar_cache_ttl() {
+            return Ok(SlotHashes::new(&slot_hashes));
+        }
+    }
+    let data = read_sysvar(rpc, &solana_sdk::sysvar::slot_hashes::ID, rpc.commitment()).await?;
     let slot_hashes = bincode::deserialize::<SlotHashes>(&data)?;
+    if let Ok(mut cache) = SYSVAR_CACHE.lock() {
+        cache.slot_hashes = Some((Instant::now(), slot_hashes.to_vec()));
+    }
     Ok(slot_hashes)
 }
 
 async fn get_round(rpc: &RpcClient, id: u64) -> Result<Round, anyhow::Error> {
-    let round_pda = ore_api::state::round_pda(id);
-    // 使用 processed 确认级别以获得最快响应
-    let account = rpc.get_account_with_commitment(&round_pda.0, CommitmentConfig::processed()).await?;
-    let account = account.value.ok_or_else(|| anyhow::anyhow!("Round account not found"))?;
-    let round = Round::try_from_bytes(&account.data)?;
-    Ok(*round)
+    Ok(get_round_at(rpc, id, None).await?.0)
 }
 
 async fn get_treasury(rpc: &RpcClient) -> Result<Treasury, anyhow::Error> {
@@ -1505,17 +14201,73 @@ async fn get_config(rpc: &RpcClient) -> Result<Config, anyhow::Error> {
 }
 
 async fn get_miner(rpc: &RpcClient, authority: Pubkey) -> Result<Miner, anyhow::Error> {
+    get_miner_with_commitment(rpc, authority, commitment_for(OpClass::Analytics)).await
+}
+
+async fn get_miner_with_commitment(
+    rpc: &RpcClient,
+    authority: Pubkey,
+    commitment: CommitmentConfig,
+) -> Result<Miner, anyhow::Error> {
     let miner_pda = ore_api::state::miner_pda(authority);
-    let account = rpc.get_account(&miner_pda.0).await?;
+    let account = rpc.get_account_with_commitment(&miner_pda.0, commitment).await?;
+    let account = account.value.ok_or_else(|| anyhow::anyhow!("Miner account not found"))?;
     let miner = Miner::try_from_bytes(&account.data)?;
     Ok(*miner)
 }
 
+// ============ 新增：链上时间与本机时钟偏差 ============
+// 每次读取 Clock 时记录 clock.unix_timestamp 与本机时间的差值，持久化的时间戳（奖励日志、快照、决策等）
+// 与按时间调度（UNTIL 等）统一用 chain_now()，本机时钟不准时也不会写出错位的时间。
+// 偏差超过 CLOCK_DRIFT_WARN_SECS（默认 30 秒）时告警一次；偏差继续扩大时再次告警
+static CHAIN_TIME_OFFSET: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(0);
+static CLOCK_DRIFT_WARNED: std::sync::atomic::AtomicI64 = std::sync::atomic::AtomicI64::new(0);
+
+fn observe_chain_clock(clock: &Clock) {
+    use std::sync::atomic::Ordering;
+    let local = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    let offset = clock.unix_timestamp - local;
+    CHAIN_TIME_OFFSET.store(offset, Ordering::Relaxed);
+
+    let threshold = std::env::var("CLOCK_DRIFT_WARN_SECS")
+        .ok()
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or(30);
+    let warned = CLOCK_DRIFT_WARNED.load(Ordering::Relaxed);
+    if offset.abs() > threshold && offset.abs() > warned + threshold {
+        CLOCK_DRIFT_WARNED.store(offset.abs(), Ordering::Relaxed);
+        out!(
+            "[clock] ⚠️  本机时钟{}链上时间 {} 秒，请检查 NTP 同步；持久化时间戳已改用链上时间",
+            if offset > 0 { "落后" } else { "领先" },
+            offset.abs()
+        );
+    }
+}
+
+// 链上时间（unix 秒）：本机时间 + 最近一次观测到的偏差；尚未读取 Clock 时等于本机时间
+fn chain_now() -> u64 {
+    let local = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64;
+    (local + CHAIN_TIME_OFFSET.load(std::sync::atomic::Ordering::Relaxed)).max(0) as u64
+}
+
 async fn get_clock(rpc: &RpcClient) -> Result<Clock, anyhow::Error> {
-    // Clock sysvar 使用 processed 确认级别以获得最快响应
-    let account = rpc.get_account_with_commitment(&solana_sdk::sysvar::clock::ID, CommitmentConfig::processed()).await?;
-    let data = account.value.ok_or_else(|| anyhow::anyhow!("Clock account not found"))?.data;
+    if let Some((fetched, clock)) = SYSVAR_CACHE.lock().ok().and_then(|c| c.clock.clone()) {
+        if fetched.elapsed() < sysvar_cache_ttl() {
+            return Ok(clock);
+        }
+    }
+    let data = read_sysvar(rpc, &solana_sdk::sysvar::clock::ID, commitment_for(OpClass::Strategy)).await?;
     let clock = bincode::deserialize::<Clock>(&data)?;
+    observe_chain_clock(&clock);
+    if let Ok(mut cache) = SYSVAR_CACHE.lock() {
+        cache.clock = Some((Instant::now(), clock.clone()));
+    }
     Ok(clock)
 }
 
@@ -1540,6 +14292,9 @@ async fn get_rounds(rpc: &RpcClient) -> Result<Vec<(Pubkey, Round)>, anyhow::Err
 
 #[allow(dead_code)]
 async fn get_miners(rpc: &RpcClient) -> Result<Vec<(Pubkey, Miner)>, anyhow::Error> {
+    if env_flag("MINER_INDEX") {
+        return Ok(sync_miner_index(rpc).await?.miners());
+    }
     let miners = get_program_accounts::<Miner>(rpc, ore_api::ID, vec![]).await?;
     Ok(miners)
 }
@@ -1568,25 +14323,328 @@ fn get_winning_square(slot_hash: &[u8]) -> u64 {
 #[allow(dead_code)]
 async fn simulate_transaction(
     rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
+    payer: &dyn TxSigner,
     instructions: &[solana_sdk::instruction::Instruction],
 ) {
     let blockhash = rpc.get_latest_blockhash().await.unwrap();
-    let x = rpc
-        .simulate_transaction(&Transaction::new_signed_with_payer(
-            instructions,
-            Some(&payer.pubkey()),
-            &[payer],
-            blockhash,
-        ))
-        .await;
-    println!("Simulation result: {:?}", x);
+    let transaction = sign_transaction(payer, instructions, blockhash).await.unwrap();
+    let x = rpc.simulate_transaction(&transaction).await;
+    out!("Simulation result: {:?}", x);
+}
+
+// ============ 新增：可插拔签名 ============
+// 交易签名统一经过 TxSigner，私钥可以不落在挖矿主机上：
+//   SIGNER=local（默认）  读取 KEYPAIR 密钥文件
+//   SIGNER=remote         远程签名服务（HTTP / KMS 网关）：REMOTE_SIGNER_URL、REMOTE_SIGNER_PUBKEY，
+//                         请求头见 REMOTE_SIGNER_HEADERS（"Name: value; Name2: value2"）
+// 远程签名协议：POST {"pubkey": "<base58>", "message": "<base64 序列化 message>"}，
+// 返回 {"signature": "<base58>"}；返回的签名会在本地用公钥校验
+type SignFuture<'a> =
+    std::pin::Pin<Box<dyn std::future::Future<Output = Result<solana_sdk::signature::Signature, anyhow::Error>> + Send + 'a>>;
+
+trait TxSigner: Send + Sync {
+    fn pubkey(&self) -> Pubkey;
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a>;
+}
+
+impl TxSigner for solana_sdk::signer::keypair::Keypair {
+    fn pubkey(&self) -> Pubkey {
+        Signer::pubkey(self)
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a> {
+        Box::pin(async move { Ok(Signer::sign_message(self, message)) })
+    }
+}
+
+struct RemoteSigner {
+    url: String,
+    pubkey: Pubkey,
+}
+
+impl TxSigner for RemoteSigner {
+    fn pubkey(&self) -> Pubkey {
+        self.pubkey
+    }
+
+    fn sign_message<'a>(&'a self, message: &'a [u8]) -> SignFuture<'a> {
+        Box::pin(async move {
+            let payload = serde_json::json!({
+                "pubkey": self.pubkey.to_string(),
+                "message": base64_encode(message),
+            });
+            let request = solana_client::client_error::reqwest::Client::new()
+                .post(&self.url)
+                .header("Content-Type", "application/json")
+                .timeout(Duration::from_secs(10))
+                .body(payload.to_string());
+            let response = with_env_headers(request, "REMOTE_SIGNER_HEADERS").send().await?;
+            if !response.status().is_success() {
+                return Err(anyhow::anyhow!("远程签名服务返回 HTTP {}", response.status()));
+            }
+            let body: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+            let signature = body["signature"]
+                .as_str()
+                .ok_or_else(|| anyhow::anyhow!("远程签名服务响应缺少 signature 字段"))?;
+            let signature = solana_sdk::signature::Signature::from_str(signature)?;
+            if !signature.verify(self.pubkey.as_ref(), message) {
+                return Err(anyhow::anyhow!("远程签名校验失败（公钥 {}）", self.pubkey));
+            }
+            Ok(signature)
+        })
+    }
+}
+
+fn payer_signer_from_env() -> Result<Box<dyn TxSigner>, CommandError> {
+    match std::env::var("SIGNER").unwrap_or("local".to_string()).as_str() {
+        "remote" => {
+            let url = required_env("REMOTE_SIGNER_URL")?;
+            let pubkey: Pubkey = parse_env("REMOTE_SIGNER_PUBKEY")?;
+            out!("[signer] 使用远程签名服务 {}（{}）", url, pubkey);
+            Ok(Box::new(RemoteSigner { url, pubkey }))
+        }
+        "local" => {
+            let path = required_env("KEYPAIR")?;
+            let keypair = read_keypair_file(&path)
+                .map_err(|e| CommandError::config(format!("Invalid KEYPAIR file {}: {}", path, e)))?;
+            Ok(Box::new(keypair))
+        }
+        other => Err(CommandError::config(format!("Invalid SIGNER: {}（local | remote）", other))),
+    }
+}
+
+// 按消息中要求的签名者顺序逐个签名（支持异步的远程签名）
+async fn sign_transaction(
+    payer: &dyn TxSigner,
+    instructions: &[solana_sdk::instruction::Instruction],
+    blockhash: solana_sdk::hash::Hash,
+) -> Result<Transaction, anyhow::Error> {
+    let message = solana_sdk::message::Message::new_with_blockhash(instructions, Some(&payer.pubkey()), &blockhash);
+    let mut transaction = Transaction::new_unsigned(message);
+    let message_data = transaction.message_data();
+    let signers = transaction_signers(payer, instructions);
+    let required = transaction.message.header.num_required_signatures as usize;
+    for i in 0..required {
+        let key = transaction.message.account_keys[i];
+        let signer = signers
+            .iter()
+            .find(|s| s.pubkey() == key)
+            .ok_or_else(|| anyhow::anyhow!("缺少签名者 {}", key))?;
+        transaction.signatures[i] = signer.sign_message(&message_data).await?;
+    }
+    Ok(transaction)
+}
+
+// ============ 新增：authority 与手续费账户分离 ============
+// AUTHORITY_KEYPAIR：矿工 authority 的密钥文件。设置后 deploy / claim 以该账户作为指令签名者
+// （部署资金、领取收益都使用该账户），KEYPAIR 只作为交易手续费支付者并共同签名；
+// checkpoint 任何账户都可提交，只需指定 authority。未设置时两者都是 KEYPAIR
+static AUTHORITY_SIGNER: std::sync::OnceLock<Box<dyn TxSigner>> = std::sync::OnceLock::new();
+// DELEGATE_KEYPAIRS：被管理 authority 的密钥文件，指令要求其签名时自动加入签名者（见 deploy_delegated）
+static DELEGATE_SIGNERS: std::sync::OnceLock<Vec<Box<dyn TxSigner>>> = std::sync::OnceLock::new();
+
+fn load_authority_signer() -> Result<(), anyhow::Error> {
+    if let Ok(path) = std::env::var("AUTHORITY_KEYPAIR") {
+        let authority = read_keypair_file(&path)
+            .map_err(|e| CommandError::config(format!("Invalid AUTHORITY_KEYPAIR file {}: {}", path, e)))?;
+        out!("[signer] 矿工 authority: {}（手续费由 KEYPAIR 支付）", Signer::pubkey(&authority));
+        let _ = AUTHORITY_SIGNER.set(Box::new(authority));
+    }
+    let delegates = read_wallet_files("DELEGATE_KEYPAIRS")?;
+    if !delegates.is_empty() {
+        out!("[signer] 已加载 {} 个被管理 authority 的签名密钥", delegates.len());
+        let _ = DELEGATE_SIGNERS.set(delegates.into_iter().map(|k| Box::new(k) as Box<dyn TxSigner>).collect());
+    }
+    Ok(())
+}
+
+fn delegate_signer(authority: &Pubkey) -> Option<&'static dyn TxSigner> {
+    DELEGATE_SIGNERS
+        .get()?
+        .iter()
+        .find(|s| s.pubkey() == *authority)
+        .map(|s| s.as_ref())
+}
+
+fn mining_authority(payer: &dyn TxSigner) -> &dyn TxSigner {
+    AUTHORITY_SIGNER.get().map(|s| s.as_ref()).unwrap_or(payer)
+}
+
+// 手续费支付者 + 指令中需要签名的 authority
+fn transaction_signers<'a>(
+    payer: &'a dyn TxSigner,
+    instructions: &[solana_sdk::instruction::Instruction],
+) -> Vec<&'a dyn TxSigner> {
+    let mut signers = vec![payer];
+    let authority = mining_authority(payer);
+    let authority_signs = instructions.iter().any(|ix| {
+        ix.accounts
+            .iter()
+            .any(|meta| meta.is_signer && meta.pubkey == authority.pubkey())
+    });
+    if authority.pubkey() != payer.pubkey() && authority_signs {
+        signers.push(authority);
+    }
+    for ix in instructions {
+        for meta in ix.accounts.iter().filter(|m| m.is_signer) {
+            if let Some(delegate) = delegate_signer(&meta.pubkey) {
+                if !signers.iter().any(|s| s.pubkey() == meta.pubkey) {
+                    signers.push(delegate);
+                }
+            }
+        }
+    }
+    signers
+}
+
+// ============ 新增：读写分离的 RPC ============
+// RPC 负责所有读取（回合数据、blockhash、签名状态），RPC_SUBMIT 只负责发送交易（例如 staked / Jito 节点），
+// 确认仍通过读取节点轮询，不要求发送节点支持 getSignatureStatuses。
+// 发送节点独立记录健康状况：连续 RPC_SUBMIT_MAX_FAILURES 次发送失败（网络 / 限流，不含交易本身的错误）后
+// 暂停使用 RPC_SUBMIT_COOLDOWN_SECS 秒，期间改由读取节点发送，冷却结束后自动恢复
+struct SubmitEndpoint {
+    client: RpcClient,
+    max_failures: u32,
+    cooldown_secs: u64,
+    failures: std::sync::atomic::AtomicU32,
+    disabled_until: std::sync::atomic::AtomicU64,
+}
+
+static SUBMIT_ENDPOINT: std::sync::OnceLock<Option<SubmitEndpoint>> = std::sync::OnceLock::new();
+
+fn submit_endpoint() -> Option<&'static SubmitEndpoint> {
+    SUBMIT_ENDPOINT
+        .get_or_init(|| {
+            let url = std::env::var("RPC_SUBMIT").ok().filter(|url| !url.trim().is_empty())?;
+            out!("[rpc] 交易发送节点: {}", url);
+            Some(SubmitEndpoint {
+                client: RpcClient::new_with_timeout_and_commitment(
+                    url,
+                    rpc_request_timeout(),
+                    commitment_for(OpClass::Confirmation),
+                ),
+                max_failures: std::env::var("RPC_SUBMIT_MAX_FAILURES")
+                    .ok()
+                    .and_then(|s| s.parse::<u32>().ok())
+                    .unwrap_or(3)
+                    .max(1),
+                cooldown_secs: std::env::var("RPC_SUBMIT_COOLDOWN_SECS")
+                    .ok()
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(60),
+                failures: std::sync::atomic::AtomicU32::new(0),
+                disabled_until: std::sync::atomic::AtomicU64::new(0),
+            })
+        })
+        .as_ref()
+}
+
+impl SubmitEndpoint {
+    fn available(&self) -> bool {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+        now >= self.disabled_until.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    fn record_success(&self) {
+        if self.failures.swap(0, std::sync::atomic::Ordering::Relaxed) >= self.max_failures {
+            out!("[rpc] 交易发送节点已恢复");
+        }
+    }
+
+    fn record_failure(&self) {
+        let failures = self.failures.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+        if failures >= self.max_failures {
+            let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_secs();
+            self.disabled_until
+                .store(now + self.cooldown_secs, std::sync::atomic::Ordering::Relaxed);
+            out!(
+                "[rpc] ⚠️  交易发送节点连续失败 {} 次，{} 秒内改用读取节点发送",
+                failures, self.cooldown_secs
+            );
+        }
+    }
+}
+
+// 交易本身被拒绝（预检 / 执行失败）不算发送节点故障
+fn is_transaction_rejection(err: &solana_client::client_error::ClientError) -> bool {
+    matches!(
+        &err.kind,
+        ClientErrorKind::TransactionError(_)
+            | ClientErrorKind::SigningError(_)
+            | ClientErrorKind::RpcError(RpcError::RpcResponseError {
+                data: RpcResponseErrorData::SendTransactionPreflightFailure(_),
+                ..
+            })
+    )
+}
+
+// 只发送不确认：优先使用 RPC_SUBMIT，不可用或发送失败时回退到读取节点
+async fn send_transaction_routed(
+    rpc: &RpcClient,
+    transaction: &Transaction,
+) -> solana_client::client_error::Result<solana_sdk::signature::Signature> {
+    let Some(submit) = submit_endpoint().filter(|s| s.available()) else {
+        return rpc.send_transaction(transaction).await;
+    };
+    match submit.client.send_transaction(transaction).await {
+        Ok(signature) => {
+            submit.record_success();
+            Ok(signature)
+        }
+        Err(e) if is_transaction_rejection(&e) => Err(e),
+        Err(e) => {
+            out!("[rpc] ⚠️  交易发送节点发送失败: {}，改用读取节点", e);
+            submit.record_failure();
+            rpc.send_transaction(transaction).await
+        }
+    }
+}
+
+// 发送并确认：发送走 send_transaction_routed，确认始终通过读取节点轮询（与 send_and_confirm_transaction 相同的判定）
+async fn send_and_confirm_routed(
+    rpc: &RpcClient,
+    transaction: &Transaction,
+) -> solana_client::client_error::Result<solana_sdk::signature::Signature> {
+    if submit_endpoint().is_none() {
+        return rpc.send_and_confirm_transaction(transaction).await;
+    }
+    let signature = send_transaction_routed(rpc, transaction).await?;
+    loop {
+        match rpc.get_signature_status(&signature).await? {
+            Some(Ok(())) => return Ok(signature),
+            Some(Err(e)) => return Err(e.into()),
+            None => {
+                if !rpc
+                    .is_blockhash_valid(&transaction.message.recent_blockhash, CommitmentConfig::processed())
+                    .await?
+                {
+                    return Err(RpcError::ForUser(
+                        "unable to confirm transaction. This can happen in situations such as transaction expiration and insufficient fee-payer funds".to_string(),
+                    )
+                    .into());
+                }
+                sleep(Duration::from_millis(500)).await;
+            }
+        }
+    }
 }
 
 async fn submit_transaction(
     rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
+    payer: &dyn TxSigner,
+    instructions: &[solana_sdk::instruction::Instruction],
+) -> Result<solana_sdk::signature::Signature, anyhow::Error> {
+    submit_transaction_tracked(rpc, payer, mining_authority(payer).pubkey(), instructions, &mut vec![]).await
+}
+
+// 同 submit_transaction，签出的每个签名都追加到 sent（调用方在报错后据此核对是否已上链）；
+// authority 为交易写入的矿工（分钱包模式下是部署钱包），重试前扫描其 miner 账户确认先前的交易没有上链
+async fn submit_transaction_tracked(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+    authority: Pubkey,
     instructions: &[solana_sdk::instruction::Instruction],
+    sent: &mut Vec<solana_sdk::signature::Signature>,
 ) -> Result<solana_sdk::signature::Signature, anyhow::Error> {
     // 从环境变量读取费用配置，默认使用更合理的值
     // compute_unit_price: 默认 1,000 microlamports (低优先级，适合大多数情况)
@@ -1601,7 +14659,32 @@ async fn submit_transaction(
         .ok()
         .and_then(|s| s.parse::<u32>().ok())
         .unwrap_or(1_400_000);
+    submit_transaction_with_fee_tracked(rpc, payer, authority, instructions, compute_unit_price, compute_unit_limit, sent).await
+}
+
+// 指定优先费提交（过期保护等需要临时提高优先费、又不能改动全局 COMPUTE_UNIT_PRICE 的场景）
+async fn submit_transaction_with_fee(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+    instructions: &[solana_sdk::instruction::Instruction],
+    compute_unit_price: u64,
+    compute_unit_limit: u32,
+) -> Result<solana_sdk::signature::Signature, anyhow::Error> {
+    let authority = mining_authority(payer).pubkey();
+    submit_transaction_with_fee_tracked(rpc, payer, authority, instructions, compute_unit_price, compute_unit_limit, &mut vec![]).await
+}
 
+// 超时等可重试错误后，上一笔交易的 blockhash 仍有效时原样重发同一笔已签名交易（签名相同，不会重复上链）；
+// 只有在该 blockhash 过期（交易再也不可能上链）且确认先前签名均未上链后，才用新 blockhash 重新签名
+async fn submit_transaction_with_fee_tracked(
+    rpc: &RpcClient,
+    payer: &dyn TxSigner,
+    authority: Pubkey,
+    instructions: &[solana_sdk::instruction::Instruction],
+    compute_unit_price: u64,
+    compute_unit_limit: u32,
+    sent: &mut Vec<solana_sdk::signature::Signature>,
+) -> Result<solana_sdk::signature::Signature, anyhow::Error> {
     // 计算预估费用（用于日志输出）
     // Solana 费用公式：费用(lamports) = (compute_unit_price * compute_units_used) / 1,000,000,000
     // 其中 compute_unit_price 单位是 microlamports per CU
@@ -1611,23 +14694,67 @@ async fn submit_transaction(
     // 费用 = (price * cu) / 1e9，然后转换为 SOL (1 SOL = 1e9 lamports)
     let typical_fee_sol = (compute_unit_price as f64 * typical_cu_usage as f64) / 1_000_000_000_000.0;
     let max_fee_sol = (compute_unit_limit as f64) * (compute_unit_price as f64) / 1_000_000_000_000.0;
-    println!("[fee] Compute Unit Price: {} microlamports/CU, Limit: {} CU",
+    out!("[fee] Compute Unit Price: {} microlamports/CU, Limit: {} CU",
         compute_unit_price, compute_unit_limit);
-    println!("[fee] 预估费用: {:.6} SOL (典型使用 {} CU), 最大费用: {:.6} SOL",
+    out!("[fee] 预估费用: {:.6} SOL (典型使用 {} CU), 最大费用: {:.6} SOL",
         typical_fee_sol, typical_cu_usage, max_fee_sol);
 
     // 添加重试机制：指数退避算法，最多重试4次
     let max_retries = 4;
     let mut retry_count = 0;
+    // 上一次发出但未确认的交易
+    let mut previous: Option<Transaction> = None;
 
     loop {
+        if let Some(signature) = find_landed_signature(rpc, authority, sent).await {
+            out!("[retry] 先前提交的交易已上链: {:?}，不再重发", signature);
+            return Ok(signature);
+        }
+
+        // 上一笔仍可能上链（blockhash 未过期，查询失败时按未过期处理）：原样重发，不重新签名
+        if let Some(transaction) = previous.take() {
+            let still_valid = rpc
+                .is_blockhash_valid(&transaction.message.recent_blockhash, CommitmentConfig::processed())
+                .await
+                .unwrap_or(true);
+            if still_valid {
+                out!("[retry] 上一笔交易的 blockhash 仍有效，原样重发 {:?}", transaction.signatures[0]);
+                let send_started = Instant::now();
+                let send_result = send_and_confirm_routed(rpc, &transaction).await;
+                record_phase(LoopPhase::Send, send_started.elapsed());
+                match send_result {
+                    Ok(signature) => {
+                        out!("[✓] 交易成功提交: {:?}", signature);
+                        return Ok(signature);
+                    }
+                    Err(e) if retry_count < max_retries && is_retryable_send_error(&e) => {
+                        retry_count += 1;
+                        let wait_secs = 2u64.pow(retry_count as u32 - 1);
+                        out!("[retry] 重发失败 (第 {} 次): {:?}，等待 {} 秒后重试...", retry_count, e, wait_secs);
+                        previous = Some(transaction);
+                        sleep(Duration::from_secs(wait_secs)).await;
+                        continue;
+                    }
+                    Err(e) => {
+                        out!("[✗] 交易提交失败（不可重试或已达最大重试次数）: {:?}", e);
+                        return Err(e.into());
+                    }
+                }
+            }
+            // blockhash 已过期：再核对一次先前的签名，确认未上链后重新签名
+            if let Some(signature) = find_landed_signature(rpc, authority, sent).await {
+                out!("[retry] 先前提交的交易已上链: {:?}，不再重发", signature);
+                return Ok(signature);
+            }
+        }
+
         let blockhash = match rpc.get_latest_blockhash().await {
             Ok(bh) => bh,
             Err(_e) => {
                 if retry_count < max_retries {
                     retry_count += 1;
                     let wait_secs = 2u64.pow(retry_count as u32 - 1);
-                    println!("[retry] 获取 blockhash 失败 (第 {} 次), 等待 {} 秒后重试...", retry_count, wait_secs);
+                    out!("[retry] 获取 blockhash 失败 (第 {} 次), 等待 {} 秒后重试...", retry_count, wait_secs);
                     sleep(Duration::from_secs(wait_secs)).await;
                     continue;
                 } else {
@@ -1641,36 +14768,30 @@ async fn submit_transaction(
             ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
         ];
         all_instructions.extend_from_slice(instructions);
-        let transaction = Transaction::new_signed_with_payer(
-            &all_instructions,
-            Some(&payer.pubkey()),
-            &[payer],
-            blockhash,
-        );
-
-        match rpc.send_and_confirm_transaction(&transaction).await {
+        let sign_started = Instant::now();
+        let transaction = sign_transaction(payer, &all_instructions, blockhash).await?;
+        record_phase(LoopPhase::Sign, sign_started.elapsed());
+        sent.push(transaction.signatures[0]);
+
+        let send_started = Instant::now();
+        let send_result = send_and_confirm_routed(rpc, &transaction).await;
+        record_phase(LoopPhase::Send, send_started.elapsed());
+        match send_result {
             Ok(signature) => {
-                println!("[✓] 交易成功提交: {:?}", signature);
+                out!("[✓] 交易成功提交: {:?}", signature);
                 return Ok(signature);
             }
             Err(e) => {
-                let err_str = e.to_string().to_lowercase();
-                // 判断是否为可重试的错误
-                let is_retryable = err_str.contains("blockhash not found")
-                    || err_str.contains("timeout")
-                    || err_str.contains("invalid nonce")
-                    || err_str.contains("connection")
-                    || matches!(e.kind, solana_client::client_error::ClientErrorKind::Io(_));
-
-                if is_retryable && retry_count < max_retries {
+                if is_retryable_send_error(&e) && retry_count < max_retries {
                     retry_count += 1;
                     let wait_secs = 2u64.pow(retry_count as u32 - 1);
-                    println!("[retry] 交易提交失败 (第 {} 次): {:?}", retry_count, e);
-                    println!("[retry] 这是可恢复错误，等待 {} 秒后重试...", wait_secs);
+                    out!("[retry] 交易提交失败 (第 {} 次): {:?}", retry_count, e);
+                    out!("[retry] 这是可恢复错误，等待 {} 秒后重试...", wait_secs);
+                    previous = Some(transaction);
                     sleep(Duration::from_secs(wait_secs)).await;
                     continue;
                 } else {
-                    println!("[✗] 交易提交失败（不可重试或已达最大重试次数）: {:?}", e);
+                    out!("[✗] 交易提交失败（不可重试或已达最大重试次数）: {:?}", e);
                     return Err(e.into());
                 }
             }
@@ -1678,12 +14799,76 @@ async fn submit_transaction(
     }
 }
 
+// 判断是否为可重试的错误
+fn is_retryable_send_error(e: &solana_client::client_error::ClientError) -> bool {
+    let err_str = e.to_string().to_lowercase();
+    err_str.contains("blockhash not found")
+        || err_str.contains("timeout")
+        || err_str.contains("invalid nonce")
+        || err_str.contains("connection")
+        || matches!(e.kind, solana_client::client_error::ClientErrorKind::Io(_))
+}
+
+// ============ 新增：重试前的重复交易检测 ============
+// 客户端超时但交易其实已上链时，直接重发会重复部署。重试前先用 getSignatureStatuses 查询此前签出的所有签名，
+// 再扫描矿工账户最近的交易确认这些签名确实不在链上；auto_mine 部署失败后还会检查 Miner 是否已有本轮部署
+async fn find_landed_signature(
+    rpc: &RpcClient,
+    authority: Pubkey,
+    sent: &[solana_sdk::signature::Signature],
+) -> Option<solana_sdk::signature::Signature> {
+    if sent.is_empty() {
+        return None;
+    }
+    if let Ok(statuses) = rpc.get_signature_statuses(sent).await {
+        for (signature, status) in sent.iter().zip(statuses.value) {
+            if status.is_some_and(|s| s.err.is_none()) {
+                return Some(*signature);
+            }
+        }
+    }
+    let recent = rpc.get_signatures_for_address(&miner_pda(authority).0).await.ok()?;
+    recent
+        .iter()
+        .take(20)
+        .filter(|s| s.err.is_none())
+        .find_map(|s| sent.iter().find(|sig| sig.to_string() == s.signature).copied())
+}
+
+// 提交报错后 Miner 已记录本轮部署时的来源
+enum LandedDeploy {
+    // 本次发出的某个签名已上链
+    Sent(solana_sdk::signature::Signature),
+    // 不是本次发出的交易（其他实例 / 手动部署），不能当作本次部署记录
+    Other,
+}
+
+// Miner 已记录本轮部署时，在本次发出的签名（sent）中找出已上链的那一笔
+async fn find_landed_deploy(
+    rpc: &RpcClient,
+    authority: Pubkey,
+    round_id: u64,
+    sent: &[solana_sdk::signature::Signature],
+) -> Option<LandedDeploy> {
+    let miner = get_miner_with_commitment(rpc, authority, commitment_for(OpClass::Confirmation))
+        .await
+        .ok()?;
+    if MinerView::new(miner).deployed_in(round_id) == 0 {
+        return None;
+    }
+    Some(match find_landed_signature(rpc, authority, sent).await {
+        Some(signature) => LandedDeploy::Sent(signature),
+        None => LandedDeploy::Other,
+    })
+}
+
 // 危险区间快速单次提交：不重试，直接返回结果
 // 用于轮次即将结束时的最后冲刺
 async fn submit_transaction_danger_zone_no_retry(
     rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
+    payer: &dyn TxSigner,
     instructions: &[solana_sdk::instruction::Instruction],
+    sent: &mut Vec<solana_sdk::signature::Signature>,
 ) -> Result<solana_sdk::signature::Signature, anyhow::Error> {
     // 获取 blockhash，这一步不重试，直接失败
     let blockhash = rpc.get_latest_blockhash().await?;
@@ -1692,6 +14877,14 @@ async fn submit_transaction_danger_zone_no_retry(
         .ok()
         .and_then(|s| s.parse::<u64>().ok())
         .unwrap_or(1_000);
+    // 预测的部署时优先费更高时使用预测值（FEE_FORECAST）
+    let compute_unit_price = match fee_forecast() {
+        Some(forecast) if forecast.cu_price > compute_unit_price => {
+            out!("[fee] 危险区间使用预测优先费 {} microlamports/CU", forecast.cu_price);
+            forecast.cu_price
+        }
+        _ => compute_unit_price,
+    };
 
     let compute_unit_limit: u32 = std::env::var("COMPUTE_UNIT_LIMIT")
         .ok()
@@ -1703,21 +14896,22 @@ async fn submit_transaction_danger_zone_no_retry(
         ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
     ];
     all_instructions.extend_from_slice(instructions);
-    let transaction = Transaction::new_signed_with_payer(
-        &all_instructions,
-        Some(&payer.pubkey()),
-        &[payer],
-        blockhash,
-    );
+    let sign_started = Instant::now();
+    let transaction = sign_transaction(payer, &all_instructions, blockhash).await?;
+    record_phase(LoopPhase::Sign, sign_started.elapsed());
+    sent.push(transaction.signatures[0]);
 
     // 单次发送，不重试
-    match rpc.send_and_confirm_transaction(&transaction).await {
+    let send_started = Instant::now();
+    let send_result = send_and_confirm_routed(rpc, &transaction).await;
+    record_phase(LoopPhase::Send, send_started.elapsed());
+    match send_result {
         Ok(signature) => {
-            println!("[✓✓✓] 危险区间提交成功！交易签名: {:?}", signature);
+            out!("[✓✓✓] 危险区间提交成功！交易签名: {:?}", signature);
             Ok(signature)
         }
         Err(e) => {
-            println!("[✗✗✗] 危险区间提交失败（不重试）: {:?}", e);
+            out!("[✗✗✗] 危险区间提交失败（不重试）: {:?}", e);
             Err(e.into())
         }
     }
@@ -1725,7 +14919,7 @@ async fn submit_transaction_danger_zone_no_retry(
 
 async fn submit_transaction_no_confirm(
     rpc: &RpcClient,
-    payer: &solana_sdk::signer::keypair::Keypair,
+    payer: &dyn TxSigner,
     instructions: &[solana_sdk::instruction::Instruction],
 ) -> Result<solana_sdk::signature::Signature, anyhow::Error> {
     let blockhash = rpc.get_latest_blockhash().await?;
@@ -1746,25 +14940,43 @@ async fn submit_transaction_no_confirm(
         ComputeBudgetInstruction::set_compute_unit_price(compute_unit_price),
     ];
     all_instructions.extend_from_slice(instructions);
-    let transaction = Transaction::new_signed_with_payer(
-        &all_instructions,
-        Some(&payer.pubkey()),
-        &[payer],
-        blockhash,
-    );
+    let transaction = sign_transaction(payer, &all_instructions, blockhash).await?;
 
-    match rpc.send_transaction(&transaction).await {
+    match send_transaction_routed(rpc, &transaction).await {
         Ok(signature) => {
-            println!("Transaction submitted: {:?}", signature);
+            out!("Transaction submitted: {:?}", signature);
             Ok(signature)
         }
         Err(e) => {
-            println!("Error submitting transaction: {:?}", e);
+            out!("Error submitting transaction: {:?}", e);
             Err(e.into())
         }
     }
 }
 
+// 账户数据长度 = 8 字节 discriminator + 结构体大小
+fn account_data_size<T>() -> u64 {
+    (8 + std::mem::size_of::<T>()) as u64
+}
+
+// 通用过滤条件：discriminator + dataSize（GPA_DATA_SIZE=false 可关闭 dataSize）
+fn program_account_filters<T>(filters: Vec<RpcFilterType>) -> Vec<RpcFilterType>
+where
+    T: Discriminator,
+{
+    let mut all_filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
+        0,
+        &T::discriminator().to_le_bytes(),
+    ))];
+    let data_size_enabled =
+        std::env::var("GPA_DATA_SIZE").is_err() || env_flag("GPA_DATA_SIZE");
+    if data_size_enabled {
+        all_filters.push(RpcFilterType::DataSize(account_data_size::<T>()));
+    }
+    all_filters.extend(filters);
+    all_filters
+}
+
 pub async fn get_program_accounts<T>(
     client: &RpcClient,
     program_id: Pubkey,
@@ -1773,18 +14985,89 @@ pub async fn get_program_accounts<T>(
 where
     T: AccountDeserialize + Discriminator + Clone,
 {
-    let mut all_filters = vec![RpcFilterType::Memcmp(Memcmp::new_base58_encoded(
-        0,
-        &T::discriminator().to_le_bytes(),
-    ))];
-    all_filters.extend(filters);
+    let all_filters = program_account_filters::<T>(filters);
+    let accounts = fetch_program_accounts(client, program_id, all_filters, None).await?;
+    let accounts = accounts
+        .into_iter()
+        .filter_map(|(pubkey, data)| {
+            if let Ok(account) = T::try_from_bytes(&data) {
+                Some((pubkey, account.clone()))
+            } else {
+                None
+            }
+        })
+        .collect();
+    Ok(accounts)
+}
+
+// 只取账户数据的一段（dataSlice），适合只需要少数字段的场景
+pub async fn get_program_accounts_sliced<T>(
+    client: &RpcClient,
+    program_id: Pubkey,
+    filters: Vec<RpcFilterType>,
+    offset: usize,
+    length: usize,
+) -> Result<Vec<(Pubkey, Vec<u8>)>, anyhow::Error>
+where
+    T: Discriminator,
+{
+    let all_filters = program_account_filters::<T>(filters);
+    let slice = UiDataSliceConfig { offset, length };
+    fetch_program_accounts(client, program_id, all_filters, Some(slice)).await
+}
+
+// GPA_CHUNKED 开启时按第一个字段的首字节（offset 8）分 256 段依次拉取，
+// 避免单次全量扫描被 RPC 拒绝或超时
+async fn fetch_program_accounts(
+    client: &RpcClient,
+    program_id: Pubkey,
+    filters: Vec<RpcFilterType>,
+    data_slice: Option<UiDataSliceConfig>,
+) -> Result<Vec<(Pubkey, Vec<u8>)>, anyhow::Error> {
+    match account_index_backend().as_str() {
+        "gpa" => {}
+        "helius" => return fetch_program_accounts_helius(client, program_id, filters, data_slice).await,
+        "index" => return fetch_program_accounts_index(client, program_id, filters, data_slice).await,
+        other => {
+            return Err(CommandError::config(format!("ACCOUNT_INDEX 无效: {}（gpa | helius | index）", other)).into())
+        }
+    }
+    if !env_flag("GPA_CHUNKED") {
+        return fetch_program_accounts_once(client, program_id, filters, data_slice).await;
+    }
+    let mut accounts = vec![];
+    for byte in 0..=u8::MAX {
+        let mut chunk_filters = filters.clone();
+        chunk_filters.push(RpcFilterType::Memcmp(Memcmp::new_base58_encoded(8, &[byte])));
+        let chunk =
+            fetch_program_accounts_once(client, program_id, chunk_filters, data_slice).await?;
+        accounts.extend(chunk);
+        if byte % 16 == 15 {
+            out!(
+                "[gpa] 已完成 {}/256 段，累计 {} 个账户",
+                byte as usize + 1,
+                accounts.len()
+            );
+        }
+    }
+    Ok(accounts)
+}
+
+async fn fetch_program_accounts_once(
+    client: &RpcClient,
+    program_id: Pubkey,
+    filters: Vec<RpcFilterType>,
+    data_slice: Option<UiDataSliceConfig>,
+) -> Result<Vec<(Pubkey, Vec<u8>)>, anyhow::Error> {
     let result = client
         .get_program_accounts_with_config(
             &program_id,
             RpcProgramAccountsConfig {
-                filters: Some(all_filters),
+                filters: Some(filters),
                 account_config: RpcAccountInfoConfig {
                     encoding: Some(UiAccountEncoding::Base64),
+                    data_slice,
+                    commitment: Some(commitment_for(OpClass::Analytics)),
                     ..Default::default()
                 },
                 ..Default::default()
@@ -1793,19 +15076,10 @@ where
         .await;
 
     match result {
-        Ok(accounts) => {
-            let accounts = accounts
-                .into_iter()
-                .filter_map(|(pubkey, account)| {
-                    if let Ok(account) = T::try_from_bytes(&account.data) {
-                        Some((pubkey, account.clone()))
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-            Ok(accounts)
-        }
+        Ok(accounts) => Ok(accounts
+            .into_iter()
+            .map(|(pubkey, account)| (pubkey, account.data))
+            .collect()),
         Err(err) => match err.kind {
             ClientErrorKind::Reqwest(err) => {
                 if let Some(status_code) = err.status() {
@@ -1821,4 +15095,308 @@ where
             _ => return Err(anyhow::anyhow!("Failed to get program accounts: {}", err)),
         },
     }
+}
+
+// ============ 新增：账户发现后端 ============
+// 很多 RPC 服务商限制 getProgramAccounts，ACCOUNT_INDEX 选择账户发现方式：
+//   gpa（默认）  标准 getProgramAccounts（GPA_CHUNKED 可分段）
+//   helius       Helius getProgramAccountsV2 分页接口，每页 ACCOUNT_INDEX_PAGE_SIZE 个（默认 5000），使用 RPC 地址
+//   index        自建索引服务：GET ACCOUNT_INDEX_URL?program=<program_id> 返回账户地址数组（base58），
+//                再用 getMultipleAccounts 每 100 个一批读取，并在本地应用 memcmp / dataSize 过滤和数据切片
+fn account_index_backend() -> String {
+    std::env::var("ACCOUNT_INDEX").unwrap_or("gpa".to_string())
+}
+
+fn base64_decode(input: &str) -> Option<Vec<u8>> {
+    let value = |c: u8| -> Option<u32> {
+        match c {
+            b'A'..=b'Z' => Some((c - b'A') as u32),
+            b'a'..=b'z' => Some((c - b'a' + 26) as u32),
+            b'0'..=b'9' => Some((c - b'0' + 52) as u32),
+            b'+' => Some(62),
+            b'/' => Some(63),
+            _ => None,
+        }
+    };
+    let bytes: Vec<u8> = input.bytes().filter(|c| *c != b'=' && !c.is_ascii_whitespace()).collect();
+    let mut out = Vec::with_capacity(bytes.len() * 3 / 4);
+    for chunk in bytes.chunks(4) {
+        let mut n = 0u32;
+        for (i, c) in chunk.iter().enumerate() {
+            n |= value(*c)? << (18 - 6 * i);
+        }
+        for i in 0..chunk.len().saturating_sub(1) {
+            out.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn apply_data_slice(data: Vec<u8>, data_slice: Option<UiDataSliceConfig>) -> Vec<u8> {
+    match data_slice {
+        Some(slice) => {
+            let start = slice.offset.min(data.len());
+            let end = (slice.offset + slice.length).min(data.len());
+            data[start..end].to_vec()
+        }
+        None => data,
+    }
+}
+
+async fn fetch_program_accounts_helius(
+    client: &RpcClient,
+    program_id: Pubkey,
+    filters: Vec<RpcFilterType>,
+    data_slice: Option<UiDataSliceConfig>,
+) -> Result<Vec<(Pubkey, Vec<u8>)>, anyhow::Error> {
+    let page_size: u64 = std::env::var("ACCOUNT_INDEX_PAGE_SIZE")
+        .ok()
+        .and_then(|s| s.parse::<u64>().ok())
+        .unwrap_or(5000);
+    let http = solana_client::client_error::reqwest::Client::new();
+    let mut accounts = vec![];
+    let mut pagination_key: Option<String> = None;
+    loop {
+        let mut config = serde_json::json!({
+            "encoding": "base64",
+            "filters": filters,
+            "limit": page_size,
+            "commitment": commitment_for(OpClass::Analytics).commitment,
+        });
+        if let Some(slice) = data_slice {
+            config["dataSlice"] = serde_json::to_value(slice)?;
+        }
+        if let Some(key) = &pagination_key {
+            config["paginationKey"] = serde_json::json!(key);
+        }
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "getProgramAccountsV2",
+            "params": [program_id.to_string(), config],
+        });
+        let response: serde_json::Value = http
+            .post(client.url())
+            .header("Content-Type", "application/json")
+            .timeout(Duration::from_secs(60))
+            .body(request.to_string())
+            .send()
+            .await?
+            .json()
+            .await?;
+        if let Some(error) = response.get("error") {
+            return Err(anyhow::anyhow!("getProgramAccountsV2 失败: {}", error));
+        }
+        let result = &response["result"];
+        for item in result["accounts"].as_array().into_iter().flatten() {
+            let pubkey = Pubkey::from_str(item["pubkey"].as_str().unwrap_or_default())?;
+            let data = item["account"]["data"][0]
+                .as_str()
+                .and_then(base64_decode)
+                .ok_or_else(|| anyhow::anyhow!("账户 {} 数据无法解码", pubkey))?;
+            accounts.push((pubkey, data));
+        }
+        out!("[index] helius 已读取 {} 个账户", accounts.len());
+        pagination_key = result["paginationKey"].as_str().map(|s| s.to_string());
+        if pagination_key.is_none() {
+            break;
+        }
+    }
+    Ok(accounts)
+}
+
+async fn fetch_program_accounts_index(
+    client: &RpcClient,
+    program_id: Pubkey,
+    filters: Vec<RpcFilterType>,
+    data_slice: Option<UiDataSliceConfig>,
+) -> Result<Vec<(Pubkey, Vec<u8>)>, anyhow::Error> {
+    let url = std::env::var("ACCOUNT_INDEX_URL")
+        .map_err(|_| CommandError::config("ACCOUNT_INDEX=index 需要设置 ACCOUNT_INDEX_URL"))?;
+    let request = solana_client::client_error::reqwest::Client::new()
+        .get(&url)
+        .query(&[("program", program_id.to_string())])
+        .timeout(Duration::from_secs(60));
+    let response = with_env_headers(request, "ACCOUNT_INDEX_HEADERS").send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow::anyhow!("索引服务返回 HTTP {}", response.status()));
+    }
+    let addresses: Vec<String> = response.json().await?;
+    let addresses: Vec<Pubkey> = addresses
+        .iter()
+        .filter_map(|a| Pubkey::from_str(a).ok())
+        .collect();
+    out!("[index] 索引服务返回 {} 个账户地址", addresses.len());
+
+    let allows = |data: &[u8]| {
+        filters.iter().all(|filter| match filter {
+            RpcFilterType::DataSize(size) => data.len() as u64 == *size,
+            RpcFilterType::Memcmp(memcmp) => memcmp.bytes_match(data),
+            _ => true,
+        })
+    };
+    let mut accounts = vec![];
+    for chunk in addresses.chunks(100) {
+        let fetched = client
+            .get_multiple_accounts_with_commitment(chunk, commitment_for(OpClass::Analytics))
+            .await?
+            .value;
+        for (pubkey, account) in chunk.iter().zip(fetched) {
+            let Some(account) = account else {
+                continue;
+            };
+            if account.owner != program_id || !allows(&account.data) {
+                continue;
+            }
+            accounts.push((*pubkey, apply_data_slice(account.data, data_slice)));
+        }
+    }
+    Ok(accounts)
+}
+
+// ============ 新增：本地 Miner 索引 ============
+// 全量扫描 Miner 账户很慢且容易被 RPC 限流，MINER_INDEX=true 时 get_miners 改用本地索引（ore.miner_index.json）：
+//   首次全量扫描一次，之后只按回合增量更新——每个部署过的 Miner 的 round_id 都会变成当轮回合号，
+//   因此扫描 synced_round..=当前回合 的参与者（memcmp 过滤，结果很小）即可覆盖所有新增与变化的 Miner；
+//   checkpoint / claim 只改 checkpoint_id 与奖励字段，待 checkpoint 的候选每次用 getMultipleAccounts 重新确认
+//   落后超过 MINER_INDEX_MAX_GAP 个回合（默认 50）时重新全量扫描
+// COMMAND=miner_index 手动同步并打印索引概况（MINER_INDEX_REBUILD=true 强制全量重建）
+const MINER_INDEX_FILE: &str = "ore.miner_index.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct MinerIndex {
+    synced_round: u64,
+    updated_at: u64,
+    // Miner 地址 -> 账户数据（base64）
+    miners: BTreeMap<String, String>,
+}
+
+impl MinerIndex {
+    fn load() -> Option<Self> {
+        let content = fs::read_to_string(state_path(MINER_INDEX_FILE)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    fn save(&self) -> Result<(), anyhow::Error> {
+        let tmp = format!("{}.tmp", state_path(MINER_INDEX_FILE));
+        fs::write(&tmp, serde_json::to_string(self)?)?;
+        fs::rename(&tmp, state_path(MINER_INDEX_FILE))?;
+        Ok(())
+    }
+
+    fn upsert(&mut self, accounts: Vec<(Pubkey, Vec<u8>)>) -> usize {
+        let mut changed = 0;
+        for (address, data) in accounts {
+            let encoded = base64_encode(&data);
+            if self.miners.get(&address.to_string()) != Some(&encoded) {
+                self.miners.insert(address.to_string(), encoded);
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    fn miners(&self) -> Vec<(Pubkey, Miner)> {
+        self.miners
+            .iter()
+            .filter_map(|(address, data)| {
+                let address = Pubkey::from_str(address).ok()?;
+                let data = base64_decode(data)?;
+                let miner = Miner::try_from_bytes(&data).ok()?;
+                Some((address, *miner))
+            })
+            .collect()
+    }
+}
+
+async fn fetch_miner_accounts_raw(
+    rpc: &RpcClient,
+    filters: Vec<RpcFilterType>,
+) -> Result<Vec<(Pubkey, Vec<u8>)>, anyhow::Error> {
+    let filters = program_account_filters::<Miner>(filters);
+    fetch_program_accounts(rpc, ore_api::ID, filters, None).await
+}
+
+async fn sync_miner_index(rpc: &RpcClient) -> Result<MinerIndex, anyhow::Error> {
+    let board = get_board(rpc).await?;
+    let max_gap: u64 = std::env::var("MINER_INDEX_MAX_GAP")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(50);
+    let cached = if env_flag("MINER_INDEX_REBUILD") { None } else { MinerIndex::load() };
+    let mut index = match cached {
+        Some(index) if board.round_id.saturating_sub(index.synced_round) <= max_gap => index,
+        _ => {
+            out!("[miner_index] 全量扫描 Miner 账户...");
+            let mut index = MinerIndex::default();
+            index.upsert(fetch_miner_accounts_raw(rpc, vec![]).await?);
+            out!("[miner_index] 全量扫描完成，{} 个 Miner", index.miners.len());
+            index.synced_round = board.round_id;
+            index
+        }
+    };
+
+    // 按回合增量：synced_round 也要重扫，上次同步时该回合可能仍在进行
+    let (mut scanned, mut changed) = (0usize, 0usize);
+    for round_id in index.synced_round..=board.round_id {
+        let filter = RpcFilterType::Memcmp(Memcmp::new_base58_encoded(512, &round_id.to_le_bytes()));
+        let accounts = fetch_miner_accounts_raw(rpc, vec![filter]).await?;
+        scanned += accounts.len();
+        changed += index.upsert(accounts);
+    }
+
+    // 待 checkpoint 的候选可能已被别人 checkpoint，重新读取确认
+    let pending: Vec<Pubkey> = index
+        .miners()
+        .into_iter()
+        .filter(|(_, miner)| miner.checkpoint_id < miner.round_id)
+        .map(|(address, _)| address)
+        .collect();
+    for chunk in pending.chunks(100) {
+        let fetched = rpc
+            .get_multiple_accounts_with_commitment(chunk, commitment_for(OpClass::Analytics))
+            .await?
+            .value;
+        let refreshed = chunk
+            .iter()
+            .zip(fetched)
+            .filter_map(|(address, account)| account.map(|a| (*address, a.data)))
+            .collect();
+        changed += index.upsert(refreshed);
+    }
+
+    index.synced_round = board.round_id;
+    index.updated_at = chain_now();
+    index.save()?;
+    if !env_flag("QUIET") {
+        out!(
+            "[miner_index] 已同步到回合 #{}：扫描 {} 个参与者，复核 {} 个待 checkpoint，更新 {} 条，共 {} 个 Miner",
+            board.round_id,
+            scanned,
+            pending.len(),
+            changed,
+            index.miners.len()
+        );
+    }
+    Ok(index)
+}
+
+async fn miner_index(rpc: &RpcClient) -> Result<(), anyhow::Error> {
+    let index = sync_miner_index(rpc).await?;
+    let miners = index.miners();
+    let board = get_board(rpc).await?;
+    let needs_checkpoint = miners
+        .iter()
+        .filter(|(_, miner)| MinerView::new(*miner).needs_checkpoint(board.round_id))
+        .count();
+    let claimable = miners
+        .iter()
+        .filter(|(_, miner)| MinerView::new(*miner).has_claimable())
+        .count();
+    out!("Miner index ({})", state_path(MINER_INDEX_FILE));
+    out!("  Miners: {}", miners.len());
+    out!("  Synced round: #{}", index.synced_round);
+    out!("  Needs checkpoint: {}", needs_checkpoint);
+    out!("  Has claimable rewards: {}", claimable);
+    Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,203 @@
+// 领域模型：在 ore_api 原始账户之上提供派生字段，
+// 统一剩余时间 / 每格 SOL / 我的占比 / 可领取等计算，避免各命令各算各的
+#![allow(dead_code)]
+
+use ore_api::prelude::*;
+use solana_sdk::{native_token::lamports_to_sol, pubkey::Pubkey};
+use spl_token::amount_to_ui_amount;
+
+// 每个 slot 约 0.4 秒
+pub const SLOT_SECS: f64 = 0.4;
+
+pub fn slots_to_secs(slots: u64) -> f64 {
+    slots as f64 * SLOT_SECS
+}
+
+// 未 checkpoint 的回合在 expires_at 前 12 小时进入费用收取期
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeWindow {
+    // 距进入费用收取期还有多少 slot
+    Closed { slots_until_open: u64 },
+    // 已进入费用收取期，距过期还有多少 slot
+    Open { slots_until_expiry: u64 },
+    Expired,
+}
+
+impl FeeWindow {
+    pub fn at(expires_at: u64, slot: u64) -> Self {
+        let fee_window_start = expires_at.saturating_sub(TWELVE_HOURS_SLOTS);
+        if slot >= expires_at {
+            FeeWindow::Expired
+        } else if slot >= fee_window_start {
+            FeeWindow::Open {
+                slots_until_expiry: expires_at - slot,
+            }
+        } else {
+            FeeWindow::Closed {
+                slots_until_open: fee_window_start - slot,
+            }
+        }
+    }
+}
+
+// Board 的当前回合视图
+#[derive(Debug, Clone, Copy)]
+pub struct BoardView {
+    pub board: Board,
+    pub slot: u64,
+}
+
+impl BoardView {
+    pub fn new(board: Board, slot: u64) -> Self {
+        Self { board, slot }
+    }
+
+    pub fn round_id(&self) -> u64 {
+        self.board.round_id
+    }
+
+    pub fn slots_remaining(&self) -> u64 {
+        self.board.end_slot.saturating_sub(self.slot)
+    }
+
+    pub fn secs_remaining(&self) -> f64 {
+        slots_to_secs(self.slots_remaining())
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.board.end_slot > self.board.start_slot
+    }
+}
+
+// Round 视图：每格 SOL、我的占比、费用收取期
+#[derive(Debug, Clone)]
+pub struct RoundView {
+    pub address: Pubkey,
+    pub round: Round,
+    pub slot: u64,
+    // 我在本轮每个格子的部署（来自 Miner，仅当 miner.round_id == round.id 时有效）
+    pub my_deployed: Option<[u64; 25]>,
+}
+
+impl RoundView {
+    pub fn new(round: Round, slot: u64) -> Self {
+        Self {
+            address: round_pda(round.id).0,
+            round,
+            slot,
+            my_deployed: None,
+        }
+    }
+
+    pub fn with_miner(mut self, miner: &Miner) -> Self {
+        if miner.round_id == self.round.id {
+            self.my_deployed = Some(miner.deployed);
+        }
+        self
+    }
+
+    pub fn id(&self) -> u64 {
+        self.round.id
+    }
+
+    pub fn square_lamports(&self, square: usize) -> u64 {
+        self.round.deployed[square]
+    }
+
+    pub fn square_sol(&self, square: usize) -> f64 {
+        lamports_to_sol(self.round.deployed[square])
+    }
+
+    // 所有 25 个格子的 SOL 部署量
+    pub fn squares_sol(&self) -> [f64; 25] {
+        let mut squares = [0.0; 25];
+        for (i, lamports) in self.round.deployed.iter().enumerate() {
+            squares[i] = lamports_to_sol(*lamports);
+        }
+        squares
+    }
+
+    // 直接按格子求和（回合进行中 total_deployed 与格子之和一致）
+    pub fn deployed_lamports(&self) -> u64 {
+        self.round.deployed.iter().sum()
+    }
+
+    pub fn deployed_sol(&self) -> f64 {
+        lamports_to_sol(self.deployed_lamports())
+    }
+
+    pub fn my_deployed_lamports(&self) -> u64 {
+        self.my_deployed.map(|d| d.iter().sum()).unwrap_or(0)
+    }
+
+    // 我在某个格子中的占比（0.0 ~ 1.0）
+    pub fn my_share(&self, square: usize) -> f64 {
+        let Some(mine) = self.my_deployed else {
+            return 0.0;
+        };
+        let total = self.round.deployed[square];
+        if total == 0 {
+            0.0
+        } else {
+            mine[square] as f64 / total as f64
+        }
+    }
+
+    pub fn fee_window(&self) -> FeeWindow {
+        FeeWindow::at(self.round.expires_at, self.slot)
+    }
+
+    pub fn is_closable(&self) -> bool {
+        self.slot >= self.round.expires_at
+    }
+}
+
+// Miner 视图：可领取合计、checkpoint 状态
+#[derive(Debug, Clone, Copy)]
+pub struct MinerView {
+    pub address: Pubkey,
+    pub miner: Miner,
+}
+
+impl MinerView {
+    pub fn new(miner: Miner) -> Self {
+        Self {
+            address: miner_pda(miner.authority).0,
+            miner,
+        }
+    }
+
+    pub fn claimable_sol_lamports(&self) -> u64 {
+        self.miner.rewards_sol
+    }
+
+    pub fn claimable_sol(&self) -> f64 {
+        lamports_to_sol(self.miner.rewards_sol)
+    }
+
+    // ORE 可领取 = 挖矿奖励 + claim 手续费分成（refined）
+    pub fn claimable_ore_amount(&self) -> u64 {
+        self.miner.rewards_ore + self.miner.refined_ore
+    }
+
+    pub fn claimable_ore(&self) -> f64 {
+        amount_to_ui_amount(self.claimable_ore_amount(), TOKEN_DECIMALS)
+    }
+
+    pub fn has_claimable(&self) -> bool {
+        self.miner.rewards_sol > 0 || self.claimable_ore_amount() > 0
+    }
+
+    // 最近参与的回合已结束但尚未 checkpoint
+    pub fn needs_checkpoint(&self, current_round_id: u64) -> bool {
+        self.miner.checkpoint_id < self.miner.round_id && self.miner.round_id < current_round_id
+    }
+
+    pub fn deployed_in(&self, round_id: u64) -> u64 {
+        if self.miner.round_id == round_id {
+            self.miner.deployed.iter().sum()
+        } else {
+            0
+        }
+    }
+}